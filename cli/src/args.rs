@@ -0,0 +1,146 @@
+//! Argumentos de línea de comandos
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ecobottle_client::ecobottle::Material;
+
+#[derive(Parser, Debug)]
+#[command(name = "ecobottle", version, about = "Operación del programa EcoBottle")]
+pub struct Cli {
+    /// Archivo de configuración de Solana (json_rpc_url, keypair_path)
+    #[arg(short = 'C', long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// URL del nodo RPC (sustituye a la de la configuración)
+    #[arg(short = 'u', long, global = true)]
+    pub url: Option<String>,
+
+    /// Keypair de la autoridad (sustituye al de la configuración)
+    #[arg(short = 'k', long, global = true)]
+    pub keypair: Option<PathBuf>,
+
+    /// Simula las transacciones sin enviarlas
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Inicializa el estado global del programa
+    Init {
+        /// Mint de ECOC (su autoridad debe ser el PDA del estado global)
+        #[arg(long)]
+        ecoc_mint: Pubkey,
+        /// Unidades de ECOC por kilogramo depositado
+        #[arg(long, default_value_t = 1_000_000)]
+        ecoc_per_kg: u64,
+        /// Peso mínimo de un depósito en gramos
+        #[arg(long, default_value_t = 100)]
+        min_deposit_weight: u64,
+    },
+    /// Actualiza los parámetros indicados de la configuración
+    UpdateConfig(UpdateConfigArgs),
+    /// Administración y estado de contenedores
+    #[command(subcommand)]
+    Container(ContainerCommand),
+    /// Perfil de un usuario
+    User {
+        /// Wallet del usuario
+        wallet: Pubkey,
+    },
+    /// Cotiza un depósito con la lógica exacta del programa (simulación)
+    Quote {
+        /// Wallet del usuario
+        wallet: Pubkey,
+        /// ID del contenedor
+        container: String,
+        /// Peso en gramos
+        weight_grams: u64,
+    },
+    /// Registros de depósito y canje vivos de un usuario
+    Records {
+        /// Wallet del usuario
+        wallet: Pubkey,
+    },
+    /// Detalle de un registro de depósito, canje o recolección
+    Record {
+        /// Dirección del registro
+        address: Pubkey,
+    },
+    /// Estadísticas globales del programa
+    Stats,
+}
+
+#[derive(Args, Debug)]
+pub struct UpdateConfigArgs {
+    #[arg(long)]
+    pub ecoc_per_kg: Option<u64>,
+    #[arg(long)]
+    pub min_deposit_weight: Option<u64>,
+    #[arg(long)]
+    pub discrepancy_tolerance_bps: Option<u16>,
+    #[arg(long)]
+    pub record_retention_secs: Option<i64>,
+    /// Plazo de recolección asignada en segundos (0 = sin flujo de SLA)
+    #[arg(long)]
+    pub collection_sla_secs: Option<i64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ContainerCommand {
+    /// Registra un contenedor nuevo
+    Register {
+        /// ID único del contenedor
+        id: String,
+        /// Ubicación física
+        #[arg(long)]
+        location: String,
+        /// Capacidad en kilogramos
+        #[arg(long)]
+        capacity_kg: u64,
+        /// Material que acepta
+        #[arg(long, value_enum, default_value_t = MaterialArg::Pet)]
+        material: MaterialArg,
+    },
+    /// Activa o desactiva un contenedor
+    Toggle {
+        /// ID del contenedor
+        id: String,
+    },
+    /// Lista los contenedores con su nivel de llenado
+    List {
+        /// Solo los que superaron su umbral de llenado
+        #[arg(long)]
+        needs_collection: bool,
+    },
+    /// Detalle de un contenedor
+    Show {
+        /// ID del contenedor
+        id: String,
+    },
+}
+
+/// `Material` del programa como valor de la CLI
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MaterialArg {
+    Pet,
+    Hdpe,
+    Aluminum,
+    Glass,
+}
+
+impl From<MaterialArg> for Material {
+    fn from(material: MaterialArg) -> Self {
+        match material {
+            MaterialArg::Pet => Material::Pet,
+            MaterialArg::Hdpe => Material::Hdpe,
+            MaterialArg::Aluminum => Material::Aluminum,
+            MaterialArg::Glass => Material::Glass,
+        }
+    }
+}
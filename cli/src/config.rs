@@ -0,0 +1,79 @@
+//! Configuración: URL RPC y keypair
+//!
+//! Se lee el mismo YAML que usa `solana config`, de modo que la CLI apunte al
+//! clúster y a la wallet que el operador ya tiene configurados. Los flags
+//! `--url` y `--keypair` tienen prioridad sobre el archivo.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::args::Cli;
+use crate::Result;
+
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+/// Campos relevantes de `~/.config/solana/cli/config.yml`
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub json_rpc_url: Option<String>,
+    #[serde(default)]
+    pub keypair_path: Option<PathBuf>,
+}
+
+/// Configuración ya resuelta entre archivo, flags y valores por defecto
+#[derive(Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub rpc_url: String,
+    pub keypair_path: PathBuf,
+}
+
+impl Config {
+    /// Lee un archivo de configuración; si no existe se usan los valores por defecto
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&contents)?)
+    }
+}
+
+/// Directorio de configuración de Solana del usuario
+fn solana_config_dir() -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    home.join(".config").join("solana")
+}
+
+pub fn default_config_path() -> PathBuf {
+    solana_config_dir().join("cli").join("config.yml")
+}
+
+pub fn default_keypair_path() -> PathBuf {
+    solana_config_dir().join("id.json")
+}
+
+impl Settings {
+    /// Combina los flags con el archivo de configuración
+    pub fn resolve(cli: &Cli) -> Result<Self> {
+        let path = cli.config.clone().unwrap_or_else(default_config_path);
+        let config = Config::load(&path)?;
+        Ok(Self::merge(cli, config))
+    }
+
+    pub fn merge(cli: &Cli, config: Config) -> Self {
+        let rpc_url = cli
+            .url
+            .clone()
+            .or(config.json_rpc_url)
+            .unwrap_or_else(|| DEFAULT_RPC_URL.to_string());
+        let keypair_path = cli
+            .keypair
+            .clone()
+            .or(config.keypair_path)
+            .unwrap_or_else(default_keypair_path);
+        Self { rpc_url, keypair_path }
+    }
+}
@@ -0,0 +1,233 @@
+//! Presentación de cuentas en texto para la terminal
+
+use std::fmt::Write;
+
+use anchor_lang::prelude::Pubkey;
+use ecobottle_client::ecobottle::{
+    CollectionRecord, DepositQuote, DepositRecord, GlobalState, Material, RedemptionRecord, SmartContainer, UserProfile,
+    MATERIAL_COUNT,
+};
+
+const MATERIALS: [Material; MATERIAL_COUNT] = [Material::Pet, Material::Hdpe, Material::Aluminum, Material::Glass];
+
+/// Gramos como kilogramos con tres decimales
+pub fn kg(grams: u64) -> String {
+    format!("{}.{:03} kg", grams / 1000, grams % 1000)
+}
+
+/// Puntos básicos como porcentaje con dos decimales
+pub fn percent(bps: u64) -> String {
+    format!("{}.{:02}%", bps / 100, bps % 100)
+}
+
+/// `Pubkey::default()` representa "ninguno" en las cuentas del programa
+fn optional(key: &Pubkey) -> String {
+    if *key == Pubkey::default() {
+        "-".to_string()
+    } else {
+        key.to_string()
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Tabla de campos alineados
+struct Fields(String);
+
+impl Fields {
+    fn new(title: &str) -> Self {
+        Self(format!("{title}\n"))
+    }
+
+    fn field(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        let _ = writeln!(self.0, "  {name:<28}{value}");
+        self
+    }
+
+    fn finish(self) -> String {
+        self.0
+    }
+}
+
+pub fn global_state(address: &Pubkey, state: &GlobalState) -> String {
+    let mut fields = Fields::new(&format!("Estado global {address}"))
+        .field("versión", state.version)
+        .field("autoridad", state.authority)
+        .field("mint ECOC", state.ecoc_mint)
+        .field("ECOC por kg", state.ecoc_per_kg)
+        .field("depósito mínimo", kg(state.min_deposit_weight))
+        .field("tolerancia de discrepancia", percent(state.discrepancy_tolerance_bps as u64))
+        .field("retención de registros", format!("{} s", state.record_retention_secs))
+        .field("SLA de recolección", format!("{} s", state.collection_sla_secs))
+        .field("usuarios", state.total_users)
+        .field("contenedores", state.total_containers)
+        .field("depósitos", state.total_deposits)
+        .field("depósitos comprimidos", state.compressed_deposits)
+        .field("PET recolectado", kg(state.total_pet_collected))
+        .field("bono por referido (kg)", state.referral_bonus_per_kg)
+        .field("incentivo recolector (kg)", state.collector_reward_per_kg)
+        .field("bono SLA recolector", percent(state.collector_sla_bonus_bps as u64))
+        .field("presupuesto recolectores", state.collector_reward_budget);
+    for material in MATERIALS {
+        fields = fields.field(
+            &format!("factor CO₂e {material:?}"),
+            format!("{} g/kg", state.emission_factors[material as usize]),
+        );
+    }
+    fields
+        .field("CO₂e evitado", kg(state.total_co2_avoided_grams))
+        .field("CO₂e certificado", kg(state.total_co2_certified_grams))
+        .field("CO₂e retirado", kg(state.total_co2_retired_grams))
+        .field("árbol de depósitos", optional(&state.deposit_tree))
+        .field("mint de impacto", optional(&state.impact_mint))
+        .finish()
+}
+
+/// Cabecera de `container list`
+pub fn container_header() -> String {
+    format!(
+        "{:<32} {:<8} {:>12} {:>9} {:>8} {:<6} {}",
+        "ID", "MATERIAL", "PESO", "CAPACIDAD", "LLENADO", "ESTADO", "DIRECCIÓN"
+    )
+}
+
+/// Fila de `container list`; `*` marca los que superaron su umbral
+pub fn container_row(address: &Pubkey, container: &SmartContainer) -> String {
+    let status = if container.is_active { "activo" } else { "inact." };
+    let alert = if container.needs_collection { "*" } else { " " };
+    format!(
+        "{:<32} {:<8} {:>12} {:>6} kg {:>7}{} {:<6} {}",
+        container.container_id,
+        format!("{:?}", container.material),
+        kg(container.current_weight),
+        container.capacity_kg,
+        percent(container.fill_level_bps()),
+        alert,
+        status,
+        address,
+    )
+}
+
+pub fn container(address: &Pubkey, container: &SmartContainer) -> String {
+    Fields::new(&format!("Contenedor {} ({address})", container.container_id))
+        .field("versión", container.version)
+        .field("ubicación", &container.location)
+        .field("material", format!("{:?}", container.material))
+        .field("activo", container.is_active)
+        .field("capacidad", format!("{} kg", container.capacity_kg))
+        .field("peso actual", kg(container.current_weight))
+        .field("llenado", percent(container.fill_level_bps()))
+        .field("umbral de alerta", percent(container.fill_threshold_bps as u64))
+        .field("requiere recolección", container.needs_collection)
+        .field("solicitud abierta", optional(&container.open_request))
+        .field("solicitudes creadas", container.collection_requests)
+        .field("depósitos", container.total_deposits)
+        .field("CO₂e evitado", kg(container.co2_avoided_grams))
+        .field("registrado", container.created_at)
+        .field("última recolección", container.last_collection)
+        .field("recolecciones archivadas", container.archived_collections)
+        .field("autoridad", container.authority)
+        .finish()
+}
+
+pub fn user(address: &Pubkey, profile: &UserProfile) -> String {
+    let referrer = profile.referrer.as_ref().map(optional).unwrap_or_else(|| "-".to_string());
+    Fields::new(&format!("Usuario {} ({address})", profile.username))
+        .field("versión", profile.version)
+        .field("wallet", profile.owner)
+        .field("suspendido", profile.is_suspended)
+        .field("reputación", profile.reputation_score)
+        .field("depósitos", profile.total_deposits)
+        .field("PET depositado", kg(profile.total_pet_weight))
+        .field("CO₂e evitado", kg(profile.co2_avoided_grams))
+        .field("ECOC ganados", profile.total_ecoc_earned)
+        .field("ECOC gastados", profile.total_ecoc_spent)
+        .field("racha actual (días)", profile.current_streak)
+        .field("mejor racha (días)", profile.longest_streak)
+        .field("racha semanal", profile.weekly_streak)
+        .field("último depósito", profile.last_deposit_at)
+        .field("referente", referrer)
+        .field("referidos", profile.referral_count)
+        .field("ganancias por referidos", profile.referral_earnings)
+        .field("contenedor habitual", optional(&profile.preferred_container))
+        .field("depósitos archivados", profile.archived_deposits)
+        .field("canjes archivados", profile.archived_redemptions)
+        .field("registrado", profile.created_at)
+        .finish()
+}
+
+pub fn deposit_quote(container_id: &str, quote: &DepositQuote) -> String {
+    Fields::new(&format!("Cotización en {container_id}"))
+        .field("peso", kg(quote.weight_grams))
+        .field("recompensa base", quote.base_reward)
+        .field("multiplicador", percent(quote.reward_multiplier_bps))
+        .field("recompensa ECOC", quote.ecoc_reward)
+        .field("peso con bono de referido", kg(quote.referral_grams))
+        .field("bono del referente", quote.referral_bonus)
+        .field("CO₂e evitado", kg(quote.co2_grams))
+        .field("llenado tras el depósito", percent(quote.fill_level_bps))
+        .field("dispara alerta de llenado", quote.triggers_collection)
+        .finish()
+}
+
+pub fn deposit_record(address: &Pubkey, record: &DepositRecord) -> String {
+    Fields::new(&format!("Depósito {address}"))
+        .field("usuario", record.user)
+        .field("contenedor", record.container)
+        .field("peso", kg(record.weight_grams))
+        .field("recompensa ECOC", record.ecoc_reward)
+        .field("fecha", record.timestamp)
+        .field("slot", record.slot)
+        .field("recibo", hex(&record.receipt_hash))
+        .field("penalizado", record.penalized)
+        .field("pagador de renta", record.payer)
+        .finish()
+}
+
+pub fn redemption_record(address: &Pubkey, record: &RedemptionRecord) -> String {
+    Fields::new(&format!("Canje {address}"))
+        .field("usuario", record.user)
+        .field("producto", &record.product_id)
+        .field("ECOC canjeados", record.amount)
+        .field("fecha", record.timestamp)
+        .field("pagador de renta", record.payer)
+        .finish()
+}
+
+pub fn collection_record(address: &Pubkey, record: &CollectionRecord) -> String {
+    Fields::new(&format!("Recolección {address}"))
+        .field("contenedor", record.container)
+        .field("recolector", record.collector)
+        .field("peso registrado", kg(record.weight_collected))
+        .field("peso medido", kg(record.measured_weight))
+        .field("discrepancia", percent(record.discrepancy_bps as u64))
+        .field("conciliada", record.reconciled)
+        .field("enviada a planta", record.shipped)
+        .field("incentivo recolector", record.collector_reward)
+        .field("bono SLA", record.sla_bonus)
+        .field("inicio del periodo", record.period_start)
+        .field("fecha", record.timestamp)
+        .field("pagador de renta", record.payer)
+        .finish()
+}
+
+/// Línea resumida de `records`
+pub fn deposit_line(address: &Pubkey, record: &DepositRecord) -> String {
+    let penalized = if record.penalized { " (penalizado)" } else { "" };
+    format!(
+        "depósito {address}  {} -> {} ECOC  {}{penalized}",
+        kg(record.weight_grams),
+        record.ecoc_reward,
+        record.timestamp
+    )
+}
+
+/// Línea resumida de `records`
+pub fn redemption_line(address: &Pubkey, record: &RedemptionRecord) -> String {
+    format!(
+        "canje    {address}  {} ECOC  {}  {}",
+        record.amount, record.product_id, record.timestamp
+    )
+}
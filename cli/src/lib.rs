@@ -0,0 +1,16 @@
+//! CLI de operación de EcoBottle
+//!
+//! Administra el programa desde la terminal: inicialización, configuración,
+//! alta y estado de contenedores, y consulta de usuarios, registros y
+//! estadísticas globales. Toma la URL RPC y el keypair del archivo de
+//! configuración de Solana (`~/.config/solana/cli/config.yml`), y con
+//! `--dry-run` simula las transacciones en lugar de enviarlas.
+
+pub mod args;
+pub mod config;
+pub mod display;
+pub mod runner;
+
+/// Error genérico de la CLI (RPC, keypair, configuración o deserialización)
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
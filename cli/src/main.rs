@@ -0,0 +1,159 @@
+use std::process::ExitCode;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AnchorDeserialize;
+use clap::Parser;
+use ecobottle_cli::args::{Cli, Command, ContainerCommand, UpdateConfigArgs};
+use ecobottle_cli::config::Settings;
+use ecobottle_cli::display;
+use ecobottle_cli::runner::{pubkey_filter, Runner, RECORD_USER_OFFSET};
+use ecobottle_cli::Result;
+use ecobottle_client::accounts::EcobottleAccount;
+use ecobottle_client::ecobottle::{
+    DepositQuote, DepositRecord, GlobalState, RedemptionRecord, SmartContainer, UserProfile,
+};
+use ecobottle_client::{instructions, pda};
+use solana_sdk::signature::Signer;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("❌ {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let runner = Runner::new(Settings::resolve(&cli)?, cli.dry_run);
+
+    match cli.command {
+        Command::Init { ecoc_mint, ecoc_per_kg, min_deposit_weight } => {
+            let authority = runner.signer()?;
+            let instruction = instructions::initialize(&authority.pubkey(), &ecoc_mint, ecoc_per_kg, min_deposit_weight);
+            runner.execute(&authority, &[instruction])
+        }
+        Command::UpdateConfig(args) => update_config(&runner, args),
+        Command::Container(command) => container(&runner, command),
+        Command::User { wallet } => {
+            let address = pda::user_profile(&wallet);
+            let profile: UserProfile = runner.account(&address)?;
+            print!("{}", display::user(&address, &profile));
+            Ok(())
+        }
+        Command::Quote { wallet, container, weight_grams } => quote(&runner, &wallet, &container, weight_grams),
+        Command::Records { wallet } => records(&runner, &wallet),
+        Command::Record { address } => record(&runner, &address),
+        Command::Stats => {
+            let address = pda::global_state();
+            let state: GlobalState = runner.account(&address)?;
+            print!("{}", display::global_state(&address, &state));
+            Ok(())
+        }
+    }
+}
+
+fn update_config(runner: &Runner, args: UpdateConfigArgs) -> Result<()> {
+    let UpdateConfigArgs {
+        ecoc_per_kg,
+        min_deposit_weight,
+        discrepancy_tolerance_bps,
+        record_retention_secs,
+        collection_sla_secs,
+    } = args;
+    if ecoc_per_kg.is_none()
+        && min_deposit_weight.is_none()
+        && discrepancy_tolerance_bps.is_none()
+        && record_retention_secs.is_none()
+        && collection_sla_secs.is_none()
+    {
+        return Err("indica al menos un parámetro a actualizar".into());
+    }
+
+    let authority = runner.signer()?;
+    let instruction = instructions::update_config(
+        &authority.pubkey(),
+        ecoc_per_kg,
+        min_deposit_weight,
+        discrepancy_tolerance_bps,
+        record_retention_secs,
+        collection_sla_secs,
+    );
+    runner.execute(&authority, &[instruction])
+}
+
+fn container(runner: &Runner, command: ContainerCommand) -> Result<()> {
+    match command {
+        ContainerCommand::Register { id, location, capacity_kg, material } => {
+            let authority = runner.signer()?;
+            let instruction =
+                instructions::register_container(&authority.pubkey(), &id, &location, capacity_kg, material.into());
+            runner.execute(&authority, &[instruction])
+        }
+        ContainerCommand::Toggle { id } => {
+            let authority = runner.signer()?;
+            let instruction = instructions::toggle_container_status(&authority.pubkey(), &pda::container(&id));
+            runner.execute(&authority, &[instruction])
+        }
+        ContainerCommand::List { needs_collection } => {
+            let mut containers = runner.program_accounts::<SmartContainer>(Vec::new())?;
+            containers.retain(|(_, container)| !needs_collection || container.needs_collection);
+            containers.sort_by(|(_, a), (_, b)| b.fill_level_bps().cmp(&a.fill_level_bps()));
+
+            println!("{}", display::container_header());
+            for (address, container) in &containers {
+                println!("{}", display::container_row(address, container));
+            }
+            println!("{} contenedores", containers.len());
+            Ok(())
+        }
+        ContainerCommand::Show { id } => {
+            let address = pda::container(&id);
+            let container: SmartContainer = runner.account(&address)?;
+            print!("{}", display::container(&address, &container));
+            Ok(())
+        }
+    }
+}
+
+fn quote(runner: &Runner, wallet: &Pubkey, id: &str, weight_grams: u64) -> Result<()> {
+    let address = pda::container(id);
+    let container: SmartContainer = runner.account(&address)?;
+    let profile: UserProfile = runner.account(&pda::user_profile(wallet))?;
+
+    let instruction =
+        instructions::quote_deposit_reward(wallet, &address, weight_grams, container.material, profile.referrer.as_ref());
+    let quote = DepositQuote::try_from_slice(&runner.view(instruction)?)?;
+    print!("{}", display::deposit_quote(&container.container_id, &quote));
+    Ok(())
+}
+
+fn records(runner: &Runner, wallet: &Pubkey) -> Result<()> {
+    let filters = || vec![pubkey_filter(RECORD_USER_OFFSET, wallet)];
+    let mut deposits = runner.program_accounts::<DepositRecord>(filters())?;
+    let mut redemptions = runner.program_accounts::<RedemptionRecord>(filters())?;
+    deposits.sort_by_key(|(_, record)| record.timestamp);
+    redemptions.sort_by_key(|(_, record)| record.timestamp);
+
+    for (address, record) in &deposits {
+        println!("{}", display::deposit_line(address, record));
+    }
+    for (address, record) in &redemptions {
+        println!("{}", display::redemption_line(address, record));
+    }
+    println!("{} depósitos, {} canjes", deposits.len(), redemptions.len());
+    Ok(())
+}
+
+fn record(runner: &Runner, address: &Pubkey) -> Result<()> {
+    let output = match EcobottleAccount::decode(&runner.data(address)?)? {
+        EcobottleAccount::DepositRecord(record) => display::deposit_record(address, &record),
+        EcobottleAccount::RedemptionRecord(record) => display::redemption_record(address, &record),
+        EcobottleAccount::CollectionRecord(record) => display::collection_record(address, &record),
+        other => return Err(format!("{address} es una cuenta {}, no un registro", other.name()).into()),
+    };
+    print!("{output}");
+    Ok(())
+}
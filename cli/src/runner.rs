@@ -0,0 +1,129 @@
+//! Acceso al clúster: lectura de cuentas y envío o simulación de transacciones
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use ecobottle_client::accounts::decode;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::config::Settings;
+use crate::Result;
+
+/// Desplazamiento del campo `user` en `DepositRecord` y `RedemptionRecord`
+/// (discriminador + versión)
+pub const RECORD_USER_OFFSET: usize = 8 + 1;
+
+pub struct Runner {
+    rpc: RpcClient,
+    settings: Settings,
+    dry_run: bool,
+}
+
+impl Runner {
+    pub fn new(settings: Settings, dry_run: bool) -> Self {
+        let rpc = RpcClient::new_with_commitment(settings.rpc_url.clone(), CommitmentConfig::confirmed());
+        Self { rpc, settings, dry_run }
+    }
+
+    /// Keypair firmante; solo se lee en los comandos que envían transacciones
+    pub fn signer(&self) -> Result<Keypair> {
+        load_keypair(&self.settings.keypair_path)
+    }
+
+    /// Envía las instrucciones firmadas por `signer`, o las simula con `--dry-run`
+    pub fn execute(&self, signer: &Keypair, instructions: &[Instruction]) -> Result<()> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&signer.pubkey()), &[signer], blockhash);
+
+        if !self.dry_run {
+            let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+            println!("✅ Transacción confirmada: {signature}");
+            return Ok(());
+        }
+
+        let simulation = self.rpc.simulate_transaction(&transaction)?.value;
+        for log in simulation.logs.unwrap_or_default() {
+            println!("  {log}");
+        }
+        if let Some(units) = simulation.units_consumed {
+            println!("Unidades de cómputo: {units}");
+        }
+        match simulation.err {
+            Some(err) => Err(format!("la simulación falló: {err}").into()),
+            None => {
+                println!("🧪 Simulación correcta (no se envió la transacción)");
+                Ok(())
+            }
+        }
+    }
+
+    /// Simula una instrucción de vista y devuelve sus datos de retorno; el
+    /// keypair configurado solo figura como pagador de la simulación
+    pub fn view(&self, instruction: Instruction) -> Result<Vec<u8>> {
+        let payer = self.signer()?.pubkey();
+        let transaction = Transaction::new_with_payer(&[instruction], Some(&payer));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            ..RpcSimulateTransactionConfig::default()
+        };
+
+        let simulation = self.rpc.simulate_transaction_with_config(&transaction, config)?.value;
+        if let Some(err) = simulation.err {
+            return Err(format!("la vista falló: {err}").into());
+        }
+        let (data, _) = simulation.return_data.ok_or("la vista no devolvió datos")?.data;
+        Ok(BASE64_STANDARD.decode(data)?)
+    }
+
+    /// Datos de una cuenta existente
+    pub fn data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        Ok(self.rpc.get_account(address)?.data)
+    }
+
+    /// Cuenta del programa de tipo conocido
+    pub fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        Ok(decode(&self.data(address)?)?)
+    }
+
+    /// Todas las cuentas del programa de tipo `T` que cumplen `filters`
+    pub fn program_accounts<T: AccountDeserialize + Discriminator>(
+        &self,
+        mut filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, T)>> {
+        filters.insert(0, RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, T::DISCRIMINATOR)));
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        self.rpc
+            .get_program_accounts_with_config(&ecobottle_client::ID, config)?
+            .into_iter()
+            .map(|(address, account)| Ok((address, decode::<T>(&account.data)?)))
+            .collect()
+    }
+}
+
+/// Filtro por una `Pubkey` en un desplazamiento fijo de la cuenta
+pub fn pubkey_filter(offset: usize, key: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, key.as_ref()))
+}
+
+pub fn load_keypair(path: &Path) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|err| format!("no se pudo leer el keypair {}: {err}", path.display()).into())
+}
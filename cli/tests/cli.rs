@@ -0,0 +1,76 @@
+//! Argumentos, configuración y presentación de la CLI de operación
+
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser};
+use ecobottle_cli::args::{Cli, Command, ContainerCommand, MaterialArg};
+use ecobottle_cli::config::{Config, Settings, DEFAULT_RPC_URL};
+use ecobottle_cli::display;
+
+#[test]
+fn parses_commands_with_global_flags() {
+    Cli::command().debug_assert();
+
+    let cli = Cli::parse_from([
+        "ecobottle",
+        "container",
+        "register",
+        "EB-001",
+        "--location",
+        "Plaza Mayor",
+        "--capacity-kg",
+        "50",
+        "--material",
+        "aluminum",
+        "--dry-run",
+        "-u",
+        "http://rpc.local",
+    ]);
+    assert!(cli.dry_run);
+    assert_eq!(cli.url.as_deref(), Some("http://rpc.local"));
+    match cli.command {
+        Command::Container(ContainerCommand::Register { id, capacity_kg, material, .. }) => {
+            assert_eq!(id, "EB-001");
+            assert_eq!(capacity_kg, 50);
+            assert_eq!(material, MaterialArg::Aluminum);
+        }
+        other => panic!("se esperaba container register, se obtuvo {other:?}"),
+    }
+
+    assert!(Cli::try_parse_from(["ecobottle", "user", "no-es-una-pubkey"]).is_err());
+}
+
+#[test]
+fn flags_take_precedence_over_config_file() {
+    let path = std::env::temp_dir().join(format!("ecobottle-cli-{}.yml", std::process::id()));
+    std::fs::write(
+        &path,
+        "---\njson_rpc_url: https://api.devnet.solana.com\nwebsocket_url: ''\nkeypair_path: /ops/id.json\ncommitment: confirmed\n",
+    )
+    .unwrap();
+
+    let cli = Cli::parse_from(["ecobottle", "stats", "--config", path.to_str().unwrap()]);
+    let settings = Settings::resolve(&cli).unwrap();
+    assert_eq!(settings.rpc_url, "https://api.devnet.solana.com");
+    assert_eq!(settings.keypair_path, PathBuf::from("/ops/id.json"));
+
+    let cli = Cli::parse_from(["ecobottle", "stats", "-u", "http://rpc.local", "-k", "/tmp/op.json"]);
+    let config = Config::load(&path).unwrap();
+    let settings = Settings::merge(&cli, config);
+    assert_eq!(settings.rpc_url, "http://rpc.local");
+    assert_eq!(settings.keypair_path, PathBuf::from("/tmp/op.json"));
+    std::fs::remove_file(&path).unwrap();
+
+    let missing = Config::load(&path).unwrap();
+    assert_eq!(Settings::merge(&cli, missing).rpc_url, "http://rpc.local");
+    let cli = Cli::parse_from(["ecobottle", "stats"]);
+    assert_eq!(Settings::merge(&cli, Config::default()).rpc_url, DEFAULT_RPC_URL);
+}
+
+#[test]
+fn formats_weights_and_percentages() {
+    assert_eq!(display::kg(0), "0.000 kg");
+    assert_eq!(display::kg(12_345), "12.345 kg");
+    assert_eq!(display::percent(8_000), "80.00%");
+    assert_eq!(display::percent(125), "1.25%");
+}
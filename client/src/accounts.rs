@@ -0,0 +1,119 @@
+//! Deserialización tipada de las cuentas de EcoBottle
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_lang::{AccountDeserialize, Discriminator, Result};
+use ecobottle::{
+    Achievement, Brand, CertifiedCollector, CollectionRecord, CollectionRequest, CompressedPenalty, DepositRecord,
+    EprState, GlobalState, GuestClaimIntent, GuestDeposit, Leaderboard, LeaderboardScore, Organization, OrganizationMember,
+    ProcessingReceipt, Recycler, RedemptionRecord, Shipment, SkuRegistry, SerialBitmap, SmartContainer, UserProfile,
+    UsernameRecord,
+};
+
+/// Deserializa una cuenta de tipo conocido, validando su discriminador
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Cualquier cuenta del programa, identificada por su discriminador
+pub enum EcobottleAccount {
+    GlobalState(Box<GlobalState>),
+    UserProfile(Box<UserProfile>),
+    UsernameRecord(Box<UsernameRecord>),
+    SmartContainer(Box<SmartContainer>),
+    DepositRecord(Box<DepositRecord>),
+    CompressedPenalty(Box<CompressedPenalty>),
+    GuestDeposit(Box<GuestDeposit>),
+    GuestClaimIntent(Box<GuestClaimIntent>),
+    RedemptionRecord(Box<RedemptionRecord>),
+    CollectionRecord(Box<CollectionRecord>),
+    CollectionRequest(Box<CollectionRequest>),
+    CertifiedCollector(Box<CertifiedCollector>),
+    Recycler(Box<Recycler>),
+    Shipment(Box<Shipment>),
+    ProcessingReceipt(Box<ProcessingReceipt>),
+    Brand(Box<Brand>),
+    Organization(Box<Organization>),
+    OrganizationMember(Box<OrganizationMember>),
+    EprState(Box<EprState>),
+    SkuRegistry(Box<SkuRegistry>),
+    SerialBitmap(Box<SerialBitmap>),
+    Achievement(Box<Achievement>),
+    Leaderboard(Box<Leaderboard>),
+    LeaderboardScore(Box<LeaderboardScore>),
+}
+
+impl EcobottleAccount {
+    /// Deserializa los datos de una cuenta propiedad del programa
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let discriminator = data.get(..8).ok_or(AnchorErrorCode::AccountDiscriminatorNotFound)?;
+
+        macro_rules! try_decode {
+            ($($variant:ident),* $(,)?) => {
+                $(
+                    if discriminator == $variant::DISCRIMINATOR {
+                        return Ok(EcobottleAccount::$variant(Box::new(decode::<$variant>(data)?)));
+                    }
+                )*
+            };
+        }
+
+        try_decode! {
+            GlobalState,
+            UserProfile,
+            UsernameRecord,
+            SmartContainer,
+            DepositRecord,
+            CompressedPenalty,
+            GuestDeposit,
+            GuestClaimIntent,
+            RedemptionRecord,
+            CollectionRecord,
+            CollectionRequest,
+            CertifiedCollector,
+            Recycler,
+            Shipment,
+            ProcessingReceipt,
+            Brand,
+            Organization,
+            OrganizationMember,
+            EprState,
+            SkuRegistry,
+            SerialBitmap,
+            Achievement,
+            Leaderboard,
+            LeaderboardScore,
+        }
+
+        Err(AnchorErrorCode::AccountDiscriminatorMismatch.into())
+    }
+
+    /// Nombre del tipo de cuenta
+    pub fn name(&self) -> &'static str {
+        match self {
+            EcobottleAccount::GlobalState(_) => "GlobalState",
+            EcobottleAccount::UserProfile(_) => "UserProfile",
+            EcobottleAccount::UsernameRecord(_) => "UsernameRecord",
+            EcobottleAccount::SmartContainer(_) => "SmartContainer",
+            EcobottleAccount::DepositRecord(_) => "DepositRecord",
+            EcobottleAccount::CompressedPenalty(_) => "CompressedPenalty",
+            EcobottleAccount::GuestDeposit(_) => "GuestDeposit",
+            EcobottleAccount::GuestClaimIntent(_) => "GuestClaimIntent",
+            EcobottleAccount::RedemptionRecord(_) => "RedemptionRecord",
+            EcobottleAccount::CollectionRecord(_) => "CollectionRecord",
+            EcobottleAccount::CollectionRequest(_) => "CollectionRequest",
+            EcobottleAccount::CertifiedCollector(_) => "CertifiedCollector",
+            EcobottleAccount::Recycler(_) => "Recycler",
+            EcobottleAccount::Shipment(_) => "Shipment",
+            EcobottleAccount::ProcessingReceipt(_) => "ProcessingReceipt",
+            EcobottleAccount::Brand(_) => "Brand",
+            EcobottleAccount::Organization(_) => "Organization",
+            EcobottleAccount::OrganizationMember(_) => "OrganizationMember",
+            EcobottleAccount::EprState(_) => "EprState",
+            EcobottleAccount::SkuRegistry(_) => "SkuRegistry",
+            EcobottleAccount::SerialBitmap(_) => "SerialBitmap",
+            EcobottleAccount::Achievement(_) => "Achievement",
+            EcobottleAccount::Leaderboard(_) => "Leaderboard",
+            EcobottleAccount::LeaderboardScore(_) => "LeaderboardScore",
+        }
+    }
+}
@@ -0,0 +1,1288 @@
+//! Constructores de las instrucciones de EcoBottle
+//!
+//! Cada función arma la instrucción completa a partir de las wallets y los
+//! argumentos, derivando los PDAs y fijando los programas del sistema. Las
+//! instrucciones con cuentas opcionales se construyen con un builder.
+
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token, token, token_2022};
+use ecobottle::{
+    accounts, instruction as args, AchievementKind, BatchDeposit, DepositLeaf, Material, PeriodKind, ScannedItem,
+    SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID,
+};
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ecobottle::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn build_with_remaining(accounts: impl ToAccountMetas, data: impl InstructionData, remaining: Vec<AccountMeta>) -> Instruction {
+    let mut instruction = build(accounts, data);
+    instruction.accounts.extend(remaining);
+    instruction
+}
+
+fn writable(keys: &[Pubkey]) -> Vec<AccountMeta> {
+    keys.iter().map(|key| AccountMeta::new(*key, false)).collect()
+}
+
+fn update_config_accounts(authority: &Pubkey) -> accounts::UpdateConfig {
+    accounts::UpdateConfig {
+        global_state: pda::global_state(),
+        authority: *authority,
+    }
+}
+
+fn container_admin_accounts(authority: &Pubkey, container: &Pubkey) -> accounts::ToggleContainerStatus {
+    accounts::ToggleContainerStatus {
+        container: *container,
+        global_state: pda::global_state(),
+        authority: *authority,
+    }
+}
+
+// ============================================================================
+// Administración
+// ============================================================================
+
+pub fn initialize(authority: &Pubkey, ecoc_mint: &Pubkey, ecoc_per_kg: u64, min_deposit_weight: u64) -> Instruction {
+    let accounts = accounts::Initialize {
+        global_state: pda::global_state(),
+        authority: *authority,
+        ecoc_mint: *ecoc_mint,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::Initialize { ecoc_per_kg, min_deposit_weight })
+}
+
+pub fn update_config(
+    authority: &Pubkey,
+    new_ecoc_per_kg: Option<u64>,
+    new_min_weight: Option<u64>,
+    new_discrepancy_tolerance_bps: Option<u16>,
+    new_record_retention_secs: Option<i64>,
+    new_collection_sla_secs: Option<i64>,
+) -> Instruction {
+    let data = args::UpdateConfig {
+        new_ecoc_per_kg,
+        new_min_weight,
+        new_discrepancy_tolerance_bps,
+        new_record_retention_secs,
+        new_collection_sla_secs,
+    };
+    build(update_config_accounts(authority), data)
+}
+
+pub fn update_referral_config(
+    authority: &Pubkey,
+    new_bonus_per_kg: Option<u64>,
+    new_max_grams: Option<u64>,
+    new_cap_per_referrer: Option<u64>,
+) -> Instruction {
+    let data = args::UpdateReferralConfig {
+        new_bonus_per_kg,
+        new_max_grams,
+        new_cap_per_referrer,
+    };
+    build(update_config_accounts(authority), data)
+}
+
+pub fn update_collector_rewards(
+    authority: &Pubkey,
+    new_reward_per_kg: Option<u64>,
+    new_sla_bonus_bps: Option<u16>,
+    add_budget: Option<u64>,
+) -> Instruction {
+    let data = args::UpdateCollectorRewards {
+        new_reward_per_kg,
+        new_sla_bonus_bps,
+        add_budget,
+    };
+    build(update_config_accounts(authority), data)
+}
+
+pub fn update_emission_factor(authority: &Pubkey, material: Material, grams_co2e_per_kg: u32) -> Instruction {
+    build(update_config_accounts(authority), args::UpdateEmissionFactor { material, grams_co2e_per_kg })
+}
+
+pub fn set_user_suspension(authority: &Pubkey, user: &Pubkey, suspended: bool) -> Instruction {
+    let accounts = accounts::SetUserSuspension {
+        user_profile: pda::user_profile(user),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::SetUserSuspension { suspended })
+}
+
+pub fn migrate_global_state(authority: &Pubkey, ecoc_mint: &Pubkey) -> Instruction {
+    let accounts = accounts::MigrateGlobalState {
+        global_state: pda::global_state(),
+        ecoc_mint: *ecoc_mint,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::MigrateGlobalState {})
+}
+
+fn migrate_account_accounts(payer: &Pubkey, account: &Pubkey) -> accounts::MigrateAccount {
+    accounts::MigrateAccount {
+        account: *account,
+        payer: *payer,
+        system_program: system_program::ID,
+    }
+}
+
+/// Migra el perfil v0 de `user` y crea la reserva de su nombre
+pub fn migrate_user_profile(payer: &Pubkey, user: &Pubkey, username: &str) -> Instruction {
+    let accounts = accounts::MigrateUserProfile {
+        account: pda::user_profile(user),
+        username_record: pda::username(username),
+        owner: None,
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::MigrateUserProfile { username: username.to_string() })
+}
+
+/// Migra el perfil v0 de `user` con un nombre nuevo (el suyo ya está
+/// reservado); `user` debe firmar
+pub fn migrate_user_profile_renamed(payer: &Pubkey, user: &Pubkey, new_username: &str) -> Instruction {
+    let accounts = accounts::MigrateUserProfile {
+        account: pda::user_profile(user),
+        username_record: pda::username(new_username),
+        owner: Some(*user),
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::MigrateUserProfile { username: new_username.to_string() })
+}
+
+pub fn migrate_container(payer: &Pubkey, container_id: &str) -> Instruction {
+    build(migrate_account_accounts(payer, &pda::container(container_id)), args::MigrateContainer {})
+}
+
+pub fn migrate_deposit_record(payer: &Pubkey, deposit_record: &Pubkey) -> Instruction {
+    build(migrate_account_accounts(payer, deposit_record), args::MigrateDepositRecord {})
+}
+
+pub fn migrate_redemption_record(payer: &Pubkey, redemption_record: &Pubkey) -> Instruction {
+    build(migrate_account_accounts(payer, redemption_record), args::MigrateRedemptionRecord {})
+}
+
+/// Migra una recolección v0 (solo autoridad); `period_start` es la recolección
+/// anterior del contenedor
+pub fn migrate_collection_record(authority: &Pubkey, collection_record: &Pubkey, period_start: i64) -> Instruction {
+    let accounts = accounts::MigrateCollectionRecord {
+        account: *collection_record,
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::MigrateCollectionRecord { period_start })
+}
+
+// ============================================================================
+// Usuarios
+// ============================================================================
+
+/// Registra al usuario, opcionalmente con la wallet de quien lo invitó;
+/// `payer` paga la renta (la propia wallet o un relayer)
+pub fn register_user(payer: &Pubkey, user: &Pubkey, username: &str, referrer: Option<&Pubkey>) -> Instruction {
+    let accounts = accounts::RegisterUser {
+        user_profile: pda::user_profile(user),
+        username_record: pda::username(username),
+        global_state: pda::global_state(),
+        referrer_profile: referrer.map(pda::user_profile),
+        user: *user,
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    let data = args::RegisterUser {
+        username: username.to_string(),
+        referrer: referrer.copied(),
+    };
+    build(accounts, data)
+}
+
+/// Cambia el nombre de usuario; `current_username` libera su reserva salvo
+/// que el nuevo nombre solo cambie mayúsculas. `payer` es quien pagó la
+/// reserva actual: recupera su renta y paga la nueva
+pub fn update_username(payer: &Pubkey, user: &Pubkey, current_username: &str, new_username: &str) -> Instruction {
+    let old_username_record = pda::username(current_username);
+    let new_username_record = pda::username(new_username);
+    let accounts = accounts::UpdateUsername {
+        user_profile: pda::user_profile(user),
+        old_username_record,
+        new_username_record: (new_username_record != old_username_record).then_some(new_username_record),
+        user: *user,
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::UpdateUsername { new_username: new_username.to_string() })
+}
+
+/// Actualiza avatar y contenedor preferido (`Pubkey::default()` lo elimina)
+pub fn update_profile(user: &Pubkey, avatar_uri: Option<String>, preferred_container: Option<Pubkey>) -> Instruction {
+    let accounts = accounts::UpdateProfile {
+        user_profile: pda::user_profile(user),
+        preferred_container: preferred_container.filter(|container| *container != Pubkey::default()),
+        user: *user,
+    };
+    build(accounts, args::UpdateProfile { avatar_uri, preferred_container })
+}
+
+pub fn get_reputation(user: &Pubkey) -> Instruction {
+    let accounts = accounts::GetReputation {
+        user_profile: pda::user_profile(user),
+    };
+    build(accounts, args::GetReputation {})
+}
+
+pub fn get_user_summary(user: &Pubkey) -> Instruction {
+    let accounts = accounts::GetUserSummary {
+        global_state: pda::global_state(),
+        user_profile: pda::user_profile(user),
+    };
+    build(accounts, args::GetUserSummary {})
+}
+
+pub fn claim_achievement(user: &Pubkey, kind: AchievementKind) -> Instruction {
+    let accounts = accounts::ClaimAchievement {
+        global_state: pda::global_state(),
+        user_profile: pda::user_profile(user),
+        achievement: pda::achievement(user, kind),
+        badge_mint: pda::badge_mint(user, kind),
+        user_badge_account: pda::badge_account(user, kind),
+        user: *user,
+        token_2022_program: token_2022::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::ClaimAchievement { kind })
+}
+
+// ============================================================================
+// Contenedores
+// ============================================================================
+
+pub fn register_container(
+    authority: &Pubkey,
+    container_id: &str,
+    location: &str,
+    capacity_kg: u64,
+    material: Material,
+) -> Instruction {
+    let accounts = accounts::RegisterContainer {
+        container: pda::container(container_id),
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    let data = args::RegisterContainer {
+        container_id: container_id.to_string(),
+        location: location.to_string(),
+        capacity_kg,
+        material,
+    };
+    build(accounts, data)
+}
+
+pub fn toggle_container_status(authority: &Pubkey, container: &Pubkey) -> Instruction {
+    build(container_admin_accounts(authority, container), args::ToggleContainerStatus {})
+}
+
+pub fn set_fill_threshold(authority: &Pubkey, container: &Pubkey, fill_threshold_bps: u16) -> Instruction {
+    build(container_admin_accounts(authority, container), args::SetFillThreshold { fill_threshold_bps })
+}
+
+pub fn get_container_status(container: &Pubkey) -> Instruction {
+    let accounts = accounts::GetContainerStatus {
+        container: *container,
+    };
+    build(accounts, args::GetContainerStatus {})
+}
+
+// ============================================================================
+// Depósitos
+// ============================================================================
+
+/// Cotiza un depósito; `referrer` es la wallet del referente si el bono aún aplica
+pub fn quote_deposit_reward(
+    user: &Pubkey,
+    container: &Pubkey,
+    weight_grams: u64,
+    material: Material,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    let accounts = accounts::QuoteDepositReward {
+        global_state: pda::global_state(),
+        container: *container,
+        user_profile: pda::user_profile(user),
+        referrer_profile: referrer.map(pda::user_profile),
+    };
+    build(accounts, args::QuoteDepositReward { weight_grams, material })
+}
+
+/// Dónde queda constancia de un depósito
+#[derive(Clone, Copy, Debug)]
+pub enum DepositLog {
+    /// `DepositRecord` con el `GlobalState::total_deposits` actual como nonce
+    Record { nonce: u64 },
+    /// Hoja en el árbol de Merkle de depósitos
+    Compressed { tree: Pubkey },
+}
+
+/// Recibo de un depósito emitido por el contenedor: hash de la atestación y
+/// número de secuencia, que debe crecer en cada depósito del contenedor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Receipt {
+    pub hash: [u8; 32],
+    pub sequence: u64,
+}
+
+/// Builder de `process_deposit`
+#[derive(Clone, Debug)]
+pub struct ProcessDeposit {
+    user: Pubkey,
+    payer: Pubkey,
+    container: Pubkey,
+    user_token_account: Pubkey,
+    ecoc_mint: Pubkey,
+    weight_grams: u64,
+    receipt: Receipt,
+    log: DepositLog,
+    referrer: Option<(Pubkey, Pubkey)>,
+    brand: Option<Pubkey>,
+    container_authority: Option<Pubkey>,
+    leaderboards: Vec<Pubkey>,
+}
+
+impl ProcessDeposit {
+    pub fn new(
+        user: &Pubkey,
+        container: &Pubkey,
+        user_token_account: &Pubkey,
+        ecoc_mint: &Pubkey,
+        weight_grams: u64,
+        receipt: Receipt,
+        log: DepositLog,
+    ) -> Self {
+        Self {
+            user: *user,
+            payer: *user,
+            container: *container,
+            user_token_account: *user_token_account,
+            ecoc_mint: *ecoc_mint,
+            weight_grams,
+            receipt,
+            log,
+            referrer: None,
+            brand: None,
+            container_authority: None,
+            leaderboards: Vec::new(),
+        }
+    }
+
+    /// Renta y comisiones a cargo de un relayer u operador (por defecto, el usuario)
+    pub fn payer(mut self, payer: &Pubkey) -> Self {
+        self.payer = *payer;
+        self
+    }
+
+    /// Paga el bono de referido a `referrer` en su token account ECOC
+    pub fn referrer(mut self, referrer: &Pubkey, referrer_token_account: &Pubkey) -> Self {
+        self.referrer = Some((*referrer, *referrer_token_account));
+        self
+    }
+
+    /// Operador del contenedor que firma el depósito (por defecto, `payer`)
+    pub fn operator(mut self, container_authority: &Pubkey) -> Self {
+        self.container_authority = Some(*container_authority);
+        self
+    }
+
+    /// Atribuye el envase a la marca de `brand_owner`; la atribución la atesta
+    /// el operador del contenedor
+    pub fn brand(mut self, brand_owner: &Pubkey) -> Self {
+        self.brand = Some(*brand_owner);
+        self
+    }
+
+    /// Actualiza las tablas de clasificación globales indicadas
+    pub fn leaderboards(mut self, leaderboards: &[Pubkey]) -> Self {
+        self.leaderboards.extend_from_slice(leaderboards);
+        self
+    }
+
+    /// Actualiza una tabla del contenedor junto con la puntuación del usuario en ella
+    pub fn container_leaderboard(mut self, leaderboard: &Pubkey) -> Self {
+        self.leaderboards.push(*leaderboard);
+        self.leaderboards.push(pda::leaderboard_score(leaderboard, &self.user));
+        self
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let (deposit_record, deposit_tree) = match self.log {
+            DepositLog::Record { nonce } => (Some(pda::deposit(&self.user, nonce)), None),
+            DepositLog::Compressed { tree } => (None, Some(tree)),
+        };
+        let compressed = deposit_tree.is_some();
+        let accounts = accounts::ProcessDeposit {
+            global_state: pda::global_state(),
+            container: self.container,
+            user_profile: pda::user_profile(&self.user),
+            deposit_record,
+            deposit_tree,
+            compression_program: compressed.then_some(SPL_ACCOUNT_COMPRESSION_ID),
+            noop_program: compressed.then_some(SPL_NOOP_ID),
+            user: self.user,
+            payer: self.payer,
+            user_token_account: self.user_token_account,
+            ecoc_mint: self.ecoc_mint,
+            referrer_profile: self.referrer.map(|(referrer, _)| pda::user_profile(&referrer)),
+            referrer_token_account: self.referrer.map(|(_, token_account)| token_account),
+            brand: self.brand.map(|brand_owner| pda::brand(&brand_owner)),
+            container_authority: self.container_authority.unwrap_or(self.payer),
+            token_program: token::ID,
+            system_program: system_program::ID,
+        };
+        let data = args::ProcessDeposit {
+            weight_grams: self.weight_grams,
+            receipt_hash: self.receipt.hash,
+            receipt_sequence: self.receipt.sequence,
+        };
+        build_with_remaining(accounts, data, writable(&self.leaderboards))
+    }
+}
+
+/// Builder de `process_deposit_batch`; agrupa las cuentas por wallet y numera
+/// el par (perfil, token account) de cada depósito
+#[derive(Clone, Debug)]
+pub struct ProcessDepositBatch {
+    container_authority: Pubkey,
+    container: Pubkey,
+    ecoc_mint: Pubkey,
+    deposit_tree: Pubkey,
+    wallets: Vec<(Pubkey, Pubkey)>,
+    deposits: Vec<BatchDeposit>,
+}
+
+impl ProcessDepositBatch {
+    pub fn new(container_authority: &Pubkey, container: &Pubkey, ecoc_mint: &Pubkey, deposit_tree: &Pubkey) -> Self {
+        Self {
+            container_authority: *container_authority,
+            container: *container,
+            ecoc_mint: *ecoc_mint,
+            deposit_tree: *deposit_tree,
+            wallets: Vec::new(),
+            deposits: Vec::new(),
+        }
+    }
+
+    /// Añade un depósito de `user` acreditado en su token account ECOC y leído
+    /// por el contenedor en `read_at`
+    pub fn deposit(
+        mut self,
+        user: &Pubkey,
+        user_token_account: &Pubkey,
+        weight_grams: u64,
+        receipt: Receipt,
+        read_at: i64,
+    ) -> Self {
+        let user_index = self.wallet_index(user, user_token_account);
+        self.deposits.push(BatchDeposit {
+            user_index,
+            weight_grams,
+            receipt_hash: receipt.hash,
+            receipt_sequence: receipt.sequence,
+            timestamp: read_at,
+        });
+        self
+    }
+
+    /// Incluye a un referente que cobra bono por depósitos del lote
+    pub fn referrer(mut self, referrer: &Pubkey, referrer_token_account: &Pubkey) -> Self {
+        self.wallet_index(referrer, referrer_token_account);
+        self
+    }
+
+    fn wallet_index(&mut self, wallet: &Pubkey, token_account: &Pubkey) -> u8 {
+        let index = match self.wallets.iter().position(|(known, _)| known == wallet) {
+            Some(index) => index,
+            None => {
+                self.wallets.push((*wallet, *token_account));
+                self.wallets.len() - 1
+            }
+        };
+        index as u8
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let accounts = accounts::ProcessDepositBatch {
+            global_state: pda::global_state(),
+            container: self.container,
+            container_authority: self.container_authority,
+            deposit_tree: self.deposit_tree,
+            compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+            noop_program: SPL_NOOP_ID,
+            ecoc_mint: self.ecoc_mint,
+            token_program: token::ID,
+        };
+        let remaining = self
+            .wallets
+            .iter()
+            .flat_map(|(wallet, token_account)| writable(&[pda::user_profile(wallet), *token_account]))
+            .collect();
+        let data = args::ProcessDepositBatch {
+            deposits: self.deposits.clone(),
+        };
+        build_with_remaining(accounts, data, remaining)
+    }
+}
+
+/// Depósito de invitado firmado por el operador; `claim_commitment` es
+/// `ecobottle::guest_claim_commitment` del código entregado al invitado
+pub fn process_guest_deposit(
+    payer: &Pubkey,
+    container_authority: &Pubkey,
+    container: &Pubkey,
+    ecoc_mint: &Pubkey,
+    claim_commitment: [u8; 32],
+    weight_grams: u64,
+    receipt: Receipt,
+) -> Instruction {
+    let guest_deposit = pda::guest_deposit(&claim_commitment);
+    let accounts = accounts::ProcessGuestDeposit {
+        global_state: pda::global_state(),
+        container: *container,
+        container_authority: *container_authority,
+        payer: *payer,
+        guest_deposit,
+        escrow: pda::guest_escrow(&guest_deposit),
+        ecoc_mint: *ecoc_mint,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    let data = args::ProcessGuestDeposit {
+        claim_commitment,
+        weight_grams,
+        receipt_hash: receipt.hash,
+        receipt_sequence: receipt.sequence,
+    };
+    build(accounts, data)
+}
+
+/// Compromiso de `claimer` con el código de un depósito de invitado; el
+/// reclamo debe enviarse en un slot posterior
+pub fn commit_guest_claim(claimer: &Pubkey, claim_code: &[u8; 32]) -> Instruction {
+    let guest_deposit = pda::guest_deposit(&ecobottle::guest_claim_commitment(claim_code));
+    let accounts = accounts::CommitGuestClaim {
+        guest_deposit,
+        claim_intent: pda::guest_claim_intent(&guest_deposit, claimer),
+        claimer: *claimer,
+        system_program: system_program::ID,
+    };
+    let binding = ecobottle::guest_claim_binding(claim_code, claimer);
+    build(accounts, args::CommitGuestClaim { binding })
+}
+
+/// Reclamo de un depósito de invitado; con `has_profile` el depósito se suma
+/// al perfil de `claimer`. `payer` es quien pagó la renta del depósito
+pub fn claim_guest_deposit(
+    claimer: &Pubkey,
+    claimer_token_account: &Pubkey,
+    claim_code: [u8; 32],
+    payer: &Pubkey,
+    has_profile: bool,
+) -> Instruction {
+    let guest_deposit = pda::guest_deposit(&ecobottle::guest_claim_commitment(&claim_code));
+    let accounts = accounts::ClaimGuestDeposit {
+        global_state: pda::global_state(),
+        guest_deposit,
+        escrow: pda::guest_escrow(&guest_deposit),
+        claim_intent: pda::guest_claim_intent(&guest_deposit, claimer),
+        claimer: *claimer,
+        claimer_token_account: *claimer_token_account,
+        user_profile: has_profile.then(|| pda::user_profile(claimer)),
+        payer: *payer,
+        token_program: token::ID,
+    };
+    build(accounts, args::ClaimGuestDeposit { claim_code })
+}
+
+/// Devuelve a la tesorería un depósito de invitado vencido
+pub fn expire_guest_deposit(guest_deposit: &Pubkey, treasury_token_account: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = accounts::ExpireGuestDeposit {
+        global_state: pda::global_state(),
+        guest_deposit: *guest_deposit,
+        escrow: pda::guest_escrow(guest_deposit),
+        treasury_token_account: *treasury_token_account,
+        payer: *payer,
+        token_program: token::ID,
+    };
+    build(accounts, args::ExpireGuestDeposit {})
+}
+
+/// Cierra el compromiso de `claimer` sobre un depósito de invitado ya cerrado
+/// y le devuelve la renta; la puede enviar cualquiera
+pub fn close_guest_claim_intent(guest_deposit: &Pubkey, claimer: &Pubkey) -> Instruction {
+    let accounts = accounts::CloseGuestClaimIntent {
+        claim_intent: pda::guest_claim_intent(guest_deposit, claimer),
+        guest_deposit: *guest_deposit,
+        claimer: *claimer,
+    };
+    build(accounts, args::CloseGuestClaimIntent {})
+}
+
+/// Depósito por unidades; los bitmaps de serie se derivan de los artículos
+pub fn process_item_deposit(
+    user: &Pubkey,
+    container: &Pubkey,
+    container_authority: &Pubkey,
+    user_token_account: &Pubkey,
+    ecoc_mint: &Pubkey,
+    items: Vec<ScannedItem>,
+    receipt: Receipt,
+) -> Instruction {
+    let bitmaps: BTreeSet<(u64, u64)> = items
+        .iter()
+        .map(|item| (item.gtin, pda::serial_bucket(item.serial)))
+        .collect();
+    let bitmaps: Vec<Pubkey> = bitmaps
+        .into_iter()
+        .map(|(gtin, bucket)| pda::serial_bitmap(gtin, bucket))
+        .collect();
+
+    let accounts = accounts::ProcessItemDeposit {
+        global_state: pda::global_state(),
+        container: *container,
+        user_profile: pda::user_profile(user),
+        sku_registry: pda::sku_registry(),
+        user: *user,
+        container_authority: *container_authority,
+        user_token_account: *user_token_account,
+        ecoc_mint: *ecoc_mint,
+        token_program: token::ID,
+    };
+    let data = args::ProcessItemDeposit {
+        items,
+        receipt_hash: receipt.hash,
+        receipt_sequence: receipt.sequence,
+    };
+    build_with_remaining(accounts, data, writable(&bitmaps))
+}
+
+pub fn init_sku_registry(authority: &Pubkey) -> Instruction {
+    let accounts = accounts::InitSkuRegistry {
+        sku_registry: pda::sku_registry(),
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitSkuRegistry {})
+}
+
+pub fn upsert_sku(authority: &Pubkey, gtin: u64, deposit_value: u64, unit_weight_grams: u32, is_active: bool) -> Instruction {
+    let accounts = accounts::UpsertSku {
+        sku_registry: pda::sku_registry(),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    let data = args::UpsertSku {
+        gtin,
+        deposit_value,
+        unit_weight_grams,
+        is_active,
+    };
+    build(accounts, data)
+}
+
+pub fn init_serial_bitmap(payer: &Pubkey, gtin: u64, bucket: u64) -> Instruction {
+    let accounts = accounts::InitSerialBitmap {
+        serial_bitmap: pda::serial_bitmap(gtin, bucket),
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitSerialBitmap { gtin, bucket })
+}
+
+pub fn init_deposit_tree(authority: &Pubkey, deposit_tree: &Pubkey, max_depth: u32, max_buffer_size: u32) -> Instruction {
+    let accounts = accounts::InitDepositTree {
+        global_state: pda::global_state(),
+        deposit_tree: *deposit_tree,
+        authority: *authority,
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+        noop_program: SPL_NOOP_ID,
+    };
+    build(accounts, args::InitDepositTree { max_depth, max_buffer_size })
+}
+
+/// Verifica una hoja del árbol; `proof` son los nodos hermanos de la hoja a la raíz
+pub fn verify_deposit(deposit_tree: &Pubkey, root: [u8; 32], leaf: DepositLeaf, index: u32, proof: &[Pubkey]) -> Instruction {
+    let accounts = accounts::VerifyDeposit {
+        global_state: pda::global_state(),
+        deposit_tree: *deposit_tree,
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+    };
+    let proof = proof.iter().map(|node| AccountMeta::new_readonly(*node, false)).collect();
+    build_with_remaining(accounts, args::VerifyDeposit { root, leaf, index }, proof)
+}
+
+/// Canje de ECOC; `total_spent` es el `UserProfile::total_ecoc_spent` actual
+pub fn redeem_tokens(
+    user: &Pubkey,
+    user_token_account: &Pubkey,
+    ecoc_mint: &Pubkey,
+    total_spent: u64,
+    amount: u64,
+    product_id: &str,
+) -> Instruction {
+    let accounts = accounts::RedeemTokens {
+        user_profile: pda::user_profile(user),
+        redemption_record: pda::redemption(user, total_spent),
+        user: *user,
+        user_token_account: *user_token_account,
+        ecoc_mint: *ecoc_mint,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    let data = args::RedeemTokens {
+        amount,
+        product_id: product_id.to_string(),
+    };
+    build(accounts, data)
+}
+
+// ============================================================================
+// Recolección
+// ============================================================================
+
+/// Builder de `collect_container`
+#[derive(Clone, Debug)]
+pub struct CollectContainer {
+    collector: Pubkey,
+    container: Pubkey,
+    total_deposits: u64,
+    collection_request: Option<Pubkey>,
+    reward: Option<(Pubkey, Pubkey)>,
+}
+
+impl CollectContainer {
+    /// `total_deposits` es el `SmartContainer::total_deposits` actual
+    pub fn new(collector: &Pubkey, container: &Pubkey, total_deposits: u64) -> Self {
+        Self {
+            collector: *collector,
+            container: *container,
+            total_deposits,
+            collection_request: None,
+            reward: None,
+        }
+    }
+
+    /// Completa la solicitud abierta del contenedor
+    pub fn request(mut self, collection_request: &Pubkey) -> Self {
+        self.collection_request = Some(*collection_request);
+        self
+    }
+
+    /// Cobra el incentivo de recolector certificado
+    pub fn reward(mut self, ecoc_mint: &Pubkey, collector_token_account: &Pubkey) -> Self {
+        self.reward = Some((*ecoc_mint, *collector_token_account));
+        self
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let accounts = accounts::CollectContainer {
+            global_state: pda::global_state(),
+            container: self.container,
+            collection_record: pda::collection(&self.container, self.total_deposits),
+            collection_request: self.collection_request,
+            certified_collector: self.reward.map(|_| pda::collector(&self.collector)),
+            ecoc_mint: self.reward.map(|(mint, _)| mint),
+            collector_token_account: self.reward.map(|(_, token_account)| token_account),
+            collector: self.collector,
+            token_program: self.reward.map(|_| token::ID),
+            system_program: system_program::ID,
+        };
+        build(accounts, args::CollectContainer {})
+    }
+}
+
+pub fn register_collector(authority: &Pubkey, collector: &Pubkey, name: &str) -> Instruction {
+    let accounts = accounts::RegisterCollector {
+        certified_collector: pda::collector(collector),
+        collector: *collector,
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterCollector { name: name.to_string() })
+}
+
+pub fn set_collector_status(authority: &Pubkey, collector: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = accounts::SetCollectorStatus {
+        certified_collector: pda::collector(collector),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::SetCollectorStatus { is_active })
+}
+
+/// Abre una solicitud; `index` es el `SmartContainer::collection_requests` actual
+pub fn request_collection(payer: &Pubkey, container: &Pubkey, index: u64) -> Instruction {
+    let accounts = accounts::RequestCollection {
+        container: *container,
+        collection_request: pda::collection_request(container, index),
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RequestCollection {})
+}
+
+pub fn claim_collection_request(collector: &Pubkey, collection_request: &Pubkey) -> Instruction {
+    let accounts = accounts::ClaimCollectionRequest {
+        global_state: pda::global_state(),
+        collection_request: *collection_request,
+        certified_collector: pda::collector(collector),
+        collector: *collector,
+    };
+    build(accounts, args::ClaimCollectionRequest {})
+}
+
+pub fn reconcile_collection(authority: &Pubkey, collection_record: &Pubkey, measured_weight_grams: u64) -> Instruction {
+    let accounts = accounts::ReconcileCollection {
+        global_state: pda::global_state(),
+        collection_record: *collection_record,
+        authority: *authority,
+    };
+    build(accounts, args::ReconcileCollection { measured_weight_grams })
+}
+
+pub fn penalize_deposit(authority: &Pubkey, collection_record: &Pubkey, deposit_record: &Pubkey, user: &Pubkey) -> Instruction {
+    let accounts = accounts::PenalizeDeposit {
+        global_state: pda::global_state(),
+        collection_record: *collection_record,
+        deposit_record: *deposit_record,
+        user_profile: pda::user_profile(user),
+        authority: *authority,
+    };
+    build(accounts, args::PenalizeDeposit {})
+}
+
+/// Penaliza un depósito comprimido; `proof` son los nodos de la prueba de Merkle
+pub fn penalize_compressed_deposit(
+    authority: &Pubkey,
+    collection_record: &Pubkey,
+    deposit_tree: &Pubkey,
+    root: [u8; 32],
+    leaf: DepositLeaf,
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let accounts = accounts::PenalizeCompressedDeposit {
+        global_state: pda::global_state(),
+        collection_record: *collection_record,
+        deposit_tree: *deposit_tree,
+        penalty: pda::compressed_penalty(deposit_tree, index),
+        user_profile: pda::user_profile(&leaf.user),
+        authority: *authority,
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+        system_program: system_program::ID,
+    };
+    let proof = proof.iter().map(|node| AccountMeta::new_readonly(*node, false)).collect();
+    build_with_remaining(accounts, args::PenalizeCompressedDeposit { root, leaf, index }, proof)
+}
+
+// ============================================================================
+// Cadena de custodia e impacto
+// ============================================================================
+
+pub fn register_recycler(authority: &Pubkey, recycler: &Pubkey, name: &str) -> Instruction {
+    let accounts = accounts::RegisterRecycler {
+        recycler: pda::recycler(recycler),
+        recycler_wallet: *recycler,
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterRecycler { name: name.to_string() })
+}
+
+pub fn set_recycler_status(authority: &Pubkey, recycler: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = accounts::SetRecyclerStatus {
+        recycler: pda::recycler(recycler),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::SetRecyclerStatus { is_active })
+}
+
+/// Despacha recolecciones; `index` es el `CertifiedCollector::total_shipments` actual
+pub fn create_shipment(collector: &Pubkey, recycler: &Pubkey, index: u64, collections: &[Pubkey]) -> Instruction {
+    let accounts = accounts::CreateShipment {
+        certified_collector: pda::collector(collector),
+        recycler: pda::recycler(recycler),
+        shipment: pda::shipment(collector, index),
+        collector: *collector,
+        system_program: system_program::ID,
+    };
+    build_with_remaining(accounts, args::CreateShipment {}, writable(collections))
+}
+
+pub fn confirm_shipment_receipt(
+    recycler: &Pubkey,
+    shipment: &Pubkey,
+    received_weight_grams: u64,
+    output_product: &str,
+    output_grams: u64,
+) -> Instruction {
+    let accounts = accounts::ConfirmShipmentReceipt {
+        shipment: *shipment,
+        recycler: pda::recycler(recycler),
+        processing_receipt: pda::processing_receipt(shipment),
+        recycler_wallet: *recycler,
+        system_program: system_program::ID,
+    };
+    let data = args::ConfirmShipmentReceipt {
+        received_weight_grams,
+        output_product: output_product.to_string(),
+        output_grams,
+    };
+    build(accounts, data)
+}
+
+pub fn init_impact_mint(authority: &Pubkey) -> Instruction {
+    let accounts = accounts::InitImpactMint {
+        global_state: pda::global_state(),
+        impact_mint: pda::impact_mint(),
+        authority: *authority,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitImpactMint {})
+}
+
+pub fn issue_impact_certificate(
+    authority: &Pubkey,
+    processing_receipt: &Pubkey,
+    recipient_token_account: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::IssueImpactCertificate {
+        global_state: pda::global_state(),
+        processing_receipt: *processing_receipt,
+        impact_mint: pda::impact_mint(),
+        recipient_token_account: *recipient_token_account,
+        authority: *authority,
+        token_program: token::ID,
+    };
+    build(accounts, args::IssueImpactCertificate {})
+}
+
+pub fn retire_impact_certificate(owner: &Pubkey, owner_token_account: &Pubkey, amount: u64, beneficiary: &str) -> Instruction {
+    let accounts = accounts::RetireImpactCertificate {
+        global_state: pda::global_state(),
+        impact_mint: pda::impact_mint(),
+        owner_token_account: *owner_token_account,
+        owner: *owner,
+        token_program: token::ID,
+    };
+    let data = args::RetireImpactCertificate {
+        amount,
+        beneficiary: beneficiary.to_string(),
+    };
+    build(accounts, data)
+}
+
+// ============================================================================
+// Marcas y créditos EPR
+// ============================================================================
+
+pub fn register_brand(authority: &Pubkey, brand_owner: &Pubkey, name: &str) -> Instruction {
+    let accounts = accounts::RegisterBrand {
+        brand: pda::brand(brand_owner),
+        brand_owner: *brand_owner,
+        global_state: pda::global_state(),
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterBrand { name: name.to_string() })
+}
+
+pub fn set_brand_status(authority: &Pubkey, brand_owner: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = accounts::SetBrandStatus {
+        brand: pda::brand(brand_owner),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::SetBrandStatus { is_active })
+}
+
+pub fn init_epr_credits(authority: &Pubkey, price_per_kg: u64, treasury: &Pubkey) -> Instruction {
+    let accounts = accounts::InitEprCredits {
+        epr_state: pda::epr_state(),
+        epr_mint: pda::epr_mint(),
+        epr_vault: pda::epr_vault(),
+        global_state: pda::global_state(),
+        authority: *authority,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitEprCredits { price_per_kg, treasury: *treasury })
+}
+
+pub fn update_epr_config(authority: &Pubkey, new_price_per_kg: Option<u64>, new_treasury: Option<Pubkey>) -> Instruction {
+    let accounts = accounts::UpdateEprConfig {
+        epr_state: pda::epr_state(),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::UpdateEprConfig { new_price_per_kg, new_treasury })
+}
+
+/// Emite a la bóveda los créditos de lo recuperado por la marca de
+/// `brand_owner` en `material`; la puede enviar cualquiera
+pub fn mint_epr_credits(brand_owner: &Pubkey, material: Material) -> Instruction {
+    let accounts = accounts::MintEprCredits {
+        brand: pda::brand(brand_owner),
+        epr_state: pda::epr_state(),
+        global_state: pda::global_state(),
+        epr_mint: pda::epr_mint(),
+        epr_vault: pda::epr_vault(),
+        token_program: token::ID,
+    };
+    build(accounts, args::MintEprCredits { material })
+}
+
+pub fn buy_epr_credits(
+    brand_owner: &Pubkey,
+    brand_token_account: &Pubkey,
+    treasury: &Pubkey,
+    amount_grams: u64,
+    material: Material,
+) -> Instruction {
+    let accounts = accounts::BuyEprCredits {
+        brand: pda::brand(brand_owner),
+        epr_state: pda::epr_state(),
+        global_state: pda::global_state(),
+        epr_mint: pda::epr_mint(),
+        epr_vault: pda::epr_vault(),
+        brand_token_account: *brand_token_account,
+        treasury: *treasury,
+        brand_owner: *brand_owner,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::BuyEprCredits { amount_grams, material })
+}
+
+pub fn retire_epr_credits(brand_owner: &Pubkey, brand_token_account: &Pubkey, amount_grams: u64) -> Instruction {
+    let accounts = accounts::RetireEprCredits {
+        brand: pda::brand(brand_owner),
+        epr_state: pda::epr_state(),
+        epr_mint: pda::epr_mint(),
+        brand_token_account: *brand_token_account,
+        brand_owner: *brand_owner,
+        token_program: token::ID,
+    };
+    build(accounts, args::RetireEprCredits { amount_grams })
+}
+
+// ============================================================================
+// Organizaciones y miembros custodiados
+// ============================================================================
+
+pub fn register_organization(authority: &Pubkey, admin: &Pubkey, ecoc_mint: &Pubkey, name: &str) -> Instruction {
+    let organization = pda::organization(admin);
+    let accounts = accounts::RegisterOrganization {
+        organization,
+        vault: pda::organization_vault(&organization),
+        admin: *admin,
+        global_state: pda::global_state(),
+        ecoc_mint: *ecoc_mint,
+        authority: *authority,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterOrganization { name: name.to_string() })
+}
+
+pub fn set_organization_status(authority: &Pubkey, admin: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = accounts::SetOrganizationStatus {
+        organization: pda::organization(admin),
+        global_state: pda::global_state(),
+        authority: *authority,
+    };
+    build(accounts, args::SetOrganizationStatus { is_active })
+}
+
+/// Alta de un miembro; `payer` paga su renta (el administrador o un relayer)
+pub fn register_member(payer: &Pubkey, admin: &Pubkey, member_id_hash: [u8; 32]) -> Instruction {
+    let organization = pda::organization(admin);
+    let accounts = accounts::RegisterMember {
+        organization,
+        member: pda::organization_member(&organization, &member_id_hash),
+        admin: *admin,
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterMember { member_id_hash })
+}
+
+/// Depósito de un miembro atestado por el operador del contenedor
+pub fn process_member_deposit(
+    container_authority: &Pubkey,
+    container: &Pubkey,
+    ecoc_mint: &Pubkey,
+    admin: &Pubkey,
+    member_id_hash: [u8; 32],
+    weight_grams: u64,
+    receipt: Receipt,
+) -> Instruction {
+    let organization = pda::organization(admin);
+    let accounts = accounts::ProcessMemberDeposit {
+        global_state: pda::global_state(),
+        container: *container,
+        container_authority: *container_authority,
+        organization,
+        member: pda::organization_member(&organization, &member_id_hash),
+        vault: pda::organization_vault(&organization),
+        ecoc_mint: *ecoc_mint,
+        token_program: token::ID,
+    };
+    let data = args::ProcessMemberDeposit {
+        weight_grams,
+        receipt_hash: receipt.hash,
+        receipt_sequence: receipt.sequence,
+    };
+    build(accounts, data)
+}
+
+/// Vincula `wallet` al miembro y le transfiere su saldo; con `has_profile`
+/// los totales del miembro se suman al perfil de la wallet
+pub fn graduate_member(
+    admin: &Pubkey,
+    member_id_hash: [u8; 32],
+    wallet: &Pubkey,
+    wallet_token_account: &Pubkey,
+    has_profile: bool,
+) -> Instruction {
+    let organization = pda::organization(admin);
+    let accounts = accounts::GraduateMember {
+        global_state: pda::global_state(),
+        organization,
+        member: pda::organization_member(&organization, &member_id_hash),
+        vault: pda::organization_vault(&organization),
+        admin: *admin,
+        wallet: *wallet,
+        wallet_token_account: *wallet_token_account,
+        user_profile: has_profile.then(|| pda::user_profile(wallet)),
+        token_program: token::ID,
+    };
+    build(accounts, args::GraduateMember {})
+}
+
+// ============================================================================
+// Clasificaciones
+// ============================================================================
+
+pub fn create_leaderboard(
+    authority: &Pubkey,
+    ecoc_mint: &Pubkey,
+    period_kind: PeriodKind,
+    period_index: u64,
+    scope: &Pubkey,
+) -> Instruction {
+    let leaderboard = pda::leaderboard(period_kind, period_index, scope);
+    let accounts = accounts::CreateLeaderboard {
+        global_state: pda::global_state(),
+        leaderboard,
+        prize_vault: pda::leaderboard_vault(&leaderboard),
+        ecoc_mint: *ecoc_mint,
+        authority: *authority,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    let data = args::CreateLeaderboard {
+        period_kind,
+        period_index,
+        scope: *scope,
+    };
+    build(accounts, data)
+}
+
+pub fn fund_leaderboard(funder: &Pubkey, funder_token_account: &Pubkey, leaderboard: &Pubkey, amount: u64) -> Instruction {
+    let accounts = accounts::FundLeaderboard {
+        leaderboard: *leaderboard,
+        prize_vault: pda::leaderboard_vault(leaderboard),
+        funder_token_account: *funder_token_account,
+        funder: *funder,
+        token_program: token::ID,
+    };
+    build(accounts, args::FundLeaderboard { amount })
+}
+
+/// Cierra el periodo; `winner_token_accounts` en el orden de la clasificación
+/// Sin ganadores, el premio va a `treasury_token_account`
+pub fn close_period(leaderboard: &Pubkey, treasury_token_account: &Pubkey, winner_token_accounts: &[Pubkey]) -> Instruction {
+    let accounts = accounts::ClosePeriod {
+        global_state: pda::global_state(),
+        leaderboard: *leaderboard,
+        prize_vault: pda::leaderboard_vault(leaderboard),
+        treasury_token_account: *treasury_token_account,
+        token_program: token::ID,
+    };
+    build_with_remaining(accounts, args::ClosePeriod {}, writable(winner_token_accounts))
+}
+
+// ============================================================================
+// Archivado de registros
+// ============================================================================
+
+/// Archiva un depósito; `user` es el dueño, `payer` quien pagó su renta (y firma)
+/// y `collection_record` la recolección conciliada que lo cubre
+pub fn close_deposit_record(
+    user: &Pubkey,
+    deposit_record: &Pubkey,
+    collection_record: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::CloseDepositRecord {
+        global_state: pda::global_state(),
+        user_profile: pda::user_profile(user),
+        deposit_record: *deposit_record,
+        collection_record: *collection_record,
+        payer: *payer,
+    };
+    build(accounts, args::CloseDepositRecord {})
+}
+
+/// Archiva un canje; `user` es el dueño y `payer` quien pagó su renta (y firma)
+pub fn close_redemption_record(user: &Pubkey, redemption_record: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = accounts::CloseRedemptionRecord {
+        global_state: pda::global_state(),
+        user_profile: pda::user_profile(user),
+        redemption_record: *redemption_record,
+        payer: *payer,
+    };
+    build(accounts, args::CloseRedemptionRecord {})
+}
+
+/// Archiva una recolección conciliada y enviada; `payer` es quien pagó su renta (y firma)
+pub fn close_collection_record(container: &Pubkey, collection_record: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = accounts::CloseCollectionRecord {
+        global_state: pda::global_state(),
+        container: *container,
+        collection_record: *collection_record,
+        payer: *payer,
+    };
+    build(accounts, args::CloseCollectionRecord {})
+}
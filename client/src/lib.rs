@@ -0,0 +1,22 @@
+//! Cliente Rust de EcoBottle
+//!
+//! Construye instrucciones, deriva PDAs y deserializa cuentas a partir de los
+//! tipos que exporta el propio programa (`ecobottle::accounts`,
+//! `ecobottle::instruction`), de modo que los servicios no repliquen semillas
+//! ni discriminadores a mano.
+//!
+//! ```
+//! use anchor_lang::prelude::Pubkey;
+//! use ecobottle_client::{instructions, pda};
+//!
+//! let wallet = Pubkey::new_unique();
+//! let instruction = instructions::register_user(&wallet, &wallet, "ana", None);
+//! assert_eq!(instruction.accounts[0].pubkey, pda::user_profile(&wallet));
+//! ```
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use ecobottle;
+pub use ecobottle::ID;
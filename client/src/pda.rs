@@ -0,0 +1,184 @@
+//! Derivación de las direcciones (PDA) de EcoBottle
+//!
+//! Las semillas replican las restricciones `seeds = [...]` del programa. Las
+//! cuentas indexadas por un contador (depósitos, canjes, recolecciones,
+//! solicitudes y envíos) reciben el valor del contador en el momento de la
+//! instrucción, que el llamador lee de la cuenta correspondiente.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use ecobottle::{AchievementKind, PeriodKind};
+
+/// PDA y bump para las semillas indicadas
+pub fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ecobottle::ID)
+}
+
+fn address(seeds: &[&[u8]]) -> Pubkey {
+    find(seeds).0
+}
+
+/// Estado global del programa
+pub fn global_state() -> Pubkey {
+    address(&[b"global_state"])
+}
+
+/// Perfil de un usuario
+pub fn user_profile(user: &Pubkey) -> Pubkey {
+    address(&[b"user_profile", user.as_ref()])
+}
+
+/// Reserva de un nombre de usuario (normalizado igual que en el programa)
+pub fn username(username: &str) -> Pubkey {
+    address(&[b"username", ecobottle::username_seed(username).as_ref()])
+}
+
+/// Contenedor por su ID (máximo 32 bytes, como toda semilla)
+pub fn container(container_id: &str) -> Pubkey {
+    address(&[b"container", container_id.as_bytes()])
+}
+
+/// Registro de depósito; `nonce` es `GlobalState::total_deposits` al depositar
+pub fn deposit(user: &Pubkey, nonce: u64) -> Pubkey {
+    address(&[b"deposit", user.as_ref(), &nonce.to_le_bytes()])
+}
+
+/// Marca de penalización de la hoja `leaf_index` del árbol de depósitos
+pub fn compressed_penalty(deposit_tree: &Pubkey, leaf_index: u32) -> Pubkey {
+    address(&[b"compressed_penalty", deposit_tree.as_ref(), &leaf_index.to_le_bytes()])
+}
+
+/// Depósito de invitado por el compromiso de su código de reclamo
+pub fn guest_deposit(claim_commitment: &[u8; 32]) -> Pubkey {
+    address(&[b"guest_deposit", claim_commitment.as_ref()])
+}
+
+/// Bóveda con la recompensa de un depósito de invitado
+pub fn guest_escrow(guest_deposit: &Pubkey) -> Pubkey {
+    address(&[b"guest_escrow", guest_deposit.as_ref()])
+}
+
+/// Compromiso de `claimer` para reclamar un depósito de invitado
+pub fn guest_claim_intent(guest_deposit: &Pubkey, claimer: &Pubkey) -> Pubkey {
+    address(&[b"guest_claim", guest_deposit.as_ref(), claimer.as_ref()])
+}
+
+/// Registro de canje; `total_spent` es `UserProfile::total_ecoc_spent` al canjear
+pub fn redemption(user: &Pubkey, total_spent: u64) -> Pubkey {
+    address(&[b"redemption", user.as_ref(), &total_spent.to_le_bytes()])
+}
+
+/// Registro de recolección; `total_deposits` es `SmartContainer::total_deposits` al recolectar
+pub fn collection(container: &Pubkey, total_deposits: u64) -> Pubkey {
+    address(&[b"collection", container.as_ref(), &total_deposits.to_le_bytes()])
+}
+
+/// Solicitud de recolección; `index` es `SmartContainer::collection_requests`
+pub fn collection_request(container: &Pubkey, index: u64) -> Pubkey {
+    address(&[b"collection_request", container.as_ref(), &index.to_le_bytes()])
+}
+
+/// Certificación de un recolector
+pub fn collector(collector: &Pubkey) -> Pubkey {
+    address(&[b"collector", collector.as_ref()])
+}
+
+/// Planta recicladora
+pub fn recycler(recycler: &Pubkey) -> Pubkey {
+    address(&[b"recycler", recycler.as_ref()])
+}
+
+/// Envío a planta; `index` es `CertifiedCollector::total_shipments`
+pub fn shipment(collector: &Pubkey, index: u64) -> Pubkey {
+    address(&[b"shipment", collector.as_ref(), &index.to_le_bytes()])
+}
+
+/// Recibo de procesamiento de un envío
+pub fn processing_receipt(shipment: &Pubkey) -> Pubkey {
+    address(&[b"processing_receipt", shipment.as_ref()])
+}
+
+/// Marca registrada por su wallet
+pub fn brand(owner: &Pubkey) -> Pubkey {
+    address(&[b"brand", owner.as_ref()])
+}
+
+/// Organización por la wallet de su administrador
+pub fn organization(admin: &Pubkey) -> Pubkey {
+    address(&[b"organization", admin.as_ref()])
+}
+
+/// Bóveda con los ECOC custodiados de una organización
+pub fn organization_vault(organization: &Pubkey) -> Pubkey {
+    address(&[b"organization_vault", organization.as_ref()])
+}
+
+/// Miembro de una organización por el hash de su identificador
+pub fn organization_member(organization: &Pubkey, member_id_hash: &[u8; 32]) -> Pubkey {
+    address(&[b"member", organization.as_ref(), member_id_hash.as_ref()])
+}
+
+/// Configuración de créditos EPR
+pub fn epr_state() -> Pubkey {
+    address(&[b"epr_state"])
+}
+
+/// Mint de créditos EPR
+pub fn epr_mint() -> Pubkey {
+    address(&[b"epr_mint"])
+}
+
+/// Bóveda de créditos EPR de la tesorería
+pub fn epr_vault() -> Pubkey {
+    address(&[b"epr_vault"])
+}
+
+/// Mint de certificados de impacto
+pub fn impact_mint() -> Pubkey {
+    address(&[b"impact_mint"])
+}
+
+/// Registro de SKUs
+pub fn sku_registry() -> Pubkey {
+    address(&[b"sku_registry"])
+}
+
+/// Bitmap de números de serie de un GTIN
+pub fn serial_bitmap(gtin: u64, bucket: u64) -> Pubkey {
+    address(&[b"serials", &gtin.to_le_bytes(), &bucket.to_le_bytes()])
+}
+
+/// Bloque del bitmap que contiene un número de serie
+pub fn serial_bucket(serial: u64) -> u64 {
+    serial / ecobottle::SERIAL_BITMAP_BITS
+}
+
+/// Tabla de clasificación de un periodo (`scope` = `Pubkey::default()` para la global)
+pub fn leaderboard(period_kind: PeriodKind, period_index: u64, scope: &Pubkey) -> Pubkey {
+    address(&[b"leaderboard", &[period_kind as u8], &period_index.to_le_bytes(), scope.as_ref()])
+}
+
+/// Bóveda del premio de una tabla de clasificación
+pub fn leaderboard_vault(leaderboard: &Pubkey) -> Pubkey {
+    address(&[b"leaderboard_vault", leaderboard.as_ref()])
+}
+
+/// Puntuación de un usuario en una tabla por contenedor
+pub fn leaderboard_score(leaderboard: &Pubkey, user: &Pubkey) -> Pubkey {
+    address(&[b"leaderboard_score", leaderboard.as_ref(), user.as_ref()])
+}
+
+/// Logro de un usuario
+pub fn achievement(user: &Pubkey, kind: AchievementKind) -> Pubkey {
+    address(&[b"achievement", user.as_ref(), &[kind as u8]])
+}
+
+/// Mint de la insignia de un logro
+pub fn badge_mint(user: &Pubkey, kind: AchievementKind) -> Pubkey {
+    address(&[b"badge_mint", user.as_ref(), &[kind as u8]])
+}
+
+/// Cuenta asociada Token-2022 que recibe la insignia
+pub fn badge_account(user: &Pubkey, kind: AchievementKind) -> Pubkey {
+    get_associated_token_address_with_program_id(user, &badge_mint(user, kind), &anchor_spl::token_2022::ID)
+}
@@ -0,0 +1,235 @@
+//! Flujo completo construido con `ecobottle-client` sobre una SVM en proceso
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AnchorDeserialize;
+use anchor_spl::token::spl_token;
+use ecobottle::{DepositRecord, GlobalState, Material, ScannedItem, SmartContainer, UserProfile};
+use ecobottle_client::accounts::{decode, EcobottleAccount};
+use ecobottle_client::instructions::{self, CollectContainer, DepositLog, ProcessDeposit, ProcessDepositBatch, Receipt};
+use ecobottle_client::pda;
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction as system_instruction;
+
+/// `ProgramTest` con el `ecobottle.so` compilado (`cargo build-sbf`, en
+/// `target/deploy` o en `SBF_OUT_DIR`)
+fn program_test() -> ProgramTest {
+    let dir = std::env::var("SBF_OUT_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy").to_string());
+    let elf = std::fs::read(std::path::Path::new(&dir).join("ecobottle.so"))
+        .expect("no se encontró ecobottle.so: compila el programa con `cargo build-sbf`");
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        ecobottle::ID,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: bpf_loader::ID,
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    program_test
+}
+
+struct Client {
+    banks_client: BanksClient,
+    payer: Keypair,
+}
+
+impl Client {
+    async fn start() -> Self {
+        let (banks_client, payer, _) = program_test().start().await;
+        Self { banks_client, payer }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.payer.pubkey()), &all_signers, blockhash);
+        self.banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    async fn data(&mut self, key: &Pubkey) -> Vec<u8> {
+        self.banks_client.get_account(*key).await.unwrap().unwrap().data
+    }
+
+    async fn create_account(&mut self, space: usize, owner: &Pubkey, init: impl FnOnce(&Pubkey) -> Instruction) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                owner,
+            ),
+            init(&account.pubkey()),
+        ];
+        self.send(&instructions, &[&account]).await;
+        account.pubkey()
+    }
+
+    async fn create_mint(&mut self, authority: &Pubkey) -> Pubkey {
+        self.create_account(spl_token::state::Mint::LEN, &spl_token::ID, |mint| {
+            spl_token::instruction::initialize_mint2(&spl_token::ID, mint, authority, None, 6).unwrap()
+        })
+        .await
+    }
+
+    async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        self.create_account(spl_token::state::Account::LEN, &spl_token::ID, |account| {
+            spl_token::instruction::initialize_account3(&spl_token::ID, account, mint, owner).unwrap()
+        })
+        .await
+    }
+}
+
+#[tokio::test]
+async fn builders_drive_deposit_and_collection() {
+    let mut client = Client::start().await;
+    let authority = client.payer.pubkey();
+    let ecoc_mint = client.create_mint(&pda::global_state()).await;
+
+    let user = Keypair::new();
+    let container = pda::container("EB-001");
+    client
+        .send(
+            &[
+                instructions::initialize(&authority, &ecoc_mint, 1_000_000, 100),
+                instructions::register_container(&authority, "EB-001", "Plaza Mayor", 50, Material::Pet),
+                system_instruction::transfer(&authority, &user.pubkey(), 1_000_000_000),
+            ],
+            &[],
+        )
+        .await;
+    client.send(&[instructions::register_user(&user.pubkey(), &user.pubkey(), "ana", None)], &[&user]).await;
+    let user_token_account = client.create_token_account(&ecoc_mint, &user.pubkey()).await;
+
+    // Las vistas se pueden incluir en una transacción sin modificar estado
+    client
+        .send(
+            &[
+                instructions::quote_deposit_reward(&user.pubkey(), &container, 2_000, Material::Pet, None),
+                instructions::get_user_summary(&user.pubkey()),
+                instructions::get_container_status(&container),
+            ],
+            &[],
+        )
+        .await;
+
+    let deposit = ProcessDeposit::new(
+        &user.pubkey(),
+        &container,
+        &user_token_account,
+        &ecoc_mint,
+        2_000,
+        Receipt { hash: [7; 32], sequence: 1 },
+        DepositLog::Record { nonce: 0 },
+    )
+    .operator(&authority);
+    client.send(&[deposit.instruction()], &[&user]).await;
+
+    let record: DepositRecord = decode(&client.data(&pda::deposit(&user.pubkey(), 0)).await).unwrap();
+    assert_eq!(record.weight_grams, 2_000);
+    assert_eq!(record.ecoc_reward, 2_000_000);
+
+    match EcobottleAccount::decode(&client.data(&pda::user_profile(&user.pubkey())).await).unwrap() {
+        EcobottleAccount::UserProfile(profile) => {
+            assert_eq!(profile.username, "ana");
+            assert_eq!(profile.total_pet_weight, 2_000);
+        }
+        other => panic!("se esperaba UserProfile, se obtuvo {}", other.name()),
+    }
+
+    let state: SmartContainer = decode(&client.data(&container).await).unwrap();
+    let collect = CollectContainer::new(&authority, &container, state.total_deposits);
+    client.send(&[collect.instruction()], &[]).await;
+
+    let state: SmartContainer = decode(&client.data(&container).await).unwrap();
+    assert_eq!(state.current_weight, 0);
+    let global_state: GlobalState = decode(&client.data(&pda::global_state()).await).unwrap();
+    assert_eq!(global_state.total_deposits, 1);
+    assert!(client
+        .banks_client
+        .get_account(pda::collection(&container, 1))
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn item_deposit_derives_each_serial_bitmap_once() {
+    let user = Pubkey::new_unique();
+    let items = vec![
+        ScannedItem { gtin: 1, serial: 3 },
+        ScannedItem { gtin: 1, serial: 4 },
+        ScannedItem { gtin: 1, serial: ecobottle::SERIAL_BITMAP_BITS + 3 },
+        ScannedItem { gtin: 2, serial: 3 },
+    ];
+    let instruction = instructions::process_item_deposit(
+        &user,
+        &pda::container("EB-001"),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        items,
+        Receipt { hash: [1; 32], sequence: 1 },
+    );
+
+    let remaining: Vec<_> = instruction.accounts[9..].iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        remaining,
+        vec![pda::serial_bitmap(1, 0), pda::serial_bitmap(1, 1), pda::serial_bitmap(2, 0)]
+    );
+    assert!(instruction.accounts[9..].iter().all(|meta| meta.is_writable));
+}
+
+#[test]
+fn deposit_batch_numbers_each_wallet_once() {
+    let (ana, beto, referrer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (ana_tokens, beto_tokens, referrer_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (operator, ecoc_mint, tree) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let instruction = ProcessDepositBatch::new(&operator, &pda::container("EB-001"), &ecoc_mint, &tree)
+        .deposit(&ana, &ana_tokens, 1_000, Receipt { hash: [1; 32], sequence: 1 }, 10)
+        .deposit(&beto, &beto_tokens, 2_000, Receipt { hash: [2; 32], sequence: 2 }, 20)
+        .referrer(&referrer, &referrer_tokens)
+        .deposit(&ana, &ana_tokens, 500, Receipt { hash: [3; 32], sequence: 3 }, 30)
+        .instruction();
+
+    let remaining: Vec<_> = instruction.accounts[8..].iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        remaining,
+        vec![
+            pda::user_profile(&ana),
+            ana_tokens,
+            pda::user_profile(&beto),
+            beto_tokens,
+            pda::user_profile(&referrer),
+            referrer_tokens,
+        ]
+    );
+    assert!(instruction.accounts[8..].iter().all(|meta| meta.is_writable));
+
+    let args = ecobottle::instruction::ProcessDepositBatch::try_from_slice(&instruction.data[8..]).unwrap();
+    let indexes: Vec<_> = args.deposits.iter().map(|deposit| deposit.user_index).collect();
+    assert_eq!(indexes, vec![0, 1, 0]);
+    let read_at: Vec<_> = args.deposits.iter().map(|deposit| deposit.timestamp).collect();
+    assert_eq!(read_at, vec![10, 20, 30]);
+}
+
+#[test]
+fn decode_rejects_foreign_accounts() {
+    assert!(EcobottleAccount::decode(&[0; 4]).is_err());
+    assert!(EcobottleAccount::decode(&[0; 64]).is_err());
+    assert!(decode::<UserProfile>(&[0; 64]).is_err());
+}
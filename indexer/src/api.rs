@@ -0,0 +1,129 @@
+//! API HTTP de consulta (JSON, solo lectura)
+//!
+//! - `GET /stats`
+//! - `GET /users/<wallet>`
+//! - `GET /containers`
+//! - `GET /deposits?container=<id>&user=<wallet>&since=<ts>&until=<ts>&limit=<n>`
+//! - `GET /redemptions?user=<wallet>`
+//! - `GET /collections?container=<id>`
+//!
+//! Los contenedores se indican por su ID (`EB-001`); el PDA se deriva aquí.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use ecobottle_client::pda;
+use serde::Serialize;
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::store::{DepositQuery, Store};
+use crate::Result;
+
+/// Respuesta de una ruta antes de escribirse en la conexión
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Reply {
+    fn json(value: &impl Serialize) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self { status: 200, body },
+            Err(err) => Self::error(500, &err.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self { status, body: serde_json::json!({ "error": message }).to_string() }
+    }
+}
+
+/// Parámetros de la query string (sin escapes: claves, IDs y números)
+fn query_params(query: &str) -> Vec<(&str, &str)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .collect()
+}
+
+fn param<'a>(params: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    params.iter().find(|(key, _)| *key == name).map(|(_, value)| *value)
+}
+
+fn parse<T: FromStr>(params: &[(&str, &str)], name: &str) -> std::result::Result<Option<T>, Reply> {
+    param(params, name)
+        .map(|value| value.parse().map_err(|_| Reply::error(400, &format!("parámetro inválido: {name}"))))
+        .transpose()
+}
+
+fn container(params: &[(&str, &str)]) -> std::result::Result<Option<Pubkey>, Reply> {
+    match param(params, "container") {
+        Some(id) if id.is_empty() || id.len() > 32 => Err(Reply::error(400, "parámetro inválido: container")),
+        Some(id) => Ok(Some(pda::container(id))),
+        None => Ok(None),
+    }
+}
+
+/// Resuelve una petición `GET` sobre el store
+pub fn route(store: &Store, url: &str) -> Reply {
+    try_route(store, url).unwrap_or_else(|reply| reply)
+}
+
+fn try_route(store: &Store, url: &str) -> std::result::Result<Reply, Reply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query_params(query);
+    let internal = |err: crate::Error| Reply::error(500, &err.to_string());
+
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let reply = match segments.as_slice() {
+        ["stats"] => Reply::json(&store.stats().map_err(internal)?),
+        ["containers"] => Reply::json(&store.containers().map_err(internal)?),
+        ["users", wallet] => {
+            let wallet = Pubkey::from_str(wallet).map_err(|_| Reply::error(400, "wallet inválida"))?;
+            match store.user(&wallet).map_err(internal)? {
+                Some(user) => Reply::json(&user),
+                None => Reply::error(404, "usuario no indexado"),
+            }
+        }
+        ["deposits"] => {
+            let query = DepositQuery {
+                user: parse(&params, "user")?,
+                container: container(&params)?,
+                since: parse(&params, "since")?,
+                until: parse(&params, "until")?,
+                limit: parse(&params, "limit")?,
+            };
+            Reply::json(&store.deposits(&query).map_err(internal)?)
+        }
+        ["redemptions"] => {
+            let user: Option<Pubkey> = parse(&params, "user")?;
+            Reply::json(&store.redemptions(user.as_ref()).map_err(internal)?)
+        }
+        ["collections"] => Reply::json(&store.collections(container(&params)?.as_ref()).map_err(internal)?),
+        _ => Reply::error(404, "ruta no encontrada"),
+    };
+    Ok(reply)
+}
+
+/// Atiende peticiones HTTP hasta que se detenga el proceso
+pub fn serve(store: &Store, bind: &str) -> Result<()> {
+    let server = Server::http(bind).map_err(|err| err.to_string())?;
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("cabecera válida");
+
+    for request in server.incoming_requests() {
+        let reply = if *request.method() == Method::Get {
+            route(store, request.url())
+        } else {
+            Reply::error(405, "solo se admite GET")
+        };
+        let response = Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("⚠️ No se pudo responder: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! Decodificación de instrucciones y eventos de EcoBottle
+//!
+//! Las altas y cambios de usuarios y contenedores se leen de los argumentos
+//! de las instrucciones; los depósitos, canjes y recolecciones, de los
+//! eventos, que llevan los valores calculados en cadena (recompensas, CO₂e,
+//! incentivos). Las posiciones de las cuentas siguen el orden de los structs
+//! `#[derive(Accounts)]` del programa.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ecobottle_client::ecobottle::{
+    self, instruction as args, ContainerCollected, DepositProcessed, ItemDepositProcessed, TokensRedeemed,
+};
+
+use crate::transaction::{IndexedInstruction, IndexedTransaction};
+use crate::Result;
+
+/// Cambio de estado que materializa el indexador
+pub enum Activity {
+    UserRegistered {
+        wallet: Pubkey,
+        profile: Pubkey,
+        username: String,
+        referrer: Option<Pubkey>,
+    },
+    UsernameUpdated {
+        wallet: Pubkey,
+        username: String,
+    },
+    UserSuspension {
+        profile: Pubkey,
+        suspended: bool,
+    },
+    ContainerRegistered {
+        address: Pubkey,
+        container_id: String,
+        location: String,
+        capacity_kg: u64,
+        material: String,
+    },
+    ContainerToggled {
+        address: Pubkey,
+    },
+    FillThresholdSet {
+        address: Pubkey,
+        fill_threshold_bps: u16,
+    },
+    Deposit(DepositProcessed),
+    ItemDeposit(ItemDepositProcessed),
+    Redemption(TokensRedeemed),
+    Collection(ContainerCollected),
+}
+
+/// Actividad de una transacción en orden: cada instrucción de primer nivel
+/// seguida de los eventos emitidos durante su ejecución
+pub fn decode_transaction(transaction: &IndexedTransaction) -> Result<Vec<Activity>> {
+    let events = events_by_instruction(&transaction.log_messages);
+    let mut activities = Vec::new();
+
+    for (index, instruction) in transaction.instructions.iter().enumerate() {
+        if instruction.program_id()? != ecobottle::ID {
+            continue;
+        }
+        if let Some(activity) = decode_instruction(instruction)? {
+            activities.push(activity);
+        }
+        for data in events.get(index).into_iter().flatten() {
+            if let Some(activity) = decode_event(data)? {
+                activities.push(activity);
+            }
+        }
+    }
+    Ok(activities)
+}
+
+fn args<T: AnchorDeserialize>(data: &[u8]) -> Result<T> {
+    Ok(T::try_from_slice(&data[8..])?)
+}
+
+fn account(accounts: &[Pubkey], index: usize) -> Result<Pubkey> {
+    Ok(*accounts.get(index).ok_or("faltan cuentas en la instrucción")?)
+}
+
+pub fn decode_instruction(instruction: &IndexedInstruction) -> Result<Option<Activity>> {
+    let data = instruction.data()?;
+    let Some(discriminator) = data.get(..8) else {
+        return Ok(None);
+    };
+    let accounts = instruction.accounts()?;
+
+    let activity = if discriminator == args::RegisterUser::DISCRIMINATOR {
+        let args::RegisterUser { username, referrer } = args(&data)?;
+        Activity::UserRegistered {
+            wallet: account(&accounts, 4)?,
+            profile: account(&accounts, 0)?,
+            username,
+            referrer,
+        }
+    } else if discriminator == args::UpdateUsername::DISCRIMINATOR {
+        let args::UpdateUsername { new_username } = args(&data)?;
+        Activity::UsernameUpdated {
+            wallet: account(&accounts, 3)?,
+            username: new_username,
+        }
+    } else if discriminator == args::SetUserSuspension::DISCRIMINATOR {
+        let args::SetUserSuspension { suspended } = args(&data)?;
+        Activity::UserSuspension {
+            profile: account(&accounts, 0)?,
+            suspended,
+        }
+    } else if discriminator == args::RegisterContainer::DISCRIMINATOR {
+        let args::RegisterContainer { container_id, location, capacity_kg, material } = args(&data)?;
+        Activity::ContainerRegistered {
+            address: account(&accounts, 0)?,
+            container_id,
+            location,
+            capacity_kg,
+            material: format!("{material:?}"),
+        }
+    } else if discriminator == args::ToggleContainerStatus::DISCRIMINATOR {
+        Activity::ContainerToggled { address: account(&accounts, 0)? }
+    } else if discriminator == args::SetFillThreshold::DISCRIMINATOR {
+        let args::SetFillThreshold { fill_threshold_bps } = args(&data)?;
+        Activity::FillThresholdSet {
+            address: account(&accounts, 0)?,
+            fill_threshold_bps,
+        }
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(activity))
+}
+
+pub fn decode_event(data: &[u8]) -> Result<Option<Activity>> {
+    let Some(discriminator) = data.get(..8) else {
+        return Ok(None);
+    };
+
+    let activity = if discriminator == DepositProcessed::DISCRIMINATOR {
+        Activity::Deposit(args(data)?)
+    } else if discriminator == ItemDepositProcessed::DISCRIMINATOR {
+        Activity::ItemDeposit(args(data)?)
+    } else if discriminator == TokensRedeemed::DISCRIMINATOR {
+        Activity::Redemption(args(data)?)
+    } else if discriminator == ContainerCollected::DISCRIMINATOR {
+        Activity::Collection(args(data)?)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(activity))
+}
+
+/// Datos de los eventos de EcoBottle agrupados por instrucción de primer nivel
+///
+/// Anchor publica cada evento como `Program data: <base64>`; se sigue la pila
+/// de invocaciones para descartar los que emitan otros programas.
+pub fn events_by_instruction(logs: &[String]) -> Vec<Vec<Vec<u8>>> {
+    let program_id = ecobottle::ID.to_string();
+    let mut events: Vec<Vec<Vec<u8>>> = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let (Some(current), Ok(bytes)) = (events.last_mut(), BASE64.decode(data)) {
+                    current.push(bytes);
+                }
+            }
+            continue;
+        }
+
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        let mut words = rest.split(' ');
+        let (Some(program), Some(action)) = (words.next(), words.next()) else {
+            continue;
+        };
+        match action {
+            "invoke" => {
+                if stack.is_empty() {
+                    events.push(Vec::new());
+                }
+                stack.push(program);
+            }
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
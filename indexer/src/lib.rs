@@ -0,0 +1,18 @@
+//! Indexador de EcoBottle
+//!
+//! Reconstruye el historial del programa en un esquema relacional (SQLite):
+//! usuarios, contenedores, depósitos, canjes y recolecciones. Consume
+//! transacciones desde un nodo RPC o desde un archivo de fixtures, decodifica
+//! las instrucciones y los eventos de `ecobottle`, y expone una API HTTP de
+//! consulta para preguntas como "todos los depósitos del contenedor X el mes
+//! pasado" sin recorrer `getProgramAccounts`.
+
+pub mod api;
+pub mod decode;
+pub mod rpc;
+pub mod store;
+pub mod transaction;
+
+/// Error genérico del indexador (RPC, SQLite, JSON o decodificación)
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
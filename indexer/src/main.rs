@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use ecobottle_indexer::store::Store;
+use ecobottle_indexer::transaction::read_fixture;
+use ecobottle_indexer::{api, rpc, Result};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Parser, Debug)]
+#[command(name = "ecobottle-indexer", version, about = "Indexador del programa EcoBottle")]
+struct Cli {
+    /// Base de datos SQLite
+    #[arg(long, global = true, default_value = "ecobottle.db")]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Importa un archivo de fixtures (una transacción JSON por línea)
+    Import {
+        fixture: PathBuf,
+    },
+    /// Sincroniza las transacciones nuevas desde un nodo RPC
+    Sync {
+        #[arg(short = 'u', long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+        /// Repite la sincronización cada N segundos
+        #[arg(long)]
+        follow: Option<u64>,
+    },
+    /// Sirve la API HTTP de consulta
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+    },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("❌ {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut store = Store::open(&cli.db)?;
+
+    match cli.command {
+        Command::Import { fixture } => {
+            let transactions = read_fixture(&fixture)?;
+            let mut indexed = 0;
+            for transaction in &transactions {
+                if store.apply(transaction)? {
+                    indexed += 1;
+                }
+            }
+            println!("📥 {indexed} de {} transacciones indexadas", transactions.len());
+            Ok(())
+        }
+        Command::Sync { url, follow } => {
+            let client = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+            loop {
+                let report = rpc::sync(&client, &mut store)?;
+                println!("🔄 {} firmas nuevas, {} transacciones indexadas", report.fetched, report.indexed);
+                match follow {
+                    Some(seconds) => thread::sleep(Duration::from_secs(seconds)),
+                    None => return Ok(()),
+                }
+            }
+        }
+        Command::Serve { bind } => {
+            println!("🌐 API de consulta en http://{bind}");
+            api::serve(&store, &bind)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Sincronización incremental desde un nodo RPC
+//!
+//! Recorre `getSignaturesForAddress` del programa hacia atrás hasta la última
+//! firma indexada, y aplica las transacciones nuevas de la más antigua a la
+//! más reciente, avanzando el cursor tras cada una.
+
+use std::str::FromStr;
+
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+
+use crate::store::Store;
+use crate::transaction::IndexedTransaction;
+use crate::Result;
+
+/// Resultado de una pasada de sincronización
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub fetched: usize,
+    pub indexed: usize,
+}
+
+pub fn sync(rpc: &RpcClient, store: &mut Store) -> Result<SyncReport> {
+    let until = store.last_signature()?.map(|signature| Signature::from_str(&signature)).transpose()?;
+
+    // Firmas nuevas, de la más reciente a la más antigua
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: None,
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(&ecobottle_client::ID, config)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        signatures.extend(page);
+    }
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let mut report = SyncReport { fetched: signatures.len(), indexed: 0 };
+    for status in signatures.iter().rev() {
+        if status.err.is_none() {
+            let confirmed = rpc.get_transaction_with_config(&Signature::from_str(&status.signature)?, config)?;
+            if let Some(transaction) = IndexedTransaction::from_rpc(&status.signature, confirmed)? {
+                if store.apply(&transaction)? {
+                    report.indexed += 1;
+                }
+            }
+        }
+        store.set_last_signature(&status.signature)?;
+    }
+    Ok(report)
+}
@@ -0,0 +1,521 @@
+//! Esquema relacional en SQLite y consultas
+//!
+//! Cada transacción se aplica dentro de una transacción SQL y queda anotada
+//! por firma, de modo que reimportar un fixture o reanudar una sincronización
+//! no duplica filas. Las claves se guardan en base58 y los montos como
+//! `INTEGER` (los `u64` del programa caben en `i64` en la práctica).
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::decode::{decode_transaction, Activity};
+use crate::transaction::IndexedTransaction;
+use crate::Result;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER
+);
+CREATE TABLE IF NOT EXISTS users (
+    wallet TEXT PRIMARY KEY,
+    profile TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL,
+    referrer TEXT,
+    suspended INTEGER NOT NULL DEFAULT 0,
+    registered_at INTEGER,
+    signature TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS containers (
+    address TEXT PRIMARY KEY,
+    container_id TEXT NOT NULL,
+    location TEXT NOT NULL,
+    capacity_kg INTEGER NOT NULL,
+    material TEXT NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    fill_threshold_bps INTEGER,
+    registered_at INTEGER,
+    signature TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS deposits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    timestamp INTEGER,
+    user TEXT NOT NULL,
+    container TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    nonce INTEGER,
+    weight_grams INTEGER NOT NULL,
+    ecoc_reward INTEGER NOT NULL,
+    referral_bonus INTEGER NOT NULL DEFAULT 0,
+    co2_grams INTEGER,
+    items INTEGER,
+    receipt_hash TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS deposits_by_container ON deposits (container, timestamp);
+CREATE INDEX IF NOT EXISTS deposits_by_user ON deposits (user, timestamp);
+CREATE TABLE IF NOT EXISTS redemptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    amount INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS redemptions_by_user ON redemptions (user, timestamp);
+CREATE TABLE IF NOT EXISTS collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    container TEXT NOT NULL,
+    collector TEXT NOT NULL,
+    record TEXT NOT NULL,
+    weight_collected INTEGER NOT NULL,
+    collector_reward INTEGER NOT NULL,
+    sla_bonus INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS collections_by_container ON collections (container, timestamp);
+CREATE TABLE IF NOT EXISTS sync_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// Clave de `sync_state` con la última firma sincronizada por RPC
+const LAST_SIGNATURE: &str = "last_signature";
+
+pub struct Store {
+    connection: Connection,
+}
+
+/// Filtros de la consulta de depósitos (rango `[since, until)` en segundos)
+#[derive(Clone, Debug, Default)]
+pub struct DepositQuery {
+    pub user: Option<Pubkey>,
+    pub container: Option<Pubkey>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DepositRow {
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: Option<i64>,
+    pub user: String,
+    pub container: String,
+    pub container_id: Option<String>,
+    pub kind: String,
+    pub nonce: Option<u64>,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub referral_bonus: u64,
+    pub co2_grams: Option<u64>,
+    pub items: Option<u64>,
+    pub receipt_hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RedemptionRow {
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub user: String,
+    pub product_id: String,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CollectionRow {
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub container: String,
+    pub collector: String,
+    pub record: String,
+    pub weight_collected: u64,
+    pub collector_reward: u64,
+    pub sla_bonus: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UserRow {
+    pub wallet: String,
+    pub profile: String,
+    pub username: String,
+    pub referrer: Option<String>,
+    pub suspended: bool,
+    pub registered_at: Option<i64>,
+    pub deposits: u64,
+    pub deposited_grams: u64,
+    pub ecoc_earned: u64,
+    pub ecoc_redeemed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContainerRow {
+    pub address: String,
+    pub container_id: String,
+    pub location: String,
+    pub capacity_kg: u64,
+    pub material: String,
+    pub is_active: bool,
+    pub fill_threshold_bps: Option<u16>,
+    pub registered_at: Option<i64>,
+    pub deposits: u64,
+    pub deposited_grams: u64,
+    pub collected_grams: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub transactions: u64,
+    pub last_slot: Option<u64>,
+    pub users: u64,
+    pub containers: u64,
+    pub deposits: u64,
+    pub deposited_grams: u64,
+    pub ecoc_rewarded: u64,
+    pub redemptions: u64,
+    pub ecoc_redeemed: u64,
+    pub collections: u64,
+    pub collected_grams: u64,
+}
+
+fn key(key: &Pubkey) -> String {
+    key.to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unsigned(row: &Row, index: usize) -> rusqlite::Result<u64> {
+    Ok(row.get::<_, i64>(index)? as u64)
+}
+
+fn optional_unsigned(row: &Row, index: usize) -> rusqlite::Result<Option<u64>> {
+    Ok(row.get::<_, Option<i64>>(index)?.map(|value| value as u64))
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Materializa una transacción; devuelve `false` si ya estaba indexada
+    pub fn apply(&mut self, transaction: &IndexedTransaction) -> Result<bool> {
+        let activities = decode_transaction(transaction)?;
+        let tx = self.connection.transaction()?;
+
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![transaction.signature, transaction.slot as i64, transaction.block_time],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let signature = &transaction.signature;
+        let slot = transaction.slot as i64;
+        let block_time = transaction.block_time;
+        for activity in activities {
+            match activity {
+                Activity::UserRegistered { wallet, profile, username, referrer } => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO users (wallet, profile, username, referrer, registered_at, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![key(&wallet), key(&profile), username, referrer.as_ref().map(key), block_time, signature],
+                    )?;
+                }
+                Activity::UsernameUpdated { wallet, username } => {
+                    tx.execute("UPDATE users SET username = ?2 WHERE wallet = ?1", params![key(&wallet), username])?;
+                }
+                Activity::UserSuspension { profile, suspended } => {
+                    tx.execute("UPDATE users SET suspended = ?2 WHERE profile = ?1", params![key(&profile), suspended])?;
+                }
+                Activity::ContainerRegistered { address, container_id, location, capacity_kg, material } => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO containers
+                         (address, container_id, location, capacity_kg, material, registered_at, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![key(&address), container_id, location, capacity_kg as i64, material, block_time, signature],
+                    )?;
+                }
+                Activity::ContainerToggled { address } => {
+                    tx.execute("UPDATE containers SET is_active = NOT is_active WHERE address = ?1", params![key(&address)])?;
+                }
+                Activity::FillThresholdSet { address, fill_threshold_bps } => {
+                    tx.execute(
+                        "UPDATE containers SET fill_threshold_bps = ?2 WHERE address = ?1",
+                        params![key(&address), fill_threshold_bps],
+                    )?;
+                }
+                Activity::Deposit(event) => {
+                    tx.execute(
+                        "INSERT INTO deposits (signature, slot, timestamp, user, container, kind, nonce, weight_grams,
+                         ecoc_reward, referral_bonus, co2_grams, receipt_hash)
+                         VALUES (?1, ?2, ?3, ?4, ?5, 'weight', ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            signature,
+                            slot,
+                            event.timestamp,
+                            key(&event.user),
+                            key(&event.container),
+                            event.nonce as i64,
+                            event.weight_grams as i64,
+                            event.ecoc_reward as i64,
+                            event.referral_bonus as i64,
+                            event.co2_grams as i64,
+                            hex(&event.receipt_hash),
+                        ],
+                    )?;
+                }
+                Activity::ItemDeposit(event) => {
+                    tx.execute(
+                        "INSERT INTO deposits (signature, slot, timestamp, user, container, kind, weight_grams,
+                         ecoc_reward, items, receipt_hash)
+                         VALUES (?1, ?2, ?3, ?4, ?5, 'items', ?6, ?7, ?8, ?9)",
+                        params![
+                            signature,
+                            slot,
+                            block_time,
+                            key(&event.user),
+                            key(&event.container),
+                            event.weight_grams as i64,
+                            event.ecoc_reward as i64,
+                            event.items.len() as i64,
+                            hex(&event.receipt_hash),
+                        ],
+                    )?;
+                }
+                Activity::Redemption(event) => {
+                    tx.execute(
+                        "INSERT INTO redemptions (signature, slot, timestamp, user, product_id, amount)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![signature, slot, event.timestamp, key(&event.user), event.product_id, event.amount as i64],
+                    )?;
+                }
+                Activity::Collection(event) => {
+                    tx.execute(
+                        "INSERT INTO collections (signature, slot, timestamp, container, collector, record,
+                         weight_collected, collector_reward, sla_bonus)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            signature,
+                            slot,
+                            event.timestamp,
+                            key(&event.container),
+                            key(&event.collector),
+                            key(&event.collection_record),
+                            event.weight_collected as i64,
+                            event.collector_reward as i64,
+                            event.sla_bonus as i64,
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Última firma sincronizada por RPC
+    pub fn last_signature(&self) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row("SELECT value FROM sync_state WHERE key = ?1", [LAST_SIGNATURE], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn set_last_signature(&self, signature: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+            params![LAST_SIGNATURE, signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn deposits(&self, query: &DepositQuery) -> Result<Vec<DepositRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT d.signature, d.slot, d.timestamp, d.user, d.container, c.container_id, d.kind, d.nonce,
+                    d.weight_grams, d.ecoc_reward, d.referral_bonus, d.co2_grams, d.items, d.receipt_hash
+             FROM deposits d LEFT JOIN containers c ON c.address = d.container
+             WHERE (?1 IS NULL OR d.user = ?1)
+               AND (?2 IS NULL OR d.container = ?2)
+               AND (?3 IS NULL OR d.timestamp >= ?3)
+               AND (?4 IS NULL OR d.timestamp < ?4)
+             ORDER BY d.slot, d.id
+             LIMIT ?5",
+        )?;
+        let limit = query.limit.map_or(-1, i64::from);
+        let rows = statement.query_map(
+            params![query.user.as_ref().map(key), query.container.as_ref().map(key), query.since, query.until, limit],
+            |row| {
+                Ok(DepositRow {
+                    signature: row.get(0)?,
+                    slot: unsigned(row, 1)?,
+                    timestamp: row.get(2)?,
+                    user: row.get(3)?,
+                    container: row.get(4)?,
+                    container_id: row.get(5)?,
+                    kind: row.get(6)?,
+                    nonce: optional_unsigned(row, 7)?,
+                    weight_grams: unsigned(row, 8)?,
+                    ecoc_reward: unsigned(row, 9)?,
+                    referral_bonus: unsigned(row, 10)?,
+                    co2_grams: optional_unsigned(row, 11)?,
+                    items: optional_unsigned(row, 12)?,
+                    receipt_hash: row.get(13)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn redemptions(&self, user: Option<&Pubkey>) -> Result<Vec<RedemptionRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT signature, slot, timestamp, user, product_id, amount FROM redemptions
+             WHERE (?1 IS NULL OR user = ?1) ORDER BY slot, id",
+        )?;
+        let rows = statement.query_map([user.map(key)], |row| {
+            Ok(RedemptionRow {
+                signature: row.get(0)?,
+                slot: unsigned(row, 1)?,
+                timestamp: row.get(2)?,
+                user: row.get(3)?,
+                product_id: row.get(4)?,
+                amount: unsigned(row, 5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn collections(&self, container: Option<&Pubkey>) -> Result<Vec<CollectionRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT signature, slot, timestamp, container, collector, record, weight_collected, collector_reward,
+                    sla_bonus
+             FROM collections WHERE (?1 IS NULL OR container = ?1) ORDER BY slot, id",
+        )?;
+        let rows = statement.query_map([container.map(key)], |row| {
+            Ok(CollectionRow {
+                signature: row.get(0)?,
+                slot: unsigned(row, 1)?,
+                timestamp: row.get(2)?,
+                container: row.get(3)?,
+                collector: row.get(4)?,
+                record: row.get(5)?,
+                weight_collected: unsigned(row, 6)?,
+                collector_reward: unsigned(row, 7)?,
+                sla_bonus: unsigned(row, 8)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn user(&self, wallet: &Pubkey) -> Result<Option<UserRow>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT u.wallet, u.profile, u.username, u.referrer, u.suspended, u.registered_at,
+                        (SELECT COUNT(*) FROM deposits WHERE user = u.wallet),
+                        (SELECT COALESCE(SUM(weight_grams), 0) FROM deposits WHERE user = u.wallet),
+                        (SELECT COALESCE(SUM(ecoc_reward), 0) FROM deposits WHERE user = u.wallet),
+                        (SELECT COALESCE(SUM(amount), 0) FROM redemptions WHERE user = u.wallet)
+                 FROM users u WHERE u.wallet = ?1",
+                [key(wallet)],
+                |row| {
+                    Ok(UserRow {
+                        wallet: row.get(0)?,
+                        profile: row.get(1)?,
+                        username: row.get(2)?,
+                        referrer: row.get(3)?,
+                        suspended: row.get(4)?,
+                        registered_at: row.get(5)?,
+                        deposits: unsigned(row, 6)?,
+                        deposited_grams: unsigned(row, 7)?,
+                        ecoc_earned: unsigned(row, 8)?,
+                        ecoc_redeemed: unsigned(row, 9)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn containers(&self) -> Result<Vec<ContainerRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT c.address, c.container_id, c.location, c.capacity_kg, c.material, c.is_active,
+                    c.fill_threshold_bps, c.registered_at,
+                    (SELECT COUNT(*) FROM deposits WHERE container = c.address),
+                    (SELECT COALESCE(SUM(weight_grams), 0) FROM deposits WHERE container = c.address),
+                    (SELECT COALESCE(SUM(weight_collected), 0) FROM collections WHERE container = c.address)
+             FROM containers c ORDER BY c.container_id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok(ContainerRow {
+                address: row.get(0)?,
+                container_id: row.get(1)?,
+                location: row.get(2)?,
+                capacity_kg: unsigned(row, 3)?,
+                material: row.get(4)?,
+                is_active: row.get(5)?,
+                fill_threshold_bps: row.get(6)?,
+                registered_at: row.get(7)?,
+                deposits: unsigned(row, 8)?,
+                deposited_grams: unsigned(row, 9)?,
+                collected_grams: unsigned(row, 10)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn stats(&self) -> Result<Stats> {
+        Ok(self.connection.query_row(
+            "SELECT (SELECT COUNT(*) FROM transactions),
+                    (SELECT MAX(slot) FROM transactions),
+                    (SELECT COUNT(*) FROM users),
+                    (SELECT COUNT(*) FROM containers),
+                    (SELECT COUNT(*) FROM deposits),
+                    (SELECT COALESCE(SUM(weight_grams), 0) FROM deposits),
+                    (SELECT COALESCE(SUM(ecoc_reward + referral_bonus), 0) FROM deposits),
+                    (SELECT COUNT(*) FROM redemptions),
+                    (SELECT COALESCE(SUM(amount), 0) FROM redemptions),
+                    (SELECT COUNT(*) FROM collections),
+                    (SELECT COALESCE(SUM(weight_collected), 0) FROM collections)",
+            [],
+            |row| {
+                Ok(Stats {
+                    transactions: unsigned(row, 0)?,
+                    last_slot: optional_unsigned(row, 1)?,
+                    users: unsigned(row, 2)?,
+                    containers: unsigned(row, 3)?,
+                    deposits: unsigned(row, 4)?,
+                    deposited_grams: unsigned(row, 5)?,
+                    ecoc_rewarded: unsigned(row, 6)?,
+                    redemptions: unsigned(row, 7)?,
+                    ecoc_redeemed: unsigned(row, 8)?,
+                    collections: unsigned(row, 9)?,
+                    collected_grams: unsigned(row, 10)?,
+                })
+            },
+        )?)
+    }
+}
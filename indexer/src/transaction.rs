@@ -0,0 +1,134 @@
+//! Transacciones confirmadas en el formato que consume el indexador
+//!
+//! Solo se conserva lo necesario para decodificar: las instrucciones de primer
+//! nivel con sus cuentas resueltas y los logs (donde Anchor publica los
+//! eventos). Es también el formato de los archivos de fixtures, una
+//! transacción JSON por línea.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use solana_transaction_status_client_types::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::Result;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub instructions: Vec<IndexedInstruction>,
+    pub log_messages: Vec<String>,
+}
+
+/// Instrucción de primer nivel; claves en base58 y datos en base64
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedInstruction {
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    pub data: String,
+}
+
+impl IndexedInstruction {
+    pub fn new(program_id: &Pubkey, accounts: &[Pubkey], data: &[u8]) -> Self {
+        Self {
+            program_id: program_id.to_string(),
+            accounts: accounts.iter().map(Pubkey::to_string).collect(),
+            data: BASE64.encode(data),
+        }
+    }
+
+    pub fn program_id(&self) -> Result<Pubkey> {
+        Ok(Pubkey::from_str(&self.program_id)?)
+    }
+
+    pub fn accounts(&self) -> Result<Vec<Pubkey>> {
+        self.accounts.iter().map(|key| Ok(Pubkey::from_str(key)?)).collect()
+    }
+
+    pub fn data(&self) -> Result<Vec<u8>> {
+        Ok(BASE64.decode(&self.data)?)
+    }
+}
+
+impl IndexedTransaction {
+    /// Convierte una transacción obtenida con `getTransaction` (codificación
+    /// base64); devuelve `None` si falló, pues no cambió el estado
+    pub fn from_rpc(signature: &str, confirmed: EncodedConfirmedTransactionWithStatusMeta) -> Result<Option<Self>> {
+        let meta = confirmed.transaction.meta.ok_or("transacción sin metadatos")?;
+        if meta.err.is_some() {
+            return Ok(None);
+        }
+        let transaction = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .ok_or("codificación de transacción no soportada")?;
+
+        // Cuentas estáticas seguidas de las cargadas desde tablas de direcciones
+        let mut keys = transaction.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = meta.loaded_addresses {
+            for key in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(Pubkey::from_str(key)?);
+            }
+        }
+        let key_at = |index: u8| keys.get(index as usize).copied().ok_or("índice de cuenta fuera de rango");
+
+        let instructions = transaction
+            .message
+            .instructions()
+            .iter()
+            .map(|instruction| {
+                let program_id = key_at(instruction.program_id_index)?;
+                let accounts = instruction
+                    .accounts
+                    .iter()
+                    .map(|index| key_at(*index))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(IndexedInstruction::new(&program_id, &accounts, &instruction.data))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let log_messages = match meta.log_messages {
+            OptionSerializer::Some(logs) => logs,
+            _ => Vec::new(),
+        };
+
+        Ok(Some(Self {
+            signature: signature.to_string(),
+            slot: confirmed.slot,
+            block_time: confirmed.block_time,
+            instructions,
+            log_messages,
+        }))
+    }
+}
+
+/// Lee un archivo de fixtures (una transacción JSON por línea)
+pub fn read_fixture(path: &Path) -> Result<Vec<IndexedTransaction>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut transactions = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            transactions.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(transactions)
+}
+
+/// Escribe un archivo de fixtures
+pub fn write_fixture(path: &Path, transactions: &[IndexedTransaction]) -> Result<()> {
+    let mut file = File::create(path)?;
+    for transaction in transactions {
+        writeln!(file, "{}", serde_json::to_string(transaction)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,293 @@
+//! Historial generado sobre una SVM en proceso, exportado como fixture e indexado
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, Event};
+use anchor_spl::token::spl_token;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ecobottle_client::accounts::decode;
+use ecobottle_client::ecobottle::{
+    self, CollectionRecord, ContainerCollected, DepositProcessed, DepositRecord, Material, RedemptionRecord,
+    SmartContainer, TokensRedeemed, UserProfile,
+};
+use ecobottle_client::instructions::{self, CollectContainer, DepositLog, ProcessDeposit, Receipt};
+use ecobottle_client::pda;
+use ecobottle_indexer::api;
+use ecobottle_indexer::decode::events_by_instruction;
+use ecobottle_indexer::store::{DepositQuery, Store};
+use ecobottle_indexer::transaction::{read_fixture, write_fixture, IndexedInstruction, IndexedTransaction};
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::clock::Clock;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction as system_instruction;
+
+/// `ProgramTest` con el `ecobottle.so` compilado (`cargo build-sbf`, en
+/// `target/deploy` o en `SBF_OUT_DIR`)
+fn program_test() -> ProgramTest {
+    let dir = std::env::var("SBF_OUT_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy").to_string());
+    let elf = std::fs::read(std::path::Path::new(&dir).join("ecobottle.so"))
+        .expect("no se encontró ecobottle.so: compila el programa con `cargo build-sbf`");
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        ecobottle::ID,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: bpf_loader::ID,
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    program_test
+}
+
+/// Ejecuta transacciones y las registra en el formato del indexador
+struct Recorder {
+    banks_client: BanksClient,
+    payer: Keypair,
+    history: Vec<IndexedTransaction>,
+}
+
+impl Recorder {
+    async fn start() -> Self {
+        let (banks_client, payer, _) = program_test().start().await;
+        Self { banks_client, payer, history: Vec::new() }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.payer.pubkey()), &all_signers, blockhash);
+
+        let result = self
+            .banks_client
+            .process_transaction_with_metadata(transaction.clone())
+            .await
+            .unwrap();
+        result.result.unwrap();
+        let clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+
+        let keys = &transaction.message.account_keys;
+        self.history.push(IndexedTransaction {
+            signature: transaction.signatures[0].to_string(),
+            slot: clock.slot,
+            block_time: Some(clock.unix_timestamp),
+            instructions: transaction
+                .message
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let accounts: Vec<_> = instruction.accounts.iter().map(|index| keys[*index as usize]).collect();
+                    IndexedInstruction::new(&keys[instruction.program_id_index as usize], &accounts, &instruction.data)
+                })
+                .collect(),
+            log_messages: result.metadata.unwrap().log_messages,
+        });
+    }
+
+    async fn account<T: AccountDeserialize>(&mut self, key: &Pubkey) -> T {
+        decode(&self.banks_client.get_account(*key).await.unwrap().unwrap().data).unwrap()
+    }
+
+    /// Añade un evento a los logs de la última transacción, donde lo publicaría
+    /// un validador: fuera de SBF `emit!` no escribe nada, así que los eventos
+    /// se reconstruyen a partir de las cuentas resultantes
+    fn emitted(&mut self, event: impl Event) {
+        let success = format!("Program {} success", ecobottle::ID);
+        let logs = &mut self.history.last_mut().unwrap().log_messages;
+        let position = logs.iter().rposition(|log| *log == success).unwrap();
+        logs.insert(position, format!("Program data: {}", BASE64.encode(event.data())));
+    }
+
+    async fn create_account(&mut self, space: usize, owner: &Pubkey, init: impl FnOnce(&Pubkey) -> Instruction) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                owner,
+            ),
+            init(&account.pubkey()),
+        ];
+        self.send(&instructions, &[&account]).await;
+        account.pubkey()
+    }
+}
+
+#[tokio::test]
+async fn indexes_history_from_fixture() {
+    let mut recorder = Recorder::start().await;
+    let authority = recorder.payer.pubkey();
+    let ecoc_mint = recorder
+        .create_account(spl_token::state::Mint::LEN, &spl_token::ID, |mint| {
+            spl_token::instruction::initialize_mint2(&spl_token::ID, mint, &pda::global_state(), None, 6).unwrap()
+        })
+        .await;
+
+    let user = Keypair::new();
+    recorder
+        .send(
+            &[
+                instructions::initialize(&authority, &ecoc_mint, 1_000_000, 100),
+                instructions::register_container(&authority, "EB-001", "Plaza Mayor", 50, Material::Pet),
+                instructions::register_container(&authority, "EB-002", "Estación Norte", 20, Material::Aluminum),
+                system_instruction::transfer(&authority, &user.pubkey(), 1_000_000_000),
+            ],
+            &[],
+        )
+        .await;
+    recorder.send(&[instructions::register_user(&user.pubkey(), &user.pubkey(), "ana", None)], &[&user]).await;
+    let user_token_account = recorder
+        .create_account(spl_token::state::Account::LEN, &spl_token::ID, |account| {
+            spl_token::instruction::initialize_account3(&spl_token::ID, account, &ecoc_mint, &user.pubkey()).unwrap()
+        })
+        .await;
+
+    let (eb1, eb2) = (pda::container("EB-001"), pda::container("EB-002"));
+    for (nonce, container, weight) in [(0, &eb1, 2_000), (1, &eb2, 1_500)] {
+        let deposit = ProcessDeposit::new(
+            &user.pubkey(),
+            container,
+            &user_token_account,
+            &ecoc_mint,
+            weight,
+            Receipt { hash: [nonce as u8 + 1; 32], sequence: nonce + 1 },
+            DepositLog::Record { nonce },
+        )
+        .operator(&authority);
+        recorder.send(&[deposit.instruction()], &[&user]).await;
+
+        let record: DepositRecord = recorder.account(&pda::deposit(&user.pubkey(), nonce)).await;
+        let state: SmartContainer = recorder.account(container).await;
+        recorder.emitted(DepositProcessed {
+            user: record.user,
+            container: record.container,
+            nonce,
+            weight_grams: record.weight_grams,
+            ecoc_reward: record.ecoc_reward,
+            referral_bonus: 0,
+            co2_grams: state.co2_avoided_grams,
+            receipt_hash: record.receipt_hash,
+            timestamp: record.timestamp,
+        });
+    }
+    recorder
+        .send(
+            &[instructions::redeem_tokens(&user.pubkey(), &user_token_account, &ecoc_mint, 0, 500_000, "cafe")],
+            &[&user],
+        )
+        .await;
+    let record: RedemptionRecord = recorder.account(&pda::redemption(&user.pubkey(), 0)).await;
+    recorder.emitted(TokensRedeemed {
+        user: record.user,
+        product_id: record.product_id,
+        amount: record.amount,
+        timestamp: record.timestamp,
+    });
+    recorder.send(&[instructions::toggle_container_status(&authority, &eb2)], &[]).await;
+    recorder.send(&[CollectContainer::new(&authority, &eb1, 1).instruction()], &[]).await;
+    let record: CollectionRecord = recorder.account(&pda::collection(&eb1, 1)).await;
+    recorder.emitted(ContainerCollected {
+        container: record.container,
+        collector: record.collector,
+        collection_record: pda::collection(&eb1, 1),
+        weight_collected: record.weight_collected,
+        collector_reward: record.collector_reward,
+        sla_bonus: record.sla_bonus,
+        timestamp: record.timestamp,
+    });
+
+    // Ida y vuelta por el archivo de fixtures
+    let path = std::env::temp_dir().join(format!("ecobottle-indexer-{}.jsonl", std::process::id()));
+    write_fixture(&path, &recorder.history).unwrap();
+    let transactions = read_fixture(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(transactions, recorder.history);
+
+    let mut store = Store::open_in_memory().unwrap();
+    for transaction in &transactions {
+        assert!(store.apply(transaction).unwrap());
+    }
+    // Reimportar no duplica filas
+    assert!(!store.apply(&transactions[0]).unwrap());
+
+    let at_eb1 = store.deposits(&DepositQuery { container: Some(eb1), ..Default::default() }).unwrap();
+    assert_eq!(at_eb1.len(), 1);
+    let deposit = &at_eb1[0];
+    assert_eq!(deposit.container_id.as_deref(), Some("EB-001"));
+    assert_eq!(deposit.user, user.pubkey().to_string());
+    assert_eq!((deposit.nonce, deposit.weight_grams, deposit.ecoc_reward), (Some(0), 2_000, 2_000_000));
+    assert_eq!(deposit.co2_grams, Some(3_000));
+
+    let timestamp = deposit.timestamp.unwrap();
+    let window = |since, until| DepositQuery { since: Some(since), until: Some(until), ..Default::default() };
+    assert_eq!(store.deposits(&window(timestamp, timestamp + 1)).unwrap().len(), 2);
+    assert!(store.deposits(&window(timestamp - 10, timestamp)).unwrap().is_empty());
+
+    let profile = store.user(&user.pubkey()).unwrap().unwrap();
+    assert_eq!(profile.username, "ana");
+    assert_eq!((profile.deposits, profile.deposited_grams), (2, 3_500));
+    let on_chain: UserProfile = recorder.account(&pda::user_profile(&user.pubkey())).await;
+    assert_eq!((profile.ecoc_earned, profile.ecoc_redeemed), (on_chain.total_ecoc_earned, 500_000));
+    assert_eq!(store.redemptions(Some(&user.pubkey())).unwrap()[0].product_id, "cafe");
+
+    let containers = store.containers().unwrap();
+    assert_eq!(containers.len(), 2);
+    assert!(containers[0].is_active);
+    assert_eq!((containers[0].deposited_grams, containers[0].collected_grams), (2_000, 2_000));
+    assert!(!containers[1].is_active);
+    assert_eq!(containers[1].material, "Aluminum");
+
+    let collections = store.collections(Some(&eb1)).unwrap();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0].record, pda::collection(&eb1, 1).to_string());
+
+    let stats = store.stats().unwrap();
+    assert_eq!((stats.users, stats.containers, stats.deposits, stats.collections), (1, 2, 2, 1));
+    assert_eq!(stats.transactions, recorder.history.len() as u64);
+
+    // API de consulta
+    let reply = api::route(&store, "/deposits?container=EB-002&limit=5");
+    assert_eq!(reply.status, 200);
+    let body: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+    assert_eq!(body[0]["weight_grams"], 1_500);
+    assert_eq!(body[0]["container_id"], "EB-002");
+    assert_eq!(api::route(&store, &format!("/users/{}", user.pubkey())).status, 200);
+    assert_eq!(api::route(&store, &format!("/users/{}", Pubkey::new_unique())).status, 404);
+    assert_eq!(api::route(&store, "/deposits?since=ayer").status, 400);
+    assert_eq!(api::route(&store, "/ledger").status, 404);
+}
+
+#[test]
+fn events_follow_top_level_instructions() {
+    let program = ecobottle::ID.to_string();
+    let other = Pubkey::new_unique().to_string();
+    let data = |bytes: &[u8]| format!("Program data: {}", BASE64.encode(bytes));
+    let logs = vec![
+        format!("Program {other} invoke [1]"),
+        data(&[9]),
+        format!("Program {other} success"),
+        format!("Program {program} invoke [1]"),
+        data(&[1]),
+        format!("Program {other} invoke [2]"),
+        data(&[9]),
+        format!("Program {other} success"),
+        data(&[2]),
+        format!("Program {program} consumed 1000 of 200000 compute units"),
+        format!("Program {program} success"),
+    ];
+
+    assert_eq!(events_by_instruction(&logs), vec![vec![], vec![vec![1], vec![2]]]);
+}
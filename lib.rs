@@ -1,8 +1,116 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Burn};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Burn, Transfer, CloseAccount};
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_interface::{self, NonTransferableMintInitialize};
+use solana_sha256_hasher::{hash, hashv};
 
 declare_id!("GhaBgqop1A6NxguXHRsBJntBxj5QMB73NEKM46WX6kQb");
 
+// ============================================================================
+// CONSTANTES
+// ============================================================================
+
+/// Versión actual del esquema de todas las cuentas del programa
+/// (0 = cuentas creadas antes de existir el campo `version`)
+pub const CURRENT_ACCOUNT_VERSION: u8 = 1;
+
+/// Longitud máxima del nombre de usuario en caracteres
+pub const MAX_USERNAME_CHARS: usize = 32;
+/// Espacio reservado para el nombre de usuario (hasta 4 bytes por carácter UTF-8)
+pub const MAX_USERNAME_BYTES: usize = MAX_USERNAME_CHARS * 4;
+/// Longitud máxima de la URI del avatar en bytes
+pub const MAX_AVATAR_URI_LEN: usize = 128;
+
+/// Segundos en un día (para rachas y consistencia)
+pub const SECONDS_PER_DAY: i64 = 86_400;
+/// Segundos en una semana (para rachas semanales)
+pub const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+/// Segundos en un periodo mensual de clasificación (30 días)
+pub const SECONDS_PER_MONTH: i64 = 30 * SECONDS_PER_DAY;
+
+/// Número de posiciones en cada tabla de clasificación
+pub const LEADERBOARD_SIZE: usize = 10;
+/// Reparto del premio por posición en puntos básicos (suma 10000)
+pub const LEADERBOARD_PRIZE_SHARES_BPS: [u64; LEADERBOARD_SIZE] =
+    [3_000, 2_000, 1_500, 1_000, 800, 600, 400, 300, 200, 200];
+
+/// Peso acumulado para el logro de 100 kg (en gramos)
+pub const ACHIEVEMENT_HUNDRED_KG_GRAMS: u64 = 100_000;
+/// Días consecutivos para el logro de racha de 30 días
+pub const ACHIEVEMENT_STREAK_DAYS: u32 = 30;
+
+/// Reputación inicial de un usuario recién registrado
+pub const REPUTATION_INITIAL: u32 = 500;
+/// Reputación máxima alcanzable
+pub const REPUTATION_MAX: u32 = 1_000;
+/// Puntos por depositar en un día distinto al último depósito (consistencia)
+pub const REPUTATION_CONSISTENCY_POINTS: u32 = 2;
+/// Máximo de puntos extra por racha en un solo depósito
+pub const REPUTATION_MAX_STREAK_POINTS: u32 = 7;
+/// Máximo de puntos por volumen (1 punto por kg) en un solo depósito
+pub const REPUTATION_MAX_VOLUME_POINTS: u32 = 5;
+/// Penalización por depósito en un contenedor con discrepancia excesiva
+pub const REPUTATION_DISCREPANCY_PENALTY: u32 = 50;
+/// Penalización por suspensión de la cuenta
+pub const REPUTATION_SUSPENSION_PENALTY: u32 = 200;
+/// Multiplicador de recompensa con reputación 0 (0.8x en puntos básicos)
+pub const REPUTATION_MIN_MULTIPLIER_BPS: u64 = 8_000;
+/// Tolerancia por defecto entre peso registrado y peso medido (10%)
+pub const DEFAULT_DISCREPANCY_TOLERANCE_BPS: u16 = 1_000;
+
+/// Programa SPL Account Compression (árboles de Merkle concurrentes)
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+/// Programa SPL Noop (registro de changelogs del árbol)
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+/// Discriminadores de instrucción de SPL Account Compression (IDL publicado,
+/// `sha256("global:<nombre>")[..8]`)
+pub const COMPRESSION_INIT_EMPTY_MERKLE_TREE: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+pub const COMPRESSION_APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+pub const COMPRESSION_VERIFY_LEAF: [u8; 8] = [124, 220, 22, 223, 104, 10, 250, 224];
+
+/// Número de materiales con factor de emisión configurable
+pub const MATERIAL_COUNT: usize = 4;
+/// Factores de emisión por defecto: gramos de CO₂e evitados por kg reciclado
+/// (PET, HDPE, aluminio, vidrio)
+pub const DEFAULT_EMISSION_FACTORS: [u32; MATERIAL_COUNT] = [1_500, 1_000, 9_000, 300];
+/// Decimales del token de certificado de impacto (1 token = 1 kg CO₂e)
+pub const IMPACT_MINT_DECIMALS: u8 = 3;
+
+/// Decimales del token de crédito EPR (1 token = 1 kg de envase recuperado)
+pub const EPR_MINT_DECIMALS: u8 = 3;
+
+/// Máximo de SKUs en el registro de depósito por envase
+pub const MAX_REGISTERED_SKUS: usize = 100;
+/// Máximo de envases escaneados en un depósito por unidades
+pub const MAX_ITEMS_PER_DEPOSIT: usize = 32;
+/// Máximo de depósitos por peso en un lote (límite de cómputo por transacción)
+pub const MAX_BATCH_DEPOSITS: usize = 16;
+/// Máximo avance de la secuencia de recibos de un contenedor en un depósito
+pub const MAX_RECEIPT_SEQUENCE_GAP: u64 = 1_000;
+/// Números de serie cubiertos por cada cuenta de bitmap (512 bytes)
+pub const SERIAL_BITMAP_BITS: u64 = 4_096;
+
+/// Máximo de recolecciones agrupadas en un envío
+pub const MAX_SHIPMENT_COLLECTIONS: usize = 16;
+
+/// Umbral de llenado por defecto para solicitar recolección (80%)
+pub const DEFAULT_FILL_THRESHOLD_BPS: u16 = 8_000;
+/// Plazo por defecto para completar una recolección asignada en despliegues
+/// nuevos (24 horas); `GlobalState` migrado empieza sin flujo de SLA
+pub const DEFAULT_COLLECTION_SLA_SECS: i64 = SECONDS_PER_DAY;
+
+/// Retención mínima por defecto de los registros antes de poder cerrarlos (90 días)
+pub const DEFAULT_RECORD_RETENTION_SECS: i64 = 90 * SECONDS_PER_DAY;
+
+/// Plazo para reclamar un depósito de invitado antes de que sus ECOC vuelvan a la tesorería (30 días)
+pub const GUEST_CLAIM_WINDOW_SECS: i64 = 30 * SECONDS_PER_DAY;
+
 /// EcoBottle: Plataforma Descentralizada de Economía Circular
 ///
 /// Este programa implementa:
@@ -30,6 +138,24 @@ pub mod ecobottle {
         global_state.total_deposits = 0;
         global_state.total_users = 0;
         global_state.total_containers = 0;
+        global_state.discrepancy_tolerance_bps = DEFAULT_DISCREPANCY_TOLERANCE_BPS;
+        global_state.ecoc_mint = ctx.accounts.ecoc_mint.key();
+        global_state.record_retention_secs = DEFAULT_RECORD_RETENTION_SECS;
+        global_state.deposit_tree = Pubkey::default();
+        global_state.compressed_deposits = 0;
+        global_state.referral_bonus_per_kg = 0;
+        global_state.referral_max_grams = 0;
+        global_state.referral_cap_per_referrer = 0;
+        global_state.collection_sla_secs = DEFAULT_COLLECTION_SLA_SECS;
+        global_state.collector_reward_per_kg = 0;
+        global_state.collector_sla_bonus_bps = 0;
+        global_state.collector_reward_budget = 0;
+        global_state.emission_factors = DEFAULT_EMISSION_FACTORS;
+        global_state.total_co2_avoided_grams = 0;
+        global_state.impact_mint = Pubkey::default();
+        global_state.total_co2_certified_grams = 0;
+        global_state.total_co2_retired_grams = 0;
+        global_state.version = CURRENT_ACCOUNT_VERSION;
         global_state.bump = ctx.bumps.global_state;
 
         msg!("🌍 EcoBottle inicializado - {} ECOC por KG", ecoc_per_kg);
@@ -37,11 +163,29 @@ pub mod ecobottle {
     }
 
     /// Registra un nuevo usuario en la plataforma
+    /// Opcionalmente registra al usuario que lo invitó (programa de referidos)
+    /// La renta la paga `payer`, que puede ser un relayer de la plataforma
     pub fn register_user(
         ctx: Context<RegisterUser>,
         username: String,
+        referrer: Option<Pubkey>,
     ) -> Result<()> {
-        require!(username.len() <= 32, ErrorCode::UsernameTooLong);
+        validate_username(&username)?;
+
+        let user_key = ctx.accounts.user.key();
+        if let Some(referrer) = referrer {
+            require_keys_neq!(referrer, user_key, ErrorCode::SelfReferral);
+
+            let referrer_profile = ctx
+                .accounts
+                .referrer_profile
+                .as_mut()
+                .ok_or(ErrorCode::MissingReferrerAccounts)?;
+            require_keys_eq!(referrer_profile.owner, referrer, ErrorCode::ReferrerMismatch);
+            require!(referrer_profile.referrer != Some(user_key), ErrorCode::SelfReferral);
+
+            referrer_profile.referral_count += 1;
+        }
 
         let user_profile = &mut ctx.accounts.user_profile;
         let global_state = &mut ctx.accounts.global_state;
@@ -53,14 +197,108 @@ pub mod ecobottle {
         user_profile.total_ecoc_earned = 0;
         user_profile.total_ecoc_spent = 0;
         user_profile.created_at = Clock::get()?.unix_timestamp;
+        user_profile.reputation_score = REPUTATION_INITIAL;
+        user_profile.current_streak = 0;
+        user_profile.longest_streak = 0;
+        user_profile.weekly_streak = 0;
+        user_profile.last_deposit_at = 0;
+        user_profile.period_week = 0;
+        user_profile.week_weight_grams = 0;
+        user_profile.period_month = 0;
+        user_profile.month_weight_grams = 0;
+        user_profile.referrer = referrer;
+        user_profile.referred_weight_grams = 0;
+        user_profile.referral_count = 0;
+        user_profile.referral_earnings = 0;
+        user_profile.avatar_uri = String::new();
+        user_profile.preferred_container = Pubkey::default();
+        user_profile.archived_deposits = 0;
+        user_profile.deposit_history_root = [0; 32];
+        user_profile.archived_redemptions = 0;
+        user_profile.redemption_history_root = [0; 32];
+        user_profile.is_suspended = false;
+        user_profile.referral_cap_exhausted_at = 0;
+        user_profile.version = CURRENT_ACCOUNT_VERSION;
         user_profile.bump = ctx.bumps.user_profile;
 
+        let username_record = &mut ctx.accounts.username_record;
+        username_record.owner = user_key;
+        username_record.username = username.clone();
+        username_record.payer = ctx.accounts.payer.key();
+        username_record.version = CURRENT_ACCOUNT_VERSION;
+        username_record.bump = ctx.bumps.username_record;
+
         global_state.total_users += 1;
 
         msg!("👤 Usuario registrado: {}", username);
         Ok(())
     }
 
+    /// Cambia el nombre de usuario liberando el anterior en el registro
+    pub fn update_username(
+        ctx: Context<UpdateUsername>,
+        new_username: String,
+    ) -> Result<()> {
+        validate_username(&new_username)?;
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        let old_username = user_profile.username.clone();
+
+        // Un cambio solo de mayúsculas conserva la misma reserva
+        if username_seed(&old_username) == username_seed(&new_username) {
+            ctx.accounts.old_username_record.username = new_username.clone();
+        } else {
+            let username_record = ctx
+                .accounts
+                .new_username_record
+                .as_mut()
+                .ok_or(ErrorCode::MissingUsernameRecord)?;
+            username_record.owner = ctx.accounts.user.key();
+            username_record.username = new_username.clone();
+            username_record.payer = ctx.accounts.payer.key();
+            username_record.version = CURRENT_ACCOUNT_VERSION;
+            username_record.bump = ctx.bumps.new_username_record.ok_or(ErrorCode::MissingUsernameRecord)?;
+
+            ctx.accounts.old_username_record.close(ctx.accounts.payer.to_account_info())?;
+        }
+
+        user_profile.username = new_username.clone();
+
+        msg!("👤 Usuario renombrado: {} -> {}", old_username, new_username);
+        Ok(())
+    }
+
+    /// Actualiza los datos opcionales del perfil (avatar y contenedor preferido)
+    /// `Pubkey::default()` como contenedor preferido lo elimina
+    pub fn update_profile(
+        ctx: Context<UpdateProfile>,
+        avatar_uri: Option<String>,
+        preferred_container: Option<Pubkey>,
+    ) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        if let Some(uri) = avatar_uri {
+            require!(uri.len() <= MAX_AVATAR_URI_LEN, ErrorCode::AvatarUriTooLong);
+            user_profile.avatar_uri = uri;
+            msg!("🖼️ Nuevo avatar: {}", user_profile.avatar_uri);
+        }
+
+        if let Some(container_key) = preferred_container {
+            if container_key != Pubkey::default() {
+                let container = ctx
+                    .accounts
+                    .preferred_container
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingPreferredContainer)?;
+                require_keys_eq!(container.key(), container_key, ErrorCode::MissingPreferredContainer);
+            }
+            user_profile.preferred_container = container_key;
+            msg!("📦 Contenedor preferido: {}", container_key);
+        }
+
+        Ok(())
+    }
+
     /// Registra un Contenedor Inteligente como NFT en la blockchain
     /// Solo la autoridad del programa puede registrar contenedores
     pub fn register_container(
@@ -68,6 +306,7 @@ pub mod ecobottle {
         container_id: String,
         location: String,
         capacity_kg: u64,
+        material: Material,
     ) -> Result<()> {
         require!(container_id.len() <= 32, ErrorCode::ContainerIdTooLong);
         require!(location.len() <= 64, ErrorCode::LocationTooLong);
@@ -84,6 +323,16 @@ pub mod ecobottle {
         container.is_active = true;
         container.created_at = Clock::get()?.unix_timestamp;
         container.last_collection = 0;
+        container.archived_collections = 0;
+        container.collection_history_root = [0; 32];
+        container.last_receipt_hash = [0; 32];
+        container.fill_threshold_bps = DEFAULT_FILL_THRESHOLD_BPS;
+        container.needs_collection = false;
+        container.open_request = Pubkey::default();
+        container.collection_requests = 0;
+        container.material = material;
+        container.co2_avoided_grams = 0;
+        container.version = CURRENT_ACCOUNT_VERSION;
         container.bump = ctx.bumps.container;
 
         global_state.total_containers += 1;
@@ -94,54 +343,169 @@ pub mod ecobottle {
 
     /// Procesa un depósito de PET y emite recompensa automáticamente
     /// Este es el corazón de la dApp - validación y recompensa instantánea
-    pub fn process_deposit(
-        ctx: Context<ProcessDeposit>,
+    ///
+    /// El depósito se registra en una cuenta `DepositRecord` o, en modo
+    /// comprimido, como hoja del árbol de Merkle de depósitos
+    ///
+    /// `receipt_hash` es el hash del recibo o nonce de atestación emitido por
+    /// el contenedor, que enlaza el depósito con el registro del sensor;
+    /// `receipt_sequence` es su número de recibo, que debe crecer en cada
+    /// depósito del contenedor para que un recibo aceptado no se repita; el
+    /// peso y el recibo los atesta con su firma el operador del contenedor
+    ///
+    /// Las tablas de clasificación del periodo en curso (global o del
+    /// contenedor) se pasan opcionalmente en `remaining_accounts`
+    ///
+    /// El usuario solo autoriza: la renta y las comisiones las puede pagar
+    /// `payer` (relayer u operador del contenedor)
+    pub fn process_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessDeposit<'info>>,
         weight_grams: u64,
+        receipt_hash: [u8; 32],
+        receipt_sequence: u64,
     ) -> Result<()> {
         let global_state = &mut ctx.accounts.global_state;
         let container = &mut ctx.accounts.container;
         let user_profile = &mut ctx.accounts.user_profile;
-        let deposit_record = &mut ctx.accounts.deposit_record;
 
         // Validaciones
-        require!(container.is_active, ErrorCode::ContainerInactive);
-        require!(weight_grams >= global_state.min_deposit_weight, ErrorCode::WeightTooLow);
-
-        let weight_kg = weight_grams as f64 / 1000.0;
-        let current_capacity = container.current_weight as f64 / 1000.0;
         require!(
-            current_capacity + weight_kg <= container.capacity_kg as f64,
-            ErrorCode::ContainerFull
+            ctx.accounts.deposit_record.is_some() != ctx.accounts.deposit_tree.is_some(),
+            ErrorCode::InvalidDepositLogMode
         );
+        container.check_receipt(&receipt_hash, receipt_sequence)?;
 
-        // Calcular recompensa en tokens ECOC
-        let ecoc_reward = (weight_grams * global_state.ecoc_per_kg) / 1000;
+        // Recompensa (ajustada por reputación), bono de referido y CO₂ evitado:
+        // la misma cotización que devuelve `quote_deposit_reward`
+        let quote = quote_deposit(
+            global_state,
+            container,
+            user_profile,
+            ctx.accounts.referrer_profile.as_deref(),
+            weight_grams,
+        )?;
+        let ecoc_reward = quote.ecoc_reward;
+        let co2_grams = quote.co2_grams;
 
         // Guardar datos antes de tomar referencias mutables
         let user_key = ctx.accounts.user.key();
         let container_key = container.key();
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        let nonce = global_state.total_deposits;
 
         // Registrar el depósito en blockchain (trazabilidad)
-        deposit_record.user = user_key;
-        deposit_record.container = container_key;
-        deposit_record.weight_grams = weight_grams;
-        deposit_record.ecoc_reward = ecoc_reward;
-        deposit_record.timestamp = Clock::get()?.unix_timestamp;
-        deposit_record.tx_signature = Clock::get()?.unix_timestamp as u64; // Simulado
-        deposit_record.bump = ctx.bumps.deposit_record;
+        let mut compressed_leaf = None;
+        if let (Some(deposit_record), Some(bump)) = (ctx.accounts.deposit_record.as_mut(), ctx.bumps.deposit_record) {
+            // Un registro del mismo segundo que la última recolección quedaría
+            // cubierto por ella (`CollectionRecord::covers`) sin contar en ella
+            require!(now > container.last_collection, ErrorCode::DepositDuringCollection);
+            container.open_deposit_records += 1;
+
+            deposit_record.user = user_key;
+            deposit_record.container = container_key;
+            deposit_record.weight_grams = weight_grams;
+            deposit_record.ecoc_reward = ecoc_reward;
+            deposit_record.timestamp = now;
+            deposit_record.slot = clock.slot;
+            deposit_record.receipt_hash = receipt_hash;
+            deposit_record.penalized = false;
+            deposit_record.migrated = false;
+            deposit_record.payer = ctx.accounts.payer.key();
+            deposit_record.version = CURRENT_ACCOUNT_VERSION;
+            deposit_record.bump = bump;
+        } else {
+            // Modo comprimido: la hoja se añade al árbol tras actualizar el estado
+            let leaf = DepositLeaf {
+                user: user_key,
+                container: container_key,
+                weight_grams,
+                ecoc_reward,
+                timestamp: now,
+                slot: clock.slot,
+                receipt_hash,
+                nonce,
+            };
+            compressed_leaf = Some((global_state.compressed_deposits, leaf));
+            global_state.compressed_deposits += 1;
+        }
 
         // Actualizar estadísticas del contenedor
-        container.current_weight += weight_grams;
-        container.total_deposits += 1;
+        container.record_deposit(weight_grams, co2_grams, receipt_hash, receipt_sequence, now);
+
+        // Envase etiquetado con una marca (código de barras leído por el contenedor):
+        // la atribución la firma el operador del contenedor, no el usuario
+        if let Some(brand) = ctx.accounts.brand.as_mut() {
+            require!(brand.is_active, ErrorCode::BrandNotActive);
+            brand.recovered_grams += weight_grams;
+            brand.recovered_by_material[container.material as usize] += weight_grams;
+            brand.tagged_deposits += 1;
+
+            emit!(BrandedDeposit {
+                brand: brand.key(),
+                user: user_key,
+                container: container_key,
+                weight_grams,
+                receipt_hash,
+            });
+            msg!("🏷️ Envase de {} recuperado", brand.name);
+        }
+
+        raise_fill_alert(container_key, container);
 
         // Actualizar perfil del usuario
-        user_profile.total_deposits += 1;
-        user_profile.total_pet_weight += weight_grams;
-        user_profile.total_ecoc_earned += ecoc_reward;
+        user_profile.record_deposit(weight_grams, ecoc_reward, co2_grams, now);
+
+        // Actualizar tablas de clasificación del periodo; las tablas de un
+        // contenedor van seguidas de la cuenta de puntuación del usuario
+        let mut seen_leaderboards: Vec<Pubkey> = Vec::new();
+        let mut remaining = ctx.remaining_accounts.iter();
+        while let Some(account_info) = remaining.next() {
+            require!(!seen_leaderboards.contains(account_info.key), ErrorCode::DuplicateLeaderboard);
+            seen_leaderboards.push(account_info.key());
+
+            let mut leaderboard = Account::<Leaderboard>::try_from(account_info)?;
+            require!(!leaderboard.is_closed, ErrorCode::LeaderboardClosed);
+            require!(
+                leaderboard.period_index == leaderboard.period_kind.index_at(now),
+                ErrorCode::LeaderboardPeriodMismatch
+            );
+
+            let score = if leaderboard.scope == Pubkey::default() {
+                user_profile.period_weight(leaderboard.period_kind)
+            } else {
+                require_keys_eq!(leaderboard.scope, container_key, ErrorCode::LeaderboardScopeMismatch);
+                let score_info = remaining.next().ok_or(ErrorCode::InvalidLeaderboardScore)?;
+                credit_leaderboard_score(
+                    leaderboard.key(),
+                    user_key,
+                    score_info,
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    weight_grams,
+                )?
+            };
+            leaderboard.upsert(user_key, score);
+            leaderboard.exit(&crate::ID)?;
+        }
+
+        // Bono de referido: ECOC para quien invitó al usuario por sus primeros kg
+        let referral_bonus = quote.referral_bonus;
+        if quote.referral_grams > 0 {
+            let referrer_profile = ctx
+                .accounts
+                .referrer_profile
+                .as_mut()
+                .ok_or(ErrorCode::MissingReferrerAccounts)?;
+
+            user_profile.referred_weight_grams += quote.referral_grams;
+            referrer_profile.referral_earnings += referral_bonus;
+        } else if quote.referral_cap_exhausted {
+            user_profile.referral_cap_exhausted_at = global_state.referral_cap_per_referrer;
+        }
 
         // Actualizar estadísticas globales
-        global_state.total_pet_collected += weight_grams;
-        global_state.total_deposits += 1;
+        global_state.record_deposit(weight_grams, co2_grams);
 
         // Mintear tokens ECOC directamente a la wallet del usuario
         let bump = global_state.bump;
@@ -161,423 +525,5959 @@ pub mod ecobottle {
 
         token::mint_to(cpi_ctx, ecoc_reward)?;
 
-        msg!("♻️ Depósito procesado: {}g de PET = {} ECOC", weight_grams, ecoc_reward);
+        if referral_bonus > 0 {
+            let referrer_token_account = ctx
+                .accounts
+                .referrer_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingReferrerAccounts)?;
+            require!(
+                user_profile.referrer == Some(referrer_token_account.owner),
+                ErrorCode::ReferrerMismatch
+            );
+
+            let cpi_accounts = MintTo {
+                mint: ctx.accounts.ecoc_mint.to_account_info(),
+                to: referrer_token_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), referral_bonus)?;
+
+            msg!("🤝 Bono de referido: {} ECOC", referral_bonus);
+        }
+
+        if let Some((leaf_index, leaf)) = compressed_leaf {
+            let deposit_tree = ctx.accounts.deposit_tree.as_ref().ok_or(ErrorCode::InvalidDepositLogMode)?;
+            let compression_program = ctx
+                .accounts
+                .compression_program
+                .as_ref()
+                .ok_or(ErrorCode::InvalidDepositLogMode)?;
+            let noop_program = ctx.accounts.noop_program.as_ref().ok_or(ErrorCode::InvalidDepositLogMode)?;
+
+            append_compressed_leaf(
+                compression_program,
+                deposit_tree,
+                &ctx.accounts.global_state.to_account_info(),
+                noop_program,
+                leaf.hash(),
+                signer,
+            )?;
+
+            emit!(DepositCompressed {
+                tree: deposit_tree.key(),
+                leaf_index,
+                leaf,
+            });
+            msg!("🌳 Depósito comprimido en la hoja #{}", leaf_index);
+        }
+
+        emit!(DepositProcessed {
+            user: user_key,
+            container: container_key,
+            nonce,
+            weight_grams,
+            ecoc_reward,
+            referral_bonus,
+            co2_grams,
+            receipt_hash,
+            timestamp: now,
+        });
+
+        msg!("♻️ Depósito procesado: {}g de {:?} = {} ECOC", weight_grams, container.material, ecoc_reward);
         msg!("📍 Contenedor: {} | Usuario: {}", container.container_id, user_profile.username);
+        msg!("🌱 CO₂ evitado: {}g", co2_grams);
+        msg!("⭐ Reputación: {} | Racha: {} días / {} semanas",
+             user_profile.reputation_score,
+             user_profile.current_streak,
+             user_profile.weekly_streak);
 
         Ok(())
     }
 
-    /// Canjea tokens ECOC por productos/servicios en el marketplace
-    /// Los tokens canjeados son quemados (deflacionario)
-    pub fn redeem_tokens(
-        ctx: Context<RedeemTokens>,
-        amount: u64,
-        product_id: String,
+    /// Procesa en una transacción los depósitos que un contenedor acumuló sin
+    /// conexión; firma el operador del contenedor en lugar de cada usuario y
+    /// cada depósito se registra como hoja del árbol de Merkle
+    ///
+    /// En `remaining_accounts` van pares (perfil, token account ECOC) de los
+    /// usuarios del lote y de sus referentes mientras el bono aplique; cada
+    /// depósito indica el par de su usuario y los ECOC se acuñan una sola vez
+    /// por wallet
+    ///
+    /// Cada depósito lleva el momento de la lectura, que no puede ser futuro ni
+    /// anterior al último depósito del contenedor; es el que se registra en la
+    /// hoja y en la actividad del perfil, sin retroceder respecto a su último
+    /// depósito para no romper rachas ni periodos. Del peso responde el
+    /// operador, que firma la transacción
+    ///
+    /// El lote no admite marcas ni tablas de clasificación: la marca la atesta
+    /// el operador depósito a depósito en `process_deposit`, y el peso del lote
+    /// suma al periodo de cada perfil, que las tablas recogen en su siguiente
+    /// depósito en línea
+    pub fn process_deposit_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessDepositBatch<'info>>,
+        deposits: Vec<BatchDeposit>,
     ) -> Result<()> {
-        require!(product_id.len() <= 32, ErrorCode::ProductIdTooLong);
+        require!(
+            !deposits.is_empty() && deposits.len() <= MAX_BATCH_DEPOSITS,
+            ErrorCode::InvalidBatchSize
+        );
+        require!(ctx.remaining_accounts.len() % 2 == 0, ErrorCode::InvalidBatchAccounts);
 
-        let user_profile = &mut ctx.accounts.user_profile;
-        let redemption = &mut ctx.accounts.redemption_record;
+        // Pares (perfil, token account ECOC) sin repetir
+        let ecoc_mint = ctx.accounts.ecoc_mint.key();
+        let mut profiles: Vec<Account<UserProfile>> = Vec::new();
+        let mut token_accounts: Vec<Account<TokenAccount>> = Vec::new();
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let profile = Account::<UserProfile>::try_from(&pair[0])?;
+            let token_account = Account::<TokenAccount>::try_from(&pair[1])?;
+            require!(
+                token_account.owner == profile.owner && token_account.mint == ecoc_mint,
+                ErrorCode::InvalidBatchAccounts
+            );
+            require!(
+                profiles.iter().all(|other| other.key() != profile.key()),
+                ErrorCode::InvalidBatchAccounts
+            );
+            profiles.push(profile);
+            token_accounts.push(token_account);
+        }
+        let mut minted = vec![0u64; profiles.len()];
 
-        // Validar que el usuario tenga suficientes tokens
-        let user_balance = ctx.accounts.user_token_account.amount;
-        require!(user_balance >= amount, ErrorCode::InsufficientTokens);
+        let global_state = &mut ctx.accounts.global_state;
+        let container = &mut ctx.accounts.container;
+        let container_key = container.key();
+        let tree_key = ctx.accounts.deposit_tree.key();
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
 
-        // Quemar los tokens ECOC (sacarlos de circulación)
-        let cpi_accounts = Burn {
-            mint: ctx.accounts.ecoc_mint.to_account_info(),
-            from: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        let bump = global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
 
-        token::burn(cpi_ctx, amount)?;
+        // Cada depósito se valora con el estado que dejó el anterior, igual
+        // que si se hubieran enviado uno a uno
+        let mut seen_receipts: Vec<[u8; 32]> = Vec::new();
+        for deposit in deposits.iter() {
+            let index = deposit.user_index as usize;
+            require!(index < profiles.len(), ErrorCode::InvalidBatchAccounts);
+            require!(!seen_receipts.contains(&deposit.receipt_hash), ErrorCode::DuplicateReceipt);
+            seen_receipts.push(deposit.receipt_hash);
+            container.check_receipt(&deposit.receipt_hash, deposit.receipt_sequence)?;
+            container.check_deposit_time(deposit.timestamp, now)?;
 
-        // Registrar el canje
-        redemption.user = ctx.accounts.user.key();
-        redemption.product_id = product_id.clone();
-        redemption.amount = amount;
-        redemption.timestamp = Clock::get()?.unix_timestamp;
-        redemption.bump = ctx.bumps.redemption_record;
+            let referrer_index = profiles[index]
+                .referrer
+                .and_then(|referrer| profiles.iter().position(|profile| profile.owner == referrer));
+            let quote = quote_deposit(
+                global_state,
+                container,
+                &profiles[index],
+                referrer_index.map(|i| &*profiles[i]),
+                deposit.weight_grams,
+            )?;
 
-        // Actualizar perfil del usuario
-        user_profile.total_ecoc_spent += amount;
+            let leaf_index = global_state.compressed_deposits;
+            let leaf = DepositLeaf {
+                user: profiles[index].owner,
+                container: container_key,
+                weight_grams: deposit.weight_grams,
+                ecoc_reward: quote.ecoc_reward,
+                timestamp: deposit.timestamp,
+                slot: clock.slot,
+                receipt_hash: deposit.receipt_hash,
+                nonce: global_state.total_deposits,
+            };
+            append_compressed_leaf(
+                &ctx.accounts.compression_program,
+                &ctx.accounts.deposit_tree,
+                &global_state.to_account_info(),
+                &ctx.accounts.noop_program,
+                leaf.hash(),
+                signer,
+            )?;
 
-        msg!("🛍️ Canje exitoso: {} ECOC por producto {}", amount, product_id);
-        msg!("🔥 Tokens quemados - Deflación activa");
+            emit!(DepositProcessed {
+                user: leaf.user,
+                container: container_key,
+                nonce: leaf.nonce,
+                weight_grams: deposit.weight_grams,
+                ecoc_reward: quote.ecoc_reward,
+                referral_bonus: quote.referral_bonus,
+                co2_grams: quote.co2_grams,
+                receipt_hash: deposit.receipt_hash,
+                timestamp: deposit.timestamp,
+            });
+            emit!(DepositCompressed {
+                tree: tree_key,
+                leaf_index,
+                leaf,
+            });
+
+            container.record_deposit(
+                deposit.weight_grams,
+                quote.co2_grams,
+                deposit.receipt_hash,
+                deposit.receipt_sequence,
+                deposit.timestamp,
+            );
+            raise_fill_alert(container_key, container);
+
+            let deposited_at = deposit.timestamp.max(profiles[index].last_deposit_at);
+            profiles[index].record_deposit(deposit.weight_grams, quote.ecoc_reward, quote.co2_grams, deposited_at);
+            minted[index] += quote.ecoc_reward;
+
+            // `quote_deposit` exige el perfil del referente mientras el bono aplique
+            if let Some(referrer_index) = referrer_index.filter(|_| quote.referral_grams > 0) {
+                profiles[index].referred_weight_grams += quote.referral_grams;
+                profiles[referrer_index].referral_earnings += quote.referral_bonus;
+                minted[referrer_index] += quote.referral_bonus;
+            } else if quote.referral_cap_exhausted {
+                profiles[index].referral_cap_exhausted_at = global_state.referral_cap_per_referrer;
+            }
+
+            global_state.compressed_deposits += 1;
+            global_state.record_deposit(deposit.weight_grams, quote.co2_grams);
+        }
+
+        // Mintear lo acumulado por cada wallet (usuarios y referentes)
+        for (token_account, amount) in token_accounts.iter().zip(minted.iter()) {
+            if *amount == 0 {
+                continue;
+            }
+            let cpi_accounts = MintTo {
+                mint: ctx.accounts.ecoc_mint.to_account_info(),
+                to: token_account.to_account_info(),
+                authority: global_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), *amount)?;
+        }
 
+        for profile in profiles.iter() {
+            profile.exit(&crate::ID)?;
+        }
+
+        msg!("📦 Lote de {} depósitos en {}: {} ECOC para {} wallets",
+             deposits.len(),
+             container.container_id,
+             minted.iter().sum::<u64>(),
+             minted.iter().filter(|amount| **amount > 0).count());
         Ok(())
     }
 
-    /// Registra la recolección de un contenedor por una empresa certificada
-    /// Genera un reporte de trazabilidad verificable en blockchain
-    pub fn collect_container(
-        ctx: Context<CollectContainer>,
+    /// Procesa el depósito de un invitado sin wallet ni perfil
+    /// El contenedor entrega al invitado un código de reclamo y solo publica su
+    /// compromiso (`guest_claim_commitment`); los ECOC quedan en una bóveda
+    /// hasta que se revele el código o venza el plazo de reclamo
+    pub fn process_guest_deposit(
+        ctx: Context<ProcessGuestDeposit>,
+        claim_commitment: [u8; 32],
+        weight_grams: u64,
+        receipt_hash: [u8; 32],
+        receipt_sequence: u64,
     ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
         let container = &mut ctx.accounts.container;
-        let collection_record = &mut ctx.accounts.collection_record;
 
-        require!(container.is_active, ErrorCode::ContainerInactive);
-        require!(container.current_weight > 0, ErrorCode::ContainerEmpty);
+        container.check_receipt(&receipt_hash, receipt_sequence)?;
+
+        // Sin perfil no hay multiplicador de reputación ni bono de referido
+        let quote = quote_guest_deposit(global_state, container, weight_grams)?;
+        let ecoc_reward = quote.ecoc_reward;
+        let co2_grams = quote.co2_grams;
 
-        let collector = ctx.accounts.collector.key();
         let container_key = container.key();
-        let weight_collected = container.current_weight;
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
 
-        // Crear registro de recolección (trazabilidad certificada)
-        collection_record.container = container_key;
-        collection_record.collector = collector;
-        collection_record.weight_collected = weight_collected;
-        collection_record.timestamp = Clock::get()?.unix_timestamp;
-        collection_record.verified = true;
-        collection_record.bump = ctx.bumps.collection_record;
+        let guest_deposit = &mut ctx.accounts.guest_deposit;
+        guest_deposit.claim_commitment = claim_commitment;
+        guest_deposit.container = container_key;
+        guest_deposit.weight_grams = weight_grams;
+        guest_deposit.ecoc_reward = ecoc_reward;
+        guest_deposit.co2_grams = co2_grams;
+        guest_deposit.timestamp = now;
+        guest_deposit.slot = clock.slot;
+        guest_deposit.expires_at = now + GUEST_CLAIM_WINDOW_SECS;
+        guest_deposit.receipt_hash = receipt_hash;
+        guest_deposit.payer = ctx.accounts.payer.key();
+        guest_deposit.version = CURRENT_ACCOUNT_VERSION;
+        guest_deposit.bump = ctx.bumps.guest_deposit;
 
-        // Actualizar contenedor
-        container.last_collection = Clock::get()?.unix_timestamp;
-        container.current_weight = 0; // Vaciar contenedor
+        // El envase cuenta para el contenedor y el total global aunque aún
+        // no tenga dueño
+        container.record_deposit(weight_grams, co2_grams, receipt_hash, receipt_sequence, now);
+        raise_fill_alert(container_key, container);
+        global_state.record_deposit(weight_grams, co2_grams);
 
-        msg!("🚛 Recolección certificada por blockchain");
-        msg!("📊 Contenedor: {} | Peso: {}g | Recolector: {:?}",
-             container.container_id,
-             collection_record.weight_collected,
-             collector);
+        // Mintear la recompensa a la bóveda del depósito
+        let bump = global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.ecoc_mint.to_account_info(),
+            to: ctx.accounts.escrow.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), ecoc_reward)?;
+
+        emit!(GuestDepositCreated {
+            guest_deposit: ctx.accounts.guest_deposit.key(),
+            container: container_key,
+            weight_grams,
+            ecoc_reward,
+            co2_grams,
+            receipt_hash,
+            expires_at: ctx.accounts.guest_deposit.expires_at,
+        });
 
+        msg!("🎟️ Depósito de invitado: {}g de {:?} = {} ECOC por reclamar", weight_grams, container.material, ecoc_reward);
         Ok(())
     }
 
-    /// Actualiza la configuración del programa (solo autoridad)
-    pub fn update_config(
-        ctx: Context<UpdateConfig>,
-        new_ecoc_per_kg: Option<u64>,
-        new_min_weight: Option<u64>,
+    /// Primer paso del reclamo de un depósito de invitado: quien reclama
+    /// publica `guest_claim_binding(código, su wallet)` sin revelar el código
+    /// Quien observe después el código en el reclamo no puede usarlo: su
+    /// compromiso tendría que ser de un slot anterior
+    pub fn commit_guest_claim(
+        ctx: Context<CommitGuestClaim>,
+        binding: [u8; 32],
     ) -> Result<()> {
-        let global_state = &mut ctx.accounts.global_state;
-
-        if let Some(rate) = new_ecoc_per_kg {
-            global_state.ecoc_per_kg = rate;
-            msg!("💰 Nueva tasa: {} ECOC por KG", rate);
-        }
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp < ctx.accounts.guest_deposit.expires_at,
+            ErrorCode::GuestDepositExpired
+        );
 
-        if let Some(weight) = new_min_weight {
-            global_state.min_deposit_weight = weight;
-            msg!("⚖️ Nuevo peso mínimo: {}g", weight);
-        }
+        let claim_intent = &mut ctx.accounts.claim_intent;
+        claim_intent.guest_deposit = ctx.accounts.guest_deposit.key();
+        claim_intent.claimer = ctx.accounts.claimer.key();
+        claim_intent.binding = binding;
+        claim_intent.slot = clock.slot;
+        claim_intent.version = CURRENT_ACCOUNT_VERSION;
+        claim_intent.bump = ctx.bumps.claim_intent;
 
+        msg!("🔏 Reclamo comprometido por {}", claim_intent.claimer);
         Ok(())
     }
 
-    /// Desactiva un contenedor (mantenimiento, daño, etc.)
-    pub fn toggle_container_status(
-        ctx: Context<ToggleContainerStatus>,
+    /// Reclama un depósito de invitado revelando el código del contenedor
+    /// Requiere el compromiso de quien reclama (`commit_guest_claim`) de un
+    /// slot anterior. Los ECOC de la bóveda pasan a la wallet que reclama (y
+    /// se suman a su perfil si lo tiene); la renta vuelve al operador que la pagó
+    pub fn claim_guest_deposit(
+        ctx: Context<ClaimGuestDeposit>,
+        claim_code: [u8; 32],
     ) -> Result<()> {
-        let container = &mut ctx.accounts.container;
-        container.is_active = !container.is_active;
+        let guest_deposit = &ctx.accounts.guest_deposit;
+        let claim_intent = &ctx.accounts.claim_intent;
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
 
-        let status = if container.is_active { "ACTIVO" } else { "INACTIVO" };
-        msg!("📦 Contenedor {} ahora está {}", container.container_id, status);
+        require!(
+            guest_claim_commitment(&claim_code) == guest_deposit.claim_commitment,
+            ErrorCode::InvalidClaimCode
+        );
+        require!(
+            guest_claim_binding(&claim_code, &ctx.accounts.claimer.key()) == claim_intent.binding,
+            ErrorCode::InvalidClaimCode
+        );
+        require!(claim_intent.slot < clock.slot, ErrorCode::ClaimIntentTooRecent);
+        require!(now < guest_deposit.expires_at, ErrorCode::GuestDepositExpired);
+
+        release_guest_escrow(
+            &ctx.accounts.global_state,
+            &ctx.accounts.escrow,
+            ctx.accounts.claimer_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        // Solo totales: la racha y la reputación premian depósitos propios
+        if let Some(user_profile) = ctx.accounts.user_profile.as_mut() {
+            user_profile.total_deposits += 1;
+            user_profile.total_pet_weight += guest_deposit.weight_grams;
+            user_profile.total_ecoc_earned += guest_deposit.ecoc_reward;
+            user_profile.co2_avoided_grams += guest_deposit.co2_grams;
+        }
+
+        emit!(GuestDepositClaimed {
+            guest_deposit: guest_deposit.key(),
+            claimer: ctx.accounts.claimer.key(),
+            ecoc_reward: guest_deposit.ecoc_reward,
+            timestamp: now,
+        });
 
+        msg!("🎟️ Depósito de invitado reclamado: {} ECOC para {}",
+             guest_deposit.ecoc_reward,
+             ctx.accounts.claimer.key());
         Ok(())
     }
-}
 
-// ============================================================================
-// ESTRUCTURAS DE CUENTAS
-// ============================================================================
+    /// Devuelve a la tesorería los ECOC de un depósito de invitado vencido
+    /// Cualquiera puede ejecutarla tras el plazo; la renta vuelve al operador
+    pub fn expire_guest_deposit(
+        ctx: Context<ExpireGuestDeposit>,
+    ) -> Result<()> {
+        let guest_deposit = &ctx.accounts.guest_deposit;
+        let now = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + GlobalState::LEN,
-        seeds = [b"global_state"],
-        bump
-    )]
-    pub global_state: Account<'info, GlobalState>,
+        require!(now >= guest_deposit.expires_at, ErrorCode::GuestDepositNotExpired);
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        release_guest_escrow(
+            &ctx.accounts.global_state,
+            &ctx.accounts.escrow,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
 
-    /// Mint de los tokens ECOC
-    pub ecoc_mint: Account<'info, Mint>,
+        emit!(GuestDepositExpired {
+            guest_deposit: guest_deposit.key(),
+            ecoc_reward: guest_deposit.ecoc_reward,
+            timestamp: now,
+        });
 
-    pub system_program: Program<'info, System>,
-}
+        msg!("⌛ Depósito de invitado vencido: {} ECOC devueltos a la tesorería", guest_deposit.ecoc_reward);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct RegisterUser<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + UserProfile::LEN,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump
-    )]
-    pub user_profile: Account<'info, UserProfile>,
+    /// Cierra el compromiso de reclamo de un depósito de invitado que ya no
+    /// existe (reclamado por otra wallet o vencido) y devuelve su renta a quien
+    /// lo comprometió. Cualquiera puede ejecutarla
+    pub fn close_guest_claim_intent(
+        ctx: Context<CloseGuestClaimIntent>,
+    ) -> Result<()> {
+        msg!("🔏 Compromiso de reclamo cerrado - renta devuelta a {}", ctx.accounts.claimer.key());
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
+    /// Registra una organización (escuela, residencia...) que gestiona perfiles
+    /// de miembros sin wallet - solo autoridad
+    /// Los ECOC de los miembros se custodian en la bóveda de la organización
+    pub fn register_organization(
+        ctx: Context<RegisterOrganization>,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= 32, ErrorCode::OrganizationNameTooLong);
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        let organization = &mut ctx.accounts.organization;
+        organization.admin = ctx.accounts.admin.key();
+        organization.name = name.clone();
+        organization.vault = ctx.accounts.vault.key();
+        organization.is_active = true;
+        organization.registered_at = Clock::get()?.unix_timestamp;
+        organization.member_count = 0;
+        organization.total_deposits = 0;
+        organization.total_pet_weight = 0;
+        organization.ecoc_held = 0;
+        organization.version = CURRENT_ACCOUNT_VERSION;
+        organization.bump = ctx.bumps.organization;
 
-    pub system_program: Program<'info, System>,
-}
+        msg!("🏫 Organización registrada: {}", name);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(container_id: String)]
-pub struct RegisterContainer<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + SmartContainer::LEN,
-        seeds = [b"container", container_id.as_bytes()],
-        bump
-    )]
-    pub container: Account<'info, SmartContainer>,
+    /// Activa o desactiva una organización (solo autoridad)
+    pub fn set_organization_status(
+        ctx: Context<SetOrganizationStatus>,
+        is_active: bool,
+    ) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.is_active = is_active;
 
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
+        let status = if is_active { "ACTIVA" } else { "INACTIVA" };
+        msg!("🏫 Organización {} ahora está {}", organization.name, status);
+        Ok(())
+    }
 
-    #[account(mut, address = global_state.authority)]
+    /// Da de alta a un miembro de la organización (firma su administrador)
+    /// `member_id_hash` es el hash del identificador interno del miembro;
+    /// el identificador en claro nunca llega a la cadena
+    pub fn register_member(
+        ctx: Context<RegisterMember>,
+        member_id_hash: [u8; 32],
+    ) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        require!(organization.is_active, ErrorCode::OrganizationNotActive);
+
+        let member = &mut ctx.accounts.member;
+        member.organization = organization.key();
+        member.member_id_hash = member_id_hash;
+        member.total_deposits = 0;
+        member.total_pet_weight = 0;
+        member.total_ecoc_earned = 0;
+        member.co2_avoided_grams = 0;
+        member.ecoc_balance = 0;
+        member.wallet = Pubkey::default();
+        member.created_at = Clock::get()?.unix_timestamp;
+        member.graduated_at = 0;
+        member.version = CURRENT_ACCOUNT_VERSION;
+        member.bump = ctx.bumps.member;
+
+        organization.member_count += 1;
+
+        msg!("🧒 Miembro #{} de {}", organization.member_count, organization.name);
+        Ok(())
+    }
+
+    /// Procesa el depósito de un miembro de una organización
+    /// El contenedor identifica al miembro (tarjeta o código de la organización)
+    /// y atesta el depósito; los ECOC se acreditan en su saldo custodiado
+    pub fn process_member_deposit(
+        ctx: Context<ProcessMemberDeposit>,
+        weight_grams: u64,
+        receipt_hash: [u8; 32],
+        receipt_sequence: u64,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        let container = &mut ctx.accounts.container;
+        let organization = &mut ctx.accounts.organization;
+        let member = &mut ctx.accounts.member;
+
+        require!(organization.is_active, ErrorCode::OrganizationNotActive);
+        require!(member.wallet == Pubkey::default(), ErrorCode::MemberGraduated);
+        container.check_receipt(&receipt_hash, receipt_sequence)?;
+
+        // Sin wallet no hay reputación ni referidos: recompensa base
+        let quote = quote_guest_deposit(global_state, container, weight_grams)?;
+        let ecoc_reward = quote.ecoc_reward;
+        let co2_grams = quote.co2_grams;
+
+        let container_key = container.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        container.record_deposit(weight_grams, co2_grams, receipt_hash, receipt_sequence, now);
+        raise_fill_alert(container_key, container);
+        global_state.record_deposit(weight_grams, co2_grams);
+
+        member.total_deposits += 1;
+        member.total_pet_weight += weight_grams;
+        member.total_ecoc_earned += ecoc_reward;
+        member.co2_avoided_grams += co2_grams;
+        member.ecoc_balance += ecoc_reward;
+
+        organization.total_deposits += 1;
+        organization.total_pet_weight += weight_grams;
+        organization.ecoc_held += ecoc_reward;
+
+        // Mintear la recompensa a la bóveda de la organización
+        let bump = global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.ecoc_mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), ecoc_reward)?;
+
+        emit!(MemberDepositProcessed {
+            organization: organization.key(),
+            member: member.key(),
+            container: container_key,
+            weight_grams,
+            ecoc_reward,
+            co2_grams,
+            receipt_hash,
+            timestamp: now,
+        });
+
+        msg!("🏫 Depósito de miembro de {}: {}g de {:?} = {} ECOC (saldo {})",
+             organization.name,
+             weight_grams,
+             container.material,
+             ecoc_reward,
+             member.ecoc_balance);
+        Ok(())
+    }
+
+    /// Vincula una wallet propia a un miembro y le transfiere su saldo
+    /// Firman el administrador (responde por la identidad del miembro) y la
+    /// nueva wallet; si ésta ya tiene perfil, los totales del miembro se suman
+    pub fn graduate_member(
+        ctx: Context<GraduateMember>,
+    ) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        let member = &mut ctx.accounts.member;
+        let wallet = ctx.accounts.wallet.key();
+
+        require!(member.wallet == Pubkey::default(), ErrorCode::MemberGraduated);
+
+        let balance = member.ecoc_balance;
+        if balance > 0 {
+            let bump = ctx.accounts.global_state.bump;
+            let seeds = &[
+                b"global_state".as_ref(),
+                &[bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.wallet_token_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), balance)?;
+        }
+
+        member.wallet = wallet;
+        member.graduated_at = Clock::get()?.unix_timestamp;
+        member.ecoc_balance = 0;
+        organization.ecoc_held -= balance;
+
+        // Solo totales, como en el reclamo de depósitos de invitado
+        if let Some(user_profile) = ctx.accounts.user_profile.as_mut() {
+            user_profile.total_deposits += member.total_deposits;
+            user_profile.total_pet_weight += member.total_pet_weight;
+            user_profile.total_ecoc_earned += member.total_ecoc_earned;
+            user_profile.co2_avoided_grams += member.co2_avoided_grams;
+        }
+
+        emit!(MemberGraduated {
+            organization: organization.key(),
+            member: member.key(),
+            wallet,
+            ecoc_withdrawn: balance,
+        });
+
+        msg!("🎓 Miembro de {} vinculado a {}: {} ECOC retirados", organization.name, wallet, balance);
+        Ok(())
+    }
+
+    /// Procesa un depósito por unidades (sistema de depósito y retorno)
+    /// El contenedor escanea el GTIN y el número de serie de cada envase;
+    /// la recompensa es el valor de depósito registrado para cada SKU
+    ///
+    /// Los bitmaps de números de serie de cada (GTIN, bloque) escaneado se
+    /// pasan en `remaining_accounts`, una sola vez cada uno
+    ///
+    /// No crea `DepositRecord` ni actualiza tablas de clasificación: cada envase
+    /// queda registrado por su serie en el bitmap y en `ItemDepositProcessed`,
+    /// y `remaining_accounts` ya lleva los bitmaps. El peso sí suma al periodo
+    /// del perfil, que las tablas recogen en su siguiente depósito a granel
+    pub fn process_item_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessItemDeposit<'info>>,
+        items: Vec<ScannedItem>,
+        receipt_hash: [u8; 32],
+        receipt_sequence: u64,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        let container = &mut ctx.accounts.container;
+        let user_profile = &mut ctx.accounts.user_profile;
+        let sku_registry = &ctx.accounts.sku_registry;
+
+        // Validaciones
+        require!(
+            !items.is_empty() && items.len() <= MAX_ITEMS_PER_DEPOSIT,
+            ErrorCode::InvalidItemCount
+        );
+        require!(container.is_active, ErrorCode::ContainerInactive);
+        require!(!user_profile.is_suspended, ErrorCode::UserSuspended);
+        container.check_receipt(&receipt_hash, receipt_sequence)?;
+
+        // Cada bitmap debe ser el PDA de su (GTIN, bloque) y no repetirse: una
+        // copia sin marcar sobrescribiría al guardar las series ya marcadas
+        let mut serial_bitmaps: Vec<Account<SerialBitmap>> = Vec::new();
+        for account_info in ctx.remaining_accounts.iter() {
+            require!(
+                !serial_bitmaps.iter().any(|b| b.key() == *account_info.key),
+                ErrorCode::DuplicateSerialBitmap
+            );
+            let serial_bitmap = Account::<SerialBitmap>::try_from(account_info)?;
+            let expected = Pubkey::create_program_address(
+                &[
+                    b"serials".as_ref(),
+                    &serial_bitmap.gtin.to_le_bytes(),
+                    &serial_bitmap.bucket.to_le_bytes(),
+                    &[serial_bitmap.bump],
+                ],
+                &crate::ID,
+            )
+            .map_err(|_| error!(ErrorCode::InvalidSerialBitmap))?;
+            require_keys_eq!(serial_bitmap.key(), expected, ErrorCode::InvalidSerialBitmap);
+            serial_bitmaps.push(serial_bitmap);
+        }
+
+        // Valorar cada envase
+        let mut ecoc_reward = 0;
+        let mut weight_grams = 0;
+        for item in items.iter() {
+            let sku = sku_registry.find(item.gtin).ok_or(ErrorCode::SkuNotRegistered)?;
+            ecoc_reward += sku.deposit_value;
+            weight_grams += sku.unit_weight_grams as u64;
+        }
+
+        require!(
+            container.current_weight + weight_grams <= container.capacity_kg * 1000,
+            ErrorCode::ContainerFull
+        );
+
+        // Marcar el número de serie de cada envase como canjeado
+        for item in items.iter() {
+            let bucket = item.serial / SERIAL_BITMAP_BITS;
+            let serial_bitmap = serial_bitmaps
+                .iter_mut()
+                .find(|b| b.gtin == item.gtin && b.bucket == bucket)
+                .ok_or(ErrorCode::MissingSerialBitmap)?;
+            require!(
+                serial_bitmap.mark_redeemed(item.serial % SERIAL_BITMAP_BITS),
+                ErrorCode::SerialAlreadyRedeemed
+            );
+        }
+
+        for serial_bitmap in serial_bitmaps.iter() {
+            serial_bitmap.exit(&crate::ID)?;
+        }
+
+        let container_key = container.key();
+        let co2_grams = global_state.co2_avoided_for(container.material, weight_grams);
+        let now = Clock::get()?.unix_timestamp;
+
+        // Actualizar estadísticas del contenedor
+        container.record_deposit(weight_grams, co2_grams, receipt_hash, receipt_sequence, now);
+        raise_fill_alert(container_key, container);
+
+        // Actualizar perfil del usuario
+        user_profile.record_deposit(weight_grams, ecoc_reward, co2_grams, now);
+
+        // Actualizar estadísticas globales
+        global_state.record_deposit(weight_grams, co2_grams);
+
+        // Mintear el valor de depósito de los envases
+        let bump = global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.ecoc_mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), ecoc_reward)?;
+
+        emit!(ItemDepositProcessed {
+            user: ctx.accounts.user.key(),
+            container: container_key,
+            items,
+            weight_grams,
+            ecoc_reward,
+            receipt_hash,
+        });
+
+        msg!("🍾 Depósito por unidades: {}g = {} ECOC", weight_grams, ecoc_reward);
+        msg!("📍 Contenedor: {} | Usuario: {}", container.container_id, user_profile.username);
+        Ok(())
+    }
+
+    /// Crea el registro de SKUs del sistema de depósito (solo autoridad)
+    pub fn init_sku_registry(
+        ctx: Context<InitSkuRegistry>,
+    ) -> Result<()> {
+        let sku_registry = &mut ctx.accounts.sku_registry;
+        sku_registry.skus = Vec::new();
+        sku_registry.version = CURRENT_ACCOUNT_VERSION;
+        sku_registry.bump = ctx.bumps.sku_registry;
+
+        msg!("🏷️ Registro de SKUs creado");
+        Ok(())
+    }
+
+    /// Registra o actualiza el valor de depósito de un SKU (solo autoridad)
+    pub fn upsert_sku(
+        ctx: Context<UpsertSku>,
+        gtin: u64,
+        deposit_value: u64,
+        unit_weight_grams: u32,
+        is_active: bool,
+    ) -> Result<()> {
+        let sku_registry = &mut ctx.accounts.sku_registry;
+        let entry = SkuEntry {
+            gtin,
+            deposit_value,
+            unit_weight_grams,
+            is_active,
+        };
+
+        match sku_registry.skus.iter_mut().find(|s| s.gtin == gtin) {
+            Some(existing) => *existing = entry,
+            None => {
+                require!(sku_registry.skus.len() < MAX_REGISTERED_SKUS, ErrorCode::SkuRegistryFull);
+                sku_registry.skus.push(entry);
+            }
+        }
+
+        msg!("🏷️ SKU {}: {} ECOC por envase ({}g)", gtin, deposit_value, unit_weight_grams);
+        Ok(())
+    }
+
+    /// Crea el bitmap de números de serie canjeados de un bloque de un GTIN
+    /// Cualquiera puede crearlo pagando la renta
+    pub fn init_serial_bitmap(
+        ctx: Context<InitSerialBitmap>,
+        gtin: u64,
+        bucket: u64,
+    ) -> Result<()> {
+        let serial_bitmap = &mut ctx.accounts.serial_bitmap;
+        serial_bitmap.gtin = gtin;
+        serial_bitmap.bucket = bucket;
+        serial_bitmap.bits = [0; (SERIAL_BITMAP_BITS / 8) as usize];
+        serial_bitmap.version = CURRENT_ACCOUNT_VERSION;
+        serial_bitmap.bump = ctx.bumps.serial_bitmap;
+
+        msg!("🔢 Bitmap de series creado: GTIN {} bloque {}", gtin, bucket);
+        Ok(())
+    }
+
+    /// Inicializa el árbol de Merkle concurrente para el registro comprimido
+    /// de depósitos (solo autoridad). La cuenta del árbol debe crearse antes
+    /// con el tamaño adecuado y como propiedad de SPL Account Compression
+    pub fn init_deposit_tree(
+        ctx: Context<InitDepositTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let bump = ctx.accounts.global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let mut data = COMPRESSION_INIT_EMPTY_MERKLE_TREE.to_vec();
+        data.extend_from_slice(&max_depth.to_le_bytes());
+        data.extend_from_slice(&max_buffer_size.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: SPL_ACCOUNT_COMPRESSION_ID,
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.deposit_tree.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.global_state.key(), true),
+                AccountMeta::new_readonly(SPL_NOOP_ID, false),
+            ],
+            data,
+        };
+        invoke_signed(
+            &instruction,
+            &[
+                ctx.accounts.deposit_tree.to_account_info(),
+                ctx.accounts.global_state.to_account_info(),
+                ctx.accounts.noop_program.to_account_info(),
+                ctx.accounts.compression_program.to_account_info(),
+            ],
+            signer,
+        )?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.deposit_tree = ctx.accounts.deposit_tree.key();
+        global_state.compressed_deposits = 0;
+
+        msg!("🌳 Árbol de depósitos inicializado: profundidad {} | buffer {}", max_depth, max_buffer_size);
+        Ok(())
+    }
+
+    /// Verifica que un depósito comprimido pertenece al árbol
+    /// Los nodos de la prueba de Merkle se pasan en `remaining_accounts`
+    pub fn verify_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyDeposit<'info>>,
+        root: [u8; 32],
+        leaf: DepositLeaf,
+        index: u32,
+    ) -> Result<()> {
+        verify_compressed_leaf(
+            &ctx.accounts.compression_program.to_account_info(),
+            &ctx.accounts.deposit_tree.to_account_info(),
+            ctx.remaining_accounts,
+            root,
+            leaf.hash(),
+            index,
+        )?;
+
+        msg!("✅ Depósito verificado en la hoja #{}: {}g en {}", index, leaf.weight_grams, leaf.container);
+        Ok(())
+    }
+
+    /// Canjea tokens ECOC por productos/servicios en el marketplace
+    /// Los tokens canjeados son quemados (deflacionario)
+    pub fn redeem_tokens(
+        ctx: Context<RedeemTokens>,
+        amount: u64,
+        product_id: String,
+    ) -> Result<()> {
+        require!(product_id.len() <= 32, ErrorCode::ProductIdTooLong);
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        let redemption = &mut ctx.accounts.redemption_record;
+
+        // Validar que el usuario tenga suficientes tokens
+        let user_balance = ctx.accounts.user_token_account.amount;
+        require!(user_balance >= amount, ErrorCode::InsufficientTokens);
+
+        // Quemar los tokens ECOC (sacarlos de circulación)
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.ecoc_mint.to_account_info(),
+            from: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        token::burn(cpi_ctx, amount)?;
+
+        // Registrar el canje
+        redemption.user = ctx.accounts.user.key();
+        redemption.product_id = product_id.clone();
+        redemption.amount = amount;
+        redemption.timestamp = Clock::get()?.unix_timestamp;
+        redemption.payer = ctx.accounts.user.key();
+        redemption.version = CURRENT_ACCOUNT_VERSION;
+        redemption.bump = ctx.bumps.redemption_record;
+
+        // Actualizar perfil del usuario
+        user_profile.total_ecoc_spent += amount;
+
+        emit!(TokensRedeemed {
+            user: redemption.user,
+            product_id: redemption.product_id.clone(),
+            amount,
+            timestamp: redemption.timestamp,
+        });
+
+        msg!("🛍️ Canje exitoso: {} ECOC por producto {}", amount, product_id);
+        msg!("🔥 Tokens quemados - Deflación activa");
+
+        Ok(())
+    }
+
+    /// Registra la recolección de un contenedor por una empresa certificada
+    /// Genera un reporte de trazabilidad verificable en blockchain
+    ///
+    /// Con el flujo de SLA activo (`collection_sla_secs > 0`) recolecta el
+    /// recolector asignado a la solicitud o, sin asignación, la autoridad
+    pub fn collect_container(
+        ctx: Context<CollectContainer>,
+    ) -> Result<()> {
+        let container = &mut ctx.accounts.container;
+        let collection_record = &mut ctx.accounts.collection_record;
+
+        require!(container.is_active, ErrorCode::ContainerInactive);
+        require!(container.current_weight > 0, ErrorCode::ContainerEmpty);
+
+        let collector = ctx.accounts.collector.key();
+        let container_key = container.key();
+        let weight_collected = container.current_weight;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Dentro del SLA: el contenedor no llegó a llenarse y, si había una
+        // solicitud asignada, se recolecta antes de su plazo
+        let mut within_sla = container.fill_level_bps() < 10_000;
+
+        // Con el flujo de SLA activo solo recolecta el recolector asignado a la
+        // solicitud o, si no hay asignación, la autoridad del programa
+        let sla_workflow = ctx.accounts.global_state.collection_sla_secs > 0;
+        let mut assigned = false;
+
+        // Completar la solicitud de recolección abierta, si la hay
+        if container.open_request != Pubkey::default() {
+            let collection_request = ctx
+                .accounts
+                .collection_request
+                .as_mut()
+                .ok_or(ErrorCode::MissingCollectionRequest)?;
+            require_keys_eq!(collection_request.key(), container.open_request, ErrorCode::MissingCollectionRequest);
+            if collection_request.status == CollectionRequestStatus::Claimed {
+                require_keys_eq!(collection_request.assigned_collector, collector, ErrorCode::NotAssignedCollector);
+                within_sla &= now <= collection_request.deadline;
+                assigned = true;
+            }
+
+            collection_request.assigned_collector = collector;
+            collection_request.status = CollectionRequestStatus::Completed;
+            collection_request.completed_at = now;
+            container.open_request = Pubkey::default();
+        }
+        require!(
+            !sla_workflow || assigned || collector == ctx.accounts.global_state.authority,
+            ErrorCode::CollectionNotAssigned
+        );
+
+        // Crear registro de recolección (trazabilidad certificada)
+        collection_record.container = container_key;
+        collection_record.collector = collector;
+        collection_record.weight_collected = weight_collected;
+        collection_record.timestamp = Clock::get()?.unix_timestamp;
+        collection_record.verified = true;
+        collection_record.period_start = container.last_collection;
+        collection_record.measured_weight = 0;
+        collection_record.discrepancy_bps = 0;
+        collection_record.reconciled = false;
+        collection_record.shipped = false;
+        collection_record.material = container.material;
+        collection_record.outstanding_deposits = container.open_deposit_records;
+        collection_record.payer = collector;
+        collection_record.version = CURRENT_ACCOUNT_VERSION;
+        collection_record.bump = ctx.bumps.collection_record;
+
+        // Actualizar contenedor
+        container.last_collection = now;
+        container.current_weight = 0; // Vaciar contenedor
+        container.needs_collection = false;
+        container.open_deposit_records = 0;
+
+        // Incentivo para recolectores certificados, acuñado dentro del
+        // presupuesto de emisión para recolectores
+        let is_certified = ctx
+            .accounts
+            .certified_collector
+            .as_ref()
+            .is_some_and(|certified| certified.is_active);
+        let global_state = &mut ctx.accounts.global_state;
+        let mut collector_reward = 0;
+        let mut sla_bonus = 0;
+        if is_certified {
+            let base_reward = weight_collected * global_state.collector_reward_per_kg / 1000;
+            let bonus = if within_sla {
+                apply_bps(base_reward, global_state.collector_sla_bonus_bps as u64)
+            } else {
+                0
+            };
+            collector_reward = (base_reward + bonus).min(global_state.collector_reward_budget);
+            sla_bonus = bonus.min(collector_reward);
+            global_state.collector_reward_budget -= collector_reward;
+        }
+        collection_record.collector_reward = collector_reward;
+        collection_record.sla_bonus = sla_bonus;
+
+        emit!(ContainerCollected {
+            container: container_key,
+            collector,
+            collection_record: collection_record.key(),
+            weight_collected,
+            collector_reward,
+            sla_bonus,
+            timestamp: now,
+        });
+
+        if collector_reward > 0 {
+            let ecoc_mint = ctx.accounts.ecoc_mint.as_ref().ok_or(ErrorCode::MissingCollectorRewardAccounts)?;
+            let collector_token_account = ctx
+                .accounts
+                .collector_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingCollectorRewardAccounts)?;
+            let token_program = ctx.accounts.token_program.as_ref().ok_or(ErrorCode::MissingCollectorRewardAccounts)?;
+
+            let bump = ctx.accounts.global_state.bump;
+            let seeds = &[
+                b"global_state".as_ref(),
+                &[bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = MintTo {
+                mint: ecoc_mint.to_account_info(),
+                to: collector_token_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            };
+            token::mint_to(
+                CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+                collector_reward,
+            )?;
+
+            msg!("💰 Incentivo al recolector: {} ECOC (bono SLA {})", collector_reward, sla_bonus);
+        }
+
+        msg!("🚛 Recolección certificada por blockchain");
+        msg!("📊 Contenedor: {} | Peso: {}g | Recolector: {:?}",
+             container.container_id,
+             collection_record.weight_collected,
+             collector);
+
+        Ok(())
+    }
+
+    /// Certifica a una empresa recolectora (solo autoridad)
+    pub fn register_collector(
+        ctx: Context<RegisterCollector>,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= 32, ErrorCode::CollectorNameTooLong);
+
+        let certified_collector = &mut ctx.accounts.certified_collector;
+        certified_collector.collector = ctx.accounts.collector.key();
+        certified_collector.name = name.clone();
+        certified_collector.is_active = true;
+        certified_collector.registered_at = Clock::get()?.unix_timestamp;
+        certified_collector.version = CURRENT_ACCOUNT_VERSION;
+        certified_collector.bump = ctx.bumps.certified_collector;
+        certified_collector.total_shipments = 0;
+
+        msg!("🚛 Recolector certificado: {}", name);
+        Ok(())
+    }
+
+    /// Activa o revoca la certificación de un recolector (solo autoridad)
+    pub fn set_collector_status(
+        ctx: Context<SetCollectorStatus>,
+        is_active: bool,
+    ) -> Result<()> {
+        let certified_collector = &mut ctx.accounts.certified_collector;
+        certified_collector.is_active = is_active;
+
+        let status = if is_active { "ACTIVO" } else { "REVOCADO" };
+        msg!("🚛 Recolector {} ahora está {}", certified_collector.name, status);
+        Ok(())
+    }
+
+    /// Configura los incentivos de recolectores (solo autoridad)
+    /// `add_budget` amplía el presupuesto de emisión disponible para incentivos
+    pub fn update_collector_rewards(
+        ctx: Context<UpdateConfig>,
+        new_reward_per_kg: Option<u64>,
+        new_sla_bonus_bps: Option<u16>,
+        add_budget: Option<u64>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        if let Some(reward) = new_reward_per_kg {
+            global_state.collector_reward_per_kg = reward;
+            msg!("🚛 Nuevo incentivo de recolección: {} ECOC por KG", reward);
+        }
+
+        if let Some(bonus) = new_sla_bonus_bps {
+            global_state.collector_sla_bonus_bps = bonus;
+            msg!("🚛 Nuevo bono SLA: {} bps", bonus);
+        }
+
+        if let Some(budget) = add_budget {
+            global_state.collector_reward_budget += budget;
+            msg!("🚛 Presupuesto de incentivos: {} ECOC", global_state.collector_reward_budget);
+        }
+
+        Ok(())
+    }
+
+    /// Configura el umbral de llenado de un contenedor (solo autoridad)
+    pub fn set_fill_threshold(
+        ctx: Context<ToggleContainerStatus>,
+        fill_threshold_bps: u16,
+    ) -> Result<()> {
+        require!(
+            fill_threshold_bps > 0 && fill_threshold_bps <= 10_000,
+            ErrorCode::InvalidFillThreshold
+        );
+
+        let container = &mut ctx.accounts.container;
+        container.fill_threshold_bps = fill_threshold_bps;
+
+        msg!("📦 Contenedor {}: umbral de llenado {} bps", container.container_id, fill_threshold_bps);
+        Ok(())
+    }
+
+    /// Abre una solicitud de recolección para un contenedor que superó su umbral
+    /// Cualquiera puede abrirla (p. ej. el propio contenedor o un bot)
+    pub fn request_collection(
+        ctx: Context<RequestCollection>,
+    ) -> Result<()> {
+        let container = &mut ctx.accounts.container;
+        let collection_request = &mut ctx.accounts.collection_request;
+
+        require!(container.needs_collection, ErrorCode::CollectionNotNeeded);
+        require!(container.open_request == Pubkey::default(), ErrorCode::CollectionAlreadyRequested);
+
+        collection_request.container = container.key();
+        collection_request.requested_at = Clock::get()?.unix_timestamp;
+        collection_request.status = CollectionRequestStatus::Open;
+        collection_request.assigned_collector = Pubkey::default();
+        collection_request.claimed_at = 0;
+        collection_request.deadline = 0;
+        collection_request.completed_at = 0;
+        collection_request.version = CURRENT_ACCOUNT_VERSION;
+        collection_request.bump = ctx.bumps.collection_request;
+
+        container.open_request = collection_request.key();
+        container.collection_requests += 1;
+
+        msg!("📋 Solicitud de recolección abierta para {}", container.container_id);
+        Ok(())
+    }
+
+    /// Un recolector certificado reclama una solicitud abierta, o una ya
+    /// asignada cuyo plazo (SLA) venció sin completarse. Sin flujo de SLA
+    /// (`collection_sla_secs == 0`) una solicitud asignada no se reasigna
+    pub fn claim_collection_request(
+        ctx: Context<ClaimCollectionRequest>,
+    ) -> Result<()> {
+        let collection_request = &mut ctx.accounts.collection_request;
+        let now = Clock::get()?.unix_timestamp;
+
+        match collection_request.status {
+            CollectionRequestStatus::Open => {}
+            CollectionRequestStatus::Claimed => {
+                require!(
+                    ctx.accounts.global_state.collection_sla_secs > 0 && now > collection_request.deadline,
+                    ErrorCode::CollectionRequestNotExpired
+                );
+                msg!("⏰ SLA vencido - reasignando solicitud de {}", collection_request.assigned_collector);
+            }
+            CollectionRequestStatus::Completed => return err!(ErrorCode::CollectionRequestCompleted),
+        }
+
+        collection_request.status = CollectionRequestStatus::Claimed;
+        collection_request.assigned_collector = ctx.accounts.collector.key();
+        collection_request.claimed_at = now;
+        collection_request.deadline = now + ctx.accounts.global_state.collection_sla_secs;
+
+        msg!("🚛 Solicitud asignada a {} - plazo hasta {}",
+             ctx.accounts.certified_collector.name,
+             collection_request.deadline);
+        Ok(())
+    }
+
+    /// Registra una planta recicladora autorizada (solo autoridad)
+    pub fn register_recycler(
+        ctx: Context<RegisterRecycler>,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= 32, ErrorCode::RecyclerNameTooLong);
+
+        let recycler = &mut ctx.accounts.recycler;
+        recycler.recycler = ctx.accounts.recycler_wallet.key();
+        recycler.name = name.clone();
+        recycler.is_active = true;
+        recycler.registered_at = Clock::get()?.unix_timestamp;
+        recycler.total_received_grams = 0;
+        recycler.total_output_grams = 0;
+        recycler.version = CURRENT_ACCOUNT_VERSION;
+        recycler.bump = ctx.bumps.recycler;
+
+        msg!("🏭 Planta recicladora registrada: {}", name);
+        Ok(())
+    }
+
+    /// Activa o revoca una planta recicladora (solo autoridad)
+    pub fn set_recycler_status(
+        ctx: Context<SetRecyclerStatus>,
+        is_active: bool,
+    ) -> Result<()> {
+        let recycler = &mut ctx.accounts.recycler;
+        recycler.is_active = is_active;
+
+        let status = if is_active { "ACTIVA" } else { "REVOCADA" };
+        msg!("🏭 Planta {} ahora está {}", recycler.name, status);
+        Ok(())
+    }
+
+    /// Agrupa recolecciones en un envío hacia una planta recicladora
+    /// Los `CollectionRecord` del recolector se pasan en `remaining_accounts`
+    pub fn create_shipment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateShipment<'info>>,
+    ) -> Result<()> {
+        let collector = ctx.accounts.collector.key();
+        let count = ctx.remaining_accounts.len();
+        require!(
+            count > 0 && count <= MAX_SHIPMENT_COLLECTIONS,
+            ErrorCode::InvalidShipmentSize
+        );
+
+        let mut collections = Vec::with_capacity(count);
+        let mut declared_weight = 0;
+        let mut material = None;
+        for account_info in ctx.remaining_accounts.iter() {
+            let mut collection_record = Account::<CollectionRecord>::try_from(account_info)?;
+            require_keys_eq!(collection_record.collector, collector, ErrorCode::CollectionNotOwned);
+            require!(!collection_record.shipped, ErrorCode::CollectionAlreadyShipped);
+            // Un envío es de un solo material: de él salen los certificados de impacto
+            require!(
+                *material.get_or_insert(collection_record.material) == collection_record.material,
+                ErrorCode::MixedShipmentMaterial
+            );
+
+            collection_record.shipped = true;
+            declared_weight += collection_record.weight_collected;
+            collections.push(collection_record.key());
+            collection_record.exit(&crate::ID)?;
+        }
+
+        let shipment = &mut ctx.accounts.shipment;
+        shipment.collector = collector;
+        shipment.recycler = ctx.accounts.recycler.recycler;
+        shipment.collections = collections;
+        shipment.declared_weight = declared_weight;
+        shipment.created_at = Clock::get()?.unix_timestamp;
+        shipment.status = ShipmentStatus::InTransit;
+        shipment.received_at = 0;
+        shipment.material = material.ok_or(ErrorCode::InvalidShipmentSize)?;
+        shipment.version = CURRENT_ACCOUNT_VERSION;
+        shipment.bump = ctx.bumps.shipment;
+
+        ctx.accounts.certified_collector.total_shipments += 1;
+
+        msg!("🚚 Envío de {} recolecciones ({}g) hacia {}",
+             count,
+             declared_weight,
+             ctx.accounts.recycler.name);
+        Ok(())
+    }
+
+    /// La planta recicladora confirma la recepción de un envío y el material
+    /// producido (p. ej. escamas de PET reciclado)
+    pub fn confirm_shipment_receipt(
+        ctx: Context<ConfirmShipmentReceipt>,
+        received_weight_grams: u64,
+        output_product: String,
+        output_grams: u64,
+    ) -> Result<()> {
+        require!(output_product.len() <= 32, ErrorCode::ProductIdTooLong);
+        require!(output_grams <= received_weight_grams, ErrorCode::InvalidProcessingOutput);
+
+        let shipment = &mut ctx.accounts.shipment;
+        let recycler = &mut ctx.accounts.recycler;
+        let processing_receipt = &mut ctx.accounts.processing_receipt;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(shipment.status == ShipmentStatus::InTransit, ErrorCode::ShipmentAlreadyReceived);
+
+        let difference = shipment.declared_weight.abs_diff(received_weight_grams);
+        let discrepancy_bps = ((difference as u128 * 10_000) / shipment.declared_weight.max(1) as u128)
+            .min(u16::MAX as u128) as u16;
+
+        processing_receipt.shipment = shipment.key();
+        processing_receipt.recycler = recycler.recycler;
+        processing_receipt.received_weight = received_weight_grams;
+        processing_receipt.discrepancy_bps = discrepancy_bps;
+        processing_receipt.output_product = output_product.clone();
+        processing_receipt.output_grams = output_grams;
+        processing_receipt.timestamp = now;
+        processing_receipt.certified = false;
+        processing_receipt.co2_certified_grams = 0;
+        processing_receipt.material = shipment.material;
+        processing_receipt.version = CURRENT_ACCOUNT_VERSION;
+        processing_receipt.bump = ctx.bumps.processing_receipt;
+
+        shipment.status = ShipmentStatus::Received;
+        shipment.received_at = now;
+
+        recycler.total_received_grams += received_weight_grams;
+        recycler.total_output_grams += output_grams;
+
+        msg!("🏭 Envío recibido por {}: {}g (discrepancia {} bps)",
+             recycler.name,
+             received_weight_grams,
+             discrepancy_bps);
+        msg!("♻️ Producción: {}g de {}", output_grams, output_product);
+        Ok(())
+    }
+
+    /// Crea el mint de certificados de impacto (solo autoridad)
+    /// 1 token = 1 kg de CO₂e evitado en material procesado y verificado
+    pub fn init_impact_mint(
+        ctx: Context<InitImpactMint>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.impact_mint = ctx.accounts.impact_mint.key();
+
+        msg!("🌱 Mint de certificados de impacto: {}", global_state.impact_mint);
+        Ok(())
+    }
+
+    /// Emite certificados de impacto por el material procesado en planta
+    /// (solo autoridad, una vez por recibo de procesamiento)
+    ///
+    /// El material es el de las recolecciones del envío y el recibo debe
+    /// cuadrar con lo declarado dentro de la tolerancia de discrepancia
+    pub fn issue_impact_certificate(
+        ctx: Context<IssueImpactCertificate>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        let processing_receipt = &mut ctx.accounts.processing_receipt;
+
+        require!(!processing_receipt.certified, ErrorCode::ImpactAlreadyCertified);
+        require!(
+            processing_receipt.discrepancy_bps <= global_state.discrepancy_tolerance_bps,
+            ErrorCode::ProcessingDiscrepancy
+        );
+
+        let material = processing_receipt.material;
+        let co2_grams = global_state.co2_avoided_for(material, processing_receipt.output_grams);
+        require!(co2_grams > 0, ErrorCode::NoImpactToCertify);
+
+        processing_receipt.certified = true;
+        processing_receipt.co2_certified_grams = co2_grams;
+        global_state.total_co2_certified_grams += co2_grams;
+
+        let bump = global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.impact_mint.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), co2_grams)?;
+
+        emit!(ImpactCertificateIssued {
+            processing_receipt: processing_receipt.key(),
+            recipient: ctx.accounts.recipient_token_account.owner,
+            material,
+            processed_grams: processing_receipt.output_grams,
+            co2_grams,
+        });
+
+        msg!("🌱 Certificado de impacto: {}g de CO₂e por {}g procesados",
+             co2_grams,
+             processing_receipt.output_grams);
+        Ok(())
+    }
+
+    /// Retira (quema) certificados de impacto para compensar emisiones
+    /// El retiro queda registrado como evento a nombre del beneficiario
+    pub fn retire_impact_certificate(
+        ctx: Context<RetireImpactCertificate>,
+        amount: u64,
+        beneficiary: String,
+    ) -> Result<()> {
+        require!(beneficiary.len() <= 64, ErrorCode::BeneficiaryTooLong);
+        require!(amount > 0, ErrorCode::NoImpactToCertify);
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.impact_mint.to_account_info(),
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::burn(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.total_co2_retired_grams += amount;
+
+        emit!(ImpactCertificateRetired {
+            owner: ctx.accounts.owner.key(),
+            beneficiary: beneficiary.clone(),
+            co2_grams: amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("🌍 {}g de CO₂e retirados a nombre de {}", amount, beneficiary);
+        Ok(())
+    }
+
+    /// Registra una marca de bebidas (productor responsable) - solo autoridad
+    pub fn register_brand(
+        ctx: Context<RegisterBrand>,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= 32, ErrorCode::BrandNameTooLong);
+
+        let brand = &mut ctx.accounts.brand;
+        brand.owner = ctx.accounts.brand_owner.key();
+        brand.name = name.clone();
+        brand.is_active = true;
+        brand.registered_at = Clock::get()?.unix_timestamp;
+        brand.recovered_grams = 0;
+        brand.tagged_deposits = 0;
+        brand.credits_purchased = 0;
+        brand.credits_retired = 0;
+        brand.recovered_by_material = [0; MATERIAL_COUNT];
+        brand.credits_by_material = [0; MATERIAL_COUNT];
+        brand.minted_by_material = [0; MATERIAL_COUNT];
+        brand.version = CURRENT_ACCOUNT_VERSION;
+        brand.bump = ctx.bumps.brand;
+
+        msg!("🏷️ Marca registrada: {}", name);
+        Ok(())
+    }
+
+    /// Activa o desactiva una marca (solo autoridad)
+    pub fn set_brand_status(
+        ctx: Context<SetBrandStatus>,
+        is_active: bool,
+    ) -> Result<()> {
+        let brand = &mut ctx.accounts.brand;
+        brand.is_active = is_active;
+
+        let status = if is_active { "ACTIVA" } else { "INACTIVA" };
+        msg!("🏷️ Marca {} ahora está {}", brand.name, status);
+        Ok(())
+    }
+
+    /// Crea el mint de créditos EPR, la bóveda de la tesorería y su
+    /// configuración (solo autoridad). Los créditos se emiten a la bóveda por
+    /// los envases recuperados de cada marca (`mint_epr_credits`)
+    pub fn init_epr_credits(
+        ctx: Context<InitEprCredits>,
+        price_per_kg: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        let epr_state = &mut ctx.accounts.epr_state;
+        epr_state.epr_mint = ctx.accounts.epr_mint.key();
+        epr_state.treasury = treasury;
+        epr_state.price_per_kg = price_per_kg;
+        epr_state.total_minted_grams = 0;
+        epr_state.total_retired_grams = 0;
+        epr_state.version = CURRENT_ACCOUNT_VERSION;
+        epr_state.bump = ctx.bumps.epr_state;
+
+        msg!("📜 Créditos EPR habilitados - {} lamports por KG", price_per_kg);
+        Ok(())
+    }
+
+    /// Actualiza el precio y la tesorería de los créditos EPR (solo autoridad)
+    pub fn update_epr_config(
+        ctx: Context<UpdateEprConfig>,
+        new_price_per_kg: Option<u64>,
+        new_treasury: Option<Pubkey>,
+    ) -> Result<()> {
+        let epr_state = &mut ctx.accounts.epr_state;
+
+        if let Some(price) = new_price_per_kg {
+            epr_state.price_per_kg = price;
+            msg!("📜 Nuevo precio EPR: {} lamports por KG", price);
+        }
+
+        if let Some(treasury) = new_treasury {
+            epr_state.treasury = treasury;
+            msg!("📜 Nueva tesorería EPR: {}", treasury);
+        }
+
+        Ok(())
+    }
+
+    /// Emite a la bóveda de la tesorería los créditos EPR de los envases de
+    /// una marca recuperados en `material` desde la última emisión
+    /// Cualquiera puede ejecutarla
+    pub fn mint_epr_credits(
+        ctx: Context<MintEprCredits>,
+        material: Material,
+    ) -> Result<()> {
+        let brand = &mut ctx.accounts.brand;
+        let amount_grams = brand.recovered_by_material[material as usize]
+            .saturating_sub(brand.minted_by_material[material as usize]);
+        require!(amount_grams > 0, ErrorCode::InsufficientEprBacking);
+
+        let bump = ctx.accounts.global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.epr_mint.to_account_info(),
+            to: ctx.accounts.epr_vault.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount_grams)?;
+
+        brand.minted_by_material[material as usize] += amount_grams;
+        ctx.accounts.epr_state.total_minted_grams += amount_grams;
+
+        msg!("📜 {}g de {:?} recuperados por {} emitidos en créditos EPR", amount_grams, material, brand.name);
+        Ok(())
+    }
+
+    /// Una marca compra créditos EPR a la tesorería (en gramos recuperados)
+    /// Solo puede comprar los créditos ya emitidos por envases de la propia
+    /// marca recuperados en el material indicado
+    pub fn buy_epr_credits(
+        ctx: Context<BuyEprCredits>,
+        amount_grams: u64,
+        material: Material,
+    ) -> Result<()> {
+        let epr_state = &ctx.accounts.epr_state;
+        let brand = &mut ctx.accounts.brand;
+
+        require!(amount_grams > 0, ErrorCode::InvalidEprAmount);
+        let available = brand.minted_by_material[material as usize]
+            .saturating_sub(brand.credits_by_material[material as usize]);
+        require!(amount_grams <= available, ErrorCode::InsufficientEprBacking);
+
+        // Pago en SOL a la tesorería
+        let cost = (amount_grams as u128 * epr_state.price_per_kg as u128 / 1000) as u64;
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.brand_owner.to_account_info(),
+            to: ctx.accounts.treasury.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.system_program.to_account_info();
+        system_program::transfer(CpiContext::new(cpi_program, cpi_accounts), cost)?;
+
+        // Entregar los créditos desde la bóveda de la tesorería
+        let bump = ctx.accounts.global_state.bump;
+        let seeds = &[
+            b"global_state".as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.epr_vault.to_account_info(),
+            to: ctx.accounts.brand_token_account.to_account_info(),
+            authority: ctx.accounts.global_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount_grams)?;
+
+        brand.credits_purchased += amount_grams;
+        brand.credits_by_material[material as usize] += amount_grams;
+
+        msg!("📜 {} compró {}g de {:?} en créditos EPR por {} lamports", brand.name, amount_grams, material, cost);
+        Ok(())
+    }
+
+    /// Una marca retira (quema) créditos EPR para acreditar su obligación
+    pub fn retire_epr_credits(
+        ctx: Context<RetireEprCredits>,
+        amount_grams: u64,
+    ) -> Result<()> {
+        require!(amount_grams > 0, ErrorCode::InvalidEprAmount);
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.epr_mint.to_account_info(),
+            from: ctx.accounts.brand_token_account.to_account_info(),
+            authority: ctx.accounts.brand_owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::burn(CpiContext::new(cpi_program, cpi_accounts), amount_grams)?;
+
+        let epr_state = &mut ctx.accounts.epr_state;
+        let brand = &mut ctx.accounts.brand;
+        epr_state.total_retired_grams += amount_grams;
+        brand.credits_retired += amount_grams;
+
+        emit!(EprCreditsRetired {
+            brand: brand.key(),
+            amount_grams,
+            total_retired_grams: brand.credits_retired,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("📜 {} retiró {}g en créditos EPR", brand.name, amount_grams);
+        Ok(())
+    }
+
+    /// Crea la tabla de clasificación de un periodo (semanal o mensual)
+    /// `scope` es `Pubkey::default()` para la tabla global o la cuenta de un contenedor
+    pub fn create_leaderboard(
+        ctx: Context<CreateLeaderboard>,
+        period_kind: PeriodKind,
+        period_index: u64,
+        scope: Pubkey,
+    ) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+
+        leaderboard.period_kind = period_kind;
+        leaderboard.period_index = period_index;
+        leaderboard.scope = scope;
+        leaderboard.entries = Vec::new();
+        leaderboard.prize_pool = 0;
+        leaderboard.prize_vault = ctx.accounts.prize_vault.key();
+        leaderboard.is_closed = false;
+        leaderboard.version = CURRENT_ACCOUNT_VERSION;
+        leaderboard.bump = ctx.bumps.leaderboard;
+
+        msg!("🏆 Clasificación {:?} #{} creada", period_kind, period_index);
+        Ok(())
+    }
+
+    /// Aporta ECOC al premio de una tabla de clasificación
+    pub fn fund_leaderboard(
+        ctx: Context<FundLeaderboard>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.leaderboard.is_closed, ErrorCode::LeaderboardClosed);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.prize_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.prize_pool += amount;
+
+        msg!("💰 Premio de clasificación: +{} ECOC (total {})", amount, leaderboard.prize_pool);
+        Ok(())
+    }
+
+    /// Cierra un periodo terminado y reparte el premio entre los ganadores
+    /// Las token accounts ECOC de los ganadores se pasan en `remaining_accounts`
+    /// en el mismo orden que la clasificación. Si nadie se clasificó, el premio
+    /// vuelve a la tesorería
+    pub fn close_period<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClosePeriod<'info>>,
+    ) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(!leaderboard.is_closed, ErrorCode::LeaderboardClosed);
+        require!(
+            now >= leaderboard.period_kind.end_of(leaderboard.period_index),
+            ErrorCode::PeriodNotEnded
+        );
+
+        leaderboard.is_closed = true;
+
+        let winners = leaderboard.entries.len();
+        if leaderboard.prize_pool > 0 && winners > 0 {
+            require!(ctx.remaining_accounts.len() >= winners, ErrorCode::MissingWinnerAccount);
+
+            let bump = ctx.accounts.global_state.bump;
+            let seeds = &[
+                b"global_state".as_ref(),
+                &[bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let total_shares: u64 = LEADERBOARD_PRIZE_SHARES_BPS[..winners].iter().sum();
+            let mut distributed = 0;
+
+            for (rank, entry) in leaderboard.entries.iter().enumerate() {
+                let winner_account = &ctx.remaining_accounts[rank];
+                let winner_token = Account::<TokenAccount>::try_from(winner_account)?;
+                require_keys_eq!(winner_token.owner, entry.user, ErrorCode::WinnerAccountMismatch);
+                require_keys_eq!(winner_token.mint, ctx.accounts.global_state.ecoc_mint, ErrorCode::WinnerAccountMismatch);
+
+                // El último ganador recibe el resto para no dejar polvo en la bóveda
+                let prize = if rank + 1 == winners {
+                    leaderboard.prize_pool - distributed
+                } else {
+                    leaderboard.prize_pool * LEADERBOARD_PRIZE_SHARES_BPS[rank] / total_shares
+                };
+                distributed += prize;
+
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.prize_vault.to_account_info(),
+                    to: winner_account.clone(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), prize)?;
+
+                msg!("🥇 Puesto {}: {} ECOC para {}", rank + 1, prize, entry.user);
+            }
+        } else if leaderboard.prize_pool > 0 {
+            // Sin ganadores el premio vuelve a la tesorería
+            let bump = ctx.accounts.global_state.bump;
+            let seeds = &[
+                b"global_state".as_ref(),
+                &[bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.prize_vault.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), leaderboard.prize_pool)?;
+
+            msg!("💰 Periodo sin ganadores: {} ECOC devueltos a la tesorería", leaderboard.prize_pool);
+        }
+
+        msg!("🏁 Periodo {:?} #{} cerrado", leaderboard.period_kind, leaderboard.period_index);
+        Ok(())
+    }
+
+    /// Concilia una recolección con el peso medido en la planta
+    /// Si la discrepancia supera la tolerancia, la recolección deja de estar verificada
+    pub fn reconcile_collection(
+        ctx: Context<ReconcileCollection>,
+        measured_weight_grams: u64,
+    ) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let collection_record = &mut ctx.accounts.collection_record;
+
+        require!(!collection_record.reconciled, ErrorCode::CollectionAlreadyReconciled);
+
+        let registered = collection_record.weight_collected;
+        let difference = registered.abs_diff(measured_weight_grams);
+        let discrepancy_bps = ((difference as u128 * 10_000) / registered as u128)
+            .min(u16::MAX as u128) as u16;
+
+        collection_record.measured_weight = measured_weight_grams;
+        collection_record.discrepancy_bps = discrepancy_bps;
+        collection_record.verified = discrepancy_bps <= global_state.discrepancy_tolerance_bps;
+        collection_record.reconciled = true;
+
+        msg!("⚖️ Recolección conciliada: registrado {}g | medido {}g | discrepancia {} bps",
+             registered,
+             measured_weight_grams,
+             discrepancy_bps);
+
+        Ok(())
+    }
+
+    /// Penaliza la reputación de un usuario cuyo depósito cae en un periodo
+    /// de recolección con discrepancia superior a la tolerancia
+    pub fn penalize_deposit(
+        ctx: Context<PenalizeDeposit>,
+    ) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let collection_record = &ctx.accounts.collection_record;
+        let deposit_record = &mut ctx.accounts.deposit_record;
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        require!(collection_record.reconciled, ErrorCode::CollectionNotReconciled);
+        require!(
+            collection_record.discrepancy_bps > global_state.discrepancy_tolerance_bps,
+            ErrorCode::DiscrepancyWithinTolerance
+        );
+        require!(
+            collection_record.covers(&deposit_record.container, deposit_record.timestamp),
+            ErrorCode::DepositNotInCollection
+        );
+        require!(!deposit_record.penalized, ErrorCode::DepositAlreadyPenalized);
+
+        deposit_record.penalized = true;
+        user_profile.apply_reputation_penalty(REPUTATION_DISCREPANCY_PENALTY);
+
+        msg!("⚠️ Penalización por discrepancia: {} -> reputación {}",
+             user_profile.username,
+             user_profile.reputation_score);
+
+        Ok(())
+    }
+
+    /// Penaliza un depósito comprimido con una prueba de Merkle de su hoja
+    /// (nodos en `remaining_accounts`); la cuenta `CompressedPenalty` de la
+    /// hoja impide penalizarlo dos veces
+    pub fn penalize_compressed_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PenalizeCompressedDeposit<'info>>,
+        root: [u8; 32],
+        leaf: DepositLeaf,
+        index: u32,
+    ) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let collection_record = &ctx.accounts.collection_record;
+
+        require!(collection_record.reconciled, ErrorCode::CollectionNotReconciled);
+        require!(
+            collection_record.discrepancy_bps > global_state.discrepancy_tolerance_bps,
+            ErrorCode::DiscrepancyWithinTolerance
+        );
+        require!(
+            collection_record.covers(&leaf.container, leaf.timestamp),
+            ErrorCode::DepositNotInCollection
+        );
+
+        verify_compressed_leaf(
+            &ctx.accounts.compression_program.to_account_info(),
+            &ctx.accounts.deposit_tree.to_account_info(),
+            ctx.remaining_accounts,
+            root,
+            leaf.hash(),
+            index,
+        )?;
+
+        let penalty = &mut ctx.accounts.penalty;
+        penalty.deposit_tree = ctx.accounts.deposit_tree.key();
+        penalty.leaf_index = index;
+        penalty.user = leaf.user;
+        penalty.collection = collection_record.key();
+        penalty.timestamp = Clock::get()?.unix_timestamp;
+        penalty.version = CURRENT_ACCOUNT_VERSION;
+        penalty.bump = ctx.bumps.penalty;
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.apply_reputation_penalty(REPUTATION_DISCREPANCY_PENALTY);
+
+        msg!("⚠️ Penalización por discrepancia (hoja #{}): {} -> reputación {}",
+             index,
+             user_profile.username,
+             user_profile.reputation_score);
+
+        Ok(())
+    }
+
+    /// Suspende o reactiva a un usuario (solo autoridad)
+    /// La suspensión aplica una penalización de reputación
+    pub fn set_user_suspension(
+        ctx: Context<SetUserSuspension>,
+        suspended: bool,
+    ) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        if suspended && !user_profile.is_suspended {
+            user_profile.apply_reputation_penalty(REPUTATION_SUSPENSION_PENALTY);
+        }
+        user_profile.is_suspended = suspended;
+
+        let status = if suspended { "SUSPENDIDO" } else { "ACTIVO" };
+        msg!("👤 Usuario {} ahora está {} (reputación {})",
+             user_profile.username,
+             status,
+             user_profile.reputation_score);
+
+        Ok(())
+    }
+
+    /// Otorga un logro como NFT soulbound (Token-2022 no transferible)
+    /// El PDA `Achievement` impide que el mismo logro se otorgue dos veces
+    pub fn claim_achievement(
+        ctx: Context<ClaimAchievement>,
+        kind: AchievementKind,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.user_profile.is_eligible_for(kind),
+            ErrorCode::AchievementNotEarned
+        );
+
+        let user_key = ctx.accounts.user.key();
+        let kind_seed = [kind as u8];
+        let mint_bump = ctx.bumps.badge_mint;
+        let mint_seeds = &[
+            b"badge_mint".as_ref(),
+            user_key.as_ref(),
+            kind_seed.as_ref(),
+            &[mint_bump],
+        ];
+        let global_bump = ctx.accounts.global_state.bump;
+        let global_seeds = &[
+            b"global_state".as_ref(),
+            &[global_bump],
+        ];
+
+        // Crear la cuenta del mint con la extensión NonTransferable
+        let space = ExtensionType::try_calculate_account_len::<token_2022::spl_token_2022::state::Mint>(
+            &[ExtensionType::NonTransferable],
+        )?;
+        create_pda_account(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.badge_mint.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            space,
+            &ctx.accounts.token_2022_program.key(),
+            &[&mint_seeds[..]],
+        )?;
+
+        token_interface::non_transferable_mint_initialize(CpiContext::new(
+            ctx.accounts.token_2022_program.to_account_info(),
+            NonTransferableMintInitialize {
+                token_program_id: ctx.accounts.token_2022_program.to_account_info(),
+                mint: ctx.accounts.badge_mint.to_account_info(),
+            },
+        ))?;
+
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_2022_program.to_account_info(),
+                token_2022::InitializeMint2 {
+                    mint: ctx.accounts.badge_mint.to_account_info(),
+                },
+            ),
+            0,
+            &ctx.accounts.global_state.key(),
+            None,
+        )?;
+
+        // Cuenta asociada del usuario para la insignia
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.user.to_account_info(),
+                associated_token: ctx.accounts.user_badge_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+                mint: ctx.accounts.badge_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_2022_program.to_account_info(),
+            },
+        ))?;
+
+        // Mintear la única unidad y cerrar la emisión
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_2022_program.to_account_info(),
+                token_2022::MintTo {
+                    mint: ctx.accounts.badge_mint.to_account_info(),
+                    to: ctx.accounts.user_badge_account.to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                },
+                &[&global_seeds[..]],
+            ),
+            1,
+        )?;
+
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_2022_program.to_account_info(),
+                token_2022::SetAuthority {
+                    current_authority: ctx.accounts.global_state.to_account_info(),
+                    account_or_mint: ctx.accounts.badge_mint.to_account_info(),
+                },
+                &[&global_seeds[..]],
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.user = user_key;
+        achievement.kind = kind;
+        achievement.mint = ctx.accounts.badge_mint.key();
+        achievement.awarded_at = Clock::get()?.unix_timestamp;
+        achievement.version = CURRENT_ACCOUNT_VERSION;
+        achievement.bump = ctx.bumps.achievement;
+
+        msg!("🏅 Logro otorgado: {:?} a {}", kind, ctx.accounts.user_profile.username);
+
+        Ok(())
+    }
+
+    /// Consulta la reputación de un usuario (vista, sin modificar estado)
+    pub fn get_reputation(
+        ctx: Context<GetReputation>,
+    ) -> Result<ReputationView> {
+        let user_profile = &ctx.accounts.user_profile;
+
+        Ok(ReputationView {
+            reputation_score: user_profile.reputation_score,
+            reward_multiplier_bps: user_profile.reward_multiplier_bps(),
+            current_streak: user_profile.current_streak,
+            is_suspended: user_profile.is_suspended,
+        })
+    }
+
+    /// Cotiza un depósito por peso con la misma lógica que `process_deposit`
+    /// (vista: multiplicador de reputación, tope de referidos, CO₂ y capacidad)
+    pub fn quote_deposit_reward(
+        ctx: Context<QuoteDepositReward>,
+        weight_grams: u64,
+        material: Material,
+    ) -> Result<DepositQuote> {
+        let container = &ctx.accounts.container;
+        require!(material == container.material, ErrorCode::MaterialMismatch);
+
+        quote_deposit(
+            &ctx.accounts.global_state,
+            container,
+            &ctx.accounts.user_profile,
+            ctx.accounts.referrer_profile.as_deref(),
+            weight_grams,
+        )
+    }
+
+    /// Resumen de actividad de un usuario (vista, sin modificar estado)
+    pub fn get_user_summary(
+        ctx: Context<GetUserSummary>,
+    ) -> Result<UserSummaryView> {
+        let global_state = &ctx.accounts.global_state;
+        let user_profile = &ctx.accounts.user_profile;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Los acumulados de periodo solo cuentan si siguen en curso
+        let week_weight_grams = if user_profile.period_week == PeriodKind::Weekly.index_at(now) {
+            user_profile.week_weight_grams
+        } else {
+            0
+        };
+        let month_weight_grams = if user_profile.period_month == PeriodKind::Monthly.index_at(now) {
+            user_profile.month_weight_grams
+        } else {
+            0
+        };
+        let referral_grams_remaining = if user_profile.referrer.is_some()
+            && global_state.referral_bonus_per_kg > 0
+            && global_state.referral_cap_per_referrer > user_profile.referral_cap_exhausted_at
+        {
+            global_state.referral_max_grams.saturating_sub(user_profile.referred_weight_grams)
+        } else {
+            0
+        };
+
+        Ok(UserSummaryView {
+            owner: user_profile.owner,
+            username: user_profile.username.clone(),
+            total_deposits: user_profile.total_deposits,
+            total_pet_weight: user_profile.total_pet_weight,
+            total_ecoc_earned: user_profile.total_ecoc_earned,
+            total_ecoc_spent: user_profile.total_ecoc_spent,
+            co2_avoided_grams: user_profile.co2_avoided_grams,
+            reputation_score: user_profile.reputation_score,
+            reward_multiplier_bps: user_profile.reward_multiplier_bps(),
+            current_streak: user_profile.current_streak,
+            longest_streak: user_profile.longest_streak,
+            week_weight_grams,
+            month_weight_grams,
+            referral_count: user_profile.referral_count,
+            referral_earnings: user_profile.referral_earnings,
+            referral_grams_remaining,
+            is_suspended: user_profile.is_suspended,
+        })
+    }
+
+    /// Estado operativo de un contenedor (vista, sin modificar estado)
+    pub fn get_container_status(
+        ctx: Context<GetContainerStatus>,
+    ) -> Result<ContainerStatusView> {
+        let container = &ctx.accounts.container;
+
+        Ok(ContainerStatusView {
+            container_id: container.container_id.clone(),
+            material: container.material,
+            is_active: container.is_active,
+            current_weight: container.current_weight,
+            capacity_kg: container.capacity_kg,
+            remaining_grams: (container.capacity_kg * 1000).saturating_sub(container.current_weight),
+            fill_level_bps: container.fill_level_bps(),
+            fill_threshold_bps: container.fill_threshold_bps,
+            needs_collection: container.needs_collection,
+            open_request: (container.open_request != Pubkey::default()).then_some(container.open_request),
+            total_deposits: container.total_deposits,
+            co2_avoided_grams: container.co2_avoided_grams,
+            last_collection: container.last_collection,
+        })
+    }
+
+    /// Actualiza la configuración del programa (solo autoridad)
+    /// Un plazo de recolección de 0 desactiva el flujo de SLA
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_ecoc_per_kg: Option<u64>,
+        new_min_weight: Option<u64>,
+        new_discrepancy_tolerance_bps: Option<u16>,
+        new_record_retention_secs: Option<i64>,
+        new_collection_sla_secs: Option<i64>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        if let Some(rate) = new_ecoc_per_kg {
+            global_state.ecoc_per_kg = rate;
+            msg!("💰 Nueva tasa: {} ECOC por KG", rate);
+        }
+
+        if let Some(weight) = new_min_weight {
+            global_state.min_deposit_weight = weight;
+            msg!("⚖️ Nuevo peso mínimo: {}g", weight);
+        }
+
+        if let Some(tolerance) = new_discrepancy_tolerance_bps {
+            global_state.discrepancy_tolerance_bps = tolerance;
+            msg!("📏 Nueva tolerancia de discrepancia: {} bps", tolerance);
+        }
+
+        if let Some(retention) = new_record_retention_secs {
+            require!(retention >= 0, ErrorCode::InvalidRetention);
+            global_state.record_retention_secs = retention;
+            msg!("🗄️ Nueva retención de registros: {}s", retention);
+        }
+
+        if let Some(sla) = new_collection_sla_secs {
+            require!(sla >= 0, ErrorCode::InvalidCollectionSla);
+            global_state.collection_sla_secs = sla;
+            msg!("⏱️ Nuevo plazo de recolección: {}s", sla);
+        }
+
+        Ok(())
+    }
+
+    /// Cierra un registro de depósito tras el periodo de retención
+    /// El registro se resume en el hash de historial del perfil y la renta
+    /// vuelve a quien la pagó. Solo se archivan depósitos liquidados: cubiertos
+    /// por una recolección conciliada y, si hubo discrepancia, ya penalizados
+    pub fn close_deposit_record(
+        ctx: Context<CloseDepositRecord>,
+    ) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let collection_record = &mut ctx.accounts.collection_record;
+        let deposit_record = &ctx.accounts.deposit_record;
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        require!(
+            Clock::get()?.unix_timestamp >= deposit_record.timestamp + global_state.record_retention_secs,
+            ErrorCode::RetentionPeriodActive
+        );
+        require!(collection_record.reconciled, ErrorCode::CollectionNotReconciled);
+        require!(
+            collection_record.covers(&deposit_record.container, deposit_record.timestamp),
+            ErrorCode::DepositNotInCollection
+        );
+        require!(
+            deposit_record.penalized || collection_record.discrepancy_bps <= global_state.discrepancy_tolerance_bps,
+            ErrorCode::DepositPenaltyPending
+        );
+
+        fold_history(&mut user_profile.deposit_history_root, &**deposit_record)?;
+        user_profile.archived_deposits += 1;
+        // Los registros v0 no se contaron en su recolección
+        if !deposit_record.migrated {
+            collection_record.outstanding_deposits = collection_record.outstanding_deposits.saturating_sub(1);
+        }
+
+        msg!("🗄️ Depósito archivado ({} en total) - renta devuelta a {}",
+             user_profile.archived_deposits,
+             deposit_record.payer);
+
+        Ok(())
+    }
+
+    /// Cierra un registro de canje tras el periodo de retención
+    pub fn close_redemption_record(
+        ctx: Context<CloseRedemptionRecord>,
+    ) -> Result<()> {
+        let redemption_record = &ctx.accounts.redemption_record;
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        require!(
+            Clock::get()?.unix_timestamp >= redemption_record.timestamp + ctx.accounts.global_state.record_retention_secs,
+            ErrorCode::RetentionPeriodActive
+        );
+
+        fold_history(&mut user_profile.redemption_history_root, &**redemption_record)?;
+        user_profile.archived_redemptions += 1;
+
+        msg!("🗄️ Canje archivado ({} en total) - renta devuelta a {}",
+             user_profile.archived_redemptions,
+             redemption_record.payer);
+
+        Ok(())
+    }
+
+    /// Cierra un registro de recolección tras el periodo de retención
+    /// El registro se resume en el hash de historial del contenedor; debe estar
+    /// conciliado y enviado a planta, y sus `DepositRecord` ya archivados (sin
+    /// ellos la recolección no podría volver a acreditarlos). Los depósitos de
+    /// una recolección fuera de tolerancia deben penalizarse antes de archivarse
+    pub fn close_collection_record(
+        ctx: Context<CloseCollectionRecord>,
+    ) -> Result<()> {
+        let collection_record = &ctx.accounts.collection_record;
+        let container = &mut ctx.accounts.container;
+
+        require!(
+            Clock::get()?.unix_timestamp >= collection_record.timestamp + ctx.accounts.global_state.record_retention_secs,
+            ErrorCode::RetentionPeriodActive
+        );
+        require!(collection_record.reconciled, ErrorCode::CollectionNotReconciled);
+        require!(collection_record.shipped, ErrorCode::CollectionNotShipped);
+        require!(collection_record.outstanding_deposits == 0, ErrorCode::CollectionHasOpenDeposits);
+
+        fold_history(&mut container.collection_history_root, &**collection_record)?;
+        container.archived_collections += 1;
+
+        msg!("🗄️ Recolección archivada ({} en total) - renta devuelta a {}",
+             container.archived_collections,
+             collection_record.payer);
+
+        Ok(())
+    }
+
+    /// Migra `GlobalState` desde el esquema v0, ampliando la cuenta en su lugar
+    pub fn migrate_global_state(
+        ctx: Context<MigrateGlobalState>,
+    ) -> Result<()> {
+        let account = ctx.accounts.global_state.to_account_info();
+        let legacy: GlobalStateV0 = read_legacy_account(&account, GlobalState::DISCRIMINATOR, GlobalStateV0::LEN)?;
+        require_keys_eq!(legacy.authority, ctx.accounts.authority.key(), ErrorCode::InvalidMigrationSource);
+
+        let mut global_state = GlobalState::from(legacy);
+        global_state.ecoc_mint = ctx.accounts.ecoc_mint.key();
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + GlobalState::LEN,
+            &global_state,
+        )?;
+
+        msg!("🔧 GlobalState migrado a la versión {}", CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Migra un `UserProfile` desde el esquema v0 (cualquiera puede pagar la ampliación).
+    /// Los perfiles v0 no tenían reserva de nombre: se crea aquí con el nombre del perfil.
+    /// Si ese nombre ya está reservado (v0 admitía duplicados), el dueño firma la
+    /// migración con un nombre nuevo.
+    pub fn migrate_user_profile(
+        ctx: Context<MigrateUserProfile>,
+        username: String,
+    ) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let legacy: UserProfileV0 = read_legacy_account(&account, UserProfile::DISCRIMINATOR, UserProfileV0::LEN)?;
+
+        let expected = Pubkey::create_program_address(
+            &[b"user_profile", legacy.owner.as_ref(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidMigrationSource)?;
+        require_keys_eq!(expected, account.key(), ErrorCode::InvalidMigrationSource);
+
+        // Solo el dueño puede migrar con un nombre distinto al del perfil
+        if legacy.username != username {
+            let owner = ctx.accounts.owner.as_ref().ok_or(ErrorCode::InvalidMigrationSource)?;
+            require_keys_eq!(owner.key(), legacy.owner, ErrorCode::InvalidMigrationSource);
+            validate_username(&username)?;
+        }
+
+        let username_record = &mut ctx.accounts.username_record;
+        username_record.version = CURRENT_ACCOUNT_VERSION;
+        username_record.owner = legacy.owner;
+        username_record.username = username.clone();
+        username_record.payer = ctx.accounts.payer.key();
+        username_record.bump = ctx.bumps.username_record;
+
+        let mut user_profile = UserProfile::from(legacy);
+        user_profile.username = username;
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + UserProfile::LEN,
+            &user_profile,
+        )?;
+
+        msg!("🔧 Perfil de {} migrado a la versión {}", user_profile.username, CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Migra un `SmartContainer` desde el esquema v0 (cualquiera puede pagar la ampliación)
+    pub fn migrate_container(
+        ctx: Context<MigrateAccount>,
+    ) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let legacy: SmartContainerV0 = read_legacy_account(&account, SmartContainer::DISCRIMINATOR, SmartContainerV0::LEN)?;
+
+        let expected = Pubkey::create_program_address(
+            &[b"container", legacy.container_id.as_bytes(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidMigrationSource)?;
+        require_keys_eq!(expected, account.key(), ErrorCode::InvalidMigrationSource);
+
+        let container = SmartContainer::from(legacy);
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + SmartContainer::LEN,
+            &container,
+        )?;
+
+        msg!("🔧 Contenedor {} migrado a la versión {}", container.container_id, CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Migra un `DepositRecord` desde el esquema v0 para poder archivarlo
+    pub fn migrate_deposit_record(
+        ctx: Context<MigrateAccount>,
+    ) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let legacy: DepositRecordV0 = read_legacy_account(&account, DepositRecord::DISCRIMINATOR, DepositRecordV0::LEN)?;
+        let deposit_record = DepositRecord::from(legacy);
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + DepositRecord::LEN,
+            &deposit_record,
+        )?;
+
+        msg!("🔧 Registro de depósito de {} migrado a la versión {}", deposit_record.user, CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Migra un `RedemptionRecord` desde el esquema v0 para poder archivarlo
+    pub fn migrate_redemption_record(
+        ctx: Context<MigrateAccount>,
+    ) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let legacy: RedemptionRecordV0 = read_legacy_account(&account, RedemptionRecord::DISCRIMINATOR, RedemptionRecordV0::LEN)?;
+        let redemption_record = RedemptionRecord::from(legacy);
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + RedemptionRecord::LEN,
+            &redemption_record,
+        )?;
+
+        msg!("🔧 Registro de canje de {} migrado a la versión {}", redemption_record.user, CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Migra un `CollectionRecord` desde el esquema v0 para poder conciliarlo y archivarlo
+    /// (solo autoridad). v0 no guardaba el inicio del periodo cubierto: la autoridad
+    /// indica `period_start` (la recolección anterior del contenedor) para que la
+    /// recolección no cubra depósitos ya liquidados por otras
+    pub fn migrate_collection_record(
+        ctx: Context<MigrateCollectionRecord>,
+        period_start: i64,
+    ) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let legacy: CollectionRecordV0 = read_legacy_account(&account, CollectionRecord::DISCRIMINATOR, CollectionRecordV0::LEN)?;
+        require!(
+            period_start >= 0 && period_start <= legacy.timestamp,
+            ErrorCode::InvalidCollectionPeriod
+        );
+        let collection_record = CollectionRecord::from_legacy(legacy, period_start);
+
+        write_migrated_account(
+            &account,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + CollectionRecord::LEN,
+            &collection_record,
+        )?;
+
+        msg!("🔧 Registro de recolección de {} migrado a la versión {}", collection_record.container, CURRENT_ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    /// Actualiza la configuración del programa de referidos (solo autoridad)
+    /// Un tope por referente de 0 deshabilita el bono
+    pub fn update_referral_config(
+        ctx: Context<UpdateConfig>,
+        new_bonus_per_kg: Option<u64>,
+        new_max_grams: Option<u64>,
+        new_cap_per_referrer: Option<u64>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        if let Some(bonus) = new_bonus_per_kg {
+            global_state.referral_bonus_per_kg = bonus;
+            msg!("🤝 Nuevo bono de referido: {} ECOC por KG", bonus);
+        }
+
+        if let Some(max_grams) = new_max_grams {
+            global_state.referral_max_grams = max_grams;
+            msg!("🤝 Bono aplicable a los primeros {}g del referido", max_grams);
+        }
+
+        if let Some(cap) = new_cap_per_referrer {
+            global_state.referral_cap_per_referrer = cap;
+            msg!("🤝 Nuevo tope por referente: {} ECOC", cap);
+        }
+
+        Ok(())
+    }
+
+    /// Actualiza el factor de emisión de un material (solo autoridad)
+    /// en gramos de CO₂e evitados por kg reciclado
+    pub fn update_emission_factor(
+        ctx: Context<UpdateConfig>,
+        material: Material,
+        grams_co2e_per_kg: u32,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.emission_factors[material as usize] = grams_co2e_per_kg;
+
+        msg!("🌱 Nuevo factor de emisión para {:?}: {}g CO₂e por KG", material, grams_co2e_per_kg);
+        Ok(())
+    }
+
+    /// Desactiva un contenedor (mantenimiento, daño, etc.)
+    pub fn toggle_container_status(
+        ctx: Context<ToggleContainerStatus>,
+    ) -> Result<()> {
+        let container = &mut ctx.accounts.container;
+        container.is_active = !container.is_active;
+
+        let status = if container.is_active { "ACTIVO" } else { "INACTIVO" };
+        msg!("📦 Contenedor {} ahora está {}", container.container_id, status);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ESTRUCTURAS DE CUENTAS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalState::LEN,
+        seeds = [b"global_state"],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Mint de los tokens ECOC
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(username: String)]
+pub struct RegisterUser<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + UserProfile::LEN,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Reserva del nombre de usuario (garantiza unicidad; una reserva
+    /// existente ya tiene dueño y se rechaza con `UsernameTaken`)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UsernameRecord::LEN,
+        seeds = [b"username", username_seed(&username).as_ref()],
+        bump,
+        constraint = username_record.owner == Pubkey::default() @ ErrorCode::UsernameTaken
+    )]
+    pub username_record: Account<'info, UsernameRecord>,
+
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Perfil de quien invita (solo si se indica un referente)
+    #[account(
+        mut,
+        seeds = [b"user_profile", referrer_profile.owner.as_ref()],
+        bump = referrer_profile.bump
+    )]
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+
+    pub user: Signer<'info>,
+
+    /// Paga la renta del perfil y de la reserva (el usuario o un relayer)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_username: String)]
+pub struct UpdateUsername<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Reserva del nombre actual (se cierra y devuelve la renta a quien la
+    /// pagó salvo que el nuevo nombre solo cambie mayúsculas)
+    #[account(
+        mut,
+        seeds = [b"username", username_seed(&user_profile.username).as_ref()],
+        bump = old_username_record.bump,
+        constraint = old_username_record.owner == user.key() @ ErrorCode::UsernameNotOwned,
+        has_one = payer
+    )]
+    pub old_username_record: Account<'info, UsernameRecord>,
+
+    /// Reserva del nuevo nombre (se omite si solo cambian mayúsculas)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UsernameRecord::LEN,
+        seeds = [b"username", username_seed(&new_username).as_ref()],
+        bump,
+        constraint = new_username_record.owner == Pubkey::default() @ ErrorCode::UsernameTaken
+    )]
+    pub new_username_record: Option<Account<'info, UsernameRecord>>,
+
+    pub user: Signer<'info>,
+
+    /// Quien pagó la reserva actual (el usuario o un relayer): recupera su
+    /// renta y paga la del nuevo nombre
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProfile<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Contenedor preferido (solo si se establece uno nuevo)
+    pub preferred_container: Option<Account<'info, SmartContainer>>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(container_id: String)]
+pub struct RegisterContainer<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SmartContainer::LEN,
+        seeds = [b"container", container_id.as_bytes()],
+        bump
+    )]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessDeposit<'info> {
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Registro completo del depósito (modo no comprimido)
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + DepositRecord::LEN,
+        seeds = [
+            b"deposit",
+            user.key().as_ref(),
+            &global_state.total_deposits.to_le_bytes()
+        ],
+        bump
+    )]
+    pub deposit_record: Option<Account<'info, DepositRecord>>,
+
+    /// CHECK: Árbol de Merkle de depósitos (modo comprimido), validado por SPL Account Compression
+    #[account(mut, address = global_state.deposit_tree)]
+    pub deposit_tree: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Programa SPL Account Compression
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Programa SPL Noop
+    #[account(address = SPL_NOOP_ID)]
+    pub noop_program: Option<UncheckedAccount<'info>>,
+
+    pub user: Signer<'info>,
+
+    /// Paga la renta del registro (el usuario, un relayer o el operador)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Token account del usuario para recibir ECOC
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Mint de ECOC tokens
+    #[account(mut)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    /// Perfil del referente (requerido mientras el bono de referido aplique)
+    #[account(
+        mut,
+        seeds = [b"user_profile", referrer_profile.owner.as_ref()],
+        bump = referrer_profile.bump
+    )]
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+
+    /// Token account ECOC del referente
+    #[account(mut, token::mint = ecoc_mint)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Marca del envase depositado (si el contenedor la identificó)
+    #[account(mut)]
+    pub brand: Option<Account<'info, Brand>>,
+
+    /// Operador del contenedor: atesta el peso, el recibo y la marca identificada
+    #[account(address = container.authority)]
+    pub container_authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessDepositBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    /// Operador del contenedor: firma el lote y responde por su peso
+    #[account(address = container.authority)]
+    pub container_authority: Signer<'info>,
+
+    /// CHECK: Árbol de Merkle de depósitos, validado por SPL Account Compression
+    #[account(mut, address = global_state.deposit_tree)]
+    pub deposit_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Programa SPL Account Compression
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Programa SPL Noop
+    #[account(address = SPL_NOOP_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+
+    /// Mint de ECOC tokens
+    #[account(mut, address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(claim_commitment: [u8; 32])]
+pub struct ProcessGuestDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    /// Operador del contenedor: responde por la atestación
+    #[account(address = container.authority)]
+    pub container_authority: Signer<'info>,
+
+    /// Paga la renta del depósito y de la bóveda
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GuestDeposit::LEN,
+        seeds = [b"guest_deposit", claim_commitment.as_ref()],
+        bump
+    )]
+    pub guest_deposit: Account<'info, GuestDeposit>,
+
+    /// Bóveda con la recompensa del invitado (autoridad: global_state)
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"guest_escrow", guest_deposit.key().as_ref()],
+        bump,
+        token::mint = ecoc_mint,
+        token::authority = global_state
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// Mint de ECOC tokens
+    #[account(mut, address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitGuestClaim<'info> {
+    #[account(
+        seeds = [b"guest_deposit", guest_deposit.claim_commitment.as_ref()],
+        bump = guest_deposit.bump
+    )]
+    pub guest_deposit: Account<'info, GuestDeposit>,
+
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + GuestClaimIntent::LEN,
+        seeds = [b"guest_claim", guest_deposit.key().as_ref(), claimer.key().as_ref()],
+        bump
+    )]
+    pub claim_intent: Account<'info, GuestClaimIntent>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimGuestDeposit<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"guest_deposit", guest_deposit.claim_commitment.as_ref()],
+        bump = guest_deposit.bump
+    )]
+    pub guest_deposit: Account<'info, GuestDeposit>,
+
+    #[account(
+        mut,
+        seeds = [b"guest_escrow", guest_deposit.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// Compromiso previo de quien reclama; su renta le vuelve al reclamar
+    #[account(
+        mut,
+        close = claimer,
+        seeds = [b"guest_claim", guest_deposit.key().as_ref(), claimer.key().as_ref()],
+        bump = claim_intent.bump
+    )]
+    pub claim_intent: Account<'info, GuestClaimIntent>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    /// Token account ECOC que recibe la recompensa
+    #[account(mut)]
+    pub claimer_token_account: Account<'info, TokenAccount>,
+
+    /// Perfil de quien reclama (opcional, para sumar el depósito a sus totales)
+    #[account(
+        mut,
+        seeds = [b"user_profile", claimer.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+
+    /// Quien pagó la renta del depósito
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireGuestDeposit<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"guest_deposit", guest_deposit.claim_commitment.as_ref()],
+        bump = guest_deposit.bump
+    )]
+    pub guest_deposit: Account<'info, GuestDeposit>,
+
+    #[account(
+        mut,
+        seeds = [b"guest_escrow", guest_deposit.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// Tesorería: token account ECOC de la autoridad del programa
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.authority
+            && treasury_token_account.mint == global_state.ecoc_mint @ ErrorCode::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Quien pagó la renta del depósito
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGuestClaimIntent<'info> {
+    #[account(
+        mut,
+        close = claimer,
+        seeds = [b"guest_claim", guest_deposit.key().as_ref(), claimer.key().as_ref()],
+        bump = claim_intent.bump
+    )]
+    pub claim_intent: Account<'info, GuestClaimIntent>,
+
+    /// CHECK: Depósito de invitado del compromiso; ya cerrado
+    #[account(constraint = guest_deposit.data_is_empty() @ ErrorCode::GuestDepositStillOpen)]
+    pub guest_deposit: UncheckedAccount<'info>,
+
+    /// Wallet que comprometió el reclamo y recibe la renta
+    #[account(mut)]
+    pub claimer: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterOrganization<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Organization::LEN,
+        seeds = [b"organization", admin.key().as_ref()],
+        bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    /// Bóveda con los ECOC de los miembros (autoridad: global_state)
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"organization_vault", organization.key().as_ref()],
+        bump,
+        token::mint = ecoc_mint,
+        token::authority = global_state
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Wallet del administrador de la organización
+    pub admin: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetOrganizationStatus<'info> {
+    #[account(mut)]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(member_id_hash: [u8; 32])]
+pub struct RegisterMember<'info> {
+    #[account(mut, has_one = admin @ ErrorCode::NotOrganizationAdmin)]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrganizationMember::LEN,
+        seeds = [b"member", organization.key().as_ref(), member_id_hash.as_ref()],
+        bump
+    )]
+    pub member: Account<'info, OrganizationMember>,
+
+    pub admin: Signer<'info>,
+
+    /// Paga la renta del miembro (el administrador o un relayer)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessMemberDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    /// Operador del contenedor: responde por la atestación
+    #[account(address = container.authority)]
+    pub container_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub organization: Account<'info, Organization>,
+
+    #[account(mut, has_one = organization)]
+    pub member: Account<'info, OrganizationMember>,
+
+    #[account(mut, address = organization.vault)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Mint de ECOC tokens
+    #[account(mut, address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GraduateMember<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, has_one = admin @ ErrorCode::NotOrganizationAdmin)]
+    pub organization: Account<'info, Organization>,
+
+    #[account(mut, has_one = organization)]
+    pub member: Account<'info, OrganizationMember>,
+
+    #[account(mut, address = organization.vault)]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+
+    /// Wallet propia del miembro
+    pub wallet: Signer<'info>,
+
+    /// Token account ECOC de la wallet que recibe el saldo
+    #[account(mut, token::mint = global_state.ecoc_mint, token::authority = wallet)]
+    pub wallet_token_account: Account<'info, TokenAccount>,
+
+    /// Perfil de la wallet (opcional, para sumar los totales del miembro)
+    #[account(
+        mut,
+        seeds = [b"user_profile", wallet.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessItemDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        seeds = [b"sku_registry"],
+        bump = sku_registry.bump
+    )]
+    pub sku_registry: Account<'info, SkuRegistry>,
+
+    pub user: Signer<'info>,
+
+    /// Operador del contenedor: atesta los envases escaneados
+    #[account(address = container.authority)]
+    pub container_authority: Signer<'info>,
+
+    /// Token account del usuario para recibir ECOC
+    #[account(mut, token::mint = ecoc_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Mint de ECOC tokens
+    #[account(mut, address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitSkuRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SkuRegistry::LEN,
+        seeds = [b"sku_registry"],
+        bump
+    )]
+    pub sku_registry: Account<'info, SkuRegistry>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpsertSku<'info> {
+    #[account(
+        mut,
+        seeds = [b"sku_registry"],
+        bump = sku_registry.bump
+    )]
+    pub sku_registry: Account<'info, SkuRegistry>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(gtin: u64, bucket: u64)]
+pub struct InitSerialBitmap<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SerialBitmap::LEN,
+        seeds = [b"serials".as_ref(), &gtin.to_le_bytes(), &bucket.to_le_bytes()],
+        bump
+    )]
+    pub serial_bitmap: Account<'info, SerialBitmap>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitDepositTree<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: Cuenta del árbol, preasignada y validada por SPL Account Compression
+    #[account(mut)]
+    pub deposit_tree: UncheckedAccount<'info>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Programa SPL Account Compression
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Programa SPL Noop
+    #[account(address = SPL_NOOP_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyDeposit<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: Árbol de Merkle de depósitos
+    #[account(address = global_state.deposit_tree)]
+    pub deposit_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Programa SPL Account Compression
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + RedemptionRecord::LEN,
+        seeds = [
+            b"redemption",
+            user.key().as_ref(),
+            &user_profile.total_ecoc_spent.to_le_bytes()
+        ],
+        bump
+    )]
+    pub redemption_record: Account<'info, RedemptionRecord>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Token account del usuario
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Mint de ECOC tokens
+    #[account(mut)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectContainer<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(
+        init,
+        payer = collector,
+        space = 8 + CollectionRecord::LEN,
+        seeds = [
+            b"collection",
+            container.key().as_ref(),
+            &container.total_deposits.to_le_bytes()
+        ],
+        bump
+    )]
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    /// Solicitud de recolección abierta del contenedor (si existe)
+    #[account(
+        mut,
+        constraint = collection_request.container == container.key() @ ErrorCode::MissingCollectionRequest
+    )]
+    pub collection_request: Option<Account<'info, CollectionRequest>>,
+
+    /// Certificación del recolector (requerida para cobrar incentivos)
+    #[account(
+        seeds = [b"collector", collector.key().as_ref()],
+        bump = certified_collector.bump
+    )]
+    pub certified_collector: Option<Account<'info, CertifiedCollector>>,
+
+    /// Mint de ECOC tokens (solo si hay incentivo)
+    #[account(mut, address = global_state.ecoc_mint)]
+    pub ecoc_mint: Option<Account<'info, Mint>>,
+
+    /// Token account ECOC del recolector (solo si hay incentivo)
+    #[account(
+        mut,
+        constraint = collector_token_account.owner == collector.key() @ ErrorCode::MissingCollectorRewardAccounts
+    )]
+    pub collector_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub collector: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCollector<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CertifiedCollector::LEN,
+        seeds = [b"collector", collector.key().as_ref()],
+        bump
+    )]
+    pub certified_collector: Account<'info, CertifiedCollector>,
+
+    /// CHECK: Wallet de la empresa recolectora
+    pub collector: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollectorStatus<'info> {
+    #[account(mut)]
+    pub certified_collector: Account<'info, CertifiedCollector>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestCollection<'info> {
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CollectionRequest::LEN,
+        seeds = [
+            b"collection_request",
+            container.key().as_ref(),
+            &container.collection_requests.to_le_bytes()
+        ],
+        bump
+    )]
+    pub collection_request: Account<'info, CollectionRequest>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCollectionRequest<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub collection_request: Account<'info, CollectionRequest>,
+
+    #[account(
+        seeds = [b"collector", collector.key().as_ref()],
+        bump = certified_collector.bump,
+        constraint = certified_collector.is_active @ ErrorCode::CollectorNotCertified
+    )]
+    pub certified_collector: Account<'info, CertifiedCollector>,
+
+    pub collector: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterBrand<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Brand::LEN,
+        seeds = [b"brand", brand_owner.key().as_ref()],
+        bump
+    )]
+    pub brand: Account<'info, Brand>,
+
+    /// CHECK: Wallet de la marca
+    pub brand_owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBrandStatus<'info> {
+    #[account(mut)]
+    pub brand: Account<'info, Brand>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitEprCredits<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EprState::LEN,
+        seeds = [b"epr_state"],
+        bump
+    )]
+    pub epr_state: Account<'info, EprState>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"epr_mint"],
+        bump,
+        mint::decimals = EPR_MINT_DECIMALS,
+        mint::authority = global_state
+    )]
+    pub epr_mint: Account<'info, Mint>,
+
+    /// Bóveda de créditos emitidos aún sin vender (autoridad: global_state)
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"epr_vault"],
+        bump,
+        token::mint = epr_mint,
+        token::authority = global_state
+    )]
+    pub epr_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEprConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"epr_state"],
+        bump = epr_state.bump
+    )]
+    pub epr_state: Account<'info, EprState>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MintEprCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"brand", brand.owner.as_ref()],
+        bump = brand.bump
+    )]
+    pub brand: Account<'info, Brand>,
+
+    #[account(
+        mut,
+        seeds = [b"epr_state"],
+        bump = epr_state.bump
+    )]
+    pub epr_state: Account<'info, EprState>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = epr_state.epr_mint)]
+    pub epr_mint: Account<'info, Mint>,
+
+    /// Bóveda de créditos de la tesorería
+    #[account(
+        mut,
+        seeds = [b"epr_vault"],
+        bump
+    )]
+    pub epr_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyEprCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"brand", brand_owner.key().as_ref()],
+        bump = brand.bump,
+        constraint = brand.is_active @ ErrorCode::BrandNotActive
+    )]
+    pub brand: Account<'info, Brand>,
+
+    #[account(
+        seeds = [b"epr_state"],
+        bump = epr_state.bump
+    )]
+    pub epr_state: Account<'info, EprState>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = epr_state.epr_mint)]
+    pub epr_mint: Account<'info, Mint>,
+
+    /// Bóveda de créditos de la tesorería
+    #[account(
+        mut,
+        seeds = [b"epr_vault"],
+        bump
+    )]
+    pub epr_vault: Account<'info, TokenAccount>,
+
+    /// Token account de la marca para recibir los créditos
+    #[account(mut, token::mint = epr_mint)]
+    pub brand_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Tesorería que recibe el pago, validada contra la configuración EPR
+    #[account(mut, address = epr_state.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub brand_owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RetireEprCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"brand", brand_owner.key().as_ref()],
+        bump = brand.bump
+    )]
+    pub brand: Account<'info, Brand>,
+
+    #[account(
+        mut,
+        seeds = [b"epr_state"],
+        bump = epr_state.bump
+    )]
+    pub epr_state: Account<'info, EprState>,
+
+    #[account(mut, address = epr_state.epr_mint)]
+    pub epr_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = epr_mint, token::authority = brand_owner)]
+    pub brand_token_account: Account<'info, TokenAccount>,
+
+    pub brand_owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterRecycler<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Recycler::LEN,
+        seeds = [b"recycler", recycler_wallet.key().as_ref()],
+        bump
+    )]
+    pub recycler: Account<'info, Recycler>,
+
+    /// CHECK: Wallet de la planta recicladora
+    pub recycler_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecyclerStatus<'info> {
+    #[account(mut)]
+    pub recycler: Account<'info, Recycler>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateShipment<'info> {
+    #[account(
+        mut,
+        seeds = [b"collector", collector.key().as_ref()],
+        bump = certified_collector.bump,
+        constraint = certified_collector.is_active @ ErrorCode::CollectorNotCertified
+    )]
+    pub certified_collector: Account<'info, CertifiedCollector>,
+
+    #[account(constraint = recycler.is_active @ ErrorCode::RecyclerNotActive)]
+    pub recycler: Account<'info, Recycler>,
+
+    #[account(
+        init,
+        payer = collector,
+        space = 8 + Shipment::LEN,
+        seeds = [
+            b"shipment",
+            collector.key().as_ref(),
+            &certified_collector.total_shipments.to_le_bytes()
+        ],
+        bump
+    )]
+    pub shipment: Account<'info, Shipment>,
+
+    #[account(mut)]
+    pub collector: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmShipmentReceipt<'info> {
+    #[account(mut)]
+    pub shipment: Account<'info, Shipment>,
+
+    #[account(
+        mut,
+        seeds = [b"recycler", recycler_wallet.key().as_ref()],
+        bump = recycler.bump,
+        constraint = recycler.recycler == shipment.recycler @ ErrorCode::WrongRecycler,
+        constraint = recycler.is_active @ ErrorCode::RecyclerNotActive
+    )]
+    pub recycler: Account<'info, Recycler>,
+
+    #[account(
+        init,
+        payer = recycler_wallet,
+        space = 8 + ProcessingReceipt::LEN,
+        seeds = [b"processing_receipt", shipment.key().as_ref()],
+        bump
+    )]
+    pub processing_receipt: Account<'info, ProcessingReceipt>,
+
+    #[account(mut)]
+    pub recycler_wallet: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitImpactMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"impact_mint"],
+        bump,
+        mint::decimals = IMPACT_MINT_DECIMALS,
+        mint::authority = global_state
+    )]
+    pub impact_mint: Account<'info, Mint>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IssueImpactCertificate<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub processing_receipt: Account<'info, ProcessingReceipt>,
+
+    #[account(mut, address = global_state.impact_mint)]
+    pub impact_mint: Account<'info, Mint>,
+
+    /// Token account del socio que recibe los certificados
+    #[account(mut, token::mint = impact_mint)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RetireImpactCertificate<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = global_state.impact_mint)]
+    pub impact_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = impact_mint, token::authority = owner)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(period_kind: PeriodKind, period_index: u64, scope: Pubkey)]
+pub struct CreateLeaderboard<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Leaderboard::LEN,
+        seeds = [
+            b"leaderboard".as_ref(),
+            &[period_kind as u8],
+            &period_index.to_le_bytes(),
+            scope.as_ref()
+        ],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    /// Bóveda con el premio del periodo (autoridad: global_state)
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"leaderboard_vault", leaderboard.key().as_ref()],
+        bump,
+        token::mint = ecoc_mint,
+        token::authority = global_state
+    )]
+    pub prize_vault: Account<'info, TokenAccount>,
+
+    #[account(address = global_state.ecoc_mint)]
+    pub ecoc_mint: Account<'info, Mint>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundLeaderboard<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    #[account(mut, address = leaderboard.prize_vault)]
+    pub prize_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+
+    pub funder: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePeriod<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    #[account(mut, address = leaderboard.prize_vault)]
+    pub prize_vault: Account<'info, TokenAccount>,
+
+    /// Tesorería: recibe el premio de un periodo sin ganadores
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.authority
+            && treasury_token_account.mint == global_state.ecoc_mint @ ErrorCode::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileCollection<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PenalizeDeposit<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    #[account(mut)]
+    pub deposit_record: Account<'info, DepositRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", deposit_record.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(root: [u8; 32], leaf: DepositLeaf, index: u32)]
+pub struct PenalizeCompressedDeposit<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    /// CHECK: Árbol de Merkle de depósitos
+    #[account(address = global_state.deposit_tree)]
+    pub deposit_tree: UncheckedAccount<'info>,
+
+    /// Marca de penalización de la hoja
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CompressedPenalty::LEN,
+        seeds = [b"compressed_penalty", deposit_tree.key().as_ref(), &index.to_le_bytes()],
+        bump
+    )]
+    pub penalty: Account<'info, CompressedPenalty>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", leaf.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Programa SPL Account Compression
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetUserSuspension<'info> {
+    #[account(mut)]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: AchievementKind)]
+pub struct ClaimAchievement<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Achievement::LEN,
+        seeds = [b"achievement", user.key().as_ref(), &[kind as u8]],
+        bump
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    /// CHECK: Mint de la insignia, creado en la instrucción con la extensión NonTransferable
+    #[account(
+        mut,
+        seeds = [b"badge_mint", user.key().as_ref(), &[kind as u8]],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Cuenta asociada Token-2022 del usuario, validada por el programa ATA
+    #[account(mut)]
+    pub user_badge_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetReputation<'info> {
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteDepositReward<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub container: Account<'info, SmartContainer>,
+
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Perfil del referente (requerido mientras el bono de referido aplique)
+    #[account(
+        seeds = [b"user_profile", referrer_profile.owner.as_ref()],
+        bump = referrer_profile.bump
+    )]
+    pub referrer_profile: Option<Account<'info, UserProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct GetUserSummary<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct GetContainerStatus<'info> {
+    pub container: Account<'info, SmartContainer>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDepositRecord<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", deposit_record.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut, close = payer, has_one = payer)]
+    pub deposit_record: Account<'info, DepositRecord>,
+
+    /// Recolección conciliada que cubre el depósito
+    #[account(mut)]
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    /// Quien pagó la renta del registro (firma el archivado)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRedemptionRecord<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", redemption_record.user.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut, close = payer, has_one = payer)]
+    pub redemption_record: Account<'info, RedemptionRecord>,
+
+    /// Quien pagó la renta del registro (firma el archivado)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCollectionRecord<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, address = collection_record.container)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(mut, close = payer, has_one = payer)]
+    pub collection_record: Account<'info, CollectionRecord>,
+
+    /// Quien pagó la renta del registro (firma el archivado)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    /// CHECK: Cuenta en esquema anterior, validada en `read_legacy_account`
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump
+    )]
+    pub global_state: UncheckedAccount<'info>,
+
+    pub ecoc_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCollectionRecord<'info> {
+    /// CHECK: Cuenta en esquema anterior, validada en la instrucción
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Indica el inicio del periodo y paga la renta adicional de la ampliación
+    #[account(mut, address = global_state.authority)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(username: String)]
+pub struct MigrateUserProfile<'info> {
+    /// CHECK: Cuenta en esquema anterior, validada en la instrucción
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+
+    /// Reserva del nombre del perfil (los perfiles v0 no la tenían)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UsernameRecord::LEN,
+        seeds = [b"username", username_seed(&username).as_ref()],
+        bump,
+        constraint = username_record.owner == Pubkey::default() @ ErrorCode::UsernameTaken
+    )]
+    pub username_record: Account<'info, UsernameRecord>,
+
+    /// Dueño del perfil; solo se exige para migrar con otro nombre
+    pub owner: Option<Signer<'info>>,
+
+    /// Paga la renta adicional de la ampliación y de la reserva
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Cuenta en esquema anterior, validada en la instrucción
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+
+    /// Paga la renta adicional de la ampliación
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleContainerStatus<'info> {
+    #[account(mut)]
+    pub container: Account<'info, SmartContainer>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = global_state.authority)]
     pub authority: Signer<'info>,
+}
+
+// ============================================================================
+// ESTRUCTURAS DE DATOS (PDAs)
+// ============================================================================
+
+/// Estado global del programa EcoBottle
+#[account]
+pub struct GlobalState {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub authority: Pubkey,              // Autoridad del programa
+    pub ecoc_per_kg: u64,              // Tokens ECOC por kilogramo de PET
+    pub min_deposit_weight: u64,       // Peso mínimo en gramos
+    pub total_pet_collected: u64,      // Total de PET recolectado en gramos
+    pub total_deposits: u64,           // Total de depósitos realizados
+    pub total_users: u64,              // Total de usuarios registrados
+    pub total_containers: u64,         // Total de contenedores registrados
+    pub discrepancy_tolerance_bps: u16, // Tolerancia de discrepancia en recolecciones
+    pub ecoc_mint: Pubkey,             // Mint de los tokens ECOC
+    pub record_retention_secs: i64,    // Retención mínima antes de cerrar registros
+    pub deposit_tree: Pubkey,          // Árbol de Merkle de depósitos comprimidos
+    pub compressed_deposits: u64,      // Hojas añadidas al árbol
+    pub referral_bonus_per_kg: u64,    // ECOC para el referente por kg del referido
+    pub referral_max_grams: u64,       // Primeros gramos del referido con bono
+    pub referral_cap_per_referrer: u64, // Tope de ECOC por referente (0 = bono deshabilitado)
+    pub bump: u8,                      // Bump seed para PDA
+    pub collection_sla_secs: i64,      // Plazo para completar una recolección asignada (0 = sin flujo de SLA)
+    pub collector_reward_per_kg: u64,  // ECOC por kg recolectado para recolectores
+    pub collector_sla_bonus_bps: u16,  // Bono por recolectar dentro del SLA
+    pub collector_reward_budget: u64,  // ECOC disponibles para incentivos
+    pub emission_factors: [u32; MATERIAL_COUNT], // g CO₂e evitados por kg de cada material
+    pub total_co2_avoided_grams: u64,  // CO₂e evitado por depósitos en gramos
+    pub impact_mint: Pubkey,           // Mint de certificados de impacto
+    pub total_co2_certified_grams: u64, // CO₂e certificado en planta
+    pub total_co2_retired_grams: u64,  // CO₂e retirado por compensaciones
+    pub reserved: [u8; 30],            // Espacio reservado para futuras versiones
+}
+
+impl GlobalState {
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 2 + 32 + 8 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 2 + 8
+        + 4 * MATERIAL_COUNT + 8 + 32 + 8 + 8 + 30;
+
+    /// Gramos de CO₂e evitados al reciclar `weight_grams` del material indicado
+    pub fn co2_avoided_for(&self, material: Material, weight_grams: u64) -> u64 {
+        weight_grams * self.emission_factors[material as usize] as u64 / 1000
+    }
+
+    /// Suma un depósito acreditado a las estadísticas globales
+    pub fn record_deposit(&mut self, weight_grams: u64, co2_grams: u64) {
+        self.total_pet_collected += weight_grams;
+        self.total_deposits += 1;
+        self.total_co2_avoided_grams += co2_grams;
+    }
+}
+
+/// Perfil de usuario en la blockchain
+#[account]
+pub struct UserProfile {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub owner: Pubkey,                 // Wallet del usuario
+    pub username: String,              // Nombre de usuario (max 32 caracteres UTF-8)
+    pub total_deposits: u64,           // Número de depósitos
+    pub total_pet_weight: u64,         // Total de PET depositado en gramos
+    pub total_ecoc_earned: u64,        // Total de ECOC ganados
+    pub total_ecoc_spent: u64,         // Total de ECOC gastados
+    pub created_at: i64,               // Timestamp de registro
+    pub reputation_score: u32,         // Puntos de reputación (0-1000)
+    pub current_streak: u32,           // Días consecutivos con depósitos
+    pub longest_streak: u32,           // Mejor racha diaria histórica
+    pub weekly_streak: u32,            // Semanas consecutivas con depósitos
+    pub last_deposit_at: i64,          // Timestamp del último depósito
+    pub period_week: u64,              // Semana de clasificación en curso
+    pub week_weight_grams: u64,        // PET depositado en la semana en curso
+    pub period_month: u64,             // Mes de clasificación en curso
+    pub month_weight_grams: u64,       // PET depositado en el mes en curso
+    pub referrer: Option<Pubkey>,      // Usuario que lo invitó
+    pub referred_weight_grams: u64,    // Peso que ya generó bono al referente
+    pub referral_count: u32,           // Usuarios invitados
+    pub referral_earnings: u64,        // ECOC ganados por referidos
+    pub avatar_uri: String,            // URI del avatar (max 128 bytes)
+    pub preferred_container: Pubkey,   // Contenedor habitual (default = ninguno)
+    pub archived_deposits: u64,        // Depósitos cerrados y resumidos
+    pub deposit_history_root: [u8; 32], // Hash encadenado de depósitos archivados
+    pub archived_redemptions: u64,     // Canjes cerrados y resumidos
+    pub redemption_history_root: [u8; 32], // Hash encadenado de canjes archivados
+    pub is_suspended: bool,            // Suspendido por la autoridad
+    pub bump: u8,                      // Bump seed para PDA
+    pub co2_avoided_grams: u64,        // CO₂e evitado por sus depósitos en gramos
+    pub referral_cap_exhausted_at: u64, // Tope por referente con el que se vio agotado (0 = nunca)
+    pub reserved: [u8; 112],           // Espacio reservado para futuras versiones
+}
+
+impl UserProfile {
+    pub const LEN: usize = 1 + 32 + (4 + MAX_USERNAME_BYTES) + 8 + 8 + 8 + 8 + 8 + 4 + 4 + 4 + 4 + 8 + 8 + 8 + 8 + 8
+        + (1 + 32) + 8 + 4 + 8 + (4 + MAX_AVATAR_URI_LEN) + 32 + 8 + 32 + 8 + 32 + 1 + 1 + 8 + 8 + 112;
+
+    /// Multiplicador de recompensa en puntos básicos según la reputación
+    /// (0 puntos = 0.8x, 500 puntos = 1.0x, 1000 puntos = 1.2x)
+    pub fn reward_multiplier_bps(&self) -> u64 {
+        REPUTATION_MIN_MULTIPLIER_BPS + self.reputation_score as u64 * 4
+    }
+
+    /// Suma un depósito acreditado al perfil (totales, racha y periodos)
+    pub fn record_deposit(&mut self, weight_grams: u64, ecoc_reward: u64, co2_grams: u64, now: i64) {
+        self.total_deposits += 1;
+        self.total_pet_weight += weight_grams;
+        self.total_ecoc_earned += ecoc_reward;
+        self.co2_avoided_grams += co2_grams;
+        self.record_deposit_activity(weight_grams, now);
+        self.record_period_weight(weight_grams, now);
+    }
+
+    /// Actualiza racha y reputación tras un depósito válido
+    pub fn record_deposit_activity(&mut self, weight_grams: u64, now: i64) {
+        let today = now / SECONDS_PER_DAY;
+        let last_day = self.last_deposit_at / SECONDS_PER_DAY;
+        let this_week = now / SECONDS_PER_WEEK;
+        let last_week = self.last_deposit_at / SECONDS_PER_WEEK;
+        let mut points = (weight_grams / 1000).min(REPUTATION_MAX_VOLUME_POINTS as u64) as u32;
+
+        if self.last_deposit_at == 0 || today > last_day {
+            // Consistencia: primer depósito del día
+            points += REPUTATION_CONSISTENCY_POINTS;
+
+            // Racha: depósito el día siguiente al último
+            self.current_streak = if self.last_deposit_at != 0 && today == last_day + 1 {
+                self.current_streak + 1
+            } else {
+                1
+            };
+            points += self.current_streak.min(REPUTATION_MAX_STREAK_POINTS);
+            self.longest_streak = self.longest_streak.max(self.current_streak);
+        }
+
+        if self.last_deposit_at == 0 || this_week > last_week {
+            self.weekly_streak = if self.last_deposit_at != 0 && this_week == last_week + 1 {
+                self.weekly_streak + 1
+            } else {
+                1
+            };
+        }
+
+        self.reputation_score = (self.reputation_score + points).min(REPUTATION_MAX);
+        self.last_deposit_at = now;
+    }
+
+    /// Acumula el peso depositado en los periodos de clasificación en curso
+    pub fn record_period_weight(&mut self, weight_grams: u64, now: i64) {
+        let week = PeriodKind::Weekly.index_at(now);
+        if self.period_week != week {
+            self.period_week = week;
+            self.week_weight_grams = 0;
+        }
+        self.week_weight_grams += weight_grams;
+
+        let month = PeriodKind::Monthly.index_at(now);
+        if self.period_month != month {
+            self.period_month = month;
+            self.month_weight_grams = 0;
+        }
+        self.month_weight_grams += weight_grams;
+    }
+
+    /// Peso depositado en el periodo en curso del tipo indicado
+    pub fn period_weight(&self, period_kind: PeriodKind) -> u64 {
+        match period_kind {
+            PeriodKind::Weekly => self.week_weight_grams,
+            PeriodKind::Monthly => self.month_weight_grams,
+        }
+    }
+
+    /// Indica si el usuario cumple los requisitos de un logro
+    pub fn is_eligible_for(&self, kind: AchievementKind) -> bool {
+        match kind {
+            AchievementKind::FirstDeposit => self.total_deposits >= 1,
+            AchievementKind::HundredKg => self.total_pet_weight >= ACHIEVEMENT_HUNDRED_KG_GRAMS,
+            AchievementKind::ThirtyDayStreak => self.longest_streak >= ACHIEVEMENT_STREAK_DAYS,
+        }
+    }
+
+    /// Resta puntos de reputación sin bajar de cero
+    pub fn apply_reputation_penalty(&mut self, points: u32) {
+        self.reputation_score = self.reputation_score.saturating_sub(points);
+    }
+}
+
+/// Contenedor Inteligente NFT - Punto de depósito físico
+#[account]
+pub struct SmartContainer {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub container_id: String,          // ID único del contenedor (max 32 chars)
+    pub location: String,              // Ubicación física (max 64 chars)
+    pub authority: Pubkey,             // Quien lo registró
+    pub capacity_kg: u64,              // Capacidad máxima en kilogramos
+    pub current_weight: u64,           // Peso actual en gramos
+    pub total_deposits: u64,           // Número de depósitos recibidos
+    pub is_active: bool,               // Si está operativo
+    pub created_at: i64,               // Fecha de registro
+    pub last_collection: i64,          // Última recolección
+    pub archived_collections: u64,     // Recolecciones cerradas y resumidas
+    pub collection_history_root: [u8; 32], // Hash encadenado de recolecciones archivadas
+    pub bump: u8,                      // Bump seed para PDA
+    pub last_receipt_hash: [u8; 32],   // Último recibo aceptado (anti-repetición)
+    pub fill_threshold_bps: u16,       // Umbral de llenado que dispara la alerta
+    pub needs_collection: bool,        // Umbral superado desde la última recolección
+    pub open_request: Pubkey,          // Solicitud de recolección abierta (default = ninguna)
+    pub collection_requests: u64,      // Solicitudes de recolección creadas
+    pub material: Material,            // Material que acepta el contenedor
+    pub co2_avoided_grams: u64,        // CO₂e evitado por sus depósitos en gramos
+    pub last_receipt_sequence: u64,    // Secuencia del último recibo aceptado (anti-repetición)
+    pub last_deposit_at: i64,          // Momento del último depósito registrado
+    pub open_deposit_records: u32,     // DepositRecords creados desde la última recolección
+    pub reserved: [u8; 24],            // Espacio reservado para futuras versiones
+}
+
+impl SmartContainer {
+    pub const LEN: usize = 1 + (4 + 32) + (4 + 64) + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 32 + 1 + 32 + 2 + 1 + 32 + 8 + 1 + 8 + 8 + 8 + 4 + 24;
+
+    /// Nivel de llenado en puntos básicos (10000 = lleno)
+    pub fn fill_level_bps(&self) -> u64 {
+        if self.capacity_kg == 0 {
+            return 10_000;
+        }
+        (self.current_weight as u128 * 10_000 / (self.capacity_kg as u128 * 1000)) as u64
+    }
+
+    /// Valida el recibo de un depósito: la secuencia del contenedor debe crecer,
+    /// así un recibo ya aceptado no se puede volver a presentar, y no puede
+    /// saltar más de `MAX_RECEIPT_SEQUENCE_GAP` (un salto a `u64::MAX` dejaría
+    /// el contenedor sin recibos válidos)
+    pub fn check_receipt(&self, receipt_hash: &[u8; 32], receipt_sequence: u64) -> Result<()> {
+        require!(*receipt_hash != [0; 32], ErrorCode::MissingReceipt);
+        require!(*receipt_hash != self.last_receipt_hash, ErrorCode::DuplicateReceipt);
+        require!(receipt_sequence > self.last_receipt_sequence, ErrorCode::StaleReceipt);
+        require!(
+            receipt_sequence - self.last_receipt_sequence <= MAX_RECEIPT_SEQUENCE_GAP,
+            ErrorCode::ReceiptSequenceGap
+        );
+        Ok(())
+    }
+
+    /// Valida el momento de una lectura sincronizada por lote: no puede ser
+    /// futuro ni anterior al último depósito registrado
+    pub fn check_deposit_time(&self, timestamp: i64, now: i64) -> Result<()> {
+        require!(timestamp <= now, ErrorCode::DepositInFuture);
+        require!(timestamp >= self.last_deposit_at, ErrorCode::DepositOutOfOrder);
+        Ok(())
+    }
+
+    /// Suma un depósito aceptado y guarda su recibo (anti-repetición)
+    pub fn record_deposit(
+        &mut self,
+        weight_grams: u64,
+        co2_grams: u64,
+        receipt_hash: [u8; 32],
+        receipt_sequence: u64,
+        deposited_at: i64,
+    ) {
+        self.last_receipt_hash = receipt_hash;
+        self.last_deposit_at = deposited_at;
+        self.last_receipt_sequence = receipt_sequence;
+        self.current_weight += weight_grams;
+        self.total_deposits += 1;
+        self.co2_avoided_grams += co2_grams;
+    }
+}
+
+/// Registro de Depósito - Prueba de Reciclaje en Blockchain
+#[account]
+pub struct DepositRecord {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub user: Pubkey,                  // Usuario que depositó
+    pub container: Pubkey,             // Contenedor donde se depositó
+    pub weight_grams: u64,             // Peso del depósito
+    pub ecoc_reward: u64,              // Recompensa otorgada
+    pub timestamp: i64,                // Cuándo se realizó
+    pub slot: u64,                     // Slot en que se procesó
+    pub penalized: bool,               // Penalizado por discrepancia
+    pub payer: Pubkey,                 // Quien pagó la renta
+    pub bump: u8,                      // Bump seed para PDA
+    pub receipt_hash: [u8; 32],        // Hash del recibo/atestación del contenedor
+    pub migrated: bool,                // Migrado de v0: no cuenta en `outstanding_deposits`
+    pub reserved: [u8; 31],            // Espacio reservado para futuras versiones
+}
+
+impl DepositRecord {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 32 + 1 + 32 + 1 + 31;
+}
+
+/// Depósito de un invitado con la recompensa en custodia hasta su reclamo
+#[account]
+pub struct GuestDeposit {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub claim_commitment: [u8; 32],    // Compromiso del código de reclamo
+    pub container: Pubkey,             // Contenedor donde se depositó
+    pub weight_grams: u64,             // Peso del depósito
+    pub ecoc_reward: u64,              // Recompensa en la bóveda
+    pub co2_grams: u64,                // CO₂e evitado en gramos
+    pub timestamp: i64,                // Cuándo se realizó
+    pub slot: u64,                     // Slot en que se procesó
+    pub expires_at: i64,               // Fin del plazo de reclamo
+    pub receipt_hash: [u8; 32],        // Hash del recibo/atestación del contenedor
+    pub payer: Pubkey,                 // Quien pagó la renta
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl GuestDeposit {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 32 + 1 + 32;
+}
+
+/// Compromiso de una wallet con el código de un depósito de invitado
+#[account]
+pub struct GuestClaimIntent {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub guest_deposit: Pubkey,         // Depósito de invitado que se reclama
+    pub claimer: Pubkey,               // Wallet que reclama
+    pub binding: [u8; 32],             // guest_claim_binding(código, claimer)
+    pub slot: u64,                     // Slot del compromiso
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 16],            // Espacio reservado para futuras versiones
+}
+
+impl GuestClaimIntent {
+    pub const LEN: usize = 1 + 32 + 32 + 32 + 8 + 1 + 16;
+}
+
+/// Organización que gestiona perfiles custodiados (escuelas, residencias...)
+#[account]
+pub struct Organization {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub admin: Pubkey,                 // Wallet del administrador
+    pub name: String,                  // Nombre (max 32 chars)
+    pub vault: Pubkey,                 // Token account con los ECOC de los miembros
+    pub is_active: bool,               // Registro vigente
+    pub registered_at: i64,            // Fecha de registro
+    pub member_count: u64,             // Miembros dados de alta
+    pub total_deposits: u64,           // Depósitos de sus miembros
+    pub total_pet_weight: u64,         // Peso depositado por sus miembros en gramos
+    pub ecoc_held: u64,                // ECOC custodiados en la bóveda
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 64],            // Espacio reservado para futuras versiones
+}
+
+impl Organization {
+    pub const LEN: usize = 1 + 32 + (4 + 32) + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 1 + 64;
+}
+
+/// Perfil custodiado de un miembro sin wallet, identificado por el hash de
+/// su identificador en la organización
+#[account]
+pub struct OrganizationMember {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub organization: Pubkey,          // Organización que lo gestiona
+    pub member_id_hash: [u8; 32],      // Hash del identificador del miembro
+    pub total_deposits: u64,           // Depósitos realizados
+    pub total_pet_weight: u64,         // Peso depositado en gramos
+    pub total_ecoc_earned: u64,        // ECOC ganados
+    pub co2_avoided_grams: u64,        // CO₂e evitado en gramos
+    pub ecoc_balance: u64,             // ECOC custodiados pendientes de retirar
+    pub wallet: Pubkey,                // Wallet propia tras graduarse (default = ninguna)
+    pub created_at: i64,               // Fecha de alta
+    pub graduated_at: i64,             // Fecha de graduación (0 = custodiado)
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl OrganizationMember {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 1 + 32;
+}
+
+/// Registro de Canje de Tokens
+#[account]
+pub struct RedemptionRecord {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub user: Pubkey,                  // Usuario que canjeó
+    pub product_id: String,            // ID del producto (max 32 chars)
+    pub amount: u64,                   // Cantidad de ECOC canjeados
+    pub timestamp: i64,                // Cuándo se canjeó
+    pub payer: Pubkey,                 // Quien pagó la renta
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl RedemptionRecord {
+    pub const LEN: usize = 1 + 32 + (4 + 32) + 8 + 8 + 32 + 1 + 32;
+}
+
+/// Registro de Recolección - Certificado de Trazabilidad
+#[account]
+pub struct CollectionRecord {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub container: Pubkey,             // Contenedor recolectado
+    pub collector: Pubkey,             // Empresa recolectora
+    pub weight_collected: u64,         // Peso recolectado en gramos
+    pub timestamp: i64,                // Fecha de recolección
+    pub verified: bool,                // Verificado por blockchain
+    pub period_start: i64,             // Recolección anterior (inicio del periodo)
+    pub measured_weight: u64,          // Peso medido en planta en gramos
+    pub discrepancy_bps: u16,          // Discrepancia registrado vs medido
+    pub reconciled: bool,              // Si ya fue conciliada
+    pub payer: Pubkey,                 // Quien pagó la renta
+    pub bump: u8,                      // Bump seed para PDA
+    pub collector_reward: u64,         // ECOC pagados al recolector
+    pub sla_bonus: u64,                // Parte del incentivo por cumplir el SLA
+    pub shipped: bool,                 // Incluida en un envío a planta
+    pub material: Material,            // Material del contenedor recolectado
+    pub outstanding_deposits: u32,     // DepositRecords cubiertos aún sin archivar
+    pub reserved: [u8; 10],            // Espacio reservado para futuras versiones
+}
+
+impl CollectionRecord {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1 + 8 + 8 + 2 + 1 + 32 + 1 + 8 + 8 + 1 + 1 + 4 + 10;
+
+    /// Indica si un depósito en `container` a las `timestamp` cae en esta recolección
+    pub fn covers(&self, container: &Pubkey, timestamp: i64) -> bool {
+        self.container == *container && timestamp > self.period_start && timestamp <= self.timestamp
+    }
+}
 
-    pub system_program: Program<'info, System>,
+/// Empresa recolectora certificada por la autoridad
+#[account]
+pub struct CertifiedCollector {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub collector: Pubkey,             // Wallet de la empresa
+    pub name: String,                  // Nombre comercial (max 32 chars)
+    pub is_active: bool,               // Certificación vigente
+    pub registered_at: i64,            // Fecha de certificación
+    pub bump: u8,                      // Bump seed para PDA
+    pub total_shipments: u64,          // Envíos a planta creados
+    pub reserved: [u8; 56],            // Espacio reservado para futuras versiones
 }
 
-#[derive(Accounts)]
-pub struct ProcessDeposit<'info> {
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
+impl CertifiedCollector {
+    pub const LEN: usize = 1 + 32 + (4 + 32) + 1 + 8 + 1 + 8 + 56;
+}
 
-    #[account(mut)]
-    pub container: Account<'info, SmartContainer>,
+/// Planta recicladora registrada - Destino final de la cadena de custodia
+#[account]
+pub struct Recycler {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub recycler: Pubkey,              // Wallet de la planta
+    pub name: String,                  // Nombre comercial (max 32 chars)
+    pub is_active: bool,               // Autorización vigente
+    pub registered_at: i64,            // Fecha de registro
+    pub total_received_grams: u64,     // PET recibido en gramos
+    pub total_output_grams: u64,       // Material reciclado producido en gramos
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 64],            // Espacio reservado para futuras versiones
+}
 
-    #[account(
-        mut,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump = user_profile.bump
-    )]
-    pub user_profile: Account<'info, UserProfile>,
+impl Recycler {
+    pub const LEN: usize = 1 + 32 + (4 + 32) + 1 + 8 + 8 + 8 + 1 + 64;
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + DepositRecord::LEN,
-        seeds = [
-            b"deposit",
-            user.key().as_ref(),
-            &global_state.total_deposits.to_le_bytes()
-        ],
-        bump
-    )]
-    pub deposit_record: Account<'info, DepositRecord>,
+/// Envío de recolecciones desde un recolector hacia una planta recicladora
+#[account]
+pub struct Shipment {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub collector: Pubkey,             // Recolector que envía
+    pub recycler: Pubkey,              // Planta de destino
+    pub collections: Vec<Pubkey>,      // Recolecciones incluidas
+    pub declared_weight: u64,          // Peso declarado en gramos
+    pub created_at: i64,               // Fecha de despacho
+    pub status: ShipmentStatus,        // Estado del envío
+    pub received_at: i64,              // Fecha de recepción en planta
+    pub bump: u8,                      // Bump seed para PDA
+    pub material: Material,            // Material de las recolecciones enviadas
+    pub reserved: [u8; 31],            // Espacio reservado para futuras versiones
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+impl Shipment {
+    pub const LEN: usize = 1 + 32 + 32 + (4 + MAX_SHIPMENT_COLLECTIONS * 32) + 8 + 8 + 1 + 8 + 1 + 1 + 31;
+}
 
-    /// Token account del usuario para recibir ECOC
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+/// Estado de un envío a planta
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShipmentStatus {
+    InTransit,                         // En camino a la planta
+    Received,                          // Recibido y procesado
+}
 
-    /// Mint de ECOC tokens
-    #[account(mut)]
-    pub ecoc_mint: Account<'info, Mint>,
+/// Recibo de procesamiento - Cierra la cadena de custodia en la planta
+#[account]
+pub struct ProcessingReceipt {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub shipment: Pubkey,              // Envío recibido
+    pub recycler: Pubkey,              // Planta que lo recibió
+    pub received_weight: u64,          // Peso recibido en gramos
+    pub discrepancy_bps: u16,          // Discrepancia declarado vs recibido
+    pub output_product: String,        // Material producido (max 32 chars)
+    pub output_grams: u64,             // Cantidad producida en gramos
+    pub timestamp: i64,                // Fecha de recepción
+    pub bump: u8,                      // Bump seed para PDA
+    pub certified: bool,               // Certificados de impacto ya emitidos
+    pub co2_certified_grams: u64,      // CO₂e certificado por este recibo
+    pub material: Material,            // Material del envío procesado
+    pub reserved: [u8; 22],            // Espacio reservado para futuras versiones
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+impl ProcessingReceipt {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 2 + (4 + 32) + 8 + 8 + 1 + 1 + 8 + 1 + 22;
 }
 
-#[derive(Accounts)]
-pub struct RedeemTokens<'info> {
-    #[account(
-        mut,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump = user_profile.bump
-    )]
-    pub user_profile: Account<'info, UserProfile>,
+/// Solicitud de recolección - Asignable a recolectores certificados con plazo (SLA)
+#[account]
+pub struct CollectionRequest {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub container: Pubkey,             // Contenedor a recolectar
+    pub requested_at: i64,             // Cuándo se abrió la solicitud
+    pub status: CollectionRequestStatus, // Estado actual
+    pub assigned_collector: Pubkey,    // Recolector asignado (default = ninguno)
+    pub claimed_at: i64,               // Cuándo se asignó
+    pub deadline: i64,                 // Fin del plazo de la asignación
+    pub completed_at: i64,             // Cuándo se completó
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
 
-    #[account(
-        init,
-        payer = user,
-        space = 8 + RedemptionRecord::LEN,
-        seeds = [
-            b"redemption",
-            user.key().as_ref(),
-            &user_profile.total_ecoc_spent.to_le_bytes()
-        ],
-        bump
-    )]
-    pub redemption_record: Account<'info, RedemptionRecord>,
+impl CollectionRequest {
+    pub const LEN: usize = 1 + 32 + 8 + 1 + 32 + 8 + 8 + 8 + 1 + 32;
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+/// Estado de una solicitud de recolección
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionRequestStatus {
+    Open,                              // Esperando recolector
+    Claimed,                           // Asignada a un recolector
+    Completed,                         // Recolección realizada
+}
 
-    /// Token account del usuario
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+/// Marca de bebidas - Productor con responsabilidad extendida (EPR)
+#[account]
+pub struct Brand {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub owner: Pubkey,                 // Wallet de la marca
+    pub name: String,                  // Nombre comercial (max 32 chars)
+    pub is_active: bool,               // Registro vigente
+    pub registered_at: i64,            // Fecha de registro
+    pub recovered_grams: u64,          // Envases propios recuperados en gramos
+    pub tagged_deposits: u64,          // Depósitos identificados con la marca
+    pub credits_purchased: u64,        // Créditos EPR comprados (gramos)
+    pub credits_retired: u64,          // Créditos EPR retirados (gramos)
+    pub bump: u8,                      // Bump seed para PDA
+    pub recovered_by_material: [u64; MATERIAL_COUNT], // Envases recuperados por material
+    pub credits_by_material: [u64; MATERIAL_COUNT],   // Créditos EPR comprados por material
+    pub minted_by_material: [u64; MATERIAL_COUNT],    // Créditos EPR emitidos a la bóveda por material
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
 
-    /// Mint de ECOC tokens
-    #[account(mut)]
-    pub ecoc_mint: Account<'info, Mint>,
+impl Brand {
+    pub const LEN: usize = 1 + 32 + (4 + 32) + 1 + 8 + 8 + 8 + 8 + 8 + 1 + 8 * MATERIAL_COUNT * 3 + 32;
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+/// Configuración de los créditos EPR (cuenta única)
+#[account]
+pub struct EprState {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub epr_mint: Pubkey,              // Mint de créditos EPR
+    pub treasury: Pubkey,              // Wallet que recibe los pagos
+    pub price_per_kg: u64,             // Precio en lamports por kg
+    pub total_minted_grams: u64,       // Créditos emitidos (gramos)
+    pub total_retired_grams: u64,      // Créditos retirados (gramos)
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 64],            // Espacio reservado para futuras versiones
 }
 
-#[derive(Accounts)]
-pub struct CollectContainer<'info> {
-    #[account(mut)]
-    pub container: Account<'info, SmartContainer>,
+impl EprState {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 1 + 64;
+}
 
-    #[account(
-        init,
-        payer = collector,
-        space = 8 + CollectionRecord::LEN,
-        seeds = [
-            b"collection",
-            container.key().as_ref(),
-            &container.total_deposits.to_le_bytes()
-        ],
-        bump
-    )]
-    pub collection_record: Account<'info, CollectionRecord>,
+/// Registro de SKUs del sistema de depósito y retorno (cuenta única)
+#[account]
+pub struct SkuRegistry {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub skus: Vec<SkuEntry>,           // SKUs registrados (max 100)
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl SkuRegistry {
+    pub const LEN: usize = 1 + (4 + MAX_REGISTERED_SKUS * SkuEntry::LEN) + 1 + 32;
+
+    /// SKU activo con el GTIN indicado
+    pub fn find(&self, gtin: u64) -> Option<&SkuEntry> {
+        self.skus.iter().find(|s| s.gtin == gtin && s.is_active)
+    }
+}
+
+/// Valor de depósito de un SKU
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SkuEntry {
+    pub gtin: u64,                     // Código GTIN del envase
+    pub deposit_value: u64,            // ECOC por envase devuelto
+    pub unit_weight_grams: u32,        // Peso de referencia del envase
+    pub is_active: bool,               // Acepta devoluciones
+}
+
+impl SkuEntry {
+    pub const LEN: usize = 8 + 8 + 4 + 1;
+}
+
+/// Números de serie canjeados de un bloque de un GTIN (1 bit por serie)
+#[account]
+pub struct SerialBitmap {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub gtin: u64,                     // Código GTIN
+    pub bucket: u64,                   // Bloque de series (serie / 4096)
+    pub bits: [u8; (SERIAL_BITMAP_BITS / 8) as usize], // Series canjeadas
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 16],            // Espacio reservado para futuras versiones
+}
+
+impl SerialBitmap {
+    pub const LEN: usize = 1 + 8 + 8 + (SERIAL_BITMAP_BITS / 8) as usize + 1 + 16;
+
+    /// Marca una serie del bloque como canjeada; `false` si ya lo estaba
+    pub fn mark_redeemed(&mut self, offset: u64) -> bool {
+        let byte = (offset / 8) as usize;
+        let mask = 1u8 << (offset % 8);
+        if self.bits[byte] & mask != 0 {
+            return false;
+        }
+        self.bits[byte] |= mask;
+        true
+    }
+}
+
+/// Depósito por peso leído sin conexión, para `process_deposit_batch`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BatchDeposit {
+    pub user_index: u8,                // Par (perfil, token account) del usuario en `remaining_accounts`
+    pub weight_grams: u64,             // Peso medido en gramos
+    pub receipt_hash: [u8; 32],        // Recibo del contenedor
+    pub receipt_sequence: u64,         // Secuencia del recibo en el contenedor (creciente)
+    pub timestamp: i64,                // Momento de la lectura en el contenedor
+}
+
+/// Envase escaneado por el contenedor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ScannedItem {
+    pub gtin: u64,                     // Código GTIN
+    pub serial: u64,                   // Número de serie individual
+}
+
+/// Reserva de nombre de usuario - Garantiza nombres únicos
+#[account]
+pub struct UsernameRecord {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub owner: Pubkey,                 // Wallet que reservó el nombre
+    pub username: String,              // Nombre tal como se registró
+    pub payer: Pubkey,                 // Quien pagó la renta
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 16],            // Espacio reservado para futuras versiones
+}
+
+impl UsernameRecord {
+    pub const LEN: usize = 1 + 32 + (4 + MAX_USERNAME_BYTES) + 32 + 1 + 16;
+}
+
+/// Logro otorgado - Insignia soulbound (NFT no transferible)
+#[account]
+pub struct Achievement {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub user: Pubkey,                  // Usuario que obtuvo el logro
+    pub kind: AchievementKind,         // Tipo de logro
+    pub mint: Pubkey,                  // Mint Token-2022 de la insignia
+    pub awarded_at: i64,               // Fecha de otorgamiento
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 16],            // Espacio reservado para futuras versiones
+}
+
+impl Achievement {
+    pub const LEN: usize = 1 + 32 + 1 + 32 + 8 + 1 + 16;
+}
+
+/// Tipos de logro disponibles
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AchievementKind {
+    FirstDeposit,                      // Primer depósito
+    HundredKg,                         // 100 kg de PET acumulados
+    ThirtyDayStreak,                   // Racha de 30 días
+}
+
+/// Material reciclable (índice en los factores de emisión)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Material {
+    Pet,                               // Botellas de PET
+    Hdpe,                              // Envases de polietileno de alta densidad
+    Aluminum,                          // Latas de aluminio
+    Glass,                             // Botellas de vidrio
+}
+
+/// Tabla de clasificación de un periodo (global o por contenedor)
+#[account]
+pub struct Leaderboard {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub period_kind: PeriodKind,       // Semanal o mensual
+    pub period_index: u64,             // Índice del periodo desde el epoch Unix
+    pub scope: Pubkey,                 // Pubkey::default() = global, o contenedor
+    pub entries: Vec<LeaderboardEntry>, // Top-N ordenado por peso (desc)
+    pub prize_pool: u64,               // ECOC aportados al premio
+    pub prize_vault: Pubkey,           // Token account con el premio
+    pub is_closed: bool,               // Periodo finalizado
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl Leaderboard {
+    pub const LEN: usize = 1 + 1 + 8 + 32 + (4 + LEADERBOARD_SIZE * LeaderboardEntry::LEN) + 8 + 32 + 1 + 1 + 32;
+
+    /// Puntuación actual de un usuario en la tabla (0 si no figura)
+    pub fn score_of(&self, user: &Pubkey) -> u64 {
+        self.entries
+            .iter()
+            .find(|entry| entry.user == *user)
+            .map_or(0, |entry| entry.weight_grams)
+    }
+
+    /// Inserta o actualiza a un usuario manteniendo solo el top-N
+    pub fn upsert(&mut self, user: Pubkey, weight_grams: u64) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.user == user) {
+            entry.weight_grams = weight_grams;
+        } else if self.entries.len() < LEADERBOARD_SIZE {
+            self.entries.push(LeaderboardEntry { user, weight_grams });
+        } else if let Some(last) = self.entries.last_mut() {
+            if weight_grams <= last.weight_grams {
+                return;
+            }
+            *last = LeaderboardEntry { user, weight_grams };
+        }
+        self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.weight_grams));
+    }
+}
+
+/// Peso acumulado de un usuario en una tabla por contenedor; conserva la
+/// puntuación aunque el usuario salga temporalmente del top-N
+#[account]
+pub struct LeaderboardScore {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub leaderboard: Pubkey,           // Tabla de clasificación
+    pub user: Pubkey,                  // Wallet del usuario
+    pub weight_grams: u64,             // Peso depositado en el contenedor durante el periodo
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl LeaderboardScore {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 1 + 32;
+}
+
+/// Posición en una tabla de clasificación
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LeaderboardEntry {
+    pub user: Pubkey,
+    pub weight_grams: u64,
+}
+
+impl LeaderboardEntry {
+    pub const LEN: usize = 32 + 8;
+}
+
+/// Duración de un periodo de clasificación
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeriodKind {
+    Weekly,                            // 7 días
+    Monthly,                           // 30 días
+}
+
+impl PeriodKind {
+    /// Duración del periodo en segundos
+    pub fn duration(&self) -> i64 {
+        match self {
+            PeriodKind::Weekly => SECONDS_PER_WEEK,
+            PeriodKind::Monthly => SECONDS_PER_MONTH,
+        }
+    }
+
+    /// Índice del periodo que contiene el timestamp indicado
+    pub fn index_at(&self, timestamp: i64) -> u64 {
+        (timestamp / self.duration()) as u64
+    }
+
+    /// Timestamp en que termina el periodo indicado
+    pub fn end_of(&self, period_index: u64) -> i64 {
+        (period_index as i64 + 1) * self.duration()
+    }
+}
+
+/// Penalización aplicada a un depósito comprimido (una por hoja del árbol)
+#[account]
+pub struct CompressedPenalty {
+    pub version: u8,                   // Versión del esquema de la cuenta
+    pub deposit_tree: Pubkey,          // Árbol de Merkle del depósito
+    pub leaf_index: u32,               // Hoja penalizada
+    pub user: Pubkey,                  // Usuario penalizado
+    pub collection: Pubkey,            // Recolección con la discrepancia
+    pub timestamp: i64,                // Cuándo se aplicó
+    pub bump: u8,                      // Bump seed para PDA
+    pub reserved: [u8; 32],            // Espacio reservado para futuras versiones
+}
+
+impl CompressedPenalty {
+    pub const LEN: usize = 1 + 32 + 4 + 32 + 32 + 8 + 1 + 32;
+}
+
+/// Hoja de un depósito comprimido (se guarda su hash en el árbol de Merkle)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DepositLeaf {
+    pub user: Pubkey,
+    pub container: Pubkey,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub receipt_hash: [u8; 32],        // Recibo/atestación del contenedor
+    pub nonce: u64,                    // Número global de depósito
+}
+
+impl DepositLeaf {
+    /// Hash sha256 de la hoja serializada
+    pub fn hash(&self) -> [u8; 32] {
+        let mut data = Vec::with_capacity(32 + 32 + 8 + 8 + 8 + 8 + 32 + 8);
+        data.extend_from_slice(self.user.as_ref());
+        data.extend_from_slice(self.container.as_ref());
+        data.extend_from_slice(&self.weight_grams.to_le_bytes());
+        data.extend_from_slice(&self.ecoc_reward.to_le_bytes());
+        data.extend_from_slice(&self.timestamp.to_le_bytes());
+        data.extend_from_slice(&self.slot.to_le_bytes());
+        data.extend_from_slice(&self.receipt_hash);
+        data.extend_from_slice(&self.nonce.to_le_bytes());
+        hash(&data).to_bytes()
+    }
+}
+
+// ============================================================================
+// ESQUEMAS ANTERIORES (MIGRACIONES)
+// ============================================================================
+
+/// Esquema v0 de `GlobalState` (sin versión ni espacio reservado)
+#[derive(AnchorDeserialize)]
+pub struct GlobalStateV0 {
+    pub authority: Pubkey,
+    pub ecoc_per_kg: u64,
+    pub min_deposit_weight: u64,
+    pub total_pet_collected: u64,
+    pub total_deposits: u64,
+    pub total_users: u64,
+    pub total_containers: u64,
+    pub bump: u8,
+}
+
+impl GlobalStateV0 {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+impl From<GlobalStateV0> for GlobalState {
+    fn from(legacy: GlobalStateV0) -> Self {
+        GlobalState {
+            version: CURRENT_ACCOUNT_VERSION,
+            authority: legacy.authority,
+            ecoc_per_kg: legacy.ecoc_per_kg,
+            min_deposit_weight: legacy.min_deposit_weight,
+            total_pet_collected: legacy.total_pet_collected,
+            total_deposits: legacy.total_deposits,
+            total_users: legacy.total_users,
+            total_containers: legacy.total_containers,
+            discrepancy_tolerance_bps: DEFAULT_DISCREPANCY_TOLERANCE_BPS,
+            ecoc_mint: Pubkey::default(),
+            record_retention_secs: DEFAULT_RECORD_RETENTION_SECS,
+            deposit_tree: Pubkey::default(),
+            compressed_deposits: 0,
+            referral_bonus_per_kg: 0,
+            referral_max_grams: 0,
+            referral_cap_per_referrer: 0,
+            bump: legacy.bump,
+            collection_sla_secs: 0, // Sin flujo de SLA hasta que la autoridad lo active
+            collector_reward_per_kg: 0,
+            collector_sla_bonus_bps: 0,
+            collector_reward_budget: 0,
+            emission_factors: DEFAULT_EMISSION_FACTORS,
+            total_co2_avoided_grams: 0,
+            impact_mint: Pubkey::default(),
+            total_co2_certified_grams: 0,
+            total_co2_retired_grams: 0,
+            reserved: [0; 30],
+        }
+    }
+}
+
+/// Esquema v0 de `UserProfile`
+#[derive(AnchorDeserialize)]
+pub struct UserProfileV0 {
+    pub owner: Pubkey,
+    pub username: String,
+    pub total_deposits: u64,
+    pub total_pet_weight: u64,
+    pub total_ecoc_earned: u64,
+    pub total_ecoc_spent: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl UserProfileV0 {
+    pub const LEN: usize = 32 + (4 + 32) + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+impl From<UserProfileV0> for UserProfile {
+    fn from(legacy: UserProfileV0) -> Self {
+        UserProfile {
+            version: CURRENT_ACCOUNT_VERSION,
+            owner: legacy.owner,
+            username: legacy.username,
+            total_deposits: legacy.total_deposits,
+            total_pet_weight: legacy.total_pet_weight,
+            total_ecoc_earned: legacy.total_ecoc_earned,
+            total_ecoc_spent: legacy.total_ecoc_spent,
+            created_at: legacy.created_at,
+            reputation_score: REPUTATION_INITIAL,
+            current_streak: 0,
+            longest_streak: 0,
+            weekly_streak: 0,
+            last_deposit_at: 0,
+            period_week: 0,
+            week_weight_grams: 0,
+            period_month: 0,
+            month_weight_grams: 0,
+            referrer: None,
+            referred_weight_grams: 0,
+            referral_count: 0,
+            referral_earnings: 0,
+            avatar_uri: String::new(),
+            preferred_container: Pubkey::default(),
+            archived_deposits: 0,
+            deposit_history_root: [0; 32],
+            archived_redemptions: 0,
+            redemption_history_root: [0; 32],
+            is_suspended: false,
+            bump: legacy.bump,
+            co2_avoided_grams: 0,
+            referral_cap_exhausted_at: 0,
+            reserved: [0; 112],
+        }
+    }
+}
+
+/// Esquema v0 de `SmartContainer`
+#[derive(AnchorDeserialize)]
+pub struct SmartContainerV0 {
+    pub container_id: String,
+    pub location: String,
+    pub authority: Pubkey,
+    pub capacity_kg: u64,
+    pub current_weight: u64,
+    pub total_deposits: u64,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub last_collection: i64,
+    pub bump: u8,
+}
+
+impl SmartContainerV0 {
+    pub const LEN: usize = (4 + 32) + (4 + 64) + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 1;
+}
+
+impl From<SmartContainerV0> for SmartContainer {
+    fn from(legacy: SmartContainerV0) -> Self {
+        SmartContainer {
+            version: CURRENT_ACCOUNT_VERSION,
+            container_id: legacy.container_id,
+            location: legacy.location,
+            authority: legacy.authority,
+            capacity_kg: legacy.capacity_kg,
+            current_weight: legacy.current_weight,
+            total_deposits: legacy.total_deposits,
+            is_active: legacy.is_active,
+            created_at: legacy.created_at,
+            last_collection: legacy.last_collection,
+            archived_collections: 0,
+            collection_history_root: [0; 32],
+            bump: legacy.bump,
+            last_receipt_hash: [0; 32],
+            fill_threshold_bps: DEFAULT_FILL_THRESHOLD_BPS,
+            needs_collection: false,
+            open_request: Pubkey::default(),
+            collection_requests: 0,
+            material: Material::Pet,
+            co2_avoided_grams: 0,
+            last_receipt_sequence: 0,
+            last_deposit_at: 0,
+            open_deposit_records: 0,
+            reserved: [0; 24],
+        }
+    }
+}
+
+/// Esquema v0 de `DepositRecord` (la renta la pagaba el usuario)
+#[derive(AnchorDeserialize)]
+pub struct DepositRecordV0 {
+    pub user: Pubkey,
+    pub container: Pubkey,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub timestamp: i64,
+    pub tx_signature: u64,
+    pub bump: u8,
+}
+
+impl DepositRecordV0 {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 1;
+}
+
+impl From<DepositRecordV0> for DepositRecord {
+    fn from(legacy: DepositRecordV0) -> Self {
+        DepositRecord {
+            version: CURRENT_ACCOUNT_VERSION,
+            user: legacy.user,
+            container: legacy.container,
+            weight_grams: legacy.weight_grams,
+            ecoc_reward: legacy.ecoc_reward,
+            timestamp: legacy.timestamp,
+            slot: 0, // `tx_signature` era simulado: slot desconocido
+            penalized: false,
+            payer: legacy.user,
+            bump: legacy.bump,
+            receipt_hash: [0; 32],
+            migrated: true,
+            reserved: [0; 31],
+        }
+    }
+}
+
+/// Esquema v0 de `RedemptionRecord` (la renta la pagaba el usuario)
+#[derive(AnchorDeserialize)]
+pub struct RedemptionRecordV0 {
+    pub user: Pubkey,
+    pub product_id: String,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl RedemptionRecordV0 {
+    pub const LEN: usize = 32 + (4 + 32) + 8 + 8 + 1;
+}
+
+impl From<RedemptionRecordV0> for RedemptionRecord {
+    fn from(legacy: RedemptionRecordV0) -> Self {
+        RedemptionRecord {
+            version: CURRENT_ACCOUNT_VERSION,
+            user: legacy.user,
+            product_id: legacy.product_id,
+            amount: legacy.amount,
+            timestamp: legacy.timestamp,
+            payer: legacy.user,
+            bump: legacy.bump,
+            reserved: [0; 32],
+        }
+    }
+}
+
+/// Esquema v0 de `CollectionRecord` (la renta la pagaba el recolector)
+#[derive(AnchorDeserialize)]
+pub struct CollectionRecordV0 {
+    pub container: Pubkey,
+    pub collector: Pubkey,
+    pub weight_collected: u64,
+    pub timestamp: i64,
+    pub verified: bool,
+    pub bump: u8,
+}
+
+impl CollectionRecordV0 {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1 + 1;
+}
+
+impl CollectionRecord {
+    /// Registro v1 de una recolección v0 que cubre los depósitos posteriores a `period_start`
+    pub fn from_legacy(legacy: CollectionRecordV0, period_start: i64) -> Self {
+        CollectionRecord {
+            version: CURRENT_ACCOUNT_VERSION,
+            container: legacy.container,
+            collector: legacy.collector,
+            weight_collected: legacy.weight_collected,
+            timestamp: legacy.timestamp,
+            verified: legacy.verified,
+            period_start,
+            measured_weight: 0,
+            discrepancy_bps: 0,
+            reconciled: false,
+            payer: legacy.collector,
+            bump: legacy.bump,
+            collector_reward: 0,
+            sla_bonus: 0,
+            shipped: false,
+            material: Material::Pet,
+            outstanding_deposits: 0,
+            reserved: [0; 10],
+        }
+    }
+}
+
+// ============================================================================
+// EVENTOS
+// ============================================================================
+
+/// Depósito por peso acreditado (con el nonce del `DepositRecord` o de la hoja comprimida)
+#[event]
+pub struct DepositProcessed {
+    pub user: Pubkey,
+    pub container: Pubkey,
+    pub nonce: u64,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub referral_bonus: u64,
+    pub co2_grams: u64,
+    pub receipt_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// ECOC quemados a cambio de un producto
+#[event]
+pub struct TokensRedeemed {
+    pub user: Pubkey,
+    pub product_id: String,
+    pub amount: u64,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub collector: Signer<'info>,
+/// Contenedor vaciado por un recolector
+#[event]
+pub struct ContainerCollected {
+    pub container: Pubkey,
+    pub collector: Pubkey,
+    pub collection_record: Pubkey,
+    pub weight_collected: u64,
+    pub collector_reward: u64,
+    pub sla_bonus: u64,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
+/// Un contenedor superó su umbral de llenado
+#[event]
+pub struct ContainerNeedsCollection {
+    pub container: Pubkey,
+    pub fill_level_bps: u64,
+    pub current_weight: u64,
+    pub capacity_kg: u64,
 }
 
-#[derive(Accounts)]
-pub struct UpdateConfig<'info> {
-    #[account(
-        mut,
-        seeds = [b"global_state"],
-        bump = global_state.bump
-    )]
-    pub global_state: Account<'info, GlobalState>,
+/// Certificados de impacto emitidos por material procesado en planta
+#[event]
+pub struct ImpactCertificateIssued {
+    pub processing_receipt: Pubkey,
+    pub recipient: Pubkey,
+    pub material: Material,
+    pub processed_grams: u64,
+    pub co2_grams: u64,
+}
 
-    #[account(address = global_state.authority)]
-    pub authority: Signer<'info>,
+/// Certificados de impacto retirados (quemados) para compensar emisiones
+#[event]
+pub struct ImpactCertificateRetired {
+    pub owner: Pubkey,
+    pub beneficiary: String,
+    pub co2_grams: u64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct ToggleContainerStatus<'info> {
-    #[account(mut)]
-    pub container: Account<'info, SmartContainer>,
+/// Depósito por unidades con los envases escaneados
+#[event]
+pub struct ItemDepositProcessed {
+    pub user: Pubkey,
+    pub container: Pubkey,
+    pub items: Vec<ScannedItem>,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub receipt_hash: [u8; 32],
+}
 
-    #[account(
-        seeds = [b"global_state"],
-        bump = global_state.bump
-    )]
-    pub global_state: Account<'info, GlobalState>,
+/// Depósito de un envase identificado con una marca
+#[event]
+pub struct BrandedDeposit {
+    pub brand: Pubkey,
+    pub user: Pubkey,
+    pub container: Pubkey,
+    pub weight_grams: u64,
+    pub receipt_hash: [u8; 32],
+}
 
-    #[account(address = global_state.authority)]
-    pub authority: Signer<'info>,
+/// Créditos EPR retirados por una marca
+#[event]
+pub struct EprCreditsRetired {
+    pub brand: Pubkey,
+    pub amount_grams: u64,
+    pub total_retired_grams: u64,
+    pub timestamp: i64,
+}
+
+/// Depósito añadido al árbol de Merkle (permite a los indexadores reconstruir la hoja)
+#[event]
+pub struct DepositCompressed {
+    pub tree: Pubkey,
+    pub leaf_index: u64,
+    pub leaf: DepositLeaf,
+}
+
+/// Depósito de invitado con la recompensa en custodia
+#[event]
+pub struct GuestDepositCreated {
+    pub guest_deposit: Pubkey,
+    pub container: Pubkey,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub co2_grams: u64,
+    pub receipt_hash: [u8; 32],
+    pub expires_at: i64,
+}
+
+/// Recompensa de un depósito de invitado entregada a quien reveló el código
+#[event]
+pub struct GuestDepositClaimed {
+    pub guest_deposit: Pubkey,
+    pub claimer: Pubkey,
+    pub ecoc_reward: u64,
+    pub timestamp: i64,
+}
+
+/// Recompensa no reclamada devuelta a la tesorería
+#[event]
+pub struct GuestDepositExpired {
+    pub guest_deposit: Pubkey,
+    pub ecoc_reward: u64,
+    pub timestamp: i64,
+}
+
+/// Depósito acreditado al saldo custodiado de un miembro
+#[event]
+pub struct MemberDepositProcessed {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub container: Pubkey,
+    pub weight_grams: u64,
+    pub ecoc_reward: u64,
+    pub co2_grams: u64,
+    pub receipt_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Miembro vinculado a una wallet propia con su saldo retirado
+#[event]
+pub struct MemberGraduated {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub wallet: Pubkey,
+    pub ecoc_withdrawn: u64,
 }
 
 // ============================================================================
-// ESTRUCTURAS DE DATOS (PDAs)
+// VISTAS (DATOS DE RETORNO)
 // ============================================================================
 
-/// Estado global del programa EcoBottle
-#[account]
-pub struct GlobalState {
-    pub authority: Pubkey,              // Autoridad del programa
-    pub ecoc_per_kg: u64,              // Tokens ECOC por kilogramo de PET
-    pub min_deposit_weight: u64,       // Peso mínimo en gramos
-    pub total_pet_collected: u64,      // Total de PET recolectado en gramos
-    pub total_deposits: u64,           // Total de depósitos realizados
-    pub total_users: u64,              // Total de usuarios registrados
-    pub total_containers: u64,         // Total de contenedores registrados
-    pub bump: u8,                      // Bump seed para PDA
+/// Reputación de un usuario devuelta por `get_reputation`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ReputationView {
+    pub reputation_score: u32,
+    pub reward_multiplier_bps: u64,
+    pub current_streak: u32,
+    pub is_suspended: bool,
 }
 
-impl GlobalState {
-    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+/// Cotización de un depósito por peso devuelta por `quote_deposit_reward`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositQuote {
+    pub weight_grams: u64,             // Peso cotizado en gramos
+    pub base_reward: u64,              // ECOC antes del multiplicador
+    pub reward_multiplier_bps: u64,    // Multiplicador de reputación aplicado
+    pub ecoc_reward: u64,              // ECOC que recibiría el usuario
+    pub referral_grams: u64,           // Peso que genera bono de referido
+    pub referral_bonus: u64,           // ECOC para el referente (tras el tope)
+    pub referral_cap_exhausted: bool,  // El referente ya cobró todo su tope
+    pub co2_grams: u64,                // CO₂e evitado en gramos
+    pub fill_level_bps: u64,           // Llenado del contenedor tras el depósito
+    pub triggers_collection: bool,     // El depósito dispararía la alerta de llenado
 }
 
-/// Perfil de usuario en la blockchain
-#[account]
-pub struct UserProfile {
+/// Resumen de un usuario devuelto por `get_user_summary`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UserSummaryView {
     pub owner: Pubkey,                 // Wallet del usuario
-    pub username: String,              // Nombre de usuario (max 32 chars)
+    pub username: String,              // Nombre de usuario
     pub total_deposits: u64,           // Número de depósitos
-    pub total_pet_weight: u64,         // Total de PET depositado en gramos
+    pub total_pet_weight: u64,         // Total depositado en gramos
     pub total_ecoc_earned: u64,        // Total de ECOC ganados
     pub total_ecoc_spent: u64,         // Total de ECOC gastados
-    pub created_at: i64,               // Timestamp de registro
-    pub bump: u8,                      // Bump seed para PDA
-}
-
-impl UserProfile {
-    pub const LEN: usize = 32 + (4 + 32) + 8 + 8 + 8 + 8 + 8 + 1;
+    pub co2_avoided_grams: u64,        // CO₂e evitado en gramos
+    pub reputation_score: u32,         // Puntos de reputación (0-1000)
+    pub reward_multiplier_bps: u64,    // Multiplicador de recompensa actual
+    pub current_streak: u32,           // Días consecutivos con depósitos
+    pub longest_streak: u32,           // Mejor racha diaria histórica
+    pub week_weight_grams: u64,        // Peso de la semana en curso
+    pub month_weight_grams: u64,       // Peso del mes en curso
+    pub referral_count: u32,           // Usuarios invitados
+    pub referral_earnings: u64,        // ECOC ganados por referidos
+    pub referral_grams_remaining: u64, // Peso que aún genera bono a su referente
+    pub is_suspended: bool,            // Suspendido por la autoridad
 }
 
-/// Contenedor Inteligente NFT - Punto de depósito físico
-#[account]
-pub struct SmartContainer {
-    pub container_id: String,          // ID único del contenedor (max 32 chars)
-    pub location: String,              // Ubicación física (max 64 chars)
-    pub authority: Pubkey,             // Quien lo registró
-    pub capacity_kg: u64,              // Capacidad máxima en kilogramos
-    pub current_weight: u64,           // Peso actual en gramos
-    pub total_deposits: u64,           // Número de depósitos recibidos
+/// Estado de un contenedor devuelto por `get_container_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ContainerStatusView {
+    pub container_id: String,          // ID del contenedor
+    pub material: Material,            // Material que acepta
     pub is_active: bool,               // Si está operativo
-    pub created_at: i64,               // Fecha de registro
+    pub current_weight: u64,           // Peso actual en gramos
+    pub capacity_kg: u64,              // Capacidad máxima en kilogramos
+    pub remaining_grams: u64,          // Capacidad libre en gramos
+    pub fill_level_bps: u64,           // Nivel de llenado (10000 = lleno)
+    pub fill_threshold_bps: u16,       // Umbral de la alerta de llenado
+    pub needs_collection: bool,        // Umbral superado desde la última recolección
+    pub open_request: Option<Pubkey>,  // Solicitud de recolección abierta
+    pub total_deposits: u64,           // Depósitos recibidos
+    pub co2_avoided_grams: u64,        // CO₂e evitado en gramos
     pub last_collection: i64,          // Última recolección
-    pub bump: u8,                      // Bump seed para PDA
 }
 
-impl SmartContainer {
-    pub const LEN: usize = (4 + 32) + (4 + 64) + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 1;
+// ============================================================================
+// FUNCIONES AUXILIARES
+// ============================================================================
+
+/// Valida un nombre de usuario contando caracteres UTF-8, no bytes
+pub fn validate_username(username: &str) -> Result<()> {
+    require!(!username.trim().is_empty(), ErrorCode::UsernameEmpty);
+    require!(username.chars().count() <= MAX_USERNAME_CHARS, ErrorCode::UsernameTooLong);
+    Ok(())
 }
 
-/// Registro de Depósito - Prueba de Reciclaje en Blockchain
-#[account]
-pub struct DepositRecord {
-    pub user: Pubkey,                  // Usuario que depositó
-    pub container: Pubkey,             // Contenedor donde se depositó
-    pub weight_grams: u64,             // Peso del depósito
-    pub ecoc_reward: u64,              // Recompensa otorgada
-    pub timestamp: i64,                // Cuándo se realizó
-    pub tx_signature: u64,             // Referencia a la transacción
-    pub bump: u8,                      // Bump seed para PDA
+/// Normaliza un nombre de usuario (sin espacios extremos y en minúsculas)
+pub fn normalize_username(username: &str) -> String {
+    username.trim().to_lowercase()
 }
 
-impl DepositRecord {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 1;
+/// Seed del PDA `UsernameRecord`: hash del nombre normalizado
+/// (un nombre UTF-8 puede superar los 32 bytes permitidos por seed)
+pub fn username_seed(username: &str) -> [u8; 32] {
+    hash(normalize_username(username).as_bytes()).to_bytes()
 }
 
-/// Registro de Canje de Tokens
-#[account]
-pub struct RedemptionRecord {
-    pub user: Pubkey,                  // Usuario que canjeó
-    pub product_id: String,            // ID del producto (max 32 chars)
-    pub amount: u64,                   // Cantidad de ECOC canjeados
-    pub timestamp: i64,                // Cuándo se canjeó
-    pub bump: u8,                      // Bump seed para PDA
+/// Compromiso publicado por el contenedor para un código de reclamo de invitado
+pub fn guest_claim_commitment(claim_code: &[u8; 32]) -> [u8; 32] {
+    hashv(&[b"guest_claim".as_ref(), claim_code.as_ref()]).to_bytes()
 }
 
-impl RedemptionRecord {
-    pub const LEN: usize = 32 + (4 + 32) + 8 + 8 + 1;
+/// Compromiso de `claimer` con un código de reclamo: liga el código a su wallet
+/// sin revelarlo (`commit_guest_claim`)
+pub fn guest_claim_binding(claim_code: &[u8; 32], claimer: &Pubkey) -> [u8; 32] {
+    hashv(&[b"guest_claim_binding".as_ref(), claim_code.as_ref(), claimer.as_ref()]).to_bytes()
 }
 
-/// Registro de Recolección - Certificado de Trazabilidad
-#[account]
-pub struct CollectionRecord {
-    pub container: Pubkey,             // Contenedor recolectado
-    pub collector: Pubkey,             // Empresa recolectora
-    pub weight_collected: u64,         // Peso recolectado en gramos
-    pub timestamp: i64,                // Fecha de recolección
-    pub verified: bool,                // Verificado por blockchain
-    pub bump: u8,                      // Bump seed para PDA
+/// Encadena un registro archivado al hash de historial: root = sha256(root || sha256(registro))
+pub fn fold_history<T: AnchorSerialize>(root: &mut [u8; 32], record: &T) -> Result<()> {
+    let data = borsh::to_vec(record)?;
+    let record_hash = hash(&data);
+    *root = hashv(&[root.as_ref(), record_hash.as_ref()]).to_bytes();
+    Ok(())
 }
 
-impl CollectionRecord {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 1 + 1;
+/// Comprueba con SPL Account Compression que `leaf` está en la hoja `index`
+/// del árbol; `proof` son los nodos de la prueba de Merkle
+pub fn verify_compressed_leaf<'info>(
+    compression_program: &AccountInfo<'info>,
+    tree: &AccountInfo<'info>,
+    proof: &[AccountInfo<'info>],
+    root: [u8; 32],
+    leaf: [u8; 32],
+    index: u32,
+) -> Result<()> {
+    let mut data = COMPRESSION_VERIFY_LEAF.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&leaf);
+    data.extend_from_slice(&index.to_le_bytes());
+
+    let mut accounts = vec![AccountMeta::new_readonly(tree.key(), false)];
+    let mut account_infos = vec![tree.clone()];
+    for node in proof.iter() {
+        accounts.push(AccountMeta::new_readonly(node.key(), false));
+        account_infos.push(node.clone());
+    }
+    account_infos.push(compression_program.clone());
+
+    let instruction = Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts,
+        data,
+    };
+    invoke(&instruction, &account_infos)?;
+    Ok(())
+}
+
+/// Añade una hoja al árbol de depósitos firmando como `global_state`
+pub fn append_compressed_leaf<'info>(
+    compression_program: &AccountInfo<'info>,
+    tree: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    noop_program: &AccountInfo<'info>,
+    leaf: [u8; 32],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = COMPRESSION_APPEND.to_vec();
+    data.extend_from_slice(&leaf);
+
+    let instruction = Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: vec![
+            AccountMeta::new(tree.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+            AccountMeta::new_readonly(noop_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[tree.clone(), authority.clone(), noop_program.clone(), compression_program.clone()],
+        signer,
+    )?;
+    Ok(())
+}
+
+/// Lee una cuenta del programa en un esquema anterior identificado por su tamaño
+pub fn read_legacy_account<T: AnchorDeserialize>(
+    account: &AccountInfo,
+    discriminator: &[u8],
+    legacy_len: usize,
+) -> Result<T> {
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidMigrationSource);
+
+    let data = account.try_borrow_data()?;
+    require!(data.len() == 8 + legacy_len, ErrorCode::AlreadyMigrated);
+    require!(data[..8] == *discriminator, ErrorCode::InvalidMigrationSource);
+
+    Ok(T::deserialize(&mut &data[8..])?)
+}
+
+/// Amplía una cuenta (pagando la renta que falte) y escribe el nuevo esquema
+pub fn write_migrated_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    new_len: usize,
+    value: &T,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(new_len);
+    let missing = required.saturating_sub(account.lamports());
+    if missing > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program_info.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            missing,
+        )?;
+    }
+
+    account.resize(new_len)?;
+    let mut data = account.try_borrow_mut_data()?;
+    value.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Crea una cuenta PDA aunque alguien haya prefinanciado la dirección:
+/// completa la renta, asigna el espacio y transfiere la propiedad a `owner`
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program_info.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer,
+            ),
+            required,
+            space as u64,
+            owner,
+        );
+    }
+
+    let missing = required.saturating_sub(account.lamports());
+    if missing > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program_info.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            missing,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program_info.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program_info.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            signer,
+        ),
+        owner,
+    )
+}
+
+/// Suma `weight_grams` a la puntuación del usuario en una tabla por contenedor,
+/// creando la cuenta `[b"leaderboard_score", leaderboard, user]` en su primer depósito
+pub fn credit_leaderboard_score<'info>(
+    leaderboard: Pubkey,
+    user: Pubkey,
+    score_info: &'info AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    weight_grams: u64,
+) -> Result<u64> {
+    let (expected, bump) = Pubkey::find_program_address(
+        &[b"leaderboard_score", leaderboard.as_ref(), user.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(score_info.key(), expected, ErrorCode::InvalidLeaderboardScore);
+
+    if score_info.owner != &crate::ID {
+        create_pda_account(
+            payer,
+            score_info,
+            system_program_info,
+            8 + LeaderboardScore::LEN,
+            &crate::ID,
+            &[&[b"leaderboard_score", leaderboard.as_ref(), user.as_ref(), &[bump]]],
+        )?;
+        let score = LeaderboardScore {
+            version: CURRENT_ACCOUNT_VERSION,
+            leaderboard,
+            user,
+            weight_grams: 0,
+            bump,
+            reserved: [0; 32],
+        };
+        score.try_serialize(&mut &mut score_info.try_borrow_mut_data()?[..])?;
+    }
+
+    let mut score = Account::<LeaderboardScore>::try_from(score_info)?;
+    score.weight_grams += weight_grams;
+    score.exit(&crate::ID)?;
+    Ok(score.weight_grams)
+}
+
+/// Aplica un multiplicador en puntos básicos a una cantidad
+pub fn apply_bps(amount: u64, bps: u64) -> u64 {
+    (amount as u128 * bps as u128 / 10_000) as u64
+}
+
+/// Vacía la bóveda de un depósito de invitado en `destination` y la cierra,
+/// devolviendo su renta a `rent_receiver`
+pub fn release_guest_escrow<'info>(
+    global_state: &Account<'info, GlobalState>,
+    escrow: &Account<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    rent_receiver: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let bump = global_state.bump;
+    let seeds = &[
+        b"global_state".as_ref(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: escrow.to_account_info(),
+        to: destination,
+        authority: global_state.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+        escrow.amount,
+    )?;
+
+    let cpi_accounts = CloseAccount {
+        account: escrow.to_account_info(),
+        destination: rent_receiver,
+        authority: global_state.to_account_info(),
+    };
+    token::close_account(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer))
+}
+
+/// Alerta de llenado: se emite una sola vez al cruzar el umbral
+pub fn raise_fill_alert(container_key: Pubkey, container: &mut SmartContainer) {
+    let fill_level_bps = container.fill_level_bps();
+    if !container.needs_collection && fill_level_bps >= container.fill_threshold_bps as u64 {
+        container.needs_collection = true;
+        emit!(ContainerNeedsCollection {
+            container: container_key,
+            fill_level_bps,
+            current_weight: container.current_weight,
+            capacity_kg: container.capacity_kg,
+        });
+        msg!("🚨 Contenedor {} al {}% - requiere recolección",
+             container.container_id,
+             fill_level_bps / 100);
+    }
+}
+
+/// Valida y valora un depósito por peso (todo salvo el recibo); compartida por
+/// `process_deposit` y la vista `quote_deposit_reward`
+pub fn quote_deposit(
+    global_state: &GlobalState,
+    container: &SmartContainer,
+    user_profile: &UserProfile,
+    referrer_profile: Option<&UserProfile>,
+    weight_grams: u64,
+) -> Result<DepositQuote> {
+    require!(!user_profile.is_suspended, ErrorCode::UserSuspended);
+    let mut quote = quote_guest_deposit(global_state, container, weight_grams)?;
+
+    // Recompensa en tokens ECOC ajustada por reputación
+    quote.reward_multiplier_bps = user_profile.reward_multiplier_bps();
+    quote.ecoc_reward = apply_bps(quote.base_reward, quote.reward_multiplier_bps);
+
+    // Bono de referido: ECOC para quien invitó al usuario por sus primeros kg
+    // Un tope por referente de 0 deshabilita el bono; los gramos con bono solo
+    // se consumen mientras al referente le quede tope por cobrar, y una vez
+    // visto agotado no se vuelven a exigir sus cuentas salvo que suba el tope
+    let bonus_grams = if user_profile.referrer.is_some()
+        && global_state.referral_bonus_per_kg > 0
+        && global_state.referral_cap_per_referrer > user_profile.referral_cap_exhausted_at
+    {
+        weight_grams.min(
+            global_state
+                .referral_max_grams
+                .saturating_sub(user_profile.referred_weight_grams),
+        )
+    } else {
+        0
+    };
+
+    if bonus_grams > 0 {
+        let referrer_profile = referrer_profile.ok_or(ErrorCode::MissingReferrerAccounts)?;
+        require!(
+            user_profile.referrer == Some(referrer_profile.owner),
+            ErrorCode::ReferrerMismatch
+        );
+
+        let remaining_cap = global_state
+            .referral_cap_per_referrer
+            .saturating_sub(referrer_profile.referral_earnings);
+        if remaining_cap > 0 {
+            quote.referral_grams = bonus_grams;
+            quote.referral_bonus = (bonus_grams * global_state.referral_bonus_per_kg / 1000).min(remaining_cap);
+        } else {
+            quote.referral_cap_exhausted = true;
+        }
+    }
+
+    Ok(quote)
+}
+
+/// Valora un depósito sin perfil (invitado): recompensa base, sin
+/// multiplicador de reputación ni bono de referido
+pub fn quote_guest_deposit(
+    global_state: &GlobalState,
+    container: &SmartContainer,
+    weight_grams: u64,
+) -> Result<DepositQuote> {
+    require!(container.is_active, ErrorCode::ContainerInactive);
+    require!(weight_grams >= global_state.min_deposit_weight, ErrorCode::WeightTooLow);
+    require!(
+        container.current_weight + weight_grams <= container.capacity_kg * 1000,
+        ErrorCode::ContainerFull
+    );
+
+    let base_reward = (weight_grams * global_state.ecoc_per_kg) / 1000;
+
+    let fill_level_bps = if container.capacity_kg == 0 {
+        10_000
+    } else {
+        ((container.current_weight + weight_grams) as u128 * 10_000 / (container.capacity_kg as u128 * 1000)) as u64
+    };
+
+    Ok(DepositQuote {
+        weight_grams,
+        base_reward,
+        reward_multiplier_bps: 10_000,
+        ecoc_reward: base_reward,
+        referral_grams: 0,
+        referral_bonus: 0,
+        referral_cap_exhausted: false,
+        co2_grams: global_state.co2_avoided_for(container.material, weight_grams),
+        fill_level_bps,
+        triggers_collection: !container.needs_collection && fill_level_bps >= container.fill_threshold_bps as u64,
+    })
 }
 
 // ============================================================================
@@ -612,4 +6512,271 @@ pub enum ErrorCode {
 
     #[msg("No tienes suficientes tokens ECOC para este canje")]
     InsufficientTokens,
+
+    #[msg("El usuario está suspendido - no puede realizar depósitos")]
+    UserSuspended,
+
+    #[msg("La recolección ya fue conciliada")]
+    CollectionAlreadyReconciled,
+
+    #[msg("La recolección todavía no ha sido conciliada")]
+    CollectionNotReconciled,
+
+    #[msg("La discrepancia de la recolección está dentro de la tolerancia")]
+    DiscrepancyWithinTolerance,
+
+    #[msg("El depósito no pertenece al periodo de esta recolección")]
+    DepositNotInCollection,
+
+    #[msg("El depósito ya fue penalizado")]
+    DepositAlreadyPenalized,
+
+    #[msg("El usuario no cumple los requisitos de este logro")]
+    AchievementNotEarned,
+
+    #[msg("La tabla de clasificación ya está cerrada")]
+    LeaderboardClosed,
+
+    #[msg("La tabla de clasificación no corresponde al periodo en curso")]
+    LeaderboardPeriodMismatch,
+
+    #[msg("La tabla de clasificación no corresponde a este contenedor")]
+    LeaderboardScopeMismatch,
+
+    #[msg("El periodo de clasificación todavía no ha terminado")]
+    PeriodNotEnded,
+
+    #[msg("Falta la cuenta de token de un ganador")]
+    MissingWinnerAccount,
+
+    #[msg("La cuenta de token no pertenece al ganador o no es de ECOC")]
+    WinnerAccountMismatch,
+
+    #[msg("Un usuario no puede referirse a sí mismo")]
+    SelfReferral,
+
+    #[msg("Faltan las cuentas del referente")]
+    MissingReferrerAccounts,
+
+    #[msg("Las cuentas no corresponden al referente del usuario")]
+    ReferrerMismatch,
+
+    #[msg("El nombre de usuario no puede estar vacío")]
+    UsernameEmpty,
+
+    #[msg("El nombre de usuario ya está en uso")]
+    UsernameTaken,
+
+    #[msg("La URI del avatar es demasiado larga (máximo 128 bytes)")]
+    AvatarUriTooLong,
+
+    #[msg("Falta la cuenta del contenedor preferido")]
+    MissingPreferredContainer,
+
+    #[msg("El registro todavía está dentro del periodo de retención")]
+    RetentionPeriodActive,
+
+    #[msg("Debe indicarse un registro de depósito o el árbol comprimido, no ambos")]
+    InvalidDepositLogMode,
+
+    #[msg("La cuenta ya está en el esquema actual")]
+    AlreadyMigrated,
+
+    #[msg("La cuenta no es válida para esta migración")]
+    InvalidMigrationSource,
+
+    #[msg("Falta el hash del recibo del contenedor")]
+    MissingReceipt,
+
+    #[msg("El recibo del contenedor ya fue utilizado")]
+    DuplicateReceipt,
+
+    #[msg("El nombre del recolector es demasiado largo (máximo 32 caracteres)")]
+    CollectorNameTooLong,
+
+    #[msg("El recolector no está certificado")]
+    CollectorNotCertified,
+
+    #[msg("El umbral de llenado debe estar entre 1 y 10000 bps")]
+    InvalidFillThreshold,
+
+    #[msg("El contenedor no ha superado su umbral de llenado")]
+    CollectionNotNeeded,
+
+    #[msg("El contenedor ya tiene una solicitud de recolección abierta")]
+    CollectionAlreadyRequested,
+
+    #[msg("Falta la solicitud de recolección abierta del contenedor")]
+    MissingCollectionRequest,
+
+    #[msg("La solicitud está asignada a otro recolector")]
+    NotAssignedCollector,
+
+    #[msg("El plazo del recolector asignado todavía no ha vencido")]
+    CollectionRequestNotExpired,
+
+    #[msg("La solicitud de recolección ya fue completada")]
+    CollectionRequestCompleted,
+
+    #[msg("Faltan las cuentas para pagar el incentivo del recolector")]
+    MissingCollectorRewardAccounts,
+
+    #[msg("El nombre de la planta es demasiado largo (máximo 32 caracteres)")]
+    RecyclerNameTooLong,
+
+    #[msg("La planta recicladora no está autorizada")]
+    RecyclerNotActive,
+
+    #[msg("El envío debe incluir entre 1 y 16 recolecciones")]
+    InvalidShipmentSize,
+
+    #[msg("La recolección no pertenece a este recolector")]
+    CollectionNotOwned,
+
+    #[msg("La recolección ya fue incluida en un envío")]
+    CollectionAlreadyShipped,
+
+    #[msg("El envío está destinado a otra planta")]
+    WrongRecycler,
+
+    #[msg("El envío ya fue recibido")]
+    ShipmentAlreadyReceived,
+
+    #[msg("La producción no puede superar el peso recibido")]
+    InvalidProcessingOutput,
+
+    #[msg("El recibo de procesamiento ya fue certificado")]
+    ImpactAlreadyCertified,
+
+    #[msg("No hay impacto que certificar o retirar")]
+    NoImpactToCertify,
+
+    #[msg("El nombre del beneficiario es demasiado largo (máximo 64 caracteres)")]
+    BeneficiaryTooLong,
+
+    #[msg("El nombre de la marca es demasiado largo (máximo 32 caracteres)")]
+    BrandNameTooLong,
+
+    #[msg("La marca no está activa")]
+    BrandNotActive,
+
+    #[msg("La cantidad de créditos EPR debe ser mayor que cero")]
+    InvalidEprAmount,
+
+    #[msg("No hay suficientes envases recuperados para respaldar los créditos")]
+    InsufficientEprBacking,
+
+    #[msg("El depósito debe incluir entre 1 y 32 envases")]
+    InvalidItemCount,
+
+    #[msg("SKU no registrado o inactivo")]
+    SkuNotRegistered,
+
+    #[msg("El registro de SKUs está lleno")]
+    SkuRegistryFull,
+
+    #[msg("Falta el bitmap de números de serie del envase")]
+    MissingSerialBitmap,
+
+    #[msg("El número de serie ya fue canjeado")]
+    SerialAlreadyRedeemed,
+
+    #[msg("El material no coincide con el que acepta el contenedor")]
+    MaterialMismatch,
+
+    #[msg("El lote debe incluir entre 1 y 16 depósitos")]
+    InvalidBatchSize,
+
+    #[msg("Las cuentas del lote deben ser pares (perfil, token account ECOC) sin repetir")]
+    InvalidBatchAccounts,
+
+    #[msg("El código de reclamo no corresponde al depósito de invitado")]
+    InvalidClaimCode,
+
+    #[msg("El plazo para reclamar el depósito de invitado ha vencido")]
+    GuestDepositExpired,
+
+    #[msg("El depósito de invitado aún puede reclamarse")]
+    GuestDepositNotExpired,
+
+    #[msg("La tesorería debe ser una token account ECOC de la autoridad del programa")]
+    InvalidTreasuryAccount,
+
+    #[msg("El nombre de la organización es demasiado largo (máximo 32 caracteres)")]
+    OrganizationNameTooLong,
+
+    #[msg("La organización no está activa")]
+    OrganizationNotActive,
+
+    #[msg("Solo el administrador de la organización puede gestionar sus miembros")]
+    NotOrganizationAdmin,
+
+    #[msg("El miembro ya vinculó su propia wallet")]
+    MemberGraduated,
+
+    #[msg("La tabla de clasificación aparece más de una vez")]
+    DuplicateLeaderboard,
+
+    #[msg("Falta la cuenta de puntuación del usuario o no corresponde a la tabla")]
+    InvalidLeaderboardScore,
+
+    #[msg("La reserva del nombre de usuario pertenece a otra wallet")]
+    UsernameNotOwned,
+
+    #[msg("Falta la reserva del nuevo nombre de usuario")]
+    MissingUsernameRecord,
+
+    #[msg("La retención de registros no puede ser negativa")]
+    InvalidRetention,
+
+    #[msg("La recolección aún no se ha enviado a planta")]
+    CollectionNotShipped,
+
+    #[msg("El depósito tiene una penalización por discrepancia pendiente")]
+    DepositPenaltyPending,
+
+    #[msg("La secuencia del recibo no es posterior a la del último recibo aceptado")]
+    StaleReceipt,
+
+    #[msg("La secuencia del recibo salta demasiados números")]
+    ReceiptSequenceGap,
+
+    #[msg("Sin una solicitud asignada solo la autoridad puede recolectar el contenedor")]
+    CollectionNotAssigned,
+
+    #[msg("El plazo de recolección no puede ser negativo")]
+    InvalidCollectionSla,
+
+    #[msg("El envío mezcla recolecciones de distintos materiales")]
+    MixedShipmentMaterial,
+
+    #[msg("El recibo de la planta no cuadra con el peso declarado del envío")]
+    ProcessingDiscrepancy,
+
+    #[msg("Bitmap de series repetido")]
+    DuplicateSerialBitmap,
+
+    #[msg("El bitmap de series no es el PDA de su GTIN y bloque")]
+    InvalidSerialBitmap,
+
+    #[msg("La lectura del depósito es posterior al momento actual")]
+    DepositInFuture,
+
+    #[msg("La lectura del depósito es anterior al último depósito del contenedor")]
+    DepositOutOfOrder,
+
+    #[msg("El compromiso del reclamo debe ser de un slot anterior")]
+    ClaimIntentTooRecent,
+
+    #[msg("El depósito de invitado sigue abierto")]
+    GuestDepositStillOpen,
+
+    #[msg("El contenedor se recolectó en este mismo segundo; reintenta el depósito")]
+    DepositDuringCollection,
+
+    #[msg("La recolección aún cubre registros de depósito sin archivar")]
+    CollectionHasOpenDeposits,
+
+    #[msg("El inicio del periodo no puede ser posterior a la recolección")]
+    InvalidCollectionPeriod,
 }
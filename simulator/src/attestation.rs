@@ -0,0 +1,64 @@
+//! Atestaciones de depósito firmadas por la llave del dispositivo
+//!
+//! El contenedor firma cada lectura del sensor con su llave ed25519. La
+//! pasarela verifica la firma contra la llave registrada del dispositivo antes
+//! de enviar la transacción, y el `receipt_hash` del depósito es el hash de la
+//! atestación firmada, de modo que el registro en cadena enlaza con ella. El
+//! programa no verifica la firma del dispositivo: ese rechazo es solo
+//! off-chain y el informe lo distingue de los errores del programa. La
+//! secuencia de la atestación es el número de recibo que el programa exige
+//! creciente por contenedor.
+
+use anchor_lang::prelude::*;
+use ecobottle_client::instructions::Receipt;
+use solana_sdk::hash::hashv;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+/// Lectura del sensor de un contenedor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    pub container_id: String,
+    pub device: Pubkey,
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub weight_grams: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedAttestation {
+    pub attestation: Attestation,
+    pub signature: Signature,
+}
+
+impl Attestation {
+    pub fn message(&self) -> Vec<u8> {
+        self.try_to_vec().expect("serialización en memoria")
+    }
+
+    pub fn sign(self, device_key: &Keypair) -> SignedAttestation {
+        let signature = device_key.sign_message(&self.message());
+        SignedAttestation { attestation: self, signature }
+    }
+}
+
+impl SignedAttestation {
+    /// Firma válida de la llave registrada para el dispositivo
+    pub fn verify(&self, registered_key: &Pubkey) -> bool {
+        self.attestation.device == *registered_key
+            && self.signature.verify(registered_key.as_ref(), &self.attestation.message())
+    }
+
+    /// `receipt_hash` del depósito: SHA-256 de la atestación y su firma
+    pub fn receipt_hash(&self) -> [u8; 32] {
+        hashv(&[&self.attestation.message(), self.signature.as_ref()]).to_bytes()
+    }
+
+    /// Recibo del depósito: hash de la atestación y su secuencia
+    pub fn receipt(&self) -> Receipt {
+        Receipt {
+            hash: self.receipt_hash(),
+            sequence: self.attestation.sequence,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Dónde se ejecutan las transacciones: SVM en proceso o validador local
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use ecobottle_client::ecobottle::{self, ErrorCode};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use tokio::runtime::Runtime;
+
+use crate::Result;
+
+/// Errores del programa que el simulador espera encontrar
+const KNOWN_ERRORS: [ErrorCode; 12] = [
+    ErrorCode::DuplicateReceipt,
+    ErrorCode::StaleReceipt,
+    ErrorCode::ReceiptSequenceGap,
+    ErrorCode::MissingReceipt,
+    ErrorCode::ContainerFull,
+    ErrorCode::ContainerInactive,
+    ErrorCode::ContainerEmpty,
+    ErrorCode::WeightTooLow,
+    ErrorCode::UserSuspended,
+    ErrorCode::DiscrepancyWithinTolerance,
+    ErrorCode::DepositNotInCollection,
+    ErrorCode::DepositAlreadyPenalized,
+];
+
+/// Nombre legible del error de una transacción rechazada
+pub fn describe(err: &TransactionError) -> String {
+    if let TransactionError::InstructionError(_, InstructionError::Custom(code)) = err {
+        if let Some(known) = KNOWN_ERRORS.iter().find(|known| u32::from(**known) == *code) {
+            return format!("{known:?}");
+        }
+    }
+    err.to_string()
+}
+
+pub trait Backend {
+    /// Paga las transacciones y actúa como autoridad del programa
+    fn payer(&self) -> &Keypair;
+
+    /// Envía una transacción; el error es el nombre del fallo
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> std::result::Result<(), String>;
+
+    fn account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>>;
+
+    fn minimum_balance(&mut self, space: usize) -> Result<u64>;
+
+    /// Avanza el reloj entre rondas (en un validador el tiempo corre solo)
+    fn advance_clock(&mut self, seconds: i64) -> Result<()>;
+}
+
+/// `ecobottle.so` compilado con `cargo build-sbf`: en `SBF_OUT_DIR` o en el
+/// `target/deploy` del workspace
+pub fn default_program_path() -> PathBuf {
+    std::env::var_os("SBF_OUT_DIR")
+        .map_or_else(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy")), PathBuf::from)
+        .join("ecobottle.so")
+}
+
+/// SVM en proceso (`solana-program-test`) con el programa compilado
+pub struct InProcess {
+    runtime: Runtime,
+    context: ProgramTestContext,
+}
+
+impl InProcess {
+    /// Arranca la SVM con el `ecobottle.so` de `program`
+    pub fn start(program: &Path) -> Result<Self> {
+        let elf = std::fs::read(program).map_err(|err| {
+            format!("no se pudo leer el programa {} (compílalo con `cargo build-sbf`): {err}", program.display())
+        })?;
+        let mut program_test = ProgramTest::default();
+        program_test.add_account(
+            ecobottle::ID,
+            Account {
+                lamports: Rent::default().minimum_balance(elf.len()),
+                data: elf,
+                owner: bpf_loader::ID,
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+        let runtime = Runtime::new()?;
+        let context = runtime.block_on(program_test.start_with_context());
+        Ok(Self { runtime, context })
+    }
+}
+
+impl Backend for InProcess {
+    fn payer(&self) -> &Keypair {
+        &self.context.payer
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> std::result::Result<(), String> {
+        let banks_client = &mut self.context.banks_client;
+        let payer = &self.context.payer;
+        self.runtime.block_on(async {
+            let blockhash = banks_client.get_latest_blockhash().await.map_err(|err| err.to_string())?;
+            let mut all_signers = vec![payer];
+            all_signers.extend_from_slice(signers);
+            let transaction =
+                Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+            banks_client.process_transaction(transaction).await.map_err(|err| describe(&err.unwrap()))
+        })
+    }
+
+    fn account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let account = self.runtime.block_on(self.context.banks_client.get_account(*address))?;
+        Ok(account.map(|account| account.data))
+    }
+
+    fn minimum_balance(&mut self, space: usize) -> Result<u64> {
+        let rent = self.runtime.block_on(self.context.banks_client.get_rent())?;
+        Ok(rent.minimum_balance(space))
+    }
+
+    fn advance_clock(&mut self, seconds: i64) -> Result<()> {
+        let mut clock: Clock = self.runtime.block_on(self.context.banks_client.get_sysvar())?;
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+        Ok(())
+    }
+}
+
+/// Validador accesible por RPC (p. ej. `solana-test-validator`)
+pub struct Validator {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl Validator {
+    pub fn new(url: String, payer: Keypair) -> Self {
+        Self { rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()), payer }
+    }
+}
+
+impl Backend for Validator {
+    fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> std::result::Result<(), String> {
+        let blockhash = self.rpc.get_latest_blockhash().map_err(|err| err.to_string())?;
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.payer.pubkey()), &all_signers, blockhash);
+        self.rpc
+            .send_and_confirm_transaction(&transaction)
+            .map(|_| ())
+            .map_err(|err| match err.get_transaction_error() {
+                Some(err) => describe(&err),
+                None => err.to_string(),
+            })
+    }
+
+    fn account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let response = self.rpc.get_account_with_commitment(address, CommitmentConfig::confirmed())?;
+        Ok(response.value.map(|account| account.data))
+    }
+
+    fn minimum_balance(&mut self, space: usize) -> Result<u64> {
+        Ok(self.rpc.get_minimum_balance_for_rent_exemption(space)?)
+    }
+
+    fn advance_clock(&mut self, _seconds: i64) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Modelo de un contenedor inteligente: llenado, ruido del sensor y fraude
+
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use rand::Rng;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::attestation::{Attestation, SignedAttestation};
+
+/// Comportamiento fraudulento de un dispositivo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fraud {
+    /// Báscula manipulada: reporta el peso real aumentado en `bps`
+    Inflate { bps: u64 },
+    /// Reenvía la atestación anterior en lugar de una nueva
+    Replay,
+    /// Firma con una llave que no es la registrada
+    Forged,
+}
+
+impl FromStr for Fraud {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some(("inflate", bps)) => bps
+                .parse()
+                .map(|bps| Fraud::Inflate { bps })
+                .map_err(|_| format!("bps inválidos: {bps}")),
+            None if value == "inflate" => Ok(Fraud::Inflate { bps: 2_000 }),
+            None if value == "replay" => Ok(Fraud::Replay),
+            None if value == "forged" => Ok(Fraud::Forged),
+            _ => Err(format!("fraude desconocido: {value} (inflate[:bps], replay, forged)")),
+        }
+    }
+}
+
+impl fmt::Display for Fraud {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fraud::Inflate { bps } => write!(f, "inflate:{bps}"),
+            Fraud::Replay => write!(f, "replay"),
+            Fraud::Forged => write!(f, "forged"),
+        }
+    }
+}
+
+/// Parámetros de un depósito típico
+#[derive(Clone, Copy, Debug)]
+pub struct FillProfile {
+    /// Peso medio de un depósito en gramos
+    pub mean_grams: u64,
+    /// Variación uniforme alrededor de la media en gramos
+    pub spread_grams: u64,
+    /// Desviación típica del ruido del sensor en puntos básicos
+    pub noise_bps: u64,
+}
+
+impl Default for FillProfile {
+    fn default() -> Self {
+        Self { mean_grams: 600, spread_grams: 400, noise_bps: 50 }
+    }
+}
+
+pub struct Device {
+    pub container_id: String,
+    pub key: Keypair,
+    pub capacity_kg: u64,
+    pub profile: FillProfile,
+    pub fraud: Option<Fraud>,
+    /// Peso real dentro del contenedor desde la última recolección
+    pub true_weight: u64,
+    sequence: u64,
+    last_attestation: Option<SignedAttestation>,
+    rogue_key: Keypair,
+}
+
+impl Device {
+    pub fn new(container_id: String, capacity_kg: u64, profile: FillProfile, fraud: Option<Fraud>) -> Self {
+        Self {
+            container_id,
+            key: Keypair::new(),
+            capacity_kg,
+            profile,
+            fraud,
+            true_weight: 0,
+            sequence: 0,
+            last_attestation: None,
+            rogue_key: Keypair::new(),
+        }
+    }
+
+    /// Peso real de un envase depositado
+    pub fn sample_weight(&self, rng: &mut impl Rng) -> u64 {
+        let FillProfile { mean_grams, spread_grams, .. } = self.profile;
+        let low = mean_grams.saturating_sub(spread_grams).max(1);
+        rng.gen_range(low..=mean_grams + spread_grams)
+    }
+
+    /// Lectura del sensor: peso real con ruido gaussiano y, si la báscula está
+    /// manipulada, inflado
+    pub fn read_sensor(&self, true_grams: u64, rng: &mut impl Rng) -> u64 {
+        // Box-Muller
+        let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+        let gaussian = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        let noise = gaussian * self.profile.noise_bps as f64 / 10_000.0;
+        let mut reading = (true_grams as f64 * (1.0 + noise)).round().max(0.0) as u64;
+        if let Some(Fraud::Inflate { bps }) = self.fraud {
+            reading += reading * bps / 10_000;
+        }
+        reading
+    }
+
+    /// Atestación firmada de un depósito; un dispositivo `Replay` reenvía la
+    /// anterior cuando la tiene
+    pub fn attest(&mut self, user: &Pubkey, weight_grams: u64, timestamp: i64) -> SignedAttestation {
+        if self.fraud == Some(Fraud::Replay) {
+            if let Some(previous) = self.last_attestation.take() {
+                return previous;
+            }
+        }
+
+        self.sequence += 1;
+        let attestation = Attestation {
+            container_id: self.container_id.clone(),
+            device: self.key.pubkey(),
+            sequence: self.sequence,
+            user: *user,
+            weight_grams,
+            timestamp,
+        };
+        let signer = if self.fraud == Some(Fraud::Forged) { &self.rogue_key } else { &self.key };
+        let signed = attestation.sign(signer);
+        self.last_attestation = Some(signed.clone());
+        signed
+    }
+
+    /// La próxima atestación será la anterior reenviada (no hay envase nuevo)
+    pub fn is_replaying(&self) -> bool {
+        self.fraud == Some(Fraud::Replay) && self.last_attestation.is_some()
+    }
+}
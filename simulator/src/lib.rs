@@ -0,0 +1,16 @@
+//! Simulador de contenedores inteligentes de EcoBottle
+//!
+//! Modela una flota de contenedores (llenado, ruido del sensor, llaves de
+//! dispositivo y fraudes), genera atestaciones de depósito firmadas, las envía
+//! como `process_deposit` y `collect_container` a una SVM en proceso o a un
+//! validador local, y resume las recompensas y los fraudes detectados.
+
+pub mod attestation;
+pub mod backend;
+pub mod device;
+pub mod report;
+pub mod simulation;
+
+/// Error genérico del simulador
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
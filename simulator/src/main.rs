@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use ecobottle_simulator::backend::{default_program_path, Backend, InProcess, Validator};
+use ecobottle_simulator::device::{FillProfile, Fraud};
+use ecobottle_simulator::simulation::{Simulation, SimulationConfig};
+use ecobottle_simulator::Result;
+use solana_sdk::signature::read_keypair_file;
+
+#[derive(Parser, Debug)]
+#[command(name = "ecobottle-simulator", version, about = "Simulador de contenedores EcoBottle")]
+struct Cli {
+    /// URL de un validador local; sin ella se usa una SVM en proceso
+    #[arg(short = 'u', long)]
+    url: Option<String>,
+
+    /// Keypair que paga y actúa como autoridad en el validador
+    #[arg(short = 'k', long, requires = "url")]
+    keypair: Option<PathBuf>,
+
+    /// `ecobottle.so` para la SVM en proceso (por defecto, el de `cargo build-sbf`)
+    #[arg(short = 'p', long, conflicts_with = "url")]
+    program: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 3)]
+    containers: usize,
+
+    #[arg(long, default_value_t = 5)]
+    users: usize,
+
+    #[arg(long, default_value_t = 30)]
+    rounds: usize,
+
+    /// Capacidad de cada contenedor en kilogramos
+    #[arg(long, default_value_t = 10)]
+    capacity_kg: u64,
+
+    /// Peso medio de un depósito en gramos
+    #[arg(long, default_value_t = 600)]
+    mean_grams: u64,
+
+    /// Desviación típica del ruido del sensor en puntos básicos
+    #[arg(long, default_value_t = 50)]
+    noise_bps: u64,
+
+    /// Fraude de un dispositivo (se asignan a los últimos contenedores):
+    /// inflate[:bps], replay o forged
+    #[arg(long)]
+    fraud: Vec<Fraud>,
+
+    /// Prefijo de los IDs de contenedor
+    #[arg(long, default_value = "SIM")]
+    prefix: String,
+
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("❌ {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let config = SimulationConfig {
+        containers: cli.containers,
+        users: cli.users,
+        rounds: cli.rounds,
+        capacity_kg: cli.capacity_kg,
+        profile: FillProfile {
+            mean_grams: cli.mean_grams,
+            spread_grams: cli.mean_grams * 2 / 3,
+            noise_bps: cli.noise_bps,
+        },
+        frauds: cli.fraud,
+        prefix: cli.prefix,
+        seed: cli.seed,
+        ..SimulationConfig::default()
+    };
+
+    match cli.url {
+        Some(url) => {
+            let path = cli.keypair.unwrap_or_else(default_keypair_path);
+            let payer = read_keypair_file(&path)
+                .map_err(|err| format!("no se pudo leer el keypair {}: {err}", path.display()))?;
+            simulate(Validator::new(url, payer), config)
+        }
+        None => {
+            let program = cli.program.unwrap_or_else(default_program_path);
+            simulate(InProcess::start(&program)?, config)
+        }
+    }
+}
+
+fn simulate(backend: impl Backend, config: SimulationConfig) -> Result<()> {
+    println!(
+        "🤖 Simulando {} contenedores y {} usuarios durante {} rondas",
+        config.containers, config.users, config.rounds
+    );
+    let report = Simulation::setup(backend, config)?.run()?;
+    print!("{report}");
+    Ok(())
+}
+
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    home.join(".config").join("solana").join("id.json")
+}
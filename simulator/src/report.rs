@@ -0,0 +1,149 @@
+//! Resultados de la simulación: recompensas y detección de fraude
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::device::Fraud;
+
+/// Motivo de rechazo de la pasarela ante una firma que no es de la llave
+/// registrada; el programa no verifica esa firma, así que solo ocurre off-chain
+pub const GATEWAY_SIGNATURE_REJECTION: &str = "InvalidDeviceSignature (pasarela, solo off-chain)";
+
+/// Recolección conciliada con el peso medido en planta
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollectionOutcome {
+    pub registered_grams: u64,
+    pub measured_grams: u64,
+    pub discrepancy_bps: u16,
+    pub flagged: bool,
+    pub penalized: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceReport {
+    pub container_id: String,
+    pub fraud: Option<Fraud>,
+    pub attempted: u64,
+    pub accepted: u64,
+    /// Rechazos por motivo (error del programa o de la pasarela)
+    pub rejected: BTreeMap<String, u64>,
+    pub reported_grams: u64,
+    pub ecoc_rewarded: u64,
+    pub collections: Vec<CollectionOutcome>,
+}
+
+impl DeviceReport {
+    pub fn new(container_id: &str, fraud: Option<Fraud>) -> Self {
+        Self {
+            container_id: container_id.to_string(),
+            fraud,
+            attempted: 0,
+            accepted: 0,
+            rejected: BTreeMap::new(),
+            reported_grams: 0,
+            ecoc_rewarded: 0,
+            collections: Vec::new(),
+        }
+    }
+
+    pub fn reject(&mut self, reason: &str) {
+        *self.rejected.entry(reason.to_string()).or_default() += 1;
+    }
+
+    pub fn record_collection(&mut self, outcome: CollectionOutcome) {
+        self.collections.push(outcome);
+    }
+
+    pub fn rejections(&self, reason: &str) -> u64 {
+        self.rejected.get(reason).copied().unwrap_or(0)
+    }
+
+    /// Rechazos de la pasarela, que no pasan por el programa
+    pub fn gateway_rejections(&self) -> u64 {
+        self.rejections(GATEWAY_SIGNATURE_REJECTION)
+    }
+
+    pub fn flagged_collections(&self) -> usize {
+        self.collections.iter().filter(|collection| collection.flagged).count()
+    }
+
+    pub fn penalized_deposits(&self) -> u64 {
+        self.collections.iter().map(|collection| collection.penalized).sum()
+    }
+
+    pub fn max_discrepancy_bps(&self) -> u16 {
+        self.collections.iter().map(|collection| collection.discrepancy_bps).max().unwrap_or(0)
+    }
+
+    /// Un fraude se considera detectado si se rechazó algún depósito o se
+    /// marcó alguna recolección
+    pub fn fraud_detected(&self) -> bool {
+        !self.rejected.is_empty() || self.flagged_collections() > 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub devices: Vec<DeviceReport>,
+}
+
+impl Report {
+    pub fn device(&self, container_id: &str) -> Option<&DeviceReport> {
+        self.devices.iter().find(|device| device.container_id == container_id)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} {:<13} {:>8} {:>9} {:>10} {:>12} {:>12} {:>9} {:>9} {:>10}",
+            "CONTENEDOR", "FRAUDE", "INTENTOS", "ACEPTADOS", "RECHAZOS", "PESO (g)", "ECOC", "RECOLEC.", "MARCADAS", "PENALIZ."
+        )?;
+        for device in &self.devices {
+            let fraud = device.fraud.map_or("-".to_string(), |fraud| fraud.to_string());
+            writeln!(
+                f,
+                "{:<12} {:<13} {:>8} {:>9} {:>10} {:>12} {:>12} {:>9} {:>9} {:>10}",
+                device.container_id,
+                fraud,
+                device.attempted,
+                device.accepted,
+                device.rejected.values().sum::<u64>(),
+                device.reported_grams,
+                device.ecoc_rewarded,
+                device.collections.len(),
+                device.flagged_collections(),
+                device.penalized_deposits(),
+            )?;
+        }
+
+        writeln!(f)?;
+        for device in &self.devices {
+            for (reason, count) in &device.rejected {
+                writeln!(f, "⛔ {}: {count} × {reason}", device.container_id)?;
+            }
+            if device.flagged_collections() > 0 {
+                writeln!(
+                    f,
+                    "⚠️ {}: discrepancia máxima {} bps en planta",
+                    device.container_id,
+                    device.max_discrepancy_bps()
+                )?;
+            }
+        }
+
+        if self.devices.iter().any(|device| device.gateway_rejections() > 0) {
+            writeln!(f, "ℹ️ Las firmas de dispositivo se verifican solo en la pasarela (off-chain), no en el programa")?;
+        }
+
+        let frauds: Vec<_> = self.devices.iter().filter(|device| device.fraud.is_some()).collect();
+        let detected = frauds.iter().filter(|device| device.fraud_detected()).count();
+        let false_positives = self
+            .devices
+            .iter()
+            .filter(|device| device.fraud.is_none() && device.fraud_detected())
+            .count();
+        writeln!(f, "🕵️ Fraudes detectados: {detected}/{} | Falsos positivos: {false_positives}", frauds.len())
+    }
+}
@@ -0,0 +1,288 @@
+//! Flota de contenedores depositando y recolectando contra el programa
+//!
+//! En cada ronda cada contenedor recibe un envase de un usuario al azar: el
+//! sensor lo pesa (con ruido), el dispositivo firma la atestación, la pasarela
+//! verifica la firma (solo off-chain) y envía `process_deposit`. Cuando el programa marca el
+//! contenedor para recolección se envía `collect_container`; la planta mide
+//! el peso real (`reconcile_collection`) y, si la discrepancia supera la
+//! tolerancia, se penalizan los depósitos del periodo.
+//!
+//! Los usuarios simulados no tienen SOL: el operador patrocina la renta y las
+//! comisiones de su registro y de sus depósitos.
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use ecobottle_client::accounts::decode;
+use ecobottle_client::ecobottle::{CollectionRecord, DepositRecord, GlobalState, Material, SmartContainer};
+use ecobottle_client::instructions::{self, CollectContainer, DepositLog, ProcessDeposit};
+use ecobottle_client::pda;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_system_interface::instruction as system_instruction;
+
+use crate::backend::Backend;
+use crate::device::{Device, FillProfile, Fraud};
+use crate::report::{CollectionOutcome, DeviceReport, Report, GATEWAY_SIGNATURE_REJECTION};
+use crate::Result;
+
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    pub containers: usize,
+    pub users: usize,
+    pub rounds: usize,
+    pub capacity_kg: u64,
+    pub seconds_per_round: i64,
+    pub profile: FillProfile,
+    /// Fraudes asignados a los últimos contenedores de la flota
+    pub frauds: Vec<Fraud>,
+    pub prefix: String,
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            containers: 3,
+            users: 5,
+            rounds: 30,
+            capacity_kg: 10,
+            seconds_per_round: 600,
+            profile: FillProfile::default(),
+            frauds: Vec::new(),
+            prefix: "SIM".to_string(),
+            seed: 42,
+        }
+    }
+}
+
+struct SimUser {
+    keypair: Keypair,
+    token_account: Pubkey,
+}
+
+/// Depósito aceptado pendiente de la próxima recolección
+struct PendingDeposit {
+    user: Pubkey,
+    nonce: u64,
+}
+
+struct FleetMember {
+    device: Device,
+    address: Pubkey,
+    pending: Vec<PendingDeposit>,
+    report: DeviceReport,
+}
+
+pub struct Simulation<B: Backend> {
+    backend: B,
+    config: SimulationConfig,
+    rng: StdRng,
+    ecoc_mint: Pubkey,
+    fleet: Vec<FleetMember>,
+    users: Vec<SimUser>,
+    clock: i64,
+}
+
+impl<B: Backend> Simulation<B> {
+    /// Prepara el programa, la flota y los usuarios
+    pub fn setup(mut backend: B, config: SimulationConfig) -> Result<Self> {
+        let authority = backend.payer().pubkey();
+        let ecoc_mint = match fetch::<GlobalState>(&mut backend, &pda::global_state())? {
+            Some(state) => state.ecoc_mint,
+            None => {
+                let mint = create_account(&mut backend, spl_token::state::Mint::LEN, |mint| {
+                    spl_token::instruction::initialize_mint2(&spl_token::ID, mint, &pda::global_state(), None, 6)
+                })?;
+                backend.send(&[instructions::initialize(&authority, &mint, 1_000_000, 100)], &[])?;
+                mint
+            }
+        };
+
+        let fraud_offset = config.containers.saturating_sub(config.frauds.len());
+        let mut fleet = Vec::new();
+        for index in 0..config.containers {
+            let container_id = format!("{}-{:03}", config.prefix, index + 1);
+            let fraud = index.checked_sub(fraud_offset).and_then(|slot| config.frauds.get(slot)).copied();
+            let mut device = Device::new(container_id.clone(), config.capacity_kg, config.profile, fraud);
+            let address = pda::container(&container_id);
+
+            match fetch::<SmartContainer>(&mut backend, &address)? {
+                Some(container) => device.true_weight = container.current_weight,
+                None => {
+                    let location = format!("Simulador #{}", index + 1);
+                    let instruction = instructions::register_container(
+                        &authority,
+                        &container_id,
+                        &location,
+                        config.capacity_kg,
+                        Material::Pet,
+                    );
+                    backend.send(&[instruction], &[])?;
+                }
+            }
+            let report = DeviceReport::new(&container_id, fraud);
+            fleet.push(FleetMember { device, address, pending: Vec::new(), report });
+        }
+
+        let mut users = Vec::new();
+        for _ in 0..config.users {
+            let keypair = Keypair::new();
+            let wallet = keypair.pubkey();
+            let username = format!("sim-{}", &wallet.to_string()[..8]);
+            backend.send(&[instructions::register_user(&authority, &wallet, &username, None)], &[&keypair])?;
+            let token_account = create_account(&mut backend, spl_token::state::Account::LEN, |account| {
+                spl_token::instruction::initialize_account3(&spl_token::ID, account, &ecoc_mint, &wallet)
+            })?;
+            users.push(SimUser { keypair, token_account });
+        }
+
+        let rng = StdRng::seed_from_u64(config.seed);
+        Ok(Self { backend, config, rng, ecoc_mint, fleet, users, clock: 0 })
+    }
+
+    /// Ejecuta todas las rondas y una recolección final de cada contenedor
+    pub fn run(mut self) -> Result<Report> {
+        for _ in 0..self.config.rounds {
+            for index in 0..self.fleet.len() {
+                self.deposit(index)?;
+            }
+            self.backend.advance_clock(self.config.seconds_per_round)?;
+            self.clock += self.config.seconds_per_round;
+        }
+        for index in 0..self.fleet.len() {
+            self.collect(index)?;
+        }
+        Ok(Report { devices: self.fleet.into_iter().map(|member| member.report).collect() })
+    }
+
+    fn deposit(&mut self, index: usize) -> Result<()> {
+        let container: SmartContainer =
+            fetch(&mut self.backend, &self.fleet[index].address)?.ok_or("contenedor inexistente")?;
+        let capacity_grams = self.config.capacity_kg * 1000;
+        let true_grams = self.fleet[index].device.sample_weight(&mut self.rng);
+        if container.needs_collection || container.current_weight + true_grams * 2 > capacity_grams {
+            self.collect(index)?;
+        }
+
+        let depositor = self.users[self.rng.gen_range(0..self.users.len())].keypair.pubkey();
+        let member = &mut self.fleet[index];
+        let replaying = member.device.is_replaying();
+        let reading = member.device.read_sensor(true_grams, &mut self.rng);
+        let signed = member.device.attest(&depositor, reading, self.clock);
+        member.report.attempted += 1;
+
+        // Pasarela: solo se envían atestaciones firmadas por la llave registrada
+        if !signed.verify(&member.device.key.pubkey()) {
+            member.report.reject(GATEWAY_SIGNATURE_REJECTION);
+            return Ok(());
+        }
+
+        // Una atestación reenviada se acredita al usuario que figura en ella
+        let wallet = signed.attestation.user;
+        let user = self.users.iter().find(|user| user.keypair.pubkey() == wallet).ok_or("usuario desconocido")?;
+        let global_state: GlobalState =
+            fetch(&mut self.backend, &pda::global_state())?.ok_or("programa sin inicializar")?;
+        let nonce = global_state.total_deposits;
+        let instruction = ProcessDeposit::new(
+            &wallet,
+            &member.address,
+            &user.token_account,
+            &self.ecoc_mint,
+            signed.attestation.weight_grams,
+            signed.receipt(),
+            DepositLog::Record { nonce },
+        )
+        .payer(&self.backend.payer().pubkey())
+        .instruction();
+
+        match self.backend.send(&[instruction], &[&user.keypair]) {
+            Ok(()) => {
+                let record: DepositRecord =
+                    fetch(&mut self.backend, &pda::deposit(&wallet, nonce))?.ok_or("registro de depósito inexistente")?;
+                let member = &mut self.fleet[index];
+                if !replaying {
+                    member.device.true_weight += true_grams;
+                }
+                member.report.accepted += 1;
+                member.report.reported_grams += record.weight_grams;
+                member.report.ecoc_rewarded += record.ecoc_reward;
+                member.pending.push(PendingDeposit { user: wallet, nonce });
+            }
+            Err(reason) => self.fleet[index].report.reject(&reason),
+        }
+        Ok(())
+    }
+
+    /// Recolecta, concilia con el peso real y penaliza si hay discrepancia
+    fn collect(&mut self, index: usize) -> Result<()> {
+        let authority = self.backend.payer().pubkey();
+        let address = self.fleet[index].address;
+        let container: SmartContainer = fetch(&mut self.backend, &address)?.ok_or("contenedor inexistente")?;
+        if container.current_weight == 0 {
+            return Ok(());
+        }
+
+        let record = pda::collection(&address, container.total_deposits);
+        let collect = CollectContainer::new(&authority, &address, container.total_deposits).instruction();
+        self.backend.send(&[collect], &[])?;
+        let measured = self.fleet[index].device.true_weight;
+        self.backend.send(&[instructions::reconcile_collection(&authority, &record, measured)], &[])?;
+        let collection: CollectionRecord = fetch(&mut self.backend, &record)?.ok_or("registro de recolección inexistente")?;
+
+        let mut outcome = CollectionOutcome {
+            registered_grams: collection.weight_collected,
+            measured_grams: measured,
+            discrepancy_bps: collection.discrepancy_bps,
+            flagged: !collection.verified,
+            penalized: 0,
+        };
+        let pending = std::mem::take(&mut self.fleet[index].pending);
+        if outcome.flagged {
+            for deposit in &pending {
+                let deposit_record = pda::deposit(&deposit.user, deposit.nonce);
+                let penalize = instructions::penalize_deposit(&authority, &record, &deposit_record, &deposit.user);
+                match self.backend.send(&[penalize], &[]) {
+                    Ok(()) => outcome.penalized += 1,
+                    Err(reason) => self.fleet[index].report.reject(&format!("penalize: {reason}")),
+                }
+            }
+        }
+
+        let member = &mut self.fleet[index];
+        member.device.true_weight = 0;
+        member.report.record_collection(outcome);
+
+        // Los depósitos del siguiente periodo deben ser posteriores a la recolección
+        self.backend.advance_clock(1)?;
+        self.clock += 1;
+        Ok(())
+    }
+}
+
+fn fetch<T: AccountDeserialize>(backend: &mut impl Backend, address: &Pubkey) -> Result<Option<T>> {
+    match backend.account_data(address)? {
+        Some(data) => Ok(Some(decode(&data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Crea una cuenta del programa de tokens y la inicializa
+fn create_account(
+    backend: &mut impl Backend,
+    space: usize,
+    init: impl FnOnce(&Pubkey) -> std::result::Result<Instruction, ProgramError>,
+) -> Result<Pubkey> {
+    let account = Keypair::new();
+    let payer = backend.payer().pubkey();
+    let lamports = backend.minimum_balance(space)?;
+    let instructions = [
+        system_instruction::create_account(&payer, &account.pubkey(), lamports, space as u64, &spl_token::ID),
+        init(&account.pubkey())?,
+    ];
+    backend.send(&instructions, &[&account])?;
+    Ok(account.pubkey())
+}
@@ -0,0 +1,76 @@
+//! Flota simulada sobre una SVM en proceso
+
+use anchor_lang::prelude::Pubkey;
+use ecobottle_simulator::attestation::Attestation;
+use ecobottle_simulator::backend::{default_program_path, InProcess};
+use ecobottle_simulator::device::Fraud;
+use ecobottle_simulator::simulation::{Simulation, SimulationConfig};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[test]
+fn attestations_bind_device_key_and_receipt() {
+    let device = Keypair::new();
+    let attestation = Attestation {
+        container_id: "SIM-001".to_string(),
+        device: device.pubkey(),
+        sequence: 1,
+        user: Pubkey::new_unique(),
+        weight_grams: 650,
+        timestamp: 1_700_000_000,
+    };
+
+    let signed = attestation.clone().sign(&device);
+    assert!(signed.verify(&device.pubkey()));
+    assert!(!signed.verify(&Keypair::new().pubkey()));
+    assert!(!attestation.clone().sign(&Keypair::new()).verify(&device.pubkey()));
+
+    let tampered = Attestation { weight_grams: 900, ..attestation.clone() }.sign(&device);
+    assert_ne!(tampered.receipt_hash(), signed.receipt_hash());
+    assert_eq!(attestation.sign(&device).receipt_hash(), signed.receipt_hash());
+
+    assert_eq!("inflate:1500".parse(), Ok(Fraud::Inflate { bps: 1_500 }));
+    assert_eq!("replay".parse(), Ok(Fraud::Replay));
+    assert!("inflate:mucho".parse::<Fraud>().is_err());
+}
+
+#[test]
+fn fleet_rewards_honest_devices_and_flags_fraud() {
+    let config = SimulationConfig {
+        containers: 4,
+        users: 3,
+        rounds: 12,
+        capacity_kg: 5,
+        frauds: vec![Fraud::Inflate { bps: 2_500 }, Fraud::Replay, Fraud::Forged],
+        ..SimulationConfig::default()
+    };
+    let report = Simulation::setup(InProcess::start(&default_program_path()).unwrap(), config).unwrap().run().unwrap();
+
+    let honest = report.device("SIM-001").unwrap();
+    assert_eq!(honest.accepted, 12);
+    assert!(honest.rejected.is_empty());
+    assert!(honest.ecoc_rewarded > 0);
+    assert!(honest.collections.len() >= 2);
+    assert!(honest.collections.iter().all(|collection| !collection.flagged && collection.discrepancy_bps < 1_000));
+
+    // Báscula manipulada: se acredita, pero la planta detecta la discrepancia
+    let inflated = report.device("SIM-002").unwrap();
+    assert_eq!(inflated.accepted, 12);
+    assert_eq!(inflated.flagged_collections(), inflated.collections.len());
+    assert_eq!(inflated.penalized_deposits(), 12);
+
+    // Reenvío: el programa rechaza el recibo repetido
+    let replay = report.device("SIM-003").unwrap();
+    assert_eq!(replay.accepted, 6);
+    assert_eq!(replay.rejections("DuplicateReceipt"), 6);
+    assert_eq!(replay.flagged_collections(), 0);
+
+    // Llave falsa: la pasarela no llega a enviar la transacción
+    let forged = report.device("SIM-004").unwrap();
+    assert_eq!(forged.accepted, 0);
+    assert_eq!(forged.gateway_rejections(), 12);
+    assert_eq!(forged.rejections("InvalidDeviceSignature (pasarela, solo off-chain)"), 12);
+
+    let summary = report.to_string();
+    assert!(summary.contains("se verifican solo en la pasarela (off-chain)"));
+    assert!(summary.contains("Fraudes detectados: 3/3 | Falsos positivos: 0"));
+}
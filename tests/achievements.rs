@@ -0,0 +1,110 @@
+//! Logros con insignias Token-2022 intransferibles
+
+mod common;
+
+use anchor_lang::system_program;
+use anchor_spl::associated_token;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use common::*;
+use ecobottle::{Achievement, AchievementKind, ErrorCode, UserProfile};
+use solana_sdk::instruction::Instruction;
+
+fn claim_ix(env: &TestEnv, user: &User, kind: AchievementKind) -> Instruction {
+    let badge_mint = pda(&[b"badge_mint", user.key().as_ref(), &[kind as u8]]);
+    let accounts = ecobottle::accounts::ClaimAchievement {
+        global_state: global_state_pda(),
+        user_profile: user.profile,
+        achievement: pda(&[b"achievement", user.key().as_ref(), &[kind as u8]]),
+        badge_mint,
+        user_badge_account: env.badge_account(&user.key(), &badge_mint),
+        user: user.key(),
+        token_2022_program: anchor_spl::token_2022::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::ClaimAchievement { kind })
+}
+
+#[tokio::test]
+async fn claim_achievement_mints_single_badge() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 1_000).await.unwrap();
+
+    let instruction = claim_ix(&env, &user, AchievementKind::FirstDeposit);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let achievement_key = pda(&[b"achievement", user.key().as_ref(), &[AchievementKind::FirstDeposit as u8]]);
+    let achievement: Achievement = env.get(&achievement_key).await;
+    assert_eq!(achievement.user, user.key());
+    assert_eq!(achievement.kind, AchievementKind::FirstDeposit);
+
+    // Una única unidad, sin autoridad de emisión
+    let mint: Mint = env.get(&achievement.mint).await;
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.decimals, 0);
+    assert!(mint.mint_authority.is_none());
+    let badge: TokenAccount = env.get(&env.badge_account(&user.key(), &achievement.mint)).await;
+    assert_eq!(badge.amount, 1);
+    assert_eq!(badge.owner, user.key());
+
+    // El logro es único por usuario y tipo
+    let instruction = claim_ix(&env, &user, AchievementKind::FirstDeposit);
+    assert!(env.process(&[instruction], &[&user.wallet]).await.is_err());
+}
+
+#[tokio::test]
+async fn claim_achievement_requires_eligibility() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let instruction = claim_ix(&env, &user, AchievementKind::FirstDeposit);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::AchievementNotEarned);
+
+    env.deposit(&user, &container, 1_000).await.unwrap();
+    let instruction = claim_ix(&env, &user, AchievementKind::HundredKg);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::AchievementNotEarned);
+}
+
+#[tokio::test]
+async fn claim_streak_achievement_after_thirty_days() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    for _ in 0..ecobottle::ACHIEVEMENT_STREAK_DAYS {
+        env.deposit(&user, &container, MIN_DEPOSIT_WEIGHT).await.unwrap();
+        env.warp_forward(ecobottle::SECONDS_PER_DAY).await;
+    }
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.longest_streak, ecobottle::ACHIEVEMENT_STREAK_DAYS);
+
+    let instruction = claim_ix(&env, &user, AchievementKind::ThirtyDayStreak);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let achievement_key = pda(&[b"achievement", user.key().as_ref(), &[AchievementKind::ThirtyDayStreak as u8]]);
+    let achievement: Achievement = env.get(&achievement_key).await;
+    assert_eq!(achievement.kind, AchievementKind::ThirtyDayStreak);
+}
+
+#[tokio::test]
+async fn claim_achievement_tolerates_prefunded_mint() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 1_000).await.unwrap();
+
+    // Un tercero deposita lamports en la dirección del mint antes del reclamo
+    let badge_mint = pda(&[b"badge_mint", user.key().as_ref(), &[AchievementKind::FirstDeposit as u8]]);
+    env.fund(&badge_mint, 1_000_000).await;
+
+    let instruction = claim_ix(&env, &user, AchievementKind::FirstDeposit);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let mint: Mint = env.get(&badge_mint).await;
+    assert_eq!(mint.supply, 1);
+}
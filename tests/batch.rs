@@ -0,0 +1,281 @@
+//! Depósitos por lote de contenedores que estuvieron sin conexión
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{
+    BatchDeposit, DepositLeaf, ErrorCode, GlobalState, SmartContainer, UserProfile, SPL_ACCOUNT_COMPRESSION_ID,
+    SPL_NOOP_ID,
+};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+
+/// Lote firmado por `operator`; `wallets` son los pares (perfil, token account)
+/// en el orden que referencian los depósitos
+fn batch_ix(
+    env: &TestEnv,
+    operator: &Keypair,
+    tree: &Pubkey,
+    container: &Pubkey,
+    wallets: &[&User],
+    deposits: Vec<BatchDeposit>,
+) -> Instruction {
+    let accounts = ecobottle::accounts::ProcessDepositBatch {
+        global_state: global_state_pda(),
+        container: *container,
+        container_authority: operator.pubkey(),
+        deposit_tree: *tree,
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+        noop_program: SPL_NOOP_ID,
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+    };
+    let remaining = wallets
+        .iter()
+        .flat_map(|user| [AccountMeta::new(user.profile, false), AccountMeta::new(user.token_account, false)])
+        .collect();
+    ix_with_remaining(accounts, ecobottle::instruction::ProcessDepositBatch { deposits }, remaining)
+}
+
+/// Momento de las lecturas sin conexión, anterior al reloj de las pruebas
+const OFFLINE_SINCE: i64 = 1_700_000_000;
+
+/// Depósito `n` del lote, leído `n` segundos después de `OFFLINE_SINCE`
+fn offline(user_index: u8, weight_grams: u64, n: u64) -> BatchDeposit {
+    BatchDeposit {
+        user_index,
+        weight_grams,
+        receipt_hash: receipt(100 + n),
+        receipt_sequence: 100 + n,
+        timestamp: OFFLINE_SINCE + n as i64,
+    }
+}
+
+#[tokio::test]
+async fn batch_matches_individual_deposits() {
+    let deposits = [(0, 1_000), (1, 2_000), (0, 500), (0, 6_000)];
+
+    // Los mismos depósitos enviados uno a uno en modo comprimido
+    let mut single = TestEnv::new().await;
+    let tree = single.init_deposit_tree().await;
+    let users = [single.new_user("ana").await, single.new_user("beto").await];
+    let container = single.new_container("EB-001", 10).await;
+    for (user_index, weight_grams) in deposits {
+        let user = &users[user_index as usize];
+        let mut accounts = single.deposit_accounts(user, &container).await;
+        accounts.deposit_record = None;
+        accounts.deposit_tree = Some(tree);
+        accounts.compression_program = Some(SPL_ACCOUNT_COMPRESSION_ID);
+        accounts.noop_program = Some(SPL_NOOP_ID);
+        let receipt_hash = single.next_receipt();
+        single.deposit_with(user, accounts, weight_grams, receipt_hash, vec![]).await.unwrap();
+    }
+
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let container = env.new_container("EB-001", 10).await;
+    let batch = deposits
+        .iter()
+        .enumerate()
+        .map(|(n, (user_index, weight_grams))| offline(*user_index, *weight_grams, n as u64))
+        .collect();
+    let operator = env.authority.insecure_clone();
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana, &beto], batch);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    for (batched, individual) in [(&ana, &users[0]), (&beto, &users[1])] {
+        assert_eq!(
+            env.token_balance(&batched.token_account).await,
+            single.token_balance(&individual.token_account).await
+        );
+        let profile: UserProfile = env.get(&batched.profile).await;
+        let expected: UserProfile = single.get(&individual.profile).await;
+        assert_eq!(profile.total_deposits, expected.total_deposits);
+        assert_eq!(profile.total_pet_weight, expected.total_pet_weight);
+        assert_eq!(profile.total_ecoc_earned, expected.total_ecoc_earned);
+        assert_eq!(profile.reputation_score, expected.reputation_score);
+    }
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 9_500);
+    assert_eq!(state.total_deposits, 4);
+    assert!(state.needs_collection);
+    assert_eq!(state.last_receipt_hash, receipt(103));
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_deposits, 4);
+    assert_eq!(global_state.compressed_deposits, 4);
+    assert_eq!(global_state.total_pet_collected, 9_500);
+}
+
+#[tokio::test]
+async fn batch_pays_referral_bonus_from_pool() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let referrer = env.new_user("ana").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: Some(500_000),
+        new_max_grams: Some(1_500),
+        new_cap_per_referrer: Some(10_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let operator = env.authority.insecure_clone();
+    let deposits = vec![offline(0, 1_000, 0), offline(0, 1_000, 1)];
+
+    // Sin el referente en el lote no se puede pagar su bono
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&user], deposits.clone());
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::MissingReferrerAccounts);
+
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&user, &referrer], deposits);
+    env.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&referrer.token_account).await, 750_000);
+
+    let referrer_profile: UserProfile = env.get(&referrer.profile).await;
+    assert_eq!(referrer_profile.referral_earnings, 750_000);
+    assert_eq!(referrer_profile.total_deposits, 0);
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referred_weight_grams, 1_500);
+}
+
+#[tokio::test]
+async fn batch_is_rejected_as_a_whole() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidBatchSize);
+
+    let too_many = (0..=ecobottle::MAX_BATCH_DEPOSITS as u64).map(|n| offline(0, 200, n)).collect();
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], too_many);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidBatchSize);
+
+    // Índice fuera de los pares, par repetido o token account de otra wallet
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![offline(1, 1_000, 0)]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidBatchAccounts);
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana, &ana], vec![offline(0, 1_000, 0)]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidBatchAccounts);
+    let mut instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![offline(0, 1_000, 0)]);
+    instruction.accounts.last_mut().unwrap().pubkey = beto.token_account;
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidBatchAccounts);
+
+    // Un recibo repetido anula también los depósitos válidos del lote
+    let deposits = vec![offline(0, 1_000, 0), offline(1, 1_000, 0)];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana, &beto], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DuplicateReceipt);
+
+    // A, B y de nuevo A dentro del mismo lote
+    let deposits = vec![offline(0, 1_000, 0), offline(1, 1_000, 1), offline(0, 1_000, 0)];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana, &beto], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DuplicateReceipt);
+
+    let deposits = vec![offline(0, 1_000, 0), offline(1, MIN_DEPOSIT_WEIGHT - 1, 1)];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana, &beto], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::WeightTooLow);
+    assert_eq!(env.token_balance(&ana.token_account).await, 0);
+
+    // Solo el operador del contenedor responde por el lote
+    let intruder = env.wallet().await;
+    let instruction = batch_ix(&env, &intruder, &tree, &container, &[&ana], vec![offline(0, 1_000, 0)]);
+    assert!(env.process(&[instruction], &[&intruder]).await.is_err());
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.total_deposits, 0);
+}
+
+#[tokio::test]
+async fn batch_rejects_repeated_receipt_hash() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let ana = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+
+    // El mismo recibo con otra secuencia, contiguo o más adelante en el lote
+    let mut repeated = offline(0, 1_000, 1);
+    repeated.receipt_hash = receipt(100);
+    let deposits = vec![offline(0, 1_000, 0), repeated.clone()];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DuplicateReceipt);
+
+    repeated.receipt_sequence = 1_002;
+    let deposits = vec![offline(0, 1_000, 0), offline(0, 1_000, 1), repeated];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DuplicateReceipt);
+    assert_eq!(env.token_balance(&ana.token_account).await, 0);
+}
+
+#[tokio::test]
+async fn batch_records_reading_time() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let ana = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+
+    let mut future = offline(0, 1_000, 0);
+    future.timestamp = env.now().await + 60;
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![future]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositInFuture);
+
+    // Las lecturas deben avanzar dentro del lote...
+    let mut earlier = offline(0, 1_000, 1);
+    earlier.timestamp = OFFLINE_SINCE - 1;
+    let deposits = vec![offline(0, 1_000, 0), earlier];
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], deposits);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositOutOfOrder);
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![offline(0, 1_000, 5)]);
+    env.process(&[instruction], &[]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.last_deposit_at, OFFLINE_SINCE + 5);
+    let profile: UserProfile = env.get(&ana.profile).await;
+    assert_eq!(profile.last_deposit_at, OFFLINE_SINCE + 5);
+
+    // La hoja lleva el momento de la lectura, no el de la sincronización
+    let leaf = DepositLeaf {
+        user: ana.key(),
+        container,
+        weight_grams: 1_000,
+        ecoc_reward: env.token_balance(&ana.token_account).await,
+        timestamp: OFFLINE_SINCE + 5,
+        slot: env.clock().await.slot,
+        receipt_hash: receipt(105),
+        nonce: global_state.total_deposits,
+    };
+    assert_eq!(env.deposit_tree_leaf(&tree, 0).await, leaf.hash());
+
+    // ...y entre lotes
+    let mut stale = offline(0, 1_000, 6);
+    stale.timestamp = OFFLINE_SINCE + 4;
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![stale]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositOutOfOrder);
+
+    // Un depósito en línea también cierra el pasado del contenedor
+    let online = env.new_container("EB-002", 50).await;
+    env.deposit(&ana, &online, 1_000).await.unwrap();
+    let now = env.now().await;
+    let instruction = batch_ix(&env, &operator, &tree, &online, &[&ana], vec![offline(0, 1_000, 6)]);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositOutOfOrder);
+
+    // En el perfil la actividad no retrocede: la lectura antigua de otro
+    // contenedor cuenta en el momento de su último depósito
+    let instruction = batch_ix(&env, &operator, &tree, &container, &[&ana], vec![offline(0, 1_000, 6)]);
+    env.process(&[instruction], &[]).await.unwrap();
+    let profile: UserProfile = env.get(&ana.profile).await;
+    assert_eq!(profile.last_deposit_at, now);
+    assert_eq!(profile.total_deposits, 3);
+}
@@ -0,0 +1,450 @@
+//! Recolección, recolectores certificados, solicitudes con SLA y conciliación
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{
+    CertifiedCollector, CollectionRecord, CollectionRequest, CollectionRequestStatus, DepositRecord, ErrorCode,
+    GlobalState, SmartContainer, UserProfile,
+};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn request_collection_ix(payer: &Pubkey, container: &Pubkey, index: u64) -> Instruction {
+    let accounts = ecobottle::accounts::RequestCollection {
+        container: *container,
+        collection_request: collection_request_pda(container, index),
+        payer: *payer,
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::RequestCollection {})
+}
+
+fn claim_ix(collector: &Pubkey, collection_request: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::ClaimCollectionRequest {
+        global_state: global_state_pda(),
+        collection_request: *collection_request,
+        certified_collector: collector_pda(collector),
+        collector: *collector,
+    };
+    ix(accounts, ecobottle::instruction::ClaimCollectionRequest {})
+}
+
+fn collector_status_ix(env: &TestEnv, collector: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = ecobottle::accounts::SetCollectorStatus {
+        certified_collector: collector_pda(collector),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::SetCollectorStatus { is_active })
+}
+
+fn reconcile_ix(env: &TestEnv, collection_record: &Pubkey, measured_weight_grams: u64) -> Instruction {
+    let accounts = ecobottle::accounts::ReconcileCollection {
+        global_state: global_state_pda(),
+        collection_record: *collection_record,
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::ReconcileCollection { measured_weight_grams })
+}
+
+fn penalize_ix(env: &TestEnv, collection_record: &Pubkey, deposit_record: &Pubkey, user: &User) -> Instruction {
+    let accounts = ecobottle::accounts::PenalizeDeposit {
+        global_state: global_state_pda(),
+        collection_record: *collection_record,
+        deposit_record: *deposit_record,
+        user_profile: user.profile,
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::PenalizeDeposit {})
+}
+
+/// Recolector certificado con fondos
+async fn certified_collector(env: &mut TestEnv, name: &str) -> Keypair {
+    let collector = env.wallet().await;
+    env.register_collector(&collector.pubkey(), name).await.unwrap();
+    collector
+}
+
+/// Contenedor de 1 kg que superó su umbral (80%) con una solicitud abierta
+async fn container_with_request(env: &mut TestEnv) -> (Pubkey, Pubkey) {
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+    env.deposit(&user, &container, 800).await.unwrap();
+
+    let instruction = request_collection_ix(&env.authority.pubkey(), &container, 0);
+    env.process(&[instruction], &[]).await.unwrap();
+    (container, collection_request_pda(&container, 0))
+}
+
+async fn collect_request(
+    env: &mut TestEnv,
+    collector: &Keypair,
+    container: &Pubkey,
+    collection_request: Option<Pubkey>,
+) -> Result<(), TransactionError> {
+    let mut accounts = env.collect_accounts(&collector.pubkey(), container).await;
+    accounts.collection_request = collection_request;
+    env.collect_with(collector, accounts).await
+}
+
+#[tokio::test]
+async fn collect_container_records_and_empties_container() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 2_000).await.unwrap();
+
+    let collector = env.authority.insecure_clone();
+    let record = env.collect(&collector, &container).await.unwrap();
+
+    let collection: CollectionRecord = env.get(&record).await;
+    assert_eq!(collection.container, container);
+    assert_eq!(collection.collector, collector.pubkey());
+    assert_eq!(collection.weight_collected, 2_000);
+    assert!(collection.verified);
+    assert!(!collection.reconciled);
+    assert_eq!(collection.collector_reward, 0);
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 0);
+    assert_eq!(state.last_collection, collection.timestamp);
+}
+
+#[tokio::test]
+async fn collect_container_validates_container() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let collector = env.authority.insecure_clone();
+
+    let result = env.collect(&collector, &container).await;
+    assert_error(result.map(|_| ()), ErrorCode::ContainerEmpty);
+
+    env.deposit(&user, &container, 2_000).await.unwrap();
+    let instruction = env.toggle_container_ix(&container);
+    env.process(&[instruction], &[]).await.unwrap();
+    let result = env.collect(&collector, &container).await;
+    assert_error(result.map(|_| ()), ErrorCode::ContainerInactive);
+}
+
+#[tokio::test]
+async fn register_collector_certifies_and_can_be_revoked() {
+    let mut env = TestEnv::new().await;
+    let collector = env.wallet().await;
+
+    let result = env.register_collector(&collector.pubkey(), &"R".repeat(33)).await;
+    assert_error(result, ErrorCode::CollectorNameTooLong);
+
+    env.register_collector(&collector.pubkey(), "Recicla SA").await.unwrap();
+    let certified: CertifiedCollector = env.get(&collector_pda(&collector.pubkey())).await;
+    assert_eq!(certified.collector, collector.pubkey());
+    assert_eq!(certified.name, "Recicla SA");
+    assert!(certified.is_active);
+
+    let instruction = collector_status_ix(&env, &collector.pubkey(), false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let certified: CertifiedCollector = env.get(&collector_pda(&collector.pubkey())).await;
+    assert!(!certified.is_active);
+}
+
+#[tokio::test]
+async fn request_collection_requires_threshold_and_single_request() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+    let payer = env.authority.pubkey();
+
+    env.deposit(&user, &container, 500).await.unwrap();
+    let instruction = request_collection_ix(&payer, &container, 0);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::CollectionNotNeeded);
+
+    env.deposit(&user, &container, 300).await.unwrap();
+    let instruction = request_collection_ix(&payer, &container, 0);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let request_key = collection_request_pda(&container, 0);
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.container, container);
+    assert_eq!(request.status, CollectionRequestStatus::Open);
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.open_request, request_key);
+    assert_eq!(state.collection_requests, 1);
+
+    let instruction = request_collection_ix(&payer, &container, 1);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::CollectionAlreadyRequested);
+}
+
+#[tokio::test]
+async fn claim_collection_request_assigns_with_deadline() {
+    let mut env = TestEnv::new().await;
+    let (_, request_key) = container_with_request(&mut env).await;
+    let first = certified_collector(&mut env, "Primera").await;
+    let second = certified_collector(&mut env, "Segunda").await;
+
+    env.process(&[claim_ix(&first.pubkey(), &request_key)], &[&first]).await.unwrap();
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.status, CollectionRequestStatus::Claimed);
+    assert_eq!(request.assigned_collector, first.pubkey());
+    assert_eq!(request.deadline, request.claimed_at + ecobottle::DEFAULT_COLLECTION_SLA_SECS);
+
+    let result = env.process(&[claim_ix(&second.pubkey(), &request_key)], &[&second]).await;
+    assert_error(result, ErrorCode::CollectionRequestNotExpired);
+
+    // Vencido el SLA otra empresa puede reasignársela
+    env.warp_forward(ecobottle::DEFAULT_COLLECTION_SLA_SECS + 1).await;
+    env.process(&[claim_ix(&second.pubkey(), &request_key)], &[&second]).await.unwrap();
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.assigned_collector, second.pubkey());
+}
+
+#[tokio::test]
+async fn claimed_request_is_not_reassigned_without_sla() {
+    let mut env = TestEnv::new().await;
+    env.update_config(ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: None,
+        new_min_weight: None,
+        new_discrepancy_tolerance_bps: None,
+        new_record_retention_secs: None,
+        new_collection_sla_secs: Some(0),
+    })
+    .await;
+    let (_, request_key) = container_with_request(&mut env).await;
+    let first = certified_collector(&mut env, "Primera").await;
+    let second = certified_collector(&mut env, "Segunda").await;
+    env.process(&[claim_ix(&first.pubkey(), &request_key)], &[&first]).await.unwrap();
+
+    // Sin SLA el plazo nunca vence: la asignación se mantiene
+    env.warp_forward(ecobottle::DEFAULT_COLLECTION_SLA_SECS + 1).await;
+    let result = env.process(&[claim_ix(&second.pubkey(), &request_key)], &[&second]).await;
+    assert_error(result, ErrorCode::CollectionRequestNotExpired);
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.assigned_collector, first.pubkey());
+}
+
+#[tokio::test]
+async fn claim_collection_request_requires_active_certification() {
+    let mut env = TestEnv::new().await;
+    let (_, request_key) = container_with_request(&mut env).await;
+    let collector = certified_collector(&mut env, "Recicla SA").await;
+
+    let instruction = collector_status_ix(&env, &collector.pubkey(), false);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let result = env.process(&[claim_ix(&collector.pubkey(), &request_key)], &[&collector]).await;
+    assert_error(result, ErrorCode::CollectorNotCertified);
+}
+
+#[tokio::test]
+async fn collect_completes_open_request() {
+    let mut env = TestEnv::new().await;
+    let (container, request_key) = container_with_request(&mut env).await;
+    let assigned = certified_collector(&mut env, "Asignada").await;
+    let other = certified_collector(&mut env, "Otra").await;
+    env.process(&[claim_ix(&assigned.pubkey(), &request_key)], &[&assigned]).await.unwrap();
+
+    let result = collect_request(&mut env, &assigned, &container, None).await;
+    assert_error(result, ErrorCode::MissingCollectionRequest);
+
+    let result = collect_request(&mut env, &other, &container, Some(request_key)).await;
+    assert_error(result, ErrorCode::NotAssignedCollector);
+
+    collect_request(&mut env, &assigned, &container, Some(request_key)).await.unwrap();
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.status, CollectionRequestStatus::Completed);
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.open_request, Pubkey::default());
+    assert!(!state.needs_collection);
+
+    let result = env.process(&[claim_ix(&other.pubkey(), &request_key)], &[&other]).await;
+    assert_error(result, ErrorCode::CollectionRequestCompleted);
+}
+
+#[tokio::test]
+async fn collect_requires_assignment_or_authority() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("beto").await;
+    let container = env.new_container("EB-002", 50).await;
+    env.deposit(&user, &container, 2_000).await.unwrap();
+    let collector = certified_collector(&mut env, "Recicla SA").await;
+
+    // Sin solicitud, ni siquiera un recolector certificado
+    let result = env.collect(&collector, &container).await;
+    assert_error(result.map(|_| ()), ErrorCode::CollectionNotAssigned);
+
+    // Solicitud abierta pero sin reclamar
+    let (requested, request_key) = container_with_request(&mut env).await;
+    let result = collect_request(&mut env, &collector, &requested, Some(request_key)).await;
+    assert_error(result, ErrorCode::CollectionNotAssigned);
+
+    // La autoridad recolecta sin asignación y completa la solicitud
+    let authority = env.authority.insecure_clone();
+    collect_request(&mut env, &authority, &requested, Some(request_key)).await.unwrap();
+    let request: CollectionRequest = env.get(&request_key).await;
+    assert_eq!(request.status, CollectionRequestStatus::Completed);
+
+    // Sin flujo de SLA cualquier recolector puede registrar la recolección
+    env.update_config(ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: None,
+        new_min_weight: None,
+        new_discrepancy_tolerance_bps: None,
+        new_record_retention_secs: None,
+        new_collection_sla_secs: Some(0),
+    })
+    .await;
+    env.collect(&collector, &container).await.unwrap();
+}
+
+#[tokio::test]
+async fn update_config_rejects_negative_collection_sla() {
+    let mut env = TestEnv::new().await;
+    let data = ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: None,
+        new_min_weight: None,
+        new_discrepancy_tolerance_bps: None,
+        new_record_retention_secs: None,
+        new_collection_sla_secs: Some(-1),
+    };
+    let instruction = ix(env.update_config_accounts(), data);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidCollectionSla);
+}
+
+#[tokio::test]
+async fn certified_collector_earns_reward_with_sla_bonus() {
+    let mut env = TestEnv::new().await;
+    let (container, request_key) = container_with_request(&mut env).await;
+    let collector = certified_collector(&mut env, "Recicla SA").await;
+    env.process(&[claim_ix(&collector.pubkey(), &request_key)], &[&collector]).await.unwrap();
+    let ecoc_mint = env.ecoc_mint;
+    let collector_token_account = env.create_token_account(&ecoc_mint, &collector.pubkey()).await;
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateCollectorRewards {
+        new_reward_per_kg: Some(100_000),
+        new_sla_bonus_bps: Some(2_000),
+        add_budget: Some(1_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let mut accounts = env.collect_accounts(&collector.pubkey(), &container).await;
+    let record = accounts.collection_record;
+    accounts.collection_request = Some(request_key);
+    accounts.certified_collector = Some(collector_pda(&collector.pubkey()));
+    accounts.ecoc_mint = Some(ecoc_mint);
+    accounts.collector_token_account = Some(collector_token_account);
+    accounts.token_program = Some(spl_token::ID);
+    env.collect_with(&collector, accounts).await.unwrap();
+
+    assert_eq!(env.token_balance(&collector_token_account).await, 96_000);
+    let collection: CollectionRecord = env.get(&record).await;
+    assert_eq!(collection.collector_reward, 96_000);
+    assert_eq!(collection.sla_bonus, 16_000);
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.collector_reward_budget, 904_000);
+}
+
+#[tokio::test]
+async fn certified_collector_reward_requires_token_accounts() {
+    let mut env = TestEnv::new().await;
+    let (container, request_key) = container_with_request(&mut env).await;
+    let collector = certified_collector(&mut env, "Recicla SA").await;
+    env.process(&[claim_ix(&collector.pubkey(), &request_key)], &[&collector]).await.unwrap();
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateCollectorRewards {
+        new_reward_per_kg: Some(100_000),
+        new_sla_bonus_bps: None,
+        add_budget: Some(1_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let mut accounts = env.collect_accounts(&collector.pubkey(), &container).await;
+    accounts.collection_request = Some(request_key);
+    accounts.certified_collector = Some(collector_pda(&collector.pubkey()));
+    let result = env.collect_with(&collector, accounts).await;
+    assert_error(result, ErrorCode::MissingCollectorRewardAccounts);
+}
+
+#[tokio::test]
+async fn reconcile_collection_flags_discrepancy_once() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 2_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    let record = env.collect(&collector, &container).await.unwrap();
+
+    let instruction = reconcile_ix(&env, &record, 1_500);
+    env.process(&[instruction], &[]).await.unwrap();
+    let collection: CollectionRecord = env.get(&record).await;
+    assert!(collection.reconciled);
+    assert_eq!(collection.measured_weight, 1_500);
+    assert_eq!(collection.discrepancy_bps, 2_500);
+    assert!(!collection.verified);
+
+    let instruction = reconcile_ix(&env, &record, 2_000);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::CollectionAlreadyReconciled);
+}
+
+#[tokio::test]
+async fn penalize_deposit_in_discrepant_collection() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let deposit = env.deposit(&user, &container, 2_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    let record = env.collect(&collector, &container).await.unwrap();
+
+    let instruction = penalize_ix(&env, &record, &deposit, &user);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::CollectionNotReconciled);
+
+    let instruction = reconcile_ix(&env, &record, 1_000);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let reputation_before = env.get::<UserProfile>(&user.profile).await.reputation_score;
+    let instruction = penalize_ix(&env, &record, &deposit, &user);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(
+        profile.reputation_score,
+        reputation_before - ecobottle::REPUTATION_DISCREPANCY_PENALTY
+    );
+    let deposit_record: DepositRecord = env.get(&deposit).await;
+    assert!(deposit_record.penalized);
+
+    let instruction = penalize_ix(&env, &record, &deposit, &user);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositAlreadyPenalized);
+}
+
+#[tokio::test]
+async fn penalize_deposit_requires_excess_discrepancy_and_matching_period() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let other_container = env.new_container("EB-002", 50).await;
+    let deposit = env.deposit(&user, &container, 2_000).await.unwrap();
+    let other_deposit = env.deposit(&user, &other_container, 2_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    let record = env.collect(&collector, &container).await.unwrap();
+
+    let instruction = reconcile_ix(&env, &record, 1_900);
+    env.process(&[instruction], &[]).await.unwrap();
+    let instruction = penalize_ix(&env, &record, &deposit, &user);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DiscrepancyWithinTolerance);
+
+    env.update_config(ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: None,
+        new_min_weight: None,
+        new_discrepancy_tolerance_bps: Some(100),
+        new_record_retention_secs: None,
+        new_collection_sla_secs: None,
+    })
+    .await;
+    let instruction = penalize_ix(&env, &record, &other_deposit, &user);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositNotInCollection);
+}
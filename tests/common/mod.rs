@@ -0,0 +1,738 @@
+//! Entorno de pruebas de integración de EcoBottle
+//!
+//! Carga el `ecobottle.so` compilado (`cargo build-sbf`, en `target/deploy` o
+//! en `SBF_OUT_DIR`) en una SVM en proceso (`solana-program-test`), sin
+//! validador.
+//!
+//! SPL Account Compression y SPL Noop se sustituyen por programas mínimos en
+//! proceso que validan la forma de las CPIs del registro comprimido.
+
+#![allow(dead_code)]
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::{Mint, TokenAccount};
+use ecobottle::{ErrorCode, Material, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::bpf_loader;
+use solana_sdk::clock::Clock;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_system_interface::instruction as system_instruction;
+
+/// Tokens ECOC por kg configurados al inicializar
+pub const ECOC_PER_KG: u64 = 1_000_000;
+/// Peso mínimo de depósito configurado al inicializar
+pub const MIN_DEPOSIT_WEIGHT: u64 = 100;
+/// Lamports con los que se fondea cada wallet de prueba
+pub const WALLET_LAMPORTS: u64 = 10_000_000_000;
+
+/// Bytes de la raíz y del contador de hojas al inicio de la cuenta del árbol
+const STUB_TREE_HEADER: usize = 32 + 4;
+
+/// Sustituto de SPL Account Compression: `init_empty_merkle_tree` y `append`
+/// exigen la firma de la autoridad; el árbol guarda las hojas en claro tras una
+/// raíz que encadena cada hoja añadida (ver `deposit_tree_root`), y `verify_leaf`
+/// solo acepta la raíz vigente y la hoja guardada en ese índice
+fn process_compression_stub(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let discriminator = data.get(..8).ok_or(ProgramError::InvalidInstructionData)?;
+    let tree = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    if discriminator == ecobottle::COMPRESSION_VERIFY_LEAF {
+        let args = data.get(8..8 + 32 + 32 + 4).ok_or(ProgramError::InvalidInstructionData)?;
+        let (root, rest) = args.split_at(32);
+        let (leaf, index) = rest.split_at(32);
+        let index = u32::from_le_bytes(index.try_into().unwrap()) as usize;
+
+        let tree_data = tree.try_borrow_data()?;
+        let count = u32::from_le_bytes(tree_data[32..STUB_TREE_HEADER].try_into().unwrap()) as usize;
+        let offset = STUB_TREE_HEADER + index * 32;
+        if root != &tree_data[..32] || index >= count || leaf != &tree_data[offset..offset + 32] {
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    if !accounts.get(1).is_some_and(|authority| authority.is_signer) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut tree_data = tree.try_borrow_mut_data()?;
+    if discriminator == ecobottle::COMPRESSION_INIT_EMPTY_MERKLE_TREE {
+        tree_data.fill(0);
+    } else if discriminator == ecobottle::COMPRESSION_APPEND {
+        let leaf = data.get(8..8 + 32).ok_or(ProgramError::InvalidInstructionData)?;
+        let count = u32::from_le_bytes(tree_data[32..STUB_TREE_HEADER].try_into().unwrap());
+        let offset = STUB_TREE_HEADER + count as usize * 32;
+        if offset + 32 > tree_data.len() {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let root = solana_sdk::hash::hashv(&[&tree_data[..32], leaf]).to_bytes();
+        tree_data[..32].copy_from_slice(&root);
+        tree_data[32..STUB_TREE_HEADER].copy_from_slice(&(count + 1).to_le_bytes());
+        tree_data[offset..offset + 32].copy_from_slice(leaf);
+    } else {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+fn process_noop_stub(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    Ok(())
+}
+
+/// `ecobottle.so` compilado para SBF
+fn sbf_program() -> Vec<u8> {
+    let dirs = ["SBF_OUT_DIR", "BPF_OUT_DIR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .chain([concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy").to_string()]);
+    dirs.map(|dir| std::path::Path::new(&dir).join("ecobottle.so"))
+        .find_map(|path| std::fs::read(path).ok())
+        .expect("no se encontró ecobottle.so: compila el programa con `cargo build-sbf`")
+}
+
+/// `ProgramTest` con EcoBottle y los sustitutos de compresión
+pub fn program_test() -> ProgramTest {
+    // Los sustitutos solo existen como procesadores nativos
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(false);
+    let elf = sbf_program();
+    program_test.add_account(
+        ecobottle::ID,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: bpf_loader::ID,
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    program_test.add_program("spl_account_compression", SPL_ACCOUNT_COMPRESSION_ID, processor!(process_compression_stub));
+    program_test.add_program("spl_noop", SPL_NOOP_ID, processor!(process_noop_stub));
+    program_test
+}
+
+/// PDA del programa para las semillas indicadas
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ecobottle::ID).0
+}
+
+pub fn global_state_pda() -> Pubkey {
+    pda(&[b"global_state"])
+}
+
+pub fn user_profile_pda(user: &Pubkey) -> Pubkey {
+    pda(&[b"user_profile", user.as_ref()])
+}
+
+pub fn username_pda(username: &str) -> Pubkey {
+    pda(&[b"username", ecobottle::username_seed(username).as_ref()])
+}
+
+pub fn container_pda(container_id: &str) -> Pubkey {
+    pda(&[b"container", container_id.as_bytes()])
+}
+
+pub fn deposit_pda(user: &Pubkey, nonce: u64) -> Pubkey {
+    pda(&[b"deposit", user.as_ref(), &nonce.to_le_bytes()])
+}
+
+pub fn redemption_pda(user: &Pubkey, total_spent: u64) -> Pubkey {
+    pda(&[b"redemption", user.as_ref(), &total_spent.to_le_bytes()])
+}
+
+pub fn collection_pda(container: &Pubkey, total_deposits: u64) -> Pubkey {
+    pda(&[b"collection", container.as_ref(), &total_deposits.to_le_bytes()])
+}
+
+pub fn collection_request_pda(container: &Pubkey, index: u64) -> Pubkey {
+    pda(&[b"collection_request", container.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn collector_pda(collector: &Pubkey) -> Pubkey {
+    pda(&[b"collector", collector.as_ref()])
+}
+
+pub fn recycler_pda(recycler: &Pubkey) -> Pubkey {
+    pda(&[b"recycler", recycler.as_ref()])
+}
+
+pub fn shipment_pda(collector: &Pubkey, index: u64) -> Pubkey {
+    pda(&[b"shipment", collector.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn processing_receipt_pda(shipment: &Pubkey) -> Pubkey {
+    pda(&[b"processing_receipt", shipment.as_ref()])
+}
+
+pub fn brand_pda(owner: &Pubkey) -> Pubkey {
+    pda(&[b"brand", owner.as_ref()])
+}
+
+pub fn serial_bitmap_pda(gtin: u64, bucket: u64) -> Pubkey {
+    pda(&[b"serials", &gtin.to_le_bytes(), &bucket.to_le_bytes()])
+}
+
+pub fn leaderboard_pda(period_kind: ecobottle::PeriodKind, period_index: u64, scope: &Pubkey) -> Pubkey {
+    pda(&[b"leaderboard", &[period_kind as u8], &period_index.to_le_bytes(), scope.as_ref()])
+}
+
+pub fn leaderboard_vault_pda(leaderboard: &Pubkey) -> Pubkey {
+    pda(&[b"leaderboard_vault", leaderboard.as_ref()])
+}
+
+pub fn leaderboard_score_pda(leaderboard: &Pubkey, user: &Pubkey) -> Pubkey {
+    pda(&[b"leaderboard_score", leaderboard.as_ref(), user.as_ref()])
+}
+
+/// Instrucción de EcoBottle a partir de sus cuentas y argumentos de Anchor
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ecobottle::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Como `ix`, añadiendo `remaining_accounts`
+pub fn ix_with_remaining(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining: Vec<AccountMeta>,
+) -> Instruction {
+    let mut instruction = ix(accounts, data);
+    instruction.accounts.extend(remaining);
+    instruction
+}
+
+/// Recibo de contenedor distinto para cada `n`
+pub fn receipt(n: u64) -> [u8; 32] {
+    let mut hash = [0xEC; 32];
+    hash[..8].copy_from_slice(&n.to_le_bytes());
+    hash
+}
+
+/// Secuencia del recibo `receipt(n)`: los recibos de prueba se numeran en orden
+pub fn receipt_sequence(receipt_hash: &[u8; 32]) -> u64 {
+    u64::from_le_bytes(receipt_hash[..8].try_into().unwrap())
+}
+
+/// Comprueba que la transacción falló con el `ErrorCode` indicado
+pub fn assert_error(result: Result<(), TransactionError>, expected: ErrorCode) {
+    let code = u32::from(expected);
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) if actual == code => {}
+        other => panic!("se esperaba {:?} ({}), se obtuvo {:?}", expected, code, other),
+    }
+}
+
+/// Usuario registrado con su token account ECOC
+pub struct User {
+    pub wallet: Keypair,
+    pub profile: Pubkey,
+    pub token_account: Pubkey,
+}
+
+impl User {
+    pub fn key(&self) -> Pubkey {
+        self.wallet.pubkey()
+    }
+}
+
+/// Programa inicializado con un mint ECOC cuya autoridad es `global_state`
+pub struct TestEnv {
+    pub ctx: ProgramTestContext,
+    pub authority: Keypair,
+    pub ecoc_mint: Pubkey,
+    receipts: u64,
+    transactions: u32,
+}
+
+impl TestEnv {
+    /// Arranca la SVM e inicializa EcoBottle
+    pub async fn new() -> Self {
+        let mut env = Self::start(program_test()).await;
+        env.ecoc_mint = env.create_mint(&global_state_pda(), 6).await;
+        env.initialize().await.unwrap();
+        env
+    }
+
+    /// Arranca la SVM sin inicializar el programa
+    pub async fn start(program_test: ProgramTest) -> Self {
+        let ctx = program_test.start_with_context().await;
+        let authority = ctx.payer.insecure_clone();
+        Self {
+            ctx,
+            authority,
+            ecoc_mint: Pubkey::default(),
+            receipts: 0,
+            transactions: 0,
+        }
+    }
+
+    pub async fn initialize(&mut self) -> Result<(), TransactionError> {
+        let accounts = ecobottle::accounts::Initialize {
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+            ecoc_mint: self.ecoc_mint,
+            system_program: system_program::ID,
+        };
+        let data = ecobottle::instruction::Initialize {
+            ecoc_per_kg: ECOC_PER_KG,
+            min_deposit_weight: MIN_DEPOSIT_WEIGHT,
+        };
+        self.process(&[ix(accounts, data)], &[]).await
+    }
+
+    /// Envía una transacción pagada por la autoridad
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        // Límite de cómputo distinto en cada transacción para que dos envíos
+        // idénticos no se dedupliquen
+        self.transactions += 1;
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000 - self.transactions)];
+        all.extend_from_slice(instructions);
+
+        let mut all_signers = vec![&self.authority];
+        all_signers.extend(signers.iter().copied().filter(|s| s.pubkey() != self.authority.pubkey()));
+
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(&all, Some(&self.authority.pubkey()), &all_signers, blockhash);
+        self.ctx
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.unwrap())
+    }
+
+    /// Simula una instrucción de vista y devuelve sus datos de retorno
+    pub async fn view(&mut self, instruction: Instruction) -> Vec<u8> {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.authority.pubkey()),
+            &[&self.authority],
+            blockhash,
+        );
+        let simulation = self.ctx.banks_client.simulate_transaction(transaction).await.unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| return_data.data)
+            .unwrap_or_default()
+    }
+
+    /// Siguiente recibo de contenedor sin usar
+    pub fn next_receipt(&mut self) -> [u8; 32] {
+        self.receipts += 1;
+        receipt(self.receipts)
+    }
+
+    pub async fn fund(&mut self, to: &Pubkey, lamports: u64) {
+        let instruction = system_instruction::transfer(&self.authority.pubkey(), to, lamports);
+        self.process(&[instruction], &[]).await.unwrap();
+    }
+
+    /// Wallet nueva con fondos
+    pub async fn wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        self.fund(&wallet.pubkey(), WALLET_LAMPORTS).await;
+        wallet
+    }
+
+    pub async fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.authority.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), authority, None, decimals)
+                .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.authority.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account3(&spl_token::ID, &account.pubkey(), mint, owner).unwrap(),
+        ];
+        self.process(&instructions, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    /// Cuenta del programa deserializada
+    pub async fn get<T: AccountDeserialize>(&mut self, key: &Pubkey) -> T {
+        let account = self.ctx.banks_client.get_account(*key).await.unwrap().expect("cuenta inexistente");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn exists(&mut self, key: &Pubkey) -> bool {
+        self.ctx.banks_client.get_account(*key).await.unwrap().is_some()
+    }
+
+    pub async fn lamports(&mut self, key: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        self.get::<TokenAccount>(token_account).await.amount
+    }
+
+    pub async fn mint_supply(&mut self, mint: &Pubkey) -> u64 {
+        self.get::<Mint>(mint).await.supply
+    }
+
+    /// Sobrescribe una cuenta del programa (estados sin instrucción que los produzca)
+    pub async fn set_program_account<T: AccountSerialize>(&mut self, key: &Pubkey, value: &T, space: usize) {
+        let mut data = Vec::with_capacity(space);
+        value.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        self.set_raw_account(key, data, ecobottle::ID).await;
+    }
+
+    /// Crea o sobrescribe una cuenta con datos arbitrarios y renta exenta
+    pub async fn set_raw_account(&mut self, key: &Pubkey, data: Vec<u8>, owner: Pubkey) {
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(key, &AccountSharedData::from(account));
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    /// Fija la hora de la SVM
+    pub async fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    /// Adelanta la hora de la SVM
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let now = self.now().await;
+        self.warp_to(now + seconds).await;
+    }
+
+    /// Pasa al slot siguiente sin mover la hora
+    pub async fn next_slot(&mut self) {
+        let mut clock = self.clock().await;
+        clock.slot += 1;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // ------------------------------------------------------------------------
+    // Usuarios y contenedores
+    // ------------------------------------------------------------------------
+
+    pub fn register_user_accounts(&self, user: &Pubkey, username: &str) -> ecobottle::accounts::RegisterUser {
+        ecobottle::accounts::RegisterUser {
+            user_profile: user_profile_pda(user),
+            username_record: username_pda(username),
+            global_state: global_state_pda(),
+            referrer_profile: None,
+            user: *user,
+            payer: *user,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub async fn register_user(
+        &mut self,
+        wallet: &Keypair,
+        username: &str,
+        referrer: Option<Pubkey>,
+        referrer_profile: Option<Pubkey>,
+    ) -> Result<(), TransactionError> {
+        let mut accounts = self.register_user_accounts(&wallet.pubkey(), username);
+        accounts.referrer_profile = referrer_profile;
+        let data = ecobottle::instruction::RegisterUser {
+            username: username.to_string(),
+            referrer,
+        };
+        self.process(&[ix(accounts, data)], &[wallet]).await
+    }
+
+    /// Registra un usuario nuevo con su token account ECOC
+    pub async fn new_user(&mut self, username: &str) -> User {
+        self.new_user_with_referrer(username, None).await
+    }
+
+    pub async fn new_user_with_referrer(&mut self, username: &str, referrer: Option<&User>) -> User {
+        let wallet = self.wallet().await;
+        self.register_user(&wallet, username, referrer.map(|r| r.key()), referrer.map(|r| r.profile))
+            .await
+            .unwrap();
+
+        let ecoc_mint = self.ecoc_mint;
+        let token_account = self.create_token_account(&ecoc_mint, &wallet.pubkey()).await;
+        User {
+            profile: user_profile_pda(&wallet.pubkey()),
+            wallet,
+            token_account,
+        }
+    }
+
+    pub async fn register_container(
+        &mut self,
+        container_id: &str,
+        capacity_kg: u64,
+        material: Material,
+    ) -> Result<Pubkey, TransactionError> {
+        let container = container_pda(container_id);
+        let accounts = ecobottle::accounts::RegisterContainer {
+            container,
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        let data = ecobottle::instruction::RegisterContainer {
+            container_id: container_id.to_string(),
+            location: "Plaza Mayor".to_string(),
+            capacity_kg,
+            material,
+        };
+        self.process(&[ix(accounts, data)], &[]).await?;
+        Ok(container)
+    }
+
+    pub async fn new_container(&mut self, container_id: &str, capacity_kg: u64) -> Pubkey {
+        self.register_container(container_id, capacity_kg, Material::Pet).await.unwrap()
+    }
+
+    pub fn toggle_container_ix(&self, container: &Pubkey) -> Instruction {
+        let accounts = ecobottle::accounts::ToggleContainerStatus {
+            container: *container,
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+        };
+        ix(accounts, ecobottle::instruction::ToggleContainerStatus {})
+    }
+
+    // ------------------------------------------------------------------------
+    // Depósitos
+    // ------------------------------------------------------------------------
+
+    /// Cuentas de un depósito en modo registro (sin referente ni marca)
+    pub async fn deposit_accounts(&mut self, user: &User, container: &Pubkey) -> ecobottle::accounts::ProcessDeposit {
+        let global_state: ecobottle::GlobalState = self.get(&global_state_pda()).await;
+        ecobottle::accounts::ProcessDeposit {
+            global_state: global_state_pda(),
+            container: *container,
+            user_profile: user.profile,
+            deposit_record: Some(deposit_pda(&user.key(), global_state.total_deposits)),
+            deposit_tree: None,
+            compression_program: None,
+            noop_program: None,
+            user: user.key(),
+            payer: user.key(),
+            user_token_account: user.token_account,
+            ecoc_mint: self.ecoc_mint,
+            referrer_profile: None,
+            referrer_token_account: None,
+            brand: None,
+            container_authority: self.authority.pubkey(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub async fn deposit_with(
+        &mut self,
+        user: &User,
+        accounts: ecobottle::accounts::ProcessDeposit,
+        weight_grams: u64,
+        receipt_hash: [u8; 32],
+        remaining: Vec<AccountMeta>,
+    ) -> Result<(), TransactionError> {
+        let data = ecobottle::instruction::ProcessDeposit {
+            weight_grams,
+            receipt_hash,
+            receipt_sequence: receipt_sequence(&receipt_hash),
+        };
+        self.process(&[ix_with_remaining(accounts, data, remaining)], &[&user.wallet]).await
+    }
+
+    /// Raíz vigente del árbol del sustituto de compresión
+    pub async fn deposit_tree_root(&mut self, tree: &Pubkey) -> [u8; 32] {
+        let account = self.ctx.banks_client.get_account(*tree).await.unwrap().expect("cuenta inexistente");
+        account.data[..32].try_into().unwrap()
+    }
+
+    /// Hoja `index` guardada por el sustituto de compresión
+    pub async fn deposit_tree_leaf(&mut self, tree: &Pubkey, index: usize) -> [u8; 32] {
+        let account = self.ctx.banks_client.get_account(*tree).await.unwrap().expect("cuenta inexistente");
+        let offset = STUB_TREE_HEADER + index * 32;
+        account.data[offset..offset + 32].try_into().unwrap()
+    }
+
+    /// Crea la cuenta del árbol (propiedad de SPL Account Compression) y la inicializa
+    pub async fn init_deposit_tree(&mut self) -> Pubkey {
+        let tree = Pubkey::new_unique();
+        self.set_raw_account(&tree, vec![0; 1_024], SPL_ACCOUNT_COMPRESSION_ID).await;
+
+        let accounts = ecobottle::accounts::InitDepositTree {
+            global_state: global_state_pda(),
+            deposit_tree: tree,
+            authority: self.authority.pubkey(),
+            compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+            noop_program: SPL_NOOP_ID,
+        };
+        let data = ecobottle::instruction::InitDepositTree {
+            max_depth: 14,
+            max_buffer_size: 64,
+        };
+        self.process(&[ix(accounts, data)], &[]).await.unwrap();
+        tree
+    }
+
+    /// Depósito en modo registro con un recibo nuevo; devuelve el registro creado
+    pub async fn deposit(&mut self, user: &User, container: &Pubkey, weight_grams: u64) -> Result<Pubkey, TransactionError> {
+        let accounts = self.deposit_accounts(user, container).await;
+        let record = accounts.deposit_record.unwrap();
+        let receipt_hash = self.next_receipt();
+        self.deposit_with(user, accounts, weight_grams, receipt_hash, vec![]).await?;
+        Ok(record)
+    }
+
+    // ------------------------------------------------------------------------
+    // Recolección
+    // ------------------------------------------------------------------------
+
+    /// Cuentas de una recolección sin solicitud ni incentivo
+    pub async fn collect_accounts(&mut self, collector: &Pubkey, container: &Pubkey) -> ecobottle::accounts::CollectContainer {
+        let state: ecobottle::SmartContainer = self.get(container).await;
+        ecobottle::accounts::CollectContainer {
+            global_state: global_state_pda(),
+            container: *container,
+            collection_record: collection_pda(container, state.total_deposits),
+            collection_request: None,
+            certified_collector: None,
+            ecoc_mint: None,
+            collector_token_account: None,
+            collector: *collector,
+            token_program: None,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub async fn collect_with(
+        &mut self,
+        collector: &Keypair,
+        accounts: ecobottle::accounts::CollectContainer,
+    ) -> Result<(), TransactionError> {
+        self.process(&[ix(accounts, ecobottle::instruction::CollectContainer {})], &[collector]).await
+    }
+
+    /// Recolección simple; devuelve el registro creado
+    pub async fn collect(&mut self, collector: &Keypair, container: &Pubkey) -> Result<Pubkey, TransactionError> {
+        let accounts = self.collect_accounts(&collector.pubkey(), container).await;
+        let record = accounts.collection_record;
+        self.collect_with(collector, accounts).await?;
+        Ok(record)
+    }
+
+    pub async fn register_collector(&mut self, collector: &Pubkey, name: &str) -> Result<(), TransactionError> {
+        let accounts = ecobottle::accounts::RegisterCollector {
+            certified_collector: collector_pda(collector),
+            collector: *collector,
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        let data = ecobottle::instruction::RegisterCollector { name: name.to_string() };
+        self.process(&[ix(accounts, data)], &[]).await
+    }
+
+    pub fn update_config_accounts(&self) -> ecobottle::accounts::UpdateConfig {
+        ecobottle::accounts::UpdateConfig {
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+        }
+    }
+
+    pub async fn update_config(&mut self, data: ecobottle::instruction::UpdateConfig) {
+        let accounts = self.update_config_accounts();
+        self.process(&[ix(accounts, data)], &[]).await.unwrap();
+    }
+
+    pub async fn register_recycler(&mut self, recycler: &Pubkey, name: &str) -> Result<(), TransactionError> {
+        let accounts = ecobottle::accounts::RegisterRecycler {
+            recycler: recycler_pda(recycler),
+            recycler_wallet: *recycler,
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        let data = ecobottle::instruction::RegisterRecycler { name: name.to_string() };
+        self.process(&[ix(accounts, data)], &[]).await
+    }
+
+    pub async fn register_brand(&mut self, owner: &Pubkey, name: &str) -> Result<Pubkey, TransactionError> {
+        let brand = brand_pda(owner);
+        let accounts = ecobottle::accounts::RegisterBrand {
+            brand,
+            brand_owner: *owner,
+            global_state: global_state_pda(),
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        let data = ecobottle::instruction::RegisterBrand { name: name.to_string() };
+        self.process(&[ix(accounts, data)], &[]).await?;
+        Ok(brand)
+    }
+
+    /// Cuenta asociada Token-2022 de una insignia
+    pub fn badge_account(&self, user: &Pubkey, badge_mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, badge_mint, &anchor_spl::token_2022::ID)
+    }
+}
+
+/// Serializa una cuenta de esquema anterior (discriminador + campos Borsh)
+pub fn legacy_account_data<T: AnchorSerialize>(discriminator: &[u8], value: &T) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    value.serialize(&mut data).unwrap();
+    data
+}
+
+/// Discriminador de una cuenta de Anchor
+pub fn discriminator<T: Discriminator>() -> &'static [u8] {
+    T::DISCRIMINATOR
+}
@@ -0,0 +1,212 @@
+//! Registro comprimido de depósitos en el árbol de Merkle
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use common::*;
+use ecobottle::{
+    CompressedPenalty, DepositLeaf, ErrorCode, GlobalState, UserProfile, COMPRESSION_APPEND,
+    COMPRESSION_INIT_EMPTY_MERKLE_TREE, COMPRESSION_VERIFY_LEAF, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID,
+};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::Signer;
+
+/// Depósito comprimido de `weight_grams`; devuelve la hoja añadida al árbol
+async fn compressed_deposit(env: &mut TestEnv, tree: &Pubkey, user: &User, container: &Pubkey, weight_grams: u64) -> DepositLeaf {
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    let mut accounts = env.deposit_accounts(user, container).await;
+    accounts.deposit_record = None;
+    accounts.deposit_tree = Some(*tree);
+    accounts.compression_program = Some(SPL_ACCOUNT_COMPRESSION_ID);
+    accounts.noop_program = Some(SPL_NOOP_ID);
+    let receipt_hash = env.next_receipt();
+    let balance = env.token_balance(&user.token_account).await;
+    env.deposit_with(user, accounts, weight_grams, receipt_hash, vec![]).await.unwrap();
+
+    let clock = env.clock().await;
+    DepositLeaf {
+        user: user.key(),
+        container: *container,
+        weight_grams,
+        ecoc_reward: env.token_balance(&user.token_account).await - balance,
+        timestamp: clock.unix_timestamp,
+        slot: clock.slot,
+        receipt_hash,
+        nonce: global_state.total_deposits,
+    }
+}
+
+fn penalize_compressed_ix(
+    env: &TestEnv,
+    collection_record: &Pubkey,
+    tree: &Pubkey,
+    root: [u8; 32],
+    leaf: DepositLeaf,
+    index: u32,
+) -> Instruction {
+    let accounts = ecobottle::accounts::PenalizeCompressedDeposit {
+        global_state: global_state_pda(),
+        collection_record: *collection_record,
+        deposit_tree: *tree,
+        penalty: pda(&[b"compressed_penalty", tree.as_ref(), &index.to_le_bytes()]),
+        user_profile: user_profile_pda(&leaf.user),
+        authority: env.authority.pubkey(),
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::PenalizeCompressedDeposit {
+        root,
+        leaf,
+        index,
+    };
+    let proof = (0..3).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false)).collect();
+    ix_with_remaining(accounts, data, proof)
+}
+
+fn reconcile_ix(env: &TestEnv, collection_record: &Pubkey, measured_weight_grams: u64) -> Instruction {
+    let accounts = ecobottle::accounts::ReconcileCollection {
+        global_state: global_state_pda(),
+        collection_record: *collection_record,
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::ReconcileCollection { measured_weight_grams })
+}
+
+#[test]
+fn compression_discriminators_match_published_idl() {
+    // Valores del IDL de spl-account-compression
+    assert_eq!(COMPRESSION_INIT_EMPTY_MERKLE_TREE, [191, 11, 119, 7, 180, 107, 220, 110]);
+    assert_eq!(COMPRESSION_APPEND, [149, 120, 18, 222, 236, 225, 88, 203]);
+    assert_eq!(COMPRESSION_VERIFY_LEAF, [124, 220, 22, 223, 104, 10, 250, 224]);
+
+    for (name, discriminator) in [
+        ("init_empty_merkle_tree", COMPRESSION_INIT_EMPTY_MERKLE_TREE),
+        ("append", COMPRESSION_APPEND),
+        ("verify_leaf", COMPRESSION_VERIFY_LEAF),
+    ] {
+        let preimage = format!("global:{}", name);
+        assert_eq!(solana_sdk::hash::hash(preimage.as_bytes()).to_bytes()[..8], discriminator);
+    }
+}
+
+#[tokio::test]
+async fn init_deposit_tree_registers_tree() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.deposit_tree, tree);
+    assert_eq!(global_state.compressed_deposits, 0);
+}
+
+#[tokio::test]
+async fn compressed_deposit_appends_leaf_without_record() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    let record = accounts.deposit_record.take().unwrap();
+    accounts.deposit_tree = Some(tree);
+    accounts.compression_program = Some(SPL_ACCOUNT_COMPRESSION_ID);
+    accounts.noop_program = Some(SPL_NOOP_ID);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+
+    assert!(!env.exists(&record).await);
+    assert_eq!(env.token_balance(&user.token_account).await, ECOC_PER_KG);
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.compressed_deposits, 1);
+    assert_eq!(global_state.total_deposits, 1);
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.total_pet_weight, 1_000);
+}
+
+#[tokio::test]
+async fn deposit_rejects_record_and_tree_together() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.deposit_tree = Some(tree);
+    accounts.compression_program = Some(SPL_ACCOUNT_COMPRESSION_ID);
+    accounts.noop_program = Some(SPL_NOOP_ID);
+    let receipt_hash = env.next_receipt();
+    let result = env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await;
+    assert_error(result, ErrorCode::InvalidDepositLogMode);
+}
+
+fn verify_deposit_ix(tree: &Pubkey, root: [u8; 32], leaf: DepositLeaf, index: u32) -> Instruction {
+    let accounts = ecobottle::accounts::VerifyDeposit {
+        global_state: global_state_pda(),
+        deposit_tree: *tree,
+        compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+    };
+    let data = ecobottle::instruction::VerifyDeposit { root, leaf, index };
+    let proof = (0..3).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false)).collect();
+    ix_with_remaining(accounts, data, proof)
+}
+
+#[tokio::test]
+async fn verify_deposit_checks_appended_leaves() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let first = compressed_deposit(&mut env, &tree, &user, &container, 1_000).await;
+    let second = compressed_deposit(&mut env, &tree, &user, &container, 2_000).await;
+    let root = env.deposit_tree_root(&tree).await;
+
+    env.process(&[verify_deposit_ix(&tree, root, first.clone(), 0)], &[]).await.unwrap();
+    env.process(&[verify_deposit_ix(&tree, root, second.clone(), 1)], &[]).await.unwrap();
+
+    // Hoja en otro índice, hoja alterada, raíz desconocida o índice sin hoja
+    assert!(env.process(&[verify_deposit_ix(&tree, root, first.clone(), 1)], &[]).await.is_err());
+    let mut forged = second.clone();
+    forged.weight_grams = 20_000;
+    assert!(env.process(&[verify_deposit_ix(&tree, root, forged, 1)], &[]).await.is_err());
+    assert!(env.process(&[verify_deposit_ix(&tree, [7; 32], first.clone(), 0)], &[]).await.is_err());
+    assert!(env.process(&[verify_deposit_ix(&tree, root, second, 2)], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn penalize_compressed_deposit_once_per_leaf() {
+    let mut env = TestEnv::new().await;
+    let tree = env.init_deposit_tree().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let leaf = compressed_deposit(&mut env, &tree, &user, &container, 1_000).await;
+    let root = env.deposit_tree_root(&tree).await;
+    let collector = env.authority.insecure_clone();
+    let collection = env.collect(&collector, &container).await.unwrap();
+
+    let instruction = penalize_compressed_ix(&env, &collection, &tree, root, leaf.clone(), 0);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::CollectionNotReconciled);
+
+    env.process(&[reconcile_ix(&env, &collection, 500)], &[]).await.unwrap();
+    let before: UserProfile = env.get(&user.profile).await;
+    let instruction = penalize_compressed_ix(&env, &collection, &tree, root, leaf.clone(), 0);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.reputation_score, before.reputation_score - ecobottle::REPUTATION_DISCREPANCY_PENALTY);
+    let penalty: CompressedPenalty = env.get(&pda(&[b"compressed_penalty", tree.as_ref(), &0u32.to_le_bytes()])).await;
+    assert_eq!(penalty.user, user.key());
+    assert_eq!(penalty.collection, collection);
+
+    // La misma hoja no se penaliza dos veces
+    env.warp_forward(1).await;
+    let instruction = penalize_compressed_ix(&env, &collection, &tree, root, leaf.clone(), 0);
+    assert!(env.process(&[instruction], &[]).await.is_err());
+
+    // Una hoja fuera del periodo de la recolección no se penaliza
+    let mut late = leaf;
+    late.timestamp += ecobottle::SECONDS_PER_DAY;
+    let instruction = penalize_compressed_ix(&env, &collection, &tree, root, late, 1);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::DepositNotInCollection);
+}
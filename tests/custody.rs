@@ -0,0 +1,417 @@
+//! Cadena de custodia: envíos a planta, recibos de procesamiento y certificados de impacto
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{
+    CertifiedCollector, CollectionRecord, ErrorCode, GlobalState, Material, ProcessingReceipt, Recycler, Shipment,
+    ShipmentStatus,
+};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn impact_mint_pda() -> Pubkey {
+    pda(&[b"impact_mint"])
+}
+
+fn confirm_ix(recycler: &Keypair, shipment: &Pubkey, received_weight_grams: u64, output_grams: u64) -> Instruction {
+    let accounts = ecobottle::accounts::ConfirmShipmentReceipt {
+        shipment: *shipment,
+        recycler: recycler_pda(&recycler.pubkey()),
+        processing_receipt: processing_receipt_pda(shipment),
+        recycler_wallet: recycler.pubkey(),
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::ConfirmShipmentReceipt {
+        received_weight_grams,
+        output_product: "rPET-flake".to_string(),
+        output_grams,
+    };
+    ix(accounts, data)
+}
+
+fn recycler_status_ix(env: &TestEnv, recycler: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = ecobottle::accounts::SetRecyclerStatus {
+        recycler: recycler_pda(recycler),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::SetRecyclerStatus { is_active })
+}
+
+fn issue_ix(env: &TestEnv, processing_receipt: &Pubkey, recipient: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::IssueImpactCertificate {
+        global_state: global_state_pda(),
+        processing_receipt: *processing_receipt,
+        impact_mint: impact_mint_pda(),
+        recipient_token_account: *recipient,
+        authority: env.authority.pubkey(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::IssueImpactCertificate {})
+}
+
+fn retire_ix(owner: &Keypair, owner_token_account: &Pubkey, amount: u64, beneficiary: &str) -> Instruction {
+    let accounts = ecobottle::accounts::RetireImpactCertificate {
+        global_state: global_state_pda(),
+        impact_mint: impact_mint_pda(),
+        owner_token_account: *owner_token_account,
+        owner: owner.pubkey(),
+        token_program: spl_token::ID,
+    };
+    let data = ecobottle::instruction::RetireImpactCertificate {
+        amount,
+        beneficiary: beneficiary.to_string(),
+    };
+    ix(accounts, data)
+}
+
+/// Recolector certificado, planta registrada y dos recolecciones de 2 kg y 3 kg
+struct Custody {
+    collector: Keypair,
+    recycler: Keypair,
+    collections: Vec<Pubkey>,
+}
+
+async fn setup_custody(env: &mut TestEnv) -> Custody {
+    let user = env.new_user("ana").await;
+    let collector = env.authority.insecure_clone();
+    env.register_collector(&collector.pubkey(), "Recicla SA").await.unwrap();
+    let recycler = env.wallet().await;
+    env.register_recycler(&recycler.pubkey(), "Planta Norte").await.unwrap();
+
+    let mut collections = Vec::new();
+    for (id, weight) in [("EB-001", 2_000), ("EB-002", 3_000)] {
+        let container = env.new_container(id, 50).await;
+        env.deposit(&user, &container, weight).await.unwrap();
+        collections.push(env.collect(&collector, &container).await.unwrap());
+    }
+
+    Custody {
+        collector,
+        recycler,
+        collections,
+    }
+}
+
+/// Contenedor de `material` con un depósito de `weight_grams` ya recolectado
+async fn collected(env: &mut TestEnv, collector: &Keypair, container_id: &str, material: Material, weight_grams: u64) -> Pubkey {
+    let user = env.new_user(&container_id.to_lowercase()).await;
+    let container = env.register_container(container_id, 50, material).await.unwrap();
+    env.deposit(&user, &container, weight_grams).await.unwrap();
+    env.collect(collector, &container).await.unwrap()
+}
+
+async fn create_shipment(
+    env: &mut TestEnv,
+    collector: &Keypair,
+    recycler: &Pubkey,
+    collections: &[Pubkey],
+) -> Result<Pubkey, TransactionError> {
+    let certified: CertifiedCollector = env.get(&collector_pda(&collector.pubkey())).await;
+    let shipment = shipment_pda(&collector.pubkey(), certified.total_shipments);
+    let accounts = ecobottle::accounts::CreateShipment {
+        certified_collector: collector_pda(&collector.pubkey()),
+        recycler: recycler_pda(recycler),
+        shipment,
+        collector: collector.pubkey(),
+        system_program: system_program::ID,
+    };
+    let remaining = collections.iter().map(|key| AccountMeta::new(*key, false)).collect();
+    let instruction = ix_with_remaining(accounts, ecobottle::instruction::CreateShipment {}, remaining);
+    env.process(&[instruction], &[collector]).await?;
+    Ok(shipment)
+}
+
+async fn init_impact_mint(env: &mut TestEnv) {
+    let accounts = ecobottle::accounts::InitImpactMint {
+        global_state: global_state_pda(),
+        impact_mint: impact_mint_pda(),
+        authority: env.authority.pubkey(),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    env.process(&[ix(accounts, ecobottle::instruction::InitImpactMint {})], &[]).await.unwrap();
+}
+
+/// Envío recibido en planta con 4 kg de escamas producidas
+async fn processed_receipt(env: &mut TestEnv) -> Pubkey {
+    let custody = setup_custody(env).await;
+    let shipment = create_shipment(env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+    let instruction = confirm_ix(&custody.recycler, &shipment, 5_000, 4_000);
+    env.process(&[instruction], &[&custody.recycler]).await.unwrap();
+    processing_receipt_pda(&shipment)
+}
+
+#[tokio::test]
+async fn register_recycler_creates_account() {
+    let mut env = TestEnv::new().await;
+    let recycler = env.wallet().await;
+
+    let result = env.register_recycler(&recycler.pubkey(), &"P".repeat(33)).await;
+    assert_error(result, ErrorCode::RecyclerNameTooLong);
+
+    env.register_recycler(&recycler.pubkey(), "Planta Norte").await.unwrap();
+    let account: Recycler = env.get(&recycler_pda(&recycler.pubkey())).await;
+    assert_eq!(account.recycler, recycler.pubkey());
+    assert_eq!(account.name, "Planta Norte");
+    assert!(account.is_active);
+}
+
+#[tokio::test]
+async fn create_shipment_groups_collections() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+
+    let shipment_key = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+
+    let shipment: Shipment = env.get(&shipment_key).await;
+    assert_eq!(shipment.collector, custody.collector.pubkey());
+    assert_eq!(shipment.recycler, custody.recycler.pubkey());
+    assert_eq!(shipment.collections, custody.collections);
+    assert_eq!(shipment.declared_weight, 5_000);
+    assert_eq!(shipment.status, ShipmentStatus::InTransit);
+
+    for collection in &custody.collections {
+        let record: CollectionRecord = env.get(collection).await;
+        assert!(record.shipped);
+    }
+    let certified: CertifiedCollector = env.get(&collector_pda(&custody.collector.pubkey())).await;
+    assert_eq!(certified.total_shipments, 1);
+}
+
+#[tokio::test]
+async fn create_shipment_validates_collections() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let recycler = custody.recycler.pubkey();
+
+    let result = create_shipment(&mut env, &custody.collector, &recycler, &[]).await;
+    assert_error(result.map(|_| ()), ErrorCode::InvalidShipmentSize);
+
+    let too_many = vec![custody.collections[0]; ecobottle::MAX_SHIPMENT_COLLECTIONS + 1];
+    let result = create_shipment(&mut env, &custody.collector, &recycler, &too_many).await;
+    assert_error(result.map(|_| ()), ErrorCode::InvalidShipmentSize);
+
+    let other = env.wallet().await;
+    env.register_collector(&other.pubkey(), "Otra").await.unwrap();
+    let result = create_shipment(&mut env, &other, &recycler, &custody.collections).await;
+    assert_error(result.map(|_| ()), ErrorCode::CollectionNotOwned);
+
+    create_shipment(&mut env, &custody.collector, &recycler, &custody.collections[..1]).await.unwrap();
+    let result = create_shipment(&mut env, &custody.collector, &recycler, &custody.collections).await;
+    assert_error(result.map(|_| ()), ErrorCode::CollectionAlreadyShipped);
+
+    let cans = collected(&mut env, &custody.collector, "EB-ALU", Material::Aluminum, 1_000).await;
+    let mixed = [custody.collections[1], cans];
+    let result = create_shipment(&mut env, &custody.collector, &recycler, &mixed).await;
+    assert_error(result.map(|_| ()), ErrorCode::MixedShipmentMaterial);
+}
+
+#[tokio::test]
+async fn create_shipment_requires_active_recycler() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let recycler = custody.recycler.pubkey();
+
+    // Solo la autoridad revoca plantas
+    let mut instruction = recycler_status_ix(&env, &recycler, false);
+    instruction.accounts[2].pubkey = custody.recycler.pubkey();
+    assert!(env.process(&[instruction], &[&custody.recycler]).await.is_err());
+
+    env.process(&[recycler_status_ix(&env, &recycler, false)], &[]).await.unwrap();
+    let account: Recycler = env.get(&recycler_pda(&recycler)).await;
+    assert!(!account.is_active);
+
+    let result = create_shipment(&mut env, &custody.collector, &recycler, &custody.collections).await;
+    assert_error(result.map(|_| ()), ErrorCode::RecyclerNotActive);
+
+    env.process(&[recycler_status_ix(&env, &recycler, true)], &[]).await.unwrap();
+    create_shipment(&mut env, &custody.collector, &recycler, &custody.collections).await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_shipment_receipt_requires_active_recycler() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let shipment_key = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+
+    // Una planta revocada tras el envío no certifica la recepción
+    env.process(&[recycler_status_ix(&env, &custody.recycler.pubkey(), false)], &[]).await.unwrap();
+    let instruction = confirm_ix(&custody.recycler, &shipment_key, 4_500, 4_000);
+    let result = env.process(&[instruction], &[&custody.recycler]).await;
+    assert_error(result, ErrorCode::RecyclerNotActive);
+    assert!(!env.exists(&processing_receipt_pda(&shipment_key)).await);
+}
+
+#[tokio::test]
+async fn confirm_shipment_receipt_records_processing() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let shipment_key = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+
+    let instruction = confirm_ix(&custody.recycler, &shipment_key, 4_500, 4_000);
+    env.process(&[instruction], &[&custody.recycler]).await.unwrap();
+
+    let receipt: ProcessingReceipt = env.get(&processing_receipt_pda(&shipment_key)).await;
+    assert_eq!(receipt.shipment, shipment_key);
+    assert_eq!(receipt.received_weight, 4_500);
+    assert_eq!(receipt.discrepancy_bps, 1_000);
+    assert_eq!(receipt.output_product, "rPET-flake");
+    assert_eq!(receipt.output_grams, 4_000);
+    assert!(!receipt.certified);
+
+    let shipment: Shipment = env.get(&shipment_key).await;
+    assert_eq!(shipment.status, ShipmentStatus::Received);
+    let recycler: Recycler = env.get(&recycler_pda(&custody.recycler.pubkey())).await;
+    assert_eq!(recycler.total_received_grams, 4_500);
+    assert_eq!(recycler.total_output_grams, 4_000);
+}
+
+#[tokio::test]
+async fn confirm_shipment_receipt_validates_recycler_and_output() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let shipment_key = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+
+    let intruder = env.wallet().await;
+    env.register_recycler(&intruder.pubkey(), "Planta Sur").await.unwrap();
+    let instruction = confirm_ix(&intruder, &shipment_key, 5_000, 4_000);
+    let result = env.process(&[instruction], &[&intruder]).await;
+    assert_error(result, ErrorCode::WrongRecycler);
+
+    let instruction = confirm_ix(&custody.recycler, &shipment_key, 5_000, 5_001);
+    let result = env.process(&[instruction], &[&custody.recycler]).await;
+    assert_error(result, ErrorCode::InvalidProcessingOutput);
+}
+
+#[tokio::test]
+async fn confirm_shipment_receipt_rejects_received_shipment() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let shipment_key = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+
+    // El recibo de procesamiento es único por envío; un envío marcado como
+    // recibido sin recibo solo puede provenir de datos inconsistentes
+    let mut shipment: Shipment = env.get(&shipment_key).await;
+    shipment.status = ShipmentStatus::Received;
+    env.set_program_account(&shipment_key, &shipment, 8 + Shipment::LEN).await;
+
+    let instruction = confirm_ix(&custody.recycler, &shipment_key, 5_000, 4_000);
+    let result = env.process(&[instruction], &[&custody.recycler]).await;
+    assert_error(result, ErrorCode::ShipmentAlreadyReceived);
+}
+
+#[tokio::test]
+async fn issue_impact_certificate_mints_co2_once() {
+    let mut env = TestEnv::new().await;
+    let processing_receipt = processed_receipt(&mut env).await;
+    init_impact_mint(&mut env).await;
+    let partner = env.wallet().await;
+    let partner_account = env.create_token_account(&impact_mint_pda(), &partner.pubkey()).await;
+
+    let instruction = issue_ix(&env, &processing_receipt, &partner_account);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    // 4 kg de PET × 1.5 kg CO₂e/kg
+    assert_eq!(env.token_balance(&partner_account).await, 6_000);
+    let receipt: ProcessingReceipt = env.get(&processing_receipt).await;
+    assert!(receipt.certified);
+    assert_eq!(receipt.co2_certified_grams, 6_000);
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.impact_mint, impact_mint_pda());
+    assert_eq!(global_state.total_co2_certified_grams, 6_000);
+
+    let instruction = issue_ix(&env, &processing_receipt, &partner_account);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::ImpactAlreadyCertified);
+}
+
+#[tokio::test]
+async fn issue_impact_certificate_requires_processed_material() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let shipment = create_shipment(&mut env, &custody.collector, &custody.recycler.pubkey(), &custody.collections)
+        .await
+        .unwrap();
+    let instruction = confirm_ix(&custody.recycler, &shipment, 5_000, 0);
+    env.process(&[instruction], &[&custody.recycler]).await.unwrap();
+    init_impact_mint(&mut env).await;
+    let partner = env.wallet().await;
+    let partner_account = env.create_token_account(&impact_mint_pda(), &partner.pubkey()).await;
+
+    let instruction = issue_ix(&env, &processing_receipt_pda(&shipment), &partner_account);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::NoImpactToCertify);
+}
+
+#[tokio::test]
+async fn retire_impact_certificate_burns_tokens() {
+    let mut env = TestEnv::new().await;
+    let processing_receipt = processed_receipt(&mut env).await;
+    init_impact_mint(&mut env).await;
+    let partner = env.wallet().await;
+    let partner_account = env.create_token_account(&impact_mint_pda(), &partner.pubkey()).await;
+    let instruction = issue_ix(&env, &processing_receipt, &partner_account);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = retire_ix(&partner, &partner_account, 1_000, &"B".repeat(65));
+    assert_error(env.process(&[instruction], &[&partner]).await, ErrorCode::BeneficiaryTooLong);
+    let instruction = retire_ix(&partner, &partner_account, 0, "Cervecería Andina");
+    assert_error(env.process(&[instruction], &[&partner]).await, ErrorCode::NoImpactToCertify);
+
+    let instruction = retire_ix(&partner, &partner_account, 2_500, "Cervecería Andina");
+    env.process(&[instruction], &[&partner]).await.unwrap();
+
+    assert_eq!(env.token_balance(&partner_account).await, 3_500);
+    assert_eq!(env.mint_supply(&impact_mint_pda()).await, 3_500);
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_co2_retired_grams, 2_500);
+}
+
+#[tokio::test]
+async fn issue_impact_certificate_uses_shipment_material_and_verified_receipt() {
+    let mut env = TestEnv::new().await;
+    let custody = setup_custody(&mut env).await;
+    let recycler = custody.recycler.pubkey();
+    init_impact_mint(&mut env).await;
+    let partner = env.wallet().await;
+    let partner_account = env.create_token_account(&impact_mint_pda(), &partner.pubkey()).await;
+
+    // La planta recibe 1.5 kg de 2 kg declarados: 25% de discrepancia
+    let cans = collected(&mut env, &custody.collector, "EB-ALU1", Material::Aluminum, 2_000).await;
+    let shipment = create_shipment(&mut env, &custody.collector, &recycler, &[cans]).await.unwrap();
+    let instruction = confirm_ix(&custody.recycler, &shipment, 1_500, 1_000);
+    env.process(&[instruction], &[&custody.recycler]).await.unwrap();
+    let instruction = issue_ix(&env, &processing_receipt_pda(&shipment), &partner_account);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::ProcessingDiscrepancy);
+
+    let cans = collected(&mut env, &custody.collector, "EB-ALU2", Material::Aluminum, 2_000).await;
+    let shipment = create_shipment(&mut env, &custody.collector, &recycler, &[cans]).await.unwrap();
+    let shipped: Shipment = env.get(&shipment).await;
+    assert_eq!(shipped.material, Material::Aluminum);
+    let instruction = confirm_ix(&custody.recycler, &shipment, 2_000, 1_000);
+    env.process(&[instruction], &[&custody.recycler]).await.unwrap();
+    let instruction = issue_ix(&env, &processing_receipt_pda(&shipment), &partner_account);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    // 1 kg de aluminio × 9 kg CO₂e/kg
+    let receipt: ProcessingReceipt = env.get(&processing_receipt_pda(&shipment)).await;
+    assert_eq!(receipt.material, Material::Aluminum);
+    assert_eq!(receipt.co2_certified_grams, 9_000);
+    assert_eq!(env.token_balance(&partner_account).await, 9_000);
+}
@@ -0,0 +1,613 @@
+//! Contenedores, depósitos por peso, referidos y canjes
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{DepositRecord, ErrorCode, GlobalState, Material, RedemptionRecord, SmartContainer, UserProfile};
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn redeem_ix(env: &TestEnv, user: &User, total_spent: u64, amount: u64, product_id: &str) -> Instruction {
+    let accounts = ecobottle::accounts::RedeemTokens {
+        user_profile: user.profile,
+        redemption_record: redemption_pda(&user.key(), total_spent),
+        user: user.key(),
+        user_token_account: user.token_account,
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::RedeemTokens {
+        amount,
+        product_id: product_id.to_string(),
+    };
+    ix(accounts, data)
+}
+
+fn fill_threshold_ix(env: &TestEnv, container: &Pubkey, fill_threshold_bps: u16) -> Instruction {
+    let accounts = ecobottle::accounts::ToggleContainerStatus {
+        container: *container,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::SetFillThreshold { fill_threshold_bps })
+}
+
+#[tokio::test]
+async fn register_container_creates_account() {
+    let mut env = TestEnv::new().await;
+    let container = env.register_container("EB-001", 50, Material::Aluminum).await.unwrap();
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.container_id, "EB-001");
+    assert_eq!(state.capacity_kg, 50);
+    assert_eq!(state.material, Material::Aluminum);
+    assert!(state.is_active);
+    assert_eq!(state.fill_threshold_bps, ecobottle::DEFAULT_FILL_THRESHOLD_BPS);
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_containers, 1);
+}
+
+#[tokio::test]
+async fn register_container_validates_lengths() {
+    let mut env = TestEnv::new().await;
+
+    let accounts = ecobottle::accounts::RegisterContainer {
+        container: container_pda("EB-001"),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::RegisterContainer {
+        container_id: "EB-001".to_string(),
+        location: "L".repeat(65),
+        capacity_kg: 50,
+        material: Material::Pet,
+    };
+    let result = env.process(&[ix(accounts, data)], &[]).await;
+    assert_error(result, ErrorCode::LocationTooLong);
+
+    // Un ID de más de 32 bytes no puede ser semilla del PDA: Anchor lo rechaza
+    // al derivar la cuenta, antes de que `ContainerIdTooLong` llegue a evaluarse
+    let accounts = ecobottle::accounts::RegisterContainer {
+        container: Pubkey::new_unique(),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::RegisterContainer {
+        container_id: "X".repeat(33),
+        location: "Plaza Mayor".to_string(),
+        capacity_kg: 50,
+        material: Material::Pet,
+    };
+    let result = env.process(&[ix(accounts, data)], &[]).await;
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(1, InstructionError::ProgramFailedToComplete))
+    );
+}
+
+#[tokio::test]
+async fn toggle_container_status_flips_activity() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let instruction = env.toggle_container_ix(&container);
+    env.process(&[instruction], &[]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert!(!state.is_active);
+
+    let instruction = env.toggle_container_ix(&container);
+    env.process(&[instruction], &[]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert!(state.is_active);
+}
+
+#[tokio::test]
+async fn set_fill_threshold_validates_range() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let instruction = fill_threshold_ix(&env, &container, 5_000);
+    env.process(&[instruction], &[]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.fill_threshold_bps, 5_000);
+
+    let instruction = fill_threshold_ix(&env, &container, 0);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidFillThreshold);
+
+    let instruction = fill_threshold_ix(&env, &container, 10_001);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidFillThreshold);
+}
+
+#[tokio::test]
+async fn deposit_mints_reward_and_records_deposit() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let record = env.deposit(&user, &container, 2_000).await.unwrap();
+
+    // Reputación inicial = multiplicador 1.0x
+    assert_eq!(env.token_balance(&user.token_account).await, 2 * ECOC_PER_KG);
+
+    let deposit: DepositRecord = env.get(&record).await;
+    assert_eq!(deposit.user, user.key());
+    assert_eq!(deposit.container, container);
+    assert_eq!(deposit.weight_grams, 2_000);
+    assert_eq!(deposit.ecoc_reward, 2 * ECOC_PER_KG);
+    assert_eq!(deposit.payer, user.key());
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.total_deposits, 1);
+    assert_eq!(profile.total_pet_weight, 2_000);
+    assert_eq!(profile.current_streak, 1);
+    assert_eq!(profile.co2_avoided_grams, 3_000);
+    assert!(profile.reputation_score > ecobottle::REPUTATION_INITIAL);
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 2_000);
+    assert_eq!(state.total_deposits, 1);
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_pet_collected, 2_000);
+    assert_eq!(global_state.total_deposits, 1);
+    assert_eq!(global_state.total_co2_avoided_grams, 3_000);
+}
+
+#[tokio::test]
+async fn relayer_sponsors_registration_and_deposit() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let relayer = env.authority.pubkey();
+
+    // Wallet sin SOL: solo firma, la renta y las comisiones las paga el relayer
+    let wallet = Keypair::new();
+    let mut accounts = env.register_user_accounts(&wallet.pubkey(), "ana");
+    accounts.payer = relayer;
+    let data = ecobottle::instruction::RegisterUser {
+        username: "ana".to_string(),
+        referrer: None,
+    };
+    env.process(&[ix(accounts, data)], &[&wallet]).await.unwrap();
+
+    let ecoc_mint = env.ecoc_mint;
+    let user = User {
+        token_account: env.create_token_account(&ecoc_mint, &wallet.pubkey()).await,
+        profile: user_profile_pda(&wallet.pubkey()),
+        wallet,
+    };
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.payer = relayer;
+    let record = accounts.deposit_record.unwrap();
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+
+    assert_eq!(env.lamports(&user.key()).await, 0);
+    assert_eq!(env.token_balance(&user.token_account).await, ECOC_PER_KG);
+    let deposit: DepositRecord = env.get(&record).await;
+    assert_eq!(deposit.payer, relayer);
+
+    // El relayer no puede depositar sin la firma del usuario
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.payer = relayer;
+    let receipt_hash = env.next_receipt();
+    let data = ecobottle::instruction::ProcessDeposit {
+        weight_grams: 1_000,
+        receipt_hash,
+        receipt_sequence: receipt_sequence(&receipt_hash),
+    };
+    let mut instruction = ix(accounts, data);
+    for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == user.key()) {
+        meta.is_signer = false;
+    }
+    assert!(env.process(&[instruction], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn deposit_raises_fill_alert_once() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+
+    env.deposit(&user, &container, 500).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert!(!state.needs_collection);
+
+    env.deposit(&user, &container, 300).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert!(state.needs_collection);
+    assert_eq!(state.fill_level_bps(), 8_000);
+}
+
+#[tokio::test]
+async fn deposit_validates_weight_and_container() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+
+    let result = env.deposit(&user, &container, MIN_DEPOSIT_WEIGHT - 1).await;
+    assert_error(result.map(|_| ()), ErrorCode::WeightTooLow);
+
+    let result = env.deposit(&user, &container, 1_001).await;
+    assert_error(result.map(|_| ()), ErrorCode::ContainerFull);
+
+    let instruction = env.toggle_container_ix(&container);
+    env.process(&[instruction], &[]).await.unwrap();
+    let result = env.deposit(&user, &container, 500).await;
+    assert_error(result.map(|_| ()), ErrorCode::ContainerInactive);
+}
+
+#[tokio::test]
+async fn deposit_rejects_suspended_user() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = ecobottle::accounts::SetUserSuspension {
+        user_profile: user.profile,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    let instruction = ix(accounts, ecobottle::instruction::SetUserSuspension { suspended: true });
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let result = env.deposit(&user, &container, 1_000).await;
+    assert_error(result.map(|_| ()), ErrorCode::UserSuspended);
+}
+
+#[tokio::test]
+async fn deposit_requires_fresh_receipt() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let result = env.deposit_with(&user, accounts, 1_000, [0; 32], vec![]).await;
+    assert_error(result, ErrorCode::MissingReceipt);
+
+    let accounts = env.deposit_accounts(&user, &container).await;
+    env.deposit_with(&user, accounts, 1_000, receipt(1_000), vec![]).await.unwrap();
+
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let result = env.deposit_with(&user, accounts, 1_200, receipt(1_000), vec![]).await;
+    assert_error(result, ErrorCode::DuplicateReceipt);
+}
+
+#[tokio::test]
+async fn deposit_rejects_replayed_older_receipt() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    // A, B y de nuevo A: A ya no es el último recibo pero su secuencia quedó atrás
+    for n in [1_000, 1_001] {
+        let accounts = env.deposit_accounts(&user, &container).await;
+        env.deposit_with(&user, accounts, 1_000, receipt(n), vec![]).await.unwrap();
+    }
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let result = env.deposit_with(&user, accounts, 1_000, receipt(1_000), vec![]).await;
+    assert_error(result, ErrorCode::StaleReceipt);
+
+    // Un recibo nuevo con una secuencia ya usada tampoco se acepta
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let data = ecobottle::instruction::ProcessDeposit {
+        weight_grams: 1_000,
+        receipt_hash: receipt(2_000),
+        receipt_sequence: 1_001,
+    };
+    let result = env.process(&[ix(accounts, data)], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::StaleReceipt);
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.last_receipt_sequence, 1_001);
+    assert_eq!(state.total_deposits, 2);
+}
+
+#[tokio::test]
+async fn deposit_caps_receipt_sequence_gap() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.deposit_accounts(&user, &container).await;
+    env.deposit_with(&user, accounts, 1_000, receipt(1), vec![]).await.unwrap();
+
+    // Un salto mayor al permitido agotaría las secuencias del contenedor
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let far = receipt(2 + ecobottle::MAX_RECEIPT_SEQUENCE_GAP);
+    let result = env.deposit_with(&user, accounts, 1_000, far, vec![]).await;
+    assert_error(result, ErrorCode::ReceiptSequenceGap);
+
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let next = receipt(1 + ecobottle::MAX_RECEIPT_SEQUENCE_GAP);
+    env.deposit_with(&user, accounts, 1_000, next, vec![]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.last_receipt_sequence, 1 + ecobottle::MAX_RECEIPT_SEQUENCE_GAP);
+}
+
+#[tokio::test]
+async fn deposit_requires_container_operator_signature() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.wallet().await;
+    let mut state: SmartContainer = env.get(&container).await;
+    state.authority = operator.pubkey();
+    env.set_program_account(&container, &state, 8 + SmartContainer::LEN).await;
+
+    // El usuario solo no puede declarar el peso ni el recibo
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.container_authority = operator.pubkey();
+    let receipt_hash = env.next_receipt();
+    let data = ecobottle::instruction::ProcessDeposit {
+        weight_grams: 1_000,
+        receipt_hash,
+        receipt_sequence: receipt_sequence(&receipt_hash),
+    };
+    let mut instruction = ix(accounts, data);
+    for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == operator.pubkey()) {
+        meta.is_signer = false;
+    }
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    let code = anchor_lang::error::ErrorCode::AccountNotSigner as u32;
+    assert!(matches!(result, Err(TransactionError::InstructionError(_, InstructionError::Custom(c))) if c == code));
+
+    // Ni con la firma de otra cuenta
+    let accounts = env.deposit_accounts(&user, &container).await;
+    let result = env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await;
+    let code = anchor_lang::error::ErrorCode::ConstraintAddress as u32;
+    assert!(matches!(result, Err(TransactionError::InstructionError(_, InstructionError::Custom(c))) if c == code));
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.container_authority = operator.pubkey();
+    let data = ecobottle::instruction::ProcessDeposit {
+        weight_grams: 1_000,
+        receipt_hash,
+        receipt_sequence: receipt_sequence(&receipt_hash),
+    };
+    env.process(&[ix(accounts, data)], &[&user.wallet, &operator]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.total_deposits, 1);
+}
+
+#[tokio::test]
+async fn deposit_requires_exactly_one_log_mode() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.deposit_record = None;
+    let receipt_hash = env.next_receipt();
+    let result = env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await;
+    assert_error(result, ErrorCode::InvalidDepositLogMode);
+}
+
+#[tokio::test]
+async fn deposit_pays_referral_bonus_up_to_limits() {
+    let mut env = TestEnv::new().await;
+    let referrer = env.new_user("ana").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: Some(500_000),
+        new_max_grams: Some(1_500),
+        new_cap_per_referrer: Some(10_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    assert_eq!(env.token_balance(&referrer.token_account).await, 500_000);
+
+    // Solo quedan 500 g con bono
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    assert_eq!(env.token_balance(&referrer.token_account).await, 750_000);
+
+    let referrer_profile: UserProfile = env.get(&referrer.profile).await;
+    assert_eq!(referrer_profile.referral_earnings, 750_000);
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referred_weight_grams, 1_500);
+
+    // Agotado el bono ya no se exigen las cuentas del referente
+    env.deposit(&user, &container, 1_000).await.unwrap();
+}
+
+#[tokio::test]
+async fn referral_cap_disabled_or_exhausted_skips_referrer_accounts() {
+    let mut env = TestEnv::new().await;
+    let referrer = env.new_user("ana").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+    let container = env.new_container("EB-001", 50).await;
+
+    // Tope 0: bono deshabilitado, sin cuentas del referente ni consumo
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: Some(500_000),
+        new_max_grams: Some(10_000),
+        new_cap_per_referrer: Some(0),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    env.deposit(&user, &container, 1_000).await.unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referred_weight_grams, 0);
+
+    // Tope de 250_000: el primer depósito lo agota
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: None,
+        new_max_grams: None,
+        new_cap_per_referrer: Some(250_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    assert_eq!(env.token_balance(&referrer.token_account).await, 250_000);
+
+    // Tope agotado: no consume el peso con bono y lo recuerda en el perfil
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referred_weight_grams, 1_000);
+    assert_eq!(profile.referral_cap_exhausted_at, 250_000);
+
+    env.deposit(&user, &container, 1_000).await.unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referred_weight_grams, 1_000);
+    assert_eq!(env.token_balance(&referrer.token_account).await, 250_000);
+
+    // Si la autoridad sube el tope vuelven a exigirse
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: None,
+        new_max_grams: None,
+        new_cap_per_referrer: Some(500_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+    let result = env.deposit(&user, &container, 1_000).await;
+    assert_error(result.map(|_| ()), ErrorCode::MissingReferrerAccounts);
+}
+
+#[tokio::test]
+async fn deposit_requires_matching_referrer_accounts() {
+    let mut env = TestEnv::new().await;
+    let referrer = env.new_user("ana").await;
+    let other = env.new_user("carla").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: Some(500_000),
+        new_max_grams: Some(10_000),
+        new_cap_per_referrer: Some(10_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let result = env.deposit(&user, &container, 1_000).await;
+    assert_error(result.map(|_| ()), ErrorCode::MissingReferrerAccounts);
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(other.profile);
+    accounts.referrer_token_account = Some(other.token_account);
+    let receipt_hash = env.next_receipt();
+    let result = env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await;
+    assert_error(result, ErrorCode::ReferrerMismatch);
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(other.token_account);
+    let receipt_hash = env.next_receipt();
+    let result = env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await;
+    assert_error(result, ErrorCode::ReferrerMismatch);
+}
+
+#[tokio::test]
+async fn update_config_changes_reward_rate() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    env.update_config(ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: Some(2 * ECOC_PER_KG),
+        new_min_weight: Some(500),
+        new_discrepancy_tolerance_bps: Some(500),
+        new_record_retention_secs: Some(60),
+        new_collection_sla_secs: Some(3_600),
+    })
+    .await;
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.ecoc_per_kg, 2 * ECOC_PER_KG);
+    assert_eq!(global_state.min_deposit_weight, 500);
+    assert_eq!(global_state.discrepancy_tolerance_bps, 500);
+    assert_eq!(global_state.record_retention_secs, 60);
+    assert_eq!(global_state.collection_sla_secs, 3_600);
+
+    let result = env.deposit(&user, &container, 400).await;
+    assert_error(result.map(|_| ()), ErrorCode::WeightTooLow);
+
+    env.deposit(&user, &container, 1_000).await.unwrap();
+    assert_eq!(env.token_balance(&user.token_account).await, 2 * ECOC_PER_KG);
+}
+
+#[tokio::test]
+async fn update_emission_factor_applies_to_material() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.register_container("EB-LATAS", 50, Material::Aluminum).await.unwrap();
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateEmissionFactor {
+        material: Material::Aluminum,
+        grams_co2e_per_kg: 10_000,
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.emission_factors[Material::Aluminum as usize], 10_000);
+    assert_eq!(global_state.emission_factors[Material::Pet as usize], ecobottle::DEFAULT_EMISSION_FACTORS[0]);
+
+    env.deposit(&user, &container, 500).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.co2_avoided_grams, 5_000);
+}
+
+#[tokio::test]
+async fn redeem_burns_tokens_and_records_redemption() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 2_000).await.unwrap();
+
+    let instruction = redeem_ix(&env, &user, 0, 500_000, "cafe-01");
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    assert_eq!(env.token_balance(&user.token_account).await, 1_500_000);
+    assert_eq!(env.mint_supply(&env.ecoc_mint.clone()).await, 1_500_000);
+
+    let redemption: RedemptionRecord = env.get(&redemption_pda(&user.key(), 0)).await;
+    assert_eq!(redemption.amount, 500_000);
+    assert_eq!(redemption.product_id, "cafe-01");
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.total_ecoc_spent, 500_000);
+}
+
+#[tokio::test]
+async fn redeem_validates_product_and_balance() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 1_000).await.unwrap();
+
+    let instruction = redeem_ix(&env, &user, 0, 100, &"p".repeat(33));
+    assert_error(env.process(&[instruction], &[&user.wallet]).await, ErrorCode::ProductIdTooLong);
+
+    let instruction = redeem_ix(&env, &user, 0, ECOC_PER_KG + 1, "cafe-01");
+    assert_error(env.process(&[instruction], &[&user.wallet]).await, ErrorCode::InsufficientTokens);
+}
@@ -0,0 +1,293 @@
+//! Marcas, depósitos identificados por marca y créditos EPR
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{Brand, EprState, ErrorCode, Material};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+/// 2 SOL por kg recuperado
+const PRICE_PER_KG: u64 = 2_000_000_000;
+
+fn epr_state_pda() -> Pubkey {
+    pda(&[b"epr_state"])
+}
+
+fn epr_mint_pda() -> Pubkey {
+    pda(&[b"epr_mint"])
+}
+
+fn epr_vault_pda() -> Pubkey {
+    pda(&[b"epr_vault"])
+}
+
+fn brand_status_ix(env: &TestEnv, brand: &Pubkey, is_active: bool) -> Instruction {
+    let accounts = ecobottle::accounts::SetBrandStatus {
+        brand: *brand,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::SetBrandStatus { is_active })
+}
+
+fn buy_ix(owner: &Keypair, brand_token_account: &Pubkey, treasury: &Pubkey, amount_grams: u64, material: Material) -> Instruction {
+    let accounts = ecobottle::accounts::BuyEprCredits {
+        brand: brand_pda(&owner.pubkey()),
+        epr_state: epr_state_pda(),
+        global_state: global_state_pda(),
+        epr_mint: epr_mint_pda(),
+        epr_vault: epr_vault_pda(),
+        brand_token_account: *brand_token_account,
+        treasury: *treasury,
+        brand_owner: owner.pubkey(),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::BuyEprCredits { amount_grams, material })
+}
+
+fn mint_ix(owner: &Pubkey, material: Material) -> Instruction {
+    let accounts = ecobottle::accounts::MintEprCredits {
+        brand: brand_pda(owner),
+        epr_state: epr_state_pda(),
+        global_state: global_state_pda(),
+        epr_mint: epr_mint_pda(),
+        epr_vault: epr_vault_pda(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::MintEprCredits { material })
+}
+
+fn retire_ix(owner: &Keypair, brand_token_account: &Pubkey, amount_grams: u64) -> Instruction {
+    let accounts = ecobottle::accounts::RetireEprCredits {
+        brand: brand_pda(&owner.pubkey()),
+        epr_state: epr_state_pda(),
+        epr_mint: epr_mint_pda(),
+        brand_token_account: *brand_token_account,
+        brand_owner: owner.pubkey(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::RetireEprCredits { amount_grams })
+}
+
+async fn init_epr(env: &mut TestEnv, treasury: &Pubkey) {
+    let accounts = ecobottle::accounts::InitEprCredits {
+        epr_state: epr_state_pda(),
+        epr_mint: epr_mint_pda(),
+        epr_vault: epr_vault_pda(),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::InitEprCredits {
+        price_per_kg: PRICE_PER_KG,
+        treasury: *treasury,
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+}
+
+/// Depósito de PET identificado por la marca y atestado por el operador del contenedor
+async fn branded_deposit(
+    env: &mut TestEnv,
+    user: &User,
+    container: &Pubkey,
+    brand: &Pubkey,
+    weight_grams: u64,
+) -> Result<(), TransactionError> {
+    let mut accounts = env.deposit_accounts(user, container).await;
+    accounts.brand = Some(*brand);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(user, accounts, weight_grams, receipt_hash, vec![]).await
+}
+
+/// Marca registrada con su token account de créditos y 2 kg de PET de la marca
+/// recuperados y emitidos en créditos (más 3 kg sin marca, que no los respaldan)
+async fn setup_epr(env: &mut TestEnv) -> (Keypair, Pubkey, Pubkey) {
+    let treasury = Pubkey::new_unique();
+    init_epr(env, &treasury).await;
+    let owner = env.wallet().await;
+    let brand_key = env.register_brand(&owner.pubkey(), "Aguas del Sur").await.unwrap();
+    let brand_token_account = env.create_token_account(&epr_mint_pda(), &owner.pubkey()).await;
+
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    branded_deposit(env, &user, &container, &brand_key, 2_000).await.unwrap();
+    env.deposit(&user, &container, 3_000).await.unwrap();
+    env.process(&[mint_ix(&owner.pubkey(), Material::Pet)], &[]).await.unwrap();
+
+    (owner, brand_token_account, treasury)
+}
+
+#[tokio::test]
+async fn register_brand_creates_account() {
+    let mut env = TestEnv::new().await;
+    let owner = env.wallet().await;
+
+    let result = env.register_brand(&owner.pubkey(), &"M".repeat(33)).await;
+    assert_error(result.map(|_| ()), ErrorCode::BrandNameTooLong);
+
+    let brand_key = env.register_brand(&owner.pubkey(), "Aguas del Sur").await.unwrap();
+    let brand: Brand = env.get(&brand_key).await;
+    assert_eq!(brand.owner, owner.pubkey());
+    assert_eq!(brand.name, "Aguas del Sur");
+    assert!(brand.is_active);
+
+    let instruction = brand_status_ix(&env, &brand_key, false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let brand: Brand = env.get(&brand_key).await;
+    assert!(!brand.is_active);
+}
+
+#[tokio::test]
+async fn branded_deposit_credits_recovered_weight() {
+    let mut env = TestEnv::new().await;
+    let owner = env.wallet().await;
+    let brand_key = env.register_brand(&owner.pubkey(), "Aguas del Sur").await.unwrap();
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    branded_deposit(&mut env, &user, &container, &brand_key, 1_500).await.unwrap();
+
+    let brand: Brand = env.get(&brand_key).await;
+    assert_eq!(brand.recovered_grams, 1_500);
+    assert_eq!(brand.recovered_by_material[Material::Pet as usize], 1_500);
+    assert_eq!(brand.tagged_deposits, 1);
+
+    let instruction = brand_status_ix(&env, &brand_key, false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let result = branded_deposit(&mut env, &user, &container, &brand_key, 1_500).await;
+    assert_error(result, ErrorCode::BrandNotActive);
+}
+
+#[tokio::test]
+async fn update_epr_config_changes_price_and_treasury() {
+    let mut env = TestEnv::new().await;
+    init_epr(&mut env, &Pubkey::new_unique()).await;
+    let treasury = Pubkey::new_unique();
+
+    let accounts = ecobottle::accounts::UpdateEprConfig {
+        epr_state: epr_state_pda(),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    let data = ecobottle::instruction::UpdateEprConfig {
+        new_price_per_kg: Some(5),
+        new_treasury: Some(treasury),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let epr_state: EprState = env.get(&epr_state_pda()).await;
+    assert_eq!(epr_state.price_per_kg, 5);
+    assert_eq!(epr_state.treasury, treasury);
+    assert_eq!(epr_state.epr_mint, epr_mint_pda());
+}
+
+#[tokio::test]
+async fn mint_epr_credits_issues_recovered_weight_to_vault() {
+    let mut env = TestEnv::new().await;
+    let treasury = Pubkey::new_unique();
+    init_epr(&mut env, &treasury).await;
+    let owner = env.wallet().await;
+    let brand_key = env.register_brand(&owner.pubkey(), "Aguas del Sur").await.unwrap();
+    let brand_token_account = env.create_token_account(&epr_mint_pda(), &owner.pubkey()).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    // Sin envases recuperados no hay créditos que emitir ni comprar
+    let result = env.process(&[mint_ix(&owner.pubkey(), Material::Pet)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientEprBacking);
+
+    // Recuperados pero aún no emitidos: no se pueden comprar
+    branded_deposit(&mut env, &user, &container, &brand_key, 2_000).await.unwrap();
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 1, Material::Pet);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::InsufficientEprBacking);
+
+    env.process(&[mint_ix(&owner.pubkey(), Material::Pet)], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&epr_vault_pda()).await, 2_000);
+    assert_eq!(env.mint_supply(&epr_mint_pda()).await, 2_000);
+    let epr_state: EprState = env.get(&epr_state_pda()).await;
+    assert_eq!(epr_state.total_minted_grams, 2_000);
+    let brand: Brand = env.get(&brand_key).await;
+    assert_eq!(brand.minted_by_material[Material::Pet as usize], 2_000);
+
+    // Lo ya emitido no se vuelve a emitir; solo lo recuperado después
+    let result = env.process(&[mint_ix(&owner.pubkey(), Material::Pet)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientEprBacking);
+    branded_deposit(&mut env, &user, &container, &brand_key, 500).await.unwrap();
+    env.process(&[mint_ix(&owner.pubkey(), Material::Pet)], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&epr_vault_pda()).await, 2_500);
+}
+
+#[tokio::test]
+async fn buy_epr_credits_pays_treasury_and_transfers_backed_credits() {
+    let mut env = TestEnv::new().await;
+    let (owner, brand_token_account, treasury) = setup_epr(&mut env).await;
+
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 1_500, Material::Pet);
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    assert_eq!(env.lamports(&treasury).await, 3_000_000_000);
+    assert_eq!(env.token_balance(&brand_token_account).await, 1_500);
+    assert_eq!(env.token_balance(&epr_vault_pda()).await, 500);
+    let epr_state: EprState = env.get(&epr_state_pda()).await;
+    assert_eq!(epr_state.total_minted_grams, 2_000);
+    let brand: Brand = env.get(&brand_pda(&owner.pubkey())).await;
+    assert_eq!(brand.credits_purchased, 1_500);
+    assert_eq!(brand.credits_by_material[Material::Pet as usize], 1_500);
+
+    // Solo quedan 500 g de PET de la marca sin respaldar créditos; los depósitos
+    // sin marca no cuentan
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 501, Material::Pet);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::InsufficientEprBacking);
+
+    // Ni los de otro material
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 1, Material::Aluminum);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::InsufficientEprBacking);
+}
+
+#[tokio::test]
+async fn buy_epr_credits_validates_amount_and_brand() {
+    let mut env = TestEnv::new().await;
+    let (owner, brand_token_account, treasury) = setup_epr(&mut env).await;
+
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 0, Material::Pet);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::InvalidEprAmount);
+
+    let instruction = brand_status_ix(&env, &brand_pda(&owner.pubkey()), false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 1_000, Material::Pet);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::BrandNotActive);
+}
+
+#[tokio::test]
+async fn retire_epr_credits_burns_and_records() {
+    let mut env = TestEnv::new().await;
+    let (owner, brand_token_account, treasury) = setup_epr(&mut env).await;
+    let instruction = buy_ix(&owner, &brand_token_account, &treasury, 2_000, Material::Pet);
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    let instruction = retire_ix(&owner, &brand_token_account, 0);
+    let result = env.process(&[instruction], &[&owner]).await;
+    assert_error(result, ErrorCode::InvalidEprAmount);
+
+    let instruction = retire_ix(&owner, &brand_token_account, 1_200);
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    assert_eq!(env.token_balance(&brand_token_account).await, 800);
+    assert_eq!(env.mint_supply(&epr_mint_pda()).await, 800);
+    let epr_state: EprState = env.get(&epr_state_pda()).await;
+    assert_eq!(epr_state.total_retired_grams, 1_200);
+    let brand: Brand = env.get(&brand_pda(&owner.pubkey())).await;
+    assert_eq!(brand.credits_retired, 1_200);
+}
@@ -0,0 +1,273 @@
+//! Depósitos de invitados con recompensa en custodia hasta su reclamo
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{ErrorCode, GlobalState, GuestDeposit, SmartContainer, UserProfile, GUEST_CLAIM_WINDOW_SECS};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn guest_deposit_pda(claim_code: &[u8; 32]) -> Pubkey {
+    pda(&[b"guest_deposit", ecobottle::guest_claim_commitment(claim_code).as_ref()])
+}
+
+fn guest_escrow_pda(guest_deposit: &Pubkey) -> Pubkey {
+    pda(&[b"guest_escrow", guest_deposit.as_ref()])
+}
+
+/// Depósito de invitado firmado por `operator` para el código `claim_code`
+fn guest_deposit_ix(env: &TestEnv, operator: &Keypair, container: &Pubkey, claim_code: &[u8; 32], weight_grams: u64, n: u64) -> Instruction {
+    let guest_deposit = guest_deposit_pda(claim_code);
+    let accounts = ecobottle::accounts::ProcessGuestDeposit {
+        global_state: global_state_pda(),
+        container: *container,
+        container_authority: operator.pubkey(),
+        payer: operator.pubkey(),
+        guest_deposit,
+        escrow: guest_escrow_pda(&guest_deposit),
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+        system_program: anchor_lang::system_program::ID,
+    };
+    let data = ecobottle::instruction::ProcessGuestDeposit {
+        claim_commitment: ecobottle::guest_claim_commitment(claim_code),
+        weight_grams,
+        receipt_hash: receipt(n),
+        receipt_sequence: n,
+    };
+    ix(accounts, data)
+}
+
+fn guest_claim_pda(guest_deposit: &Pubkey, claimer: &Pubkey) -> Pubkey {
+    pda(&[b"guest_claim", guest_deposit.as_ref(), claimer.as_ref()])
+}
+
+/// Compromiso de `claimer` con `claim_code` (primer paso del reclamo)
+fn commit_ix(claimer: &User, guest_deposit: &Pubkey, claim_code: &[u8; 32]) -> Instruction {
+    let accounts = ecobottle::accounts::CommitGuestClaim {
+        guest_deposit: *guest_deposit,
+        claim_intent: guest_claim_pda(guest_deposit, &claimer.key()),
+        claimer: claimer.key(),
+        system_program: anchor_lang::system_program::ID,
+    };
+    let binding = ecobottle::guest_claim_binding(claim_code, &claimer.key());
+    ix(accounts, ecobottle::instruction::CommitGuestClaim { binding })
+}
+
+/// Compromete el reclamo de `claimer` y pasa al slot siguiente
+async fn commit(env: &mut TestEnv, claimer: &User, guest_deposit: &Pubkey, claim_code: &[u8; 32]) {
+    let instruction = commit_ix(claimer, guest_deposit, claim_code);
+    env.process(&[instruction], &[&claimer.wallet]).await.unwrap();
+    env.next_slot().await;
+}
+
+fn claim_ix(env: &TestEnv, claimer: &User, guest_deposit: &Pubkey, claim_code: [u8; 32], with_profile: bool) -> Instruction {
+    let accounts = ecobottle::accounts::ClaimGuestDeposit {
+        global_state: global_state_pda(),
+        guest_deposit: *guest_deposit,
+        escrow: guest_escrow_pda(guest_deposit),
+        claim_intent: guest_claim_pda(guest_deposit, &claimer.key()),
+        claimer: claimer.key(),
+        claimer_token_account: claimer.token_account,
+        user_profile: with_profile.then_some(claimer.profile),
+        payer: env.authority.pubkey(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::ClaimGuestDeposit { claim_code })
+}
+
+fn expire_ix(env: &TestEnv, guest_deposit: &Pubkey, treasury_token_account: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::ExpireGuestDeposit {
+        global_state: global_state_pda(),
+        guest_deposit: *guest_deposit,
+        escrow: guest_escrow_pda(guest_deposit),
+        treasury_token_account: *treasury_token_account,
+        payer: env.authority.pubkey(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::ExpireGuestDeposit {})
+}
+
+/// Cierre del compromiso de `claimer`; no necesita su firma
+fn close_intent_ix(claimer: &User, guest_deposit: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::CloseGuestClaimIntent {
+        claim_intent: guest_claim_pda(guest_deposit, &claimer.key()),
+        guest_deposit: *guest_deposit,
+        claimer: claimer.key(),
+    };
+    ix(accounts, ecobottle::instruction::CloseGuestClaimIntent {})
+}
+
+#[tokio::test]
+async fn guest_deposit_is_escrowed_until_claimed() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+    let claim_code = [7; 32];
+
+    let instruction = guest_deposit_ix(&env, &operator, &container, &claim_code, 2_000, 1);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let guest_deposit = guest_deposit_pda(&claim_code);
+    let escrow = guest_escrow_pda(&guest_deposit);
+    assert_eq!(env.token_balance(&escrow).await, 2 * ECOC_PER_KG);
+    let record: GuestDeposit = env.get(&guest_deposit).await;
+    assert_eq!(record.expires_at, record.timestamp + GUEST_CLAIM_WINDOW_SECS);
+    assert_eq!(record.payer, operator.pubkey());
+
+    // El envase cuenta para el contenedor y el total global desde el depósito
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 2_000);
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_deposits, 1);
+
+    // Un código equivocado no libera la bóveda
+    let ana = env.new_user("ana").await;
+    commit(&mut env, &ana, &guest_deposit, &claim_code).await;
+    let instruction = claim_ix(&env, &ana, &guest_deposit, [8; 32], true);
+    assert_error(env.process(&[instruction], &[&ana.wallet]).await, ErrorCode::InvalidClaimCode);
+
+    let instruction = claim_ix(&env, &ana, &guest_deposit, claim_code, true);
+    env.process(&[instruction], &[&ana.wallet]).await.unwrap();
+
+    assert_eq!(env.token_balance(&ana.token_account).await, 2 * ECOC_PER_KG);
+    let profile: UserProfile = env.get(&ana.profile).await;
+    assert_eq!(profile.total_deposits, 1);
+    assert_eq!(profile.total_pet_weight, 2_000);
+    assert_eq!(profile.total_ecoc_earned, 2 * ECOC_PER_KG);
+    assert_eq!(profile.current_streak, 0);
+    assert!(!env.exists(&guest_deposit).await);
+    assert!(!env.exists(&escrow).await);
+    assert!(!env.exists(&guest_claim_pda(&guest_deposit, &ana.key())).await);
+}
+
+#[tokio::test]
+async fn observed_claim_code_is_bound_to_its_claimer() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+    let claim_code = [7; 32];
+    let instruction = guest_deposit_ix(&env, &operator, &container, &claim_code, 2_000, 1);
+    env.process(&[instruction], &[]).await.unwrap();
+    let guest_deposit = guest_deposit_pda(&claim_code);
+
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    commit(&mut env, &ana, &guest_deposit, &claim_code).await;
+
+    // Beto ve el código en el reclamo de Ana: sin compromiso propio no puede usarlo
+    let instruction = claim_ix(&env, &beto, &guest_deposit, claim_code, false);
+    assert!(env.process(&[instruction], &[&beto.wallet]).await.is_err());
+
+    // Ni comprometiéndose en el mismo slot en que lo observa
+    let instruction = commit_ix(&beto, &guest_deposit, &claim_code);
+    env.process(&[instruction], &[&beto.wallet]).await.unwrap();
+    let instruction = claim_ix(&env, &beto, &guest_deposit, claim_code, false);
+    assert_error(env.process(&[instruction], &[&beto.wallet]).await, ErrorCode::ClaimIntentTooRecent);
+
+    // Ni con el compromiso de Ana, que solo vale para su wallet
+    let mut instruction = claim_ix(&env, &beto, &guest_deposit, claim_code, false);
+    instruction.accounts[3].pubkey = guest_claim_pda(&guest_deposit, &ana.key());
+    assert!(env.process(&[instruction], &[&beto.wallet]).await.is_err());
+
+    let instruction = claim_ix(&env, &ana, &guest_deposit, claim_code, false);
+    env.process(&[instruction], &[&ana.wallet]).await.unwrap();
+    assert_eq!(env.token_balance(&ana.token_account).await, 2 * ECOC_PER_KG);
+    assert_eq!(env.token_balance(&beto.token_account).await, 0);
+}
+
+#[tokio::test]
+async fn unclaimed_guest_deposit_returns_to_treasury() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+    let claim_code = [7; 32];
+    let ecoc_mint = env.ecoc_mint;
+    let treasury = env.create_token_account(&ecoc_mint, &operator.pubkey()).await;
+
+    let instruction = guest_deposit_ix(&env, &operator, &container, &claim_code, 1_000, 1);
+    env.process(&[instruction], &[]).await.unwrap();
+    let guest_deposit = guest_deposit_pda(&claim_code);
+
+    let instruction = expire_ix(&env, &guest_deposit, &treasury);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::GuestDepositNotExpired);
+
+    let ana = env.new_user("ana").await;
+    commit(&mut env, &ana, &guest_deposit, &claim_code).await;
+    env.warp_forward(GUEST_CLAIM_WINDOW_SECS).await;
+    let instruction = claim_ix(&env, &ana, &guest_deposit, claim_code, false);
+    assert_error(env.process(&[instruction], &[&ana.wallet]).await, ErrorCode::GuestDepositExpired);
+
+    // La tesorería es la token account ECOC de la autoridad del programa
+    let instruction = expire_ix(&env, &guest_deposit, &ana.token_account);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidTreasuryAccount);
+
+    let instruction = expire_ix(&env, &guest_deposit, &treasury);
+    env.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&treasury).await, ECOC_PER_KG);
+    assert!(!env.exists(&guest_deposit).await);
+
+    // El compromiso de Ana sobre el depósito vencido también se puede cerrar
+    let instruction = close_intent_ix(&ana, &guest_deposit);
+    env.process(&[instruction], &[]).await.unwrap();
+    assert!(!env.exists(&guest_claim_pda(&guest_deposit, &ana.key())).await);
+}
+
+#[tokio::test]
+async fn stale_claim_intent_refunds_its_claimer() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+    let claim_code = [7; 32];
+    let instruction = guest_deposit_ix(&env, &operator, &container, &claim_code, 2_000, 1);
+    env.process(&[instruction], &[]).await.unwrap();
+    let guest_deposit = guest_deposit_pda(&claim_code);
+
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let lamports = env.lamports(&beto.key()).await;
+    commit(&mut env, &beto, &guest_deposit, &claim_code).await;
+    let intent = guest_claim_pda(&guest_deposit, &beto.key());
+    assert_eq!(env.lamports(&beto.key()).await, lamports - env.lamports(&intent).await);
+
+    // Mientras el depósito exista el compromiso sigue vigente
+    let instruction = close_intent_ix(&beto, &guest_deposit);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::GuestDepositStillOpen);
+
+    commit(&mut env, &ana, &guest_deposit, &claim_code).await;
+    let instruction = claim_ix(&env, &ana, &guest_deposit, claim_code, false);
+    env.process(&[instruction], &[&ana.wallet]).await.unwrap();
+
+    // Reclamado por Ana: cualquiera cierra el compromiso de Beto y su renta le vuelve
+    let instruction = close_intent_ix(&beto, &guest_deposit);
+    env.process(&[instruction], &[]).await.unwrap();
+    assert!(!env.exists(&intent).await);
+    assert_eq!(env.lamports(&beto.key()).await, lamports);
+
+    // El compromiso debe ser de la wallet que recibe la renta
+    let mut instruction = close_intent_ix(&ana, &guest_deposit);
+    instruction.accounts[0].pubkey = intent;
+    assert!(env.process(&[instruction], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn guest_deposit_requires_container_operator() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let operator = env.authority.insecure_clone();
+
+    let intruder = env.wallet().await;
+    let instruction = guest_deposit_ix(&env, &intruder, &container, &[7; 32], 1_000, 1);
+    assert!(env.process(&[instruction], &[&intruder]).await.is_err());
+
+    // Un compromiso ya usado no admite un segundo depósito
+    let instruction = guest_deposit_ix(&env, &operator, &container, &[7; 32], 1_000, 1);
+    env.process(&[instruction], &[]).await.unwrap();
+    let instruction = guest_deposit_ix(&env, &operator, &container, &[7; 32], 1_000, 2);
+    assert!(env.process(&[instruction], &[]).await.is_err());
+
+    let instruction = guest_deposit_ix(&env, &operator, &container, &[9; 32], MIN_DEPOSIT_WEIGHT - 1, 3);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::WeightTooLow);
+}
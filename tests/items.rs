@@ -0,0 +1,274 @@
+//! Depósito por unidades: registro de SKUs y bitmaps de números de serie
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{ErrorCode, ScannedItem, SerialBitmap, SkuEntry, SkuRegistry, SmartContainer, UserProfile};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const AGUA: u64 = 8_410_000_000_017;
+const REFRESCO: u64 = 8_410_000_000_024;
+
+fn sku_registry_pda() -> Pubkey {
+    pda(&[b"sku_registry"])
+}
+
+fn upsert_sku_ix(env: &TestEnv, gtin: u64, deposit_value: u64, unit_weight_grams: u32, is_active: bool) -> Instruction {
+    let accounts = ecobottle::accounts::UpsertSku {
+        sku_registry: sku_registry_pda(),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    let data = ecobottle::instruction::UpsertSku {
+        gtin,
+        deposit_value,
+        unit_weight_grams,
+        is_active,
+    };
+    ix(accounts, data)
+}
+
+/// Registro de SKUs con agua (0.25 ECOC, 30 g) y refresco (0.10 ECOC, 500 g)
+async fn setup_skus(env: &mut TestEnv) {
+    let accounts = ecobottle::accounts::InitSkuRegistry {
+        sku_registry: sku_registry_pda(),
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    let instructions = [
+        ix(accounts, ecobottle::instruction::InitSkuRegistry {}),
+        upsert_sku_ix(env, AGUA, 250_000, 30, true),
+        upsert_sku_ix(env, REFRESCO, 100_000, 500, true),
+    ];
+    env.process(&instructions, &[]).await.unwrap();
+}
+
+async fn init_bitmap(env: &mut TestEnv, gtin: u64, bucket: u64) -> Pubkey {
+    let payer = env.wallet().await;
+    let serial_bitmap = serial_bitmap_pda(gtin, bucket);
+    let accounts = ecobottle::accounts::InitSerialBitmap {
+        serial_bitmap,
+        payer: payer.pubkey(),
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::InitSerialBitmap { gtin, bucket };
+    env.process(&[ix(accounts, data)], &[&payer]).await.unwrap();
+    serial_bitmap
+}
+
+async fn item_deposit(
+    env: &mut TestEnv,
+    user: &User,
+    container: &Pubkey,
+    items: Vec<ScannedItem>,
+    bitmaps: &[Pubkey],
+) -> Result<(), TransactionError> {
+    let accounts = ecobottle::accounts::ProcessItemDeposit {
+        global_state: global_state_pda(),
+        container: *container,
+        user_profile: user.profile,
+        sku_registry: sku_registry_pda(),
+        user: user.key(),
+        container_authority: env.authority.pubkey(),
+        user_token_account: user.token_account,
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+    };
+    let receipt_hash = env.next_receipt();
+    let data = ecobottle::instruction::ProcessItemDeposit {
+        items,
+        receipt_hash,
+        receipt_sequence: receipt_sequence(&receipt_hash),
+    };
+    let remaining = bitmaps.iter().map(|key| AccountMeta::new(*key, false)).collect();
+    env.process(&[ix_with_remaining(accounts, data, remaining)], &[&user.wallet]).await
+}
+
+fn item(gtin: u64, serial: u64) -> ScannedItem {
+    ScannedItem { gtin, serial }
+}
+
+#[tokio::test]
+async fn sku_registry_upserts_entries() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+
+    let instruction = upsert_sku_ix(&env, AGUA, 300_000, 32, false);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let registry: SkuRegistry = env.get(&sku_registry_pda()).await;
+    assert_eq!(registry.skus.len(), 2);
+    let agua = registry.skus.iter().find(|s| s.gtin == AGUA).unwrap();
+    assert_eq!(agua.deposit_value, 300_000);
+    assert_eq!(agua.unit_weight_grams, 32);
+    assert!(registry.find(AGUA).is_none());
+    assert!(registry.find(REFRESCO).is_some());
+}
+
+#[tokio::test]
+async fn sku_registry_rejects_entries_beyond_capacity() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+
+    let mut registry: SkuRegistry = env.get(&sku_registry_pda()).await;
+    registry.skus = (0..ecobottle::MAX_REGISTERED_SKUS as u64)
+        .map(|gtin| SkuEntry {
+            gtin,
+            deposit_value: 1,
+            unit_weight_grams: 1,
+            is_active: true,
+        })
+        .collect();
+    env.set_program_account(&sku_registry_pda(), &registry, 8 + SkuRegistry::LEN).await;
+
+    let instruction = upsert_sku_ix(&env, AGUA, 250_000, 30, true);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::SkuRegistryFull);
+
+    // Actualizar un SKU existente sigue permitido
+    let instruction = upsert_sku_ix(&env, 7, 2, 2, true);
+    env.process(&[instruction], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn item_deposit_pays_deposit_values_and_marks_serials() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let agua_0 = init_bitmap(&mut env, AGUA, 0).await;
+    let agua_1 = init_bitmap(&mut env, AGUA, 1).await;
+    let refresco_0 = init_bitmap(&mut env, REFRESCO, 0).await;
+
+    let items = vec![item(AGUA, 3), item(AGUA, 4_096 + 9), item(REFRESCO, 10)];
+    item_deposit(&mut env, &user, &container, items, &[agua_0, agua_1, refresco_0]).await.unwrap();
+
+    assert_eq!(env.token_balance(&user.token_account).await, 600_000);
+
+    let bitmap: SerialBitmap = env.get(&agua_0).await;
+    assert_eq!(bitmap.bits[0], 1 << 3);
+    let bitmap: SerialBitmap = env.get(&agua_1).await;
+    assert_eq!((bitmap.gtin, bitmap.bucket), (AGUA, 1));
+    assert_eq!(bitmap.bits[1], 1 << 1);
+
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 560);
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.total_deposits, 1);
+    assert_eq!(profile.total_ecoc_earned, 600_000);
+}
+
+#[tokio::test]
+async fn item_deposit_validates_item_count() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let bitmap = init_bitmap(&mut env, AGUA, 0).await;
+
+    let result = item_deposit(&mut env, &user, &container, vec![], &[bitmap]).await;
+    assert_error(result, ErrorCode::InvalidItemCount);
+
+    let items = (0..=ecobottle::MAX_ITEMS_PER_DEPOSIT as u64).map(|serial| item(AGUA, serial)).collect();
+    let result = item_deposit(&mut env, &user, &container, items, &[bitmap]).await;
+    assert_error(result, ErrorCode::InvalidItemCount);
+}
+
+#[tokio::test]
+async fn item_deposit_rejects_unknown_or_inactive_sku() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let bitmap = init_bitmap(&mut env, AGUA, 0).await;
+
+    let result = item_deposit(&mut env, &user, &container, vec![item(42, 1)], &[bitmap]).await;
+    assert_error(result, ErrorCode::SkuNotRegistered);
+
+    let instruction = upsert_sku_ix(&env, AGUA, 250_000, 30, false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 1)], &[bitmap]).await;
+    assert_error(result, ErrorCode::SkuNotRegistered);
+}
+
+#[tokio::test]
+async fn item_deposit_requires_serial_bitmap() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let bitmap = init_bitmap(&mut env, AGUA, 0).await;
+
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 1)], &[]).await;
+    assert_error(result, ErrorCode::MissingSerialBitmap);
+
+    // La serie 5000 pertenece al bloque 1
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 5_000)], &[bitmap]).await;
+    assert_error(result, ErrorCode::MissingSerialBitmap);
+}
+
+#[tokio::test]
+async fn item_deposit_raises_fill_alert_and_respects_capacity() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+    let bitmap = init_bitmap(&mut env, REFRESCO, 0).await;
+
+    item_deposit(&mut env, &user, &container, vec![item(REFRESCO, 1), item(REFRESCO, 2)], &[bitmap]).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.current_weight, 1_000);
+    assert!(state.needs_collection);
+
+    // La capacidad se valida antes de tocar los bitmaps de serie
+    let result = item_deposit(&mut env, &user, &container, vec![item(REFRESCO, 3)], &[]).await;
+    assert_error(result, ErrorCode::ContainerFull);
+    let serials: SerialBitmap = env.get(&bitmap).await;
+    assert_eq!(serials.bits[0], 0b110);
+}
+
+#[tokio::test]
+async fn item_deposit_rejects_redeemed_serials() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let bitmap = init_bitmap(&mut env, AGUA, 0).await;
+
+    let items = vec![item(AGUA, 7), item(AGUA, 7)];
+    let result = item_deposit(&mut env, &user, &container, items, &[bitmap]).await;
+    assert_error(result, ErrorCode::SerialAlreadyRedeemed);
+
+    item_deposit(&mut env, &user, &container, vec![item(AGUA, 7)], &[bitmap]).await.unwrap();
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 7)], &[bitmap]).await;
+    assert_error(result, ErrorCode::SerialAlreadyRedeemed);
+}
+
+#[tokio::test]
+async fn item_deposit_rejects_duplicate_or_foreign_bitmaps() {
+    let mut env = TestEnv::new().await;
+    setup_skus(&mut env).await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let bitmap = init_bitmap(&mut env, AGUA, 0).await;
+
+    // Con el mismo bitmap dos veces, la copia sin marcar borraría la serie canjeada
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 7)], &[bitmap, bitmap]).await;
+    assert_error(result, ErrorCode::DuplicateSerialBitmap);
+
+    // Un bitmap fuera de su PDA no puede sustituir al canónico
+    let forged = Pubkey::new_unique();
+    let serial_bitmap: SerialBitmap = env.get(&bitmap).await;
+    env.set_program_account(&forged, &serial_bitmap, 8 + SerialBitmap::LEN).await;
+    let result = item_deposit(&mut env, &user, &container, vec![item(AGUA, 7)], &[forged]).await;
+    assert_error(result, ErrorCode::InvalidSerialBitmap);
+
+    item_deposit(&mut env, &user, &container, vec![item(AGUA, 7)], &[bitmap]).await.unwrap();
+    let serial_bitmap: SerialBitmap = env.get(&bitmap).await;
+    assert_eq!(serial_bitmap.bits[0], 1 << 7);
+}
@@ -0,0 +1,302 @@
+//! Tablas de clasificación por periodo y reparto de premios
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::{spl_token, TokenAccount};
+use common::*;
+use ecobottle::{ErrorCode, Leaderboard, LeaderboardScore, PeriodKind};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+/// Semana de referencia para los tests (índice desde el epoch Unix)
+const WEEK: u64 = 2_900;
+
+fn week_start() -> i64 {
+    WEEK as i64 * ecobottle::SECONDS_PER_WEEK
+}
+
+fn create_leaderboard_ix(env: &TestEnv, authority: &Pubkey, period_kind: PeriodKind, period_index: u64, scope: Pubkey) -> Instruction {
+    let leaderboard = leaderboard_pda(period_kind, period_index, &scope);
+    let accounts = ecobottle::accounts::CreateLeaderboard {
+        global_state: global_state_pda(),
+        leaderboard,
+        prize_vault: leaderboard_vault_pda(&leaderboard),
+        ecoc_mint: env.ecoc_mint,
+        authority: *authority,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::CreateLeaderboard {
+        period_kind,
+        period_index,
+        scope,
+    };
+    ix(accounts, data)
+}
+
+async fn create_leaderboard(env: &mut TestEnv, period_kind: PeriodKind, period_index: u64, scope: Pubkey) -> Pubkey {
+    let authority = env.authority.pubkey();
+    let instruction = create_leaderboard_ix(env, &authority, period_kind, period_index, scope);
+    env.process(&[instruction], &[]).await.unwrap();
+    leaderboard_pda(period_kind, period_index, &scope)
+}
+
+fn fund_ix(user: &User, leaderboard: &Pubkey, amount: u64) -> Instruction {
+    let accounts = ecobottle::accounts::FundLeaderboard {
+        leaderboard: *leaderboard,
+        prize_vault: leaderboard_vault_pda(leaderboard),
+        funder_token_account: user.token_account,
+        funder: user.key(),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::FundLeaderboard { amount })
+}
+
+fn close_ix(leaderboard: &Pubkey, treasury: &Pubkey, winners: &[Pubkey]) -> Instruction {
+    let accounts = ecobottle::accounts::ClosePeriod {
+        global_state: global_state_pda(),
+        leaderboard: *leaderboard,
+        prize_vault: leaderboard_vault_pda(leaderboard),
+        treasury_token_account: *treasury,
+        token_program: spl_token::ID,
+    };
+    let remaining = winners.iter().map(|key| AccountMeta::new(*key, false)).collect();
+    ix_with_remaining(accounts, ecobottle::instruction::ClosePeriod {}, remaining)
+}
+
+/// Token account ECOC de la autoridad (tesorería)
+async fn treasury(env: &mut TestEnv) -> Pubkey {
+    let ecoc_mint = env.ecoc_mint;
+    let authority = env.authority.pubkey();
+    env.create_token_account(&ecoc_mint, &authority).await
+}
+
+async fn ranked_deposit(
+    env: &mut TestEnv,
+    user: &User,
+    container: &Pubkey,
+    weight_grams: u64,
+    leaderboards: &[Pubkey],
+) -> Result<(), TransactionError> {
+    let accounts = env.deposit_accounts(user, container).await;
+    let receipt_hash = env.next_receipt();
+    let remaining = leaderboards.iter().map(|key| AccountMeta::new(*key, false)).collect();
+    env.deposit_with(user, accounts, weight_grams, receipt_hash, remaining).await
+}
+
+#[tokio::test]
+async fn create_leaderboard_creates_prize_vault() {
+    let mut env = TestEnv::new().await;
+    let leaderboard_key = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+
+    let leaderboard: Leaderboard = env.get(&leaderboard_key).await;
+    assert_eq!(leaderboard.period_kind, PeriodKind::Weekly);
+    assert_eq!(leaderboard.period_index, WEEK);
+    assert_eq!(leaderboard.scope, Pubkey::default());
+    assert!(leaderboard.entries.is_empty());
+    assert!(!leaderboard.is_closed);
+    assert_eq!(leaderboard.prize_vault, leaderboard_vault_pda(&leaderboard_key));
+
+    let vault: TokenAccount = env.get(&leaderboard.prize_vault).await;
+    assert_eq!(vault.mint, env.ecoc_mint);
+    assert_eq!(vault.owner, global_state_pda());
+}
+
+#[tokio::test]
+async fn deposit_ranks_users_in_global_and_container_leaderboards() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let container = env.new_container("EB-001", 50).await;
+    let other_container = env.new_container("EB-002", 50).await;
+    let global = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+    let local = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, container).await;
+
+    let ana_score = leaderboard_score_pda(&local, &ana.key());
+    let beto_score = leaderboard_score_pda(&local, &beto.key());
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[global, local, ana_score]).await.unwrap();
+    ranked_deposit(&mut env, &beto, &container, 3_000, &[global, local, beto_score]).await.unwrap();
+    // El depósito en otro contenedor solo suma en la tabla global
+    ranked_deposit(&mut env, &ana, &other_container, 2_500, &[global]).await.unwrap();
+
+    let leaderboard: Leaderboard = env.get(&global).await;
+    let ranking: Vec<_> = leaderboard.entries.iter().map(|e| (e.user, e.weight_grams)).collect();
+    assert_eq!(ranking, vec![(ana.key(), 3_500), (beto.key(), 3_000)]);
+
+    let leaderboard: Leaderboard = env.get(&local).await;
+    let ranking: Vec<_> = leaderboard.entries.iter().map(|e| (e.user, e.weight_grams)).collect();
+    assert_eq!(ranking, vec![(beto.key(), 3_000), (ana.key(), 1_000)]);
+    let score: LeaderboardScore = env.get(&ana_score).await;
+    assert_eq!(score.weight_grams, 1_000);
+}
+
+#[tokio::test]
+async fn create_leaderboard_requires_authority() {
+    let mut env = TestEnv::new().await;
+    let intruder = env.wallet().await;
+
+    let instruction = create_leaderboard_ix(&env, &intruder.pubkey(), PeriodKind::Weekly, WEEK, Pubkey::default());
+    assert!(env.process(&[instruction], &[&intruder]).await.is_err());
+    assert!(!env.exists(&leaderboard_pda(PeriodKind::Weekly, WEEK, &Pubkey::default())).await);
+}
+
+#[tokio::test]
+async fn deposit_rejects_repeated_leaderboard() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let global = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+    let local = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, container).await;
+    let score = leaderboard_score_pda(&local, &ana.key());
+
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[global, global]).await;
+    assert_error(result, ErrorCode::DuplicateLeaderboard);
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[local, score, local, score]).await;
+    assert_error(result, ErrorCode::DuplicateLeaderboard);
+
+    // La tabla del contenedor exige la cuenta de puntuación del propio usuario
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[local]).await;
+    assert_error(result, ErrorCode::InvalidLeaderboardScore);
+    let other_score = leaderboard_score_pda(&local, &global);
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[local, other_score]).await;
+    assert_error(result, ErrorCode::InvalidLeaderboardScore);
+}
+
+#[tokio::test]
+async fn container_score_survives_leaving_the_top() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let container = env.new_container("EB-001", 500).await;
+    let local = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, container).await;
+
+    let ana = env.new_user("ana").await;
+    let ana_score = leaderboard_score_pda(&local, &ana.key());
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[local, ana_score]).await.unwrap();
+
+    // Diez usuarios con más peso desplazan a ana del top-N
+    for n in 0..ecobottle::LEADERBOARD_SIZE {
+        let user = env.new_user(&format!("user{}", n)).await;
+        let score = leaderboard_score_pda(&local, &user.key());
+        ranked_deposit(&mut env, &user, &container, 1_500, &[local, score]).await.unwrap();
+    }
+    let leaderboard: Leaderboard = env.get(&local).await;
+    assert_eq!(leaderboard.score_of(&ana.key()), 0);
+
+    // Su siguiente depósito suma sobre lo acumulado y vuelve a la cabeza
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[local, ana_score]).await.unwrap();
+    let leaderboard: Leaderboard = env.get(&local).await;
+    assert_eq!(leaderboard.entries[0].user, ana.key());
+    assert_eq!(leaderboard.entries[0].weight_grams, 2_000);
+}
+
+#[tokio::test]
+async fn deposit_validates_leaderboards() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let other_container = env.new_container("EB-002", 50).await;
+
+    let other_scope = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, other_container).await;
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[other_scope]).await;
+    assert_error(result, ErrorCode::LeaderboardScopeMismatch);
+
+    let previous = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK - 1, Pubkey::default()).await;
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[previous]).await;
+    assert_error(result, ErrorCode::LeaderboardPeriodMismatch);
+
+    let treasury = treasury(&mut env).await;
+    env.process(&[close_ix(&previous, &treasury, &[])], &[]).await.unwrap();
+    let result = ranked_deposit(&mut env, &ana, &container, 1_000, &[previous]).await;
+    assert_error(result, ErrorCode::LeaderboardClosed);
+}
+
+#[tokio::test]
+async fn close_period_distributes_prize_by_rank() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let container = env.new_container("EB-001", 50).await;
+    let global = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[global]).await.unwrap();
+    ranked_deposit(&mut env, &beto, &container, 3_000, &[global]).await.unwrap();
+
+    env.process(&[fund_ix(&ana, &global, 1_000_000)], &[&ana.wallet]).await.unwrap();
+    let leaderboard: Leaderboard = env.get(&global).await;
+    assert_eq!(leaderboard.prize_pool, 1_000_000);
+    assert_eq!(env.token_balance(&leaderboard_vault_pda(&global)).await, 1_000_000);
+
+    let treasury = treasury(&mut env).await;
+    let winners = [beto.token_account, ana.token_account];
+    let result = env.process(&[close_ix(&global, &treasury, &winners)], &[]).await;
+    assert_error(result, ErrorCode::PeriodNotEnded);
+
+    env.warp_to(week_start() + ecobottle::SECONDS_PER_WEEK).await;
+    let ana_before = env.token_balance(&ana.token_account).await;
+    let beto_before = env.token_balance(&beto.token_account).await;
+    env.process(&[close_ix(&global, &treasury, &winners)], &[]).await.unwrap();
+
+    // 30% y 20% del reparto, normalizados entre los dos ganadores
+    assert_eq!(env.token_balance(&beto.token_account).await - beto_before, 600_000);
+    assert_eq!(env.token_balance(&ana.token_account).await - ana_before, 400_000);
+    assert_eq!(env.token_balance(&leaderboard_vault_pda(&global)).await, 0);
+    assert_eq!(env.token_balance(&treasury).await, 0);
+    let leaderboard: Leaderboard = env.get(&global).await;
+    assert!(leaderboard.is_closed);
+
+    let result = env.process(&[close_ix(&global, &treasury, &winners)], &[]).await;
+    assert_error(result, ErrorCode::LeaderboardClosed);
+    let result = env.process(&[fund_ix(&ana, &global, 1)], &[&ana.wallet]).await;
+    assert_error(result, ErrorCode::LeaderboardClosed);
+}
+
+#[tokio::test]
+async fn close_period_requires_winner_accounts_in_order() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+    let container = env.new_container("EB-001", 50).await;
+    let global = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[global]).await.unwrap();
+    ranked_deposit(&mut env, &beto, &container, 3_000, &[global]).await.unwrap();
+    env.process(&[fund_ix(&ana, &global, 1_000_000)], &[&ana.wallet]).await.unwrap();
+    env.warp_to(week_start() + ecobottle::SECONDS_PER_WEEK).await;
+    let treasury = treasury(&mut env).await;
+
+    let result = env.process(&[close_ix(&global, &treasury, &[beto.token_account])], &[]).await;
+    assert_error(result, ErrorCode::MissingWinnerAccount);
+
+    let result = env.process(&[close_ix(&global, &treasury, &[ana.token_account, beto.token_account])], &[]).await;
+    assert_error(result, ErrorCode::WinnerAccountMismatch);
+}
+
+#[tokio::test]
+async fn close_period_without_winners_returns_prize_to_treasury() {
+    let mut env = TestEnv::new().await;
+    env.warp_to(week_start() + 3_600).await;
+    let ana = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let global = create_leaderboard(&mut env, PeriodKind::Weekly, WEEK, Pubkey::default()).await;
+    // Ana gana ECOC fuera de la tabla y los aporta al premio
+    ranked_deposit(&mut env, &ana, &container, 1_000, &[]).await.unwrap();
+    env.process(&[fund_ix(&ana, &global, 1_000_000)], &[&ana.wallet]).await.unwrap();
+    env.warp_to(week_start() + ecobottle::SECONDS_PER_WEEK).await;
+
+    // La tesorería debe ser de la autoridad
+    let result = env.process(&[close_ix(&global, &ana.token_account, &[])], &[]).await;
+    assert_error(result, ErrorCode::InvalidTreasuryAccount);
+
+    let treasury = treasury(&mut env).await;
+    env.process(&[close_ix(&global, &treasury, &[])], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&treasury).await, 1_000_000);
+    assert_eq!(env.token_balance(&leaderboard_vault_pda(&global)).await, 0);
+    let leaderboard: Leaderboard = env.get(&global).await;
+    assert!(leaderboard.is_closed);
+}
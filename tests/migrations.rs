@@ -0,0 +1,375 @@
+//! Migración de cuentas creadas con el esquema v0
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_lang::AnchorSerialize;
+use common::*;
+use ecobottle::{
+    CollectionRecord, CollectionRecordV0, DepositRecord, DepositRecordV0, ErrorCode, GlobalState, GlobalStateV0,
+    RedemptionRecord, RedemptionRecordV0, SmartContainer, SmartContainerV0, UserProfile, UserProfileV0,
+    UsernameRecord,
+};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Signer;
+
+/// Cuenta v0 con el tamaño fijo de su esquema (las cadenas no ocupan todo su espacio)
+fn legacy_data<T: AnchorSerialize>(discriminator: &[u8], value: &T, legacy_len: usize) -> Vec<u8> {
+    let mut data = legacy_account_data(discriminator, value);
+    data.resize(8 + legacy_len, 0);
+    data
+}
+
+fn bump(seeds: &[&[u8]]) -> u8 {
+    Pubkey::find_program_address(seeds, &ecobottle::ID).1
+}
+
+fn migrate_global_state_ix(env: &TestEnv) -> Instruction {
+    let accounts = ecobottle::accounts::MigrateGlobalState {
+        global_state: global_state_pda(),
+        ecoc_mint: env.ecoc_mint,
+        authority: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::MigrateGlobalState {})
+}
+
+fn migrate_account_ix(env: &TestEnv, account: &Pubkey, data: impl anchor_lang::InstructionData) -> Instruction {
+    let accounts = ecobottle::accounts::MigrateAccount {
+        account: *account,
+        payer: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    ix(accounts, data)
+}
+
+fn migrate_collection_ix(env: &TestEnv, account: &Pubkey, period_start: i64) -> Instruction {
+    let accounts = ecobottle::accounts::MigrateCollectionRecord {
+        account: *account,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::MigrateCollectionRecord { period_start })
+}
+
+fn migrate_user_profile_ix(env: &TestEnv, account: &Pubkey, username: &str) -> Instruction {
+    migrate_user_profile_as(env, account, username, None)
+}
+
+/// Migración firmada por `owner` (necesaria para elegir otro nombre)
+fn migrate_user_profile_as(env: &TestEnv, account: &Pubkey, username: &str, owner: Option<Pubkey>) -> Instruction {
+    let accounts = ecobottle::accounts::MigrateUserProfile {
+        account: *account,
+        username_record: username_pda(username),
+        owner,
+        payer: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::MigrateUserProfile { username: username.to_string() })
+}
+
+/// Perfil v0 de `owner` guardado en su PDA
+async fn legacy_profile(env: &mut TestEnv, owner: &Pubkey, key: &Pubkey) {
+    let legacy = (
+        *owner,
+        "ana".to_string(),
+        3u64,
+        4_500u64,
+        4_500_000u64,
+        1_000_000u64,
+        1_700_000_000i64,
+        bump(&[b"user_profile", owner.as_ref()]),
+    );
+    let data = legacy_data(discriminator::<UserProfile>(), &legacy, UserProfileV0::LEN);
+    env.set_raw_account(key, data, ecobottle::ID).await;
+}
+
+#[tokio::test]
+async fn migrate_global_state_preserves_counters() {
+    let mut env = TestEnv::new().await;
+    let legacy = (
+        env.authority.pubkey(),
+        ECOC_PER_KG,
+        MIN_DEPOSIT_WEIGHT,
+        120_000u64,
+        80u64,
+        25u64,
+        4u64,
+        bump(&[b"global_state"]),
+    );
+    let data = legacy_data(discriminator::<GlobalState>(), &legacy, GlobalStateV0::LEN);
+    env.set_raw_account(&global_state_pda(), data, ecobottle::ID).await;
+
+    let instruction = migrate_global_state_ix(&env);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!(global_state.authority, env.authority.pubkey());
+    assert_eq!(global_state.total_pet_collected, 120_000);
+    assert_eq!(global_state.total_deposits, 80);
+    assert_eq!(global_state.total_users, 25);
+    assert_eq!(global_state.total_containers, 4);
+    assert_eq!(global_state.ecoc_mint, env.ecoc_mint);
+    assert_eq!(global_state.record_retention_secs, ecobottle::DEFAULT_RECORD_RETENTION_SECS);
+    // Un despliegue migrado no restringe a los recolectores hasta activar el SLA
+    assert_eq!(global_state.collection_sla_secs, 0);
+
+    let instruction = migrate_global_state_ix(&env);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn migrate_global_state_requires_legacy_authority() {
+    let mut env = TestEnv::new().await;
+    let legacy = (
+        Pubkey::new_unique(),
+        ECOC_PER_KG,
+        MIN_DEPOSIT_WEIGHT,
+        0u64,
+        0u64,
+        0u64,
+        0u64,
+        bump(&[b"global_state"]),
+    );
+    let data = legacy_data(discriminator::<GlobalState>(), &legacy, GlobalStateV0::LEN);
+    env.set_raw_account(&global_state_pda(), data, ecobottle::ID).await;
+
+    let instruction = migrate_global_state_ix(&env);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidMigrationSource);
+}
+
+#[tokio::test]
+async fn migrate_user_profile_extends_account() {
+    let mut env = TestEnv::new().await;
+    let owner = Pubkey::new_unique();
+    let profile_key = user_profile_pda(&owner);
+    legacy_profile(&mut env, &owner, &profile_key).await;
+
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "ana");
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let profile: UserProfile = env.get(&profile_key).await;
+    assert_eq!(profile.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!(profile.owner, owner);
+    assert_eq!(profile.username, "ana");
+    assert_eq!(profile.total_deposits, 3);
+    assert_eq!(profile.total_pet_weight, 4_500);
+    assert_eq!(profile.total_ecoc_spent, 1_000_000);
+    assert_eq!(profile.reputation_score, ecobottle::REPUTATION_INITIAL);
+
+    let username_record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(username_record.owner, owner);
+    assert_eq!(username_record.username, "ana");
+    assert_eq!(username_record.payer, env.authority.pubkey());
+
+    // La reserva creada en la primera migración impide repetirla
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "ana");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::UsernameTaken);
+}
+
+#[tokio::test]
+async fn migrate_user_profile_rejects_invalid_sources() {
+    let mut env = TestEnv::new().await;
+    let owner = Pubkey::new_unique();
+
+    // Datos v0 válidos fuera del PDA del dueño
+    let stray = Pubkey::new_unique();
+    legacy_profile(&mut env, &owner, &stray).await;
+    let instruction = migrate_user_profile_ix(&env, &stray, "ana");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidMigrationSource);
+
+    // Nombre distinto al del perfil (reservaría un nombre ajeno)
+    let profile_key = user_profile_pda(&owner);
+    legacy_profile(&mut env, &owner, &profile_key).await;
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "eva");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidMigrationSource);
+
+    // Nombre del perfil v0 reservado después por otro usuario
+    let eva = env.new_user("eva").await;
+    let legacy = (owner, "Eva".to_string(), 0u64, 0u64, 0u64, 0u64, 0i64, bump(&[b"user_profile", owner.as_ref()]));
+    let data = legacy_data(discriminator::<UserProfile>(), &legacy, UserProfileV0::LEN);
+    env.set_raw_account(&profile_key, data, ecobottle::ID).await;
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "Eva");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::UsernameTaken);
+    let username_record: UsernameRecord = env.get(&username_pda("eva")).await;
+    assert_eq!(username_record.owner, eva.key());
+
+    // Discriminador de otro tipo de cuenta
+    let mut data = legacy_data(discriminator::<SmartContainer>(), &owner, UserProfileV0::LEN);
+    env.set_raw_account(&profile_key, data.clone(), ecobottle::ID).await;
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "ana");
+    assert_error(env.process(std::slice::from_ref(&instruction), &[]).await, ErrorCode::InvalidMigrationSource);
+
+    // Cuenta que no pertenece al programa
+    data[..8].copy_from_slice(discriminator::<UserProfile>());
+    env.set_raw_account(&profile_key, data, system_program::ID).await;
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidMigrationSource);
+}
+
+#[tokio::test]
+async fn migrate_user_profile_with_taken_name_renames_it() {
+    let mut env = TestEnv::new().await;
+    let owner = env.wallet().await;
+    let profile_key = user_profile_pda(&owner.pubkey());
+    legacy_profile(&mut env, &owner.pubkey(), &profile_key).await;
+
+    // Otro usuario registró el mismo nombre después de la actualización
+    let ana = env.new_user("ana").await;
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "ana");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::UsernameTaken);
+
+    // Sin la firma del dueño no se puede elegir otro nombre
+    let instruction = migrate_user_profile_ix(&env, &profile_key, "ana_v0");
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidMigrationSource);
+    let intruder = env.wallet().await;
+    let instruction = migrate_user_profile_as(&env, &profile_key, "ana_v0", Some(intruder.pubkey()));
+    assert_error(env.process(&[instruction], &[&intruder]).await, ErrorCode::InvalidMigrationSource);
+
+    // El nuevo nombre también debe estar libre
+    let instruction = migrate_user_profile_as(&env, &profile_key, "ANA", Some(owner.pubkey()));
+    assert_error(env.process(&[instruction], &[&owner]).await, ErrorCode::UsernameTaken);
+
+    let instruction = migrate_user_profile_as(&env, &profile_key, "ana_v0", Some(owner.pubkey()));
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    let profile: UserProfile = env.get(&profile_key).await;
+    assert_eq!(profile.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!(profile.username, "ana_v0");
+    assert_eq!(profile.total_deposits, 3);
+    let username_record: UsernameRecord = env.get(&username_pda("ana_v0")).await;
+    assert_eq!(username_record.owner, owner.pubkey());
+    let username_record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(username_record.owner, ana.key());
+}
+
+#[tokio::test]
+async fn migrate_container_extends_account() {
+    let mut env = TestEnv::new().await;
+    let container_key = container_pda("EB-LEGACY");
+    let legacy = (
+        "EB-LEGACY".to_string(),
+        "Plaza Mayor".to_string(),
+        env.authority.pubkey(),
+        100u64,
+        12_000u64,
+        9u64,
+        true,
+        1_700_000_000i64,
+        1_700_100_000i64,
+        bump(&[b"container", b"EB-LEGACY"]),
+    );
+    let data = legacy_data(discriminator::<SmartContainer>(), &legacy, SmartContainerV0::LEN);
+    env.set_raw_account(&container_key, data, ecobottle::ID).await;
+
+    let instruction = migrate_account_ix(&env, &container_key, ecobottle::instruction::MigrateContainer {});
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let container: SmartContainer = env.get(&container_key).await;
+    assert_eq!(container.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!(container.container_id, "EB-LEGACY");
+    assert_eq!(container.location, "Plaza Mayor");
+    assert_eq!(container.current_weight, 12_000);
+    assert_eq!(container.total_deposits, 9);
+    assert!(container.is_active);
+    assert_eq!(container.fill_threshold_bps, ecobottle::DEFAULT_FILL_THRESHOLD_BPS);
+
+    let instruction = migrate_account_ix(&env, &container_key, ecobottle::instruction::MigrateContainer {});
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn migrate_v0_records_keeps_their_data() {
+    let mut env = TestEnv::new().await;
+    let user = Pubkey::new_unique();
+    let container = Pubkey::new_unique();
+    let collector = Pubkey::new_unique();
+
+    let deposit_key = deposit_pda(&user, 0);
+    let legacy = (user, container, 1_500u64, 1_500_000u64, 1_700_000_000i64, 42u64, bump(&[b"deposit", user.as_ref(), &0u64.to_le_bytes()]));
+    let data = legacy_data(discriminator::<DepositRecord>(), &legacy, DepositRecordV0::LEN);
+    env.set_raw_account(&deposit_key, data, ecobottle::ID).await;
+
+    let collection_key = collection_pda(&container, 9);
+    let legacy = (container, collector, 12_000u64, 1_700_100_000i64, true, 254u8);
+    let data = legacy_data(discriminator::<CollectionRecord>(), &legacy, CollectionRecordV0::LEN);
+    env.set_raw_account(&collection_key, data, ecobottle::ID).await;
+
+    // Solo la autoridad fija el periodo de la recolección v0
+    let intruder = env.wallet().await;
+    let mut instruction = migrate_collection_ix(&env, &collection_key, 1_699_900_000);
+    instruction.accounts[2].pubkey = intruder.pubkey();
+    assert!(env.process(&[instruction], &[&intruder]).await.is_err());
+
+    // El periodo no puede empezar después de la recolección
+    let instruction = migrate_collection_ix(&env, &collection_key, 1_700_100_001);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidCollectionPeriod);
+
+    let instructions = [
+        migrate_account_ix(&env, &deposit_key, ecobottle::instruction::MigrateDepositRecord {}),
+        migrate_collection_ix(&env, &collection_key, 1_699_900_000),
+    ];
+    env.process(&instructions, &[]).await.unwrap();
+
+    let deposit: DepositRecord = env.get(&deposit_key).await;
+    assert_eq!(deposit.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!((deposit.user, deposit.container), (user, container));
+    assert_eq!((deposit.weight_grams, deposit.ecoc_reward), (1_500, 1_500_000));
+    assert_eq!(deposit.timestamp, 1_700_000_000);
+    assert_eq!(deposit.slot, 0);
+    assert_eq!(deposit.payer, user);
+    assert!(!deposit.penalized);
+    assert!(deposit.migrated);
+
+    let collection: CollectionRecord = env.get(&collection_key).await;
+    assert_eq!(collection.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!((collection.container, collection.collector), (container, collector));
+    assert_eq!(collection.weight_collected, 12_000);
+    assert_eq!(collection.period_start, 1_699_900_000);
+    assert!(collection.covers(&container, deposit.timestamp));
+    assert!(!collection.covers(&container, 1_699_900_000));
+    assert_eq!(collection.payer, collector);
+    assert!(collection.verified && !collection.reconciled && !collection.shipped);
+    assert_eq!(collection.bump, 254);
+
+    let instruction = migrate_account_ix(&env, &deposit_key, ecobottle::instruction::MigrateDepositRecord {});
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn migrated_v0_redemption_record_can_be_closed() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let redemption_key = redemption_pda(&user.key(), 0);
+    let timestamp = env.now().await - ecobottle::DEFAULT_RECORD_RETENTION_SECS - 1;
+    let legacy = (
+        user.key(),
+        "cafe-01".to_string(),
+        500_000u64,
+        timestamp,
+        bump(&[b"redemption", user.key().as_ref(), &0u64.to_le_bytes()]),
+    );
+    let data = legacy_data(discriminator::<RedemptionRecord>(), &legacy, RedemptionRecordV0::LEN);
+    env.set_raw_account(&redemption_key, data, ecobottle::ID).await;
+
+    let instruction = migrate_account_ix(&env, &redemption_key, ecobottle::instruction::MigrateRedemptionRecord {});
+    env.process(&[instruction], &[]).await.unwrap();
+    let record: RedemptionRecord = env.get(&redemption_key).await;
+    assert_eq!(record.product_id, "cafe-01");
+    assert_eq!(record.payer, user.key());
+
+    let accounts = ecobottle::accounts::CloseRedemptionRecord {
+        global_state: global_state_pda(),
+        user_profile: user.profile,
+        redemption_record: redemption_key,
+        payer: user.key(),
+    };
+    let instruction = ix(accounts, ecobottle::instruction::CloseRedemptionRecord {});
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+    assert!(!env.exists(&redemption_key).await);
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.archived_redemptions, 1);
+}
@@ -0,0 +1,186 @@
+//! Organizaciones con perfiles custodiados para miembros sin wallet
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{ErrorCode, GlobalState, Organization, OrganizationMember, UserProfile};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+
+const MEMBER: [u8; 32] = [1; 32];
+
+fn organization_pda(admin: &Pubkey) -> Pubkey {
+    pda(&[b"organization", admin.as_ref()])
+}
+
+fn member_pda(organization: &Pubkey, member_id_hash: &[u8; 32]) -> Pubkey {
+    pda(&[b"member", organization.as_ref(), member_id_hash.as_ref()])
+}
+
+fn vault_pda(organization: &Pubkey) -> Pubkey {
+    pda(&[b"organization_vault", organization.as_ref()])
+}
+
+fn register_organization_ix(env: &TestEnv, admin: &Pubkey, name: &str) -> Instruction {
+    let organization = organization_pda(admin);
+    let accounts = ecobottle::accounts::RegisterOrganization {
+        organization,
+        vault: vault_pda(&organization),
+        admin: *admin,
+        global_state: global_state_pda(),
+        ecoc_mint: env.ecoc_mint,
+        authority: env.authority.pubkey(),
+        token_program: spl_token::ID,
+        system_program: anchor_lang::system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::RegisterOrganization { name: name.to_string() })
+}
+
+/// Registra una organización administrada por una wallet nueva
+async fn new_organization(env: &mut TestEnv, name: &str) -> Keypair {
+    let admin = env.wallet().await;
+    let instruction = register_organization_ix(env, &admin.pubkey(), name);
+    env.process(&[instruction], &[]).await.unwrap();
+    admin
+}
+
+fn register_member_ix(organization: &Pubkey, admin: &Keypair, member_id_hash: [u8; 32]) -> Instruction {
+    let accounts = ecobottle::accounts::RegisterMember {
+        organization: *organization,
+        member: member_pda(organization, &member_id_hash),
+        admin: admin.pubkey(),
+        payer: admin.pubkey(),
+        system_program: anchor_lang::system_program::ID,
+    };
+    ix(accounts, ecobottle::instruction::RegisterMember { member_id_hash })
+}
+
+fn member_deposit_ix(env: &TestEnv, container: &Pubkey, organization: &Pubkey, weight_grams: u64, n: u64) -> Instruction {
+    let accounts = ecobottle::accounts::ProcessMemberDeposit {
+        global_state: global_state_pda(),
+        container: *container,
+        container_authority: env.authority.pubkey(),
+        organization: *organization,
+        member: member_pda(organization, &MEMBER),
+        vault: vault_pda(organization),
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+    };
+    let data = ecobottle::instruction::ProcessMemberDeposit {
+        weight_grams,
+        receipt_hash: receipt(n),
+        receipt_sequence: n,
+    };
+    ix(accounts, data)
+}
+
+fn graduate_ix(organization: &Pubkey, admin: &Keypair, user: &User, with_profile: bool) -> Instruction {
+    let accounts = ecobottle::accounts::GraduateMember {
+        global_state: global_state_pda(),
+        organization: *organization,
+        member: member_pda(organization, &MEMBER),
+        vault: vault_pda(organization),
+        admin: admin.pubkey(),
+        wallet: user.key(),
+        wallet_token_account: user.token_account,
+        user_profile: with_profile.then_some(user.profile),
+        token_program: spl_token::ID,
+    };
+    ix(accounts, ecobottle::instruction::GraduateMember {})
+}
+
+#[tokio::test]
+async fn member_deposits_accrue_until_graduation() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let admin = new_organization(&mut env, "Escuela Norte").await;
+    let organization = organization_pda(&admin.pubkey());
+
+    env.process(&[register_member_ix(&organization, &admin, MEMBER)], &[&admin]).await.unwrap();
+    for (n, weight_grams) in [(1, 1_000), (2, 2_000)] {
+        let instruction = member_deposit_ix(&env, &container, &organization, weight_grams, n);
+        env.process(&[instruction], &[]).await.unwrap();
+    }
+
+    let member: OrganizationMember = env.get(&member_pda(&organization, &MEMBER)).await;
+    assert_eq!(member.total_deposits, 2);
+    assert_eq!(member.total_pet_weight, 3_000);
+    assert_eq!(member.ecoc_balance, 3 * ECOC_PER_KG);
+    assert_eq!(env.token_balance(&vault_pda(&organization)).await, 3 * ECOC_PER_KG);
+    let state: Organization = env.get(&organization).await;
+    assert_eq!(state.member_count, 1);
+    assert_eq!(state.total_pet_weight, 3_000);
+    assert_eq!(state.ecoc_held, 3 * ECOC_PER_KG);
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_deposits, 2);
+
+    // La wallet nueva recibe el saldo y su perfil suma los totales del miembro
+    let user = env.new_user("ana").await;
+    env.process(&[graduate_ix(&organization, &admin, &user, true)], &[&admin, &user.wallet]).await.unwrap();
+
+    assert_eq!(env.token_balance(&user.token_account).await, 3 * ECOC_PER_KG);
+    assert_eq!(env.token_balance(&vault_pda(&organization)).await, 0);
+    let member: OrganizationMember = env.get(&member_pda(&organization, &MEMBER)).await;
+    assert_eq!(member.wallet, user.key());
+    assert_eq!(member.ecoc_balance, 0);
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.total_deposits, 2);
+    assert_eq!(profile.total_ecoc_earned, 3 * ECOC_PER_KG);
+    let state: Organization = env.get(&organization).await;
+    assert_eq!(state.ecoc_held, 0);
+
+    // Tras graduarse deposita con su propia wallet
+    let instruction = member_deposit_ix(&env, &container, &organization, 1_000, 3);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::MemberGraduated);
+    let instruction = graduate_ix(&organization, &admin, &user, false);
+    assert_error(env.process(&[instruction], &[&admin, &user.wallet]).await, ErrorCode::MemberGraduated);
+}
+
+#[tokio::test]
+async fn organization_admin_manages_members() {
+    let mut env = TestEnv::new().await;
+    let container = env.new_container("EB-001", 50).await;
+    let admin = new_organization(&mut env, "Residencia Sur").await;
+    let organization = organization_pda(&admin.pubkey());
+
+    let intruder = env.wallet().await;
+    let instruction = register_member_ix(&organization, &intruder, MEMBER);
+    assert_error(env.process(&[instruction], &[&intruder]).await, ErrorCode::NotOrganizationAdmin);
+
+    env.process(&[register_member_ix(&organization, &admin, MEMBER)], &[&admin]).await.unwrap();
+    let user = env.new_user("ana").await;
+    let instruction = graduate_ix(&organization, &intruder, &user, false);
+    assert_error(
+        env.process(&[instruction], &[&intruder, &user.wallet]).await,
+        ErrorCode::NotOrganizationAdmin,
+    );
+
+    // Una organización desactivada no acepta depósitos ni altas
+    let accounts = ecobottle::accounts::SetOrganizationStatus {
+        organization,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    let data = ecobottle::instruction::SetOrganizationStatus { is_active: false };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let instruction = member_deposit_ix(&env, &container, &organization, 1_000, 1);
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::OrganizationNotActive);
+    let instruction = register_member_ix(&organization, &admin, [2; 32]);
+    assert_error(env.process(&[instruction], &[&admin]).await, ErrorCode::OrganizationNotActive);
+}
+
+#[tokio::test]
+async fn register_organization_rejects_long_name() {
+    let mut env = TestEnv::new().await;
+    let admin = env.wallet().await;
+
+    let instruction = register_organization_ix(&env, &admin.pubkey(), &"E".repeat(33));
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::OrganizationNameTooLong);
+    assert!(!env.exists(&organization_pda(&admin.pubkey())).await);
+
+    let instruction = register_organization_ix(&env, &admin.pubkey(), &"E".repeat(32));
+    env.process(&[instruction], &[]).await.unwrap();
+}
@@ -0,0 +1,305 @@
+//! Archivado de registros antiguos con devolución de renta
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use common::*;
+use ecobottle::{
+    CollectionRecord, DepositRecord, DepositRecordV0, ErrorCode, RedemptionRecord, SmartContainer, UserProfile,
+};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn close_deposit_ix(user: &User, deposit_record: &Pubkey, collection_record: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::CloseDepositRecord {
+        global_state: global_state_pda(),
+        user_profile: user.profile,
+        deposit_record: *deposit_record,
+        collection_record: *collection_record,
+        payer: user.key(),
+    };
+    ix(accounts, ecobottle::instruction::CloseDepositRecord {})
+}
+
+fn close_redemption_ix(user: &User, redemption_record: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::CloseRedemptionRecord {
+        global_state: global_state_pda(),
+        user_profile: user.profile,
+        redemption_record: *redemption_record,
+        payer: user.key(),
+    };
+    ix(accounts, ecobottle::instruction::CloseRedemptionRecord {})
+}
+
+fn close_collection_ix(container: &Pubkey, collection_record: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = ecobottle::accounts::CloseCollectionRecord {
+        global_state: global_state_pda(),
+        container: *container,
+        collection_record: *collection_record,
+        payer: *payer,
+    };
+    ix(accounts, ecobottle::instruction::CloseCollectionRecord {})
+}
+
+fn reconcile_ix(env: &TestEnv, collection_record: &Pubkey, measured_weight_grams: u64) -> Instruction {
+    let accounts = ecobottle::accounts::ReconcileCollection {
+        global_state: global_state_pda(),
+        collection_record: *collection_record,
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::ReconcileCollection { measured_weight_grams })
+}
+
+fn retention_config(retention_secs: i64) -> ecobottle::instruction::UpdateConfig {
+    ecobottle::instruction::UpdateConfig {
+        new_ecoc_per_kg: None,
+        new_min_weight: None,
+        new_discrepancy_tolerance_bps: None,
+        new_record_retention_secs: Some(retention_secs),
+        new_collection_sla_secs: None,
+    }
+}
+
+/// Envía la recolección a una planta nueva
+async fn ship(env: &mut TestEnv, collector: &Keypair, collection_record: &Pubkey) {
+    let recycler = env.wallet().await;
+    env.register_recycler(&recycler.pubkey(), "Planta Norte").await.unwrap();
+    let accounts = ecobottle::accounts::CreateShipment {
+        certified_collector: collector_pda(&collector.pubkey()),
+        recycler: recycler_pda(&recycler.pubkey()),
+        shipment: shipment_pda(&collector.pubkey(), 0),
+        collector: collector.pubkey(),
+        system_program: system_program::ID,
+    };
+    let remaining = vec![AccountMeta::new(*collection_record, false)];
+    let instruction = ix_with_remaining(accounts, ecobottle::instruction::CreateShipment {}, remaining);
+    env.process(&[instruction], &[collector]).await.unwrap();
+}
+
+#[tokio::test]
+async fn close_deposit_record_folds_history_after_retention() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let deposit = env.deposit(&user, &container, 1_000).await.unwrap();
+    let record: DepositRecord = env.get(&deposit).await;
+    let collector = env.authority.insecure_clone();
+    let collection = env.collect(&collector, &container).await.unwrap();
+
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::RetentionPeriodActive);
+
+    env.warp_forward(ecobottle::DEFAULT_RECORD_RETENTION_SECS).await;
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::CollectionNotReconciled);
+
+    env.process(&[reconcile_ix(&env, &collection, 1_000)], &[]).await.unwrap();
+    let rent = env.lamports(&deposit).await;
+    let balance_before = env.lamports(&user.key()).await;
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    assert!(!env.exists(&deposit).await);
+    assert_eq!(env.lamports(&user.key()).await, balance_before + rent);
+
+    let mut expected_root = [0; 32];
+    ecobottle::fold_history(&mut expected_root, &record).unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.archived_deposits, 1);
+    assert_eq!(profile.deposit_history_root, expected_root);
+}
+
+#[tokio::test]
+async fn close_deposit_record_requires_settled_deposit() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let other_container = env.new_container("EB-002", 50).await;
+    let deposit = env.deposit(&user, &container, 1_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    let collection = env.collect(&collector, &container).await.unwrap();
+    env.deposit(&user, &other_container, 1_000).await.unwrap();
+    let other_collection = env.collect(&collector, &other_container).await.unwrap();
+    env.process(&[reconcile_ix(&env, &other_collection, 1_000)], &[]).await.unwrap();
+    env.warp_forward(ecobottle::DEFAULT_RECORD_RETENTION_SECS).await;
+
+    let instruction = close_deposit_ix(&user, &deposit, &other_collection);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::DepositNotInCollection);
+
+    // Con discrepancia, el depósito no se archiva hasta ser penalizado
+    env.process(&[reconcile_ix(&env, &collection, 500)], &[]).await.unwrap();
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::DepositPenaltyPending);
+
+    let accounts = ecobottle::accounts::PenalizeDeposit {
+        global_state: global_state_pda(),
+        collection_record: collection,
+        deposit_record: deposit,
+        user_profile: user.profile,
+        authority: env.authority.pubkey(),
+    };
+    env.process(&[ix(accounts, ecobottle::instruction::PenalizeDeposit {})], &[]).await.unwrap();
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+    assert!(!env.exists(&deposit).await);
+}
+
+#[tokio::test]
+async fn close_redemption_record_folds_history_after_retention() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    env.deposit(&user, &container, 1_000).await.unwrap();
+
+    let redemption = redemption_pda(&user.key(), 0);
+    let accounts = ecobottle::accounts::RedeemTokens {
+        user_profile: user.profile,
+        redemption_record: redemption,
+        user: user.key(),
+        user_token_account: user.token_account,
+        ecoc_mint: env.ecoc_mint,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    let data = ecobottle::instruction::RedeemTokens {
+        amount: 500_000,
+        product_id: "cafe-01".to_string(),
+    };
+    env.process(&[ix(accounts, data)], &[&user.wallet]).await.unwrap();
+    let record: RedemptionRecord = env.get(&redemption).await;
+
+    let instruction = close_redemption_ix(&user, &redemption);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::RetentionPeriodActive);
+
+    env.warp_forward(ecobottle::DEFAULT_RECORD_RETENTION_SECS).await;
+    let instruction = close_redemption_ix(&user, &redemption);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    assert!(!env.exists(&redemption).await);
+    let mut expected_root = [0; 32];
+    ecobottle::fold_history(&mut expected_root, &record).unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.archived_redemptions, 1);
+    assert_eq!(profile.redemption_history_root, expected_root);
+}
+
+#[tokio::test]
+async fn close_collection_record_folds_history_after_retention() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+    let deposit = env.deposit(&user, &container, 1_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    env.register_collector(&collector.pubkey(), "Recicla SA").await.unwrap();
+    let collection = env.collect(&collector, &container).await.unwrap();
+    let record: CollectionRecord = env.get(&collection).await;
+    assert_eq!(record.outstanding_deposits, 1);
+
+    // Un registro en el mismo segundo quedaría dentro de la recolección ya creada
+    let result = env.deposit(&user, &container, 1_000).await.map(|_| ());
+    assert_error(result, ErrorCode::DepositDuringCollection);
+
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    let result = env.process(&[instruction], &[&collector]).await;
+    assert_error(result, ErrorCode::RetentionPeriodActive);
+
+    // La retención es configurable por la autoridad
+    env.update_config(retention_config(ecobottle::SECONDS_PER_DAY)).await;
+    env.warp_forward(ecobottle::SECONDS_PER_DAY).await;
+
+    // Solo se archivan recolecciones conciliadas y enviadas a planta
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    let result = env.process(&[instruction], &[&collector]).await;
+    assert_error(result, ErrorCode::CollectionNotReconciled);
+    env.process(&[reconcile_ix(&env, &collection, 1_000)], &[]).await.unwrap();
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    let result = env.process(&[instruction], &[&collector]).await;
+    assert_error(result, ErrorCode::CollectionNotShipped);
+    ship(&mut env, &collector, &collection).await;
+
+    // Cerrarla antes que sus depósitos dejaría su renta bloqueada
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    let result = env.process(&[instruction], &[&collector]).await;
+    assert_error(result, ErrorCode::CollectionHasOpenDeposits);
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let record: CollectionRecord = env.get(&collection).await;
+    assert_eq!(record.outstanding_deposits, 0);
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    env.process(&[instruction], &[&collector]).await.unwrap();
+
+    assert!(!env.exists(&collection).await);
+    let mut expected_root = [0; 32];
+    ecobottle::fold_history(&mut expected_root, &record).unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.archived_collections, 1);
+    assert_eq!(state.collection_history_root, expected_root);
+}
+
+#[tokio::test]
+async fn migrated_v0_deposit_does_not_release_collection() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 50).await;
+
+    // Depósito v0 anterior a la primera recolección, migrado sin contar en ella
+    let legacy_deposit = deposit_pda(&user.key(), 1_000);
+    let user_key = user.key();
+    let seeds: &[&[u8]] = &[b"deposit", user_key.as_ref(), &1_000u64.to_le_bytes()];
+    let bump = Pubkey::find_program_address(seeds, &ecobottle::ID).1;
+    let timestamp = env.now().await - 10;
+    let legacy = (user.key(), container, 1_500u64, 1_500_000u64, timestamp, 42u64, bump);
+    let mut data = legacy_account_data(discriminator::<DepositRecord>(), &legacy);
+    data.resize(8 + DepositRecordV0::LEN, 0);
+    env.set_raw_account(&legacy_deposit, data, ecobottle::ID).await;
+    let accounts = ecobottle::accounts::MigrateAccount {
+        account: legacy_deposit,
+        payer: env.authority.pubkey(),
+        system_program: system_program::ID,
+    };
+    env.process(&[ix(accounts, ecobottle::instruction::MigrateDepositRecord {})], &[]).await.unwrap();
+    let record: DepositRecord = env.get(&legacy_deposit).await;
+    assert!(record.migrated);
+
+    let deposit = env.deposit(&user, &container, 1_000).await.unwrap();
+    let collector = env.authority.insecure_clone();
+    env.register_collector(&collector.pubkey(), "Recicla SA").await.unwrap();
+    let collection = env.collect(&collector, &container).await.unwrap();
+    let record: CollectionRecord = env.get(&collection).await;
+    assert!(record.covers(&container, timestamp));
+    assert_eq!(record.outstanding_deposits, 1);
+
+    env.warp_forward(ecobottle::DEFAULT_RECORD_RETENTION_SECS).await;
+    env.process(&[reconcile_ix(&env, &collection, 1_000)], &[]).await.unwrap();
+    ship(&mut env, &collector, &collection).await;
+
+    // Archivar el registro v0 no descuenta el depósito v1 pendiente
+    let instruction = close_deposit_ix(&user, &legacy_deposit, &collection);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+    let record: CollectionRecord = env.get(&collection).await;
+    assert_eq!(record.outstanding_deposits, 1);
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    let result = env.process(&[instruction], &[&collector]).await;
+    assert_error(result, ErrorCode::CollectionHasOpenDeposits);
+
+    let instruction = close_deposit_ix(&user, &deposit, &collection);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+    let instruction = close_collection_ix(&container, &collection, &collector.pubkey());
+    env.process(&[instruction], &[&collector]).await.unwrap();
+    assert!(!env.exists(&collection).await);
+}
+
+#[tokio::test]
+async fn update_config_rejects_negative_retention() {
+    let mut env = TestEnv::new().await;
+    let instruction = ix(env.update_config_accounts(), retention_config(-1));
+    assert_error(env.process(&[instruction], &[]).await, ErrorCode::InvalidRetention);
+}
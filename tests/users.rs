@@ -0,0 +1,350 @@
+//! Inicialización, usuarios, perfiles y reputación
+
+mod common;
+
+use anchor_lang::AnchorDeserialize;
+use common::*;
+use ecobottle::{ErrorCode, GlobalState, ReputationView, UserProfile, UsernameRecord};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn update_username_ix(user: &User, old_username: &str, new_username: &str) -> solana_sdk::instruction::Instruction {
+    let accounts = ecobottle::accounts::UpdateUsername {
+        user_profile: user.profile,
+        old_username_record: username_pda(old_username),
+        new_username_record: (username_pda(new_username) != username_pda(old_username)).then(|| username_pda(new_username)),
+        user: user.key(),
+        payer: user.key(),
+        system_program: anchor_lang::system_program::ID,
+    };
+    let data = ecobottle::instruction::UpdateUsername {
+        new_username: new_username.to_string(),
+    };
+    ix(accounts, data)
+}
+
+fn update_profile_ix(
+    user: &User,
+    avatar_uri: Option<String>,
+    preferred_container: Option<anchor_lang::prelude::Pubkey>,
+    container_account: Option<anchor_lang::prelude::Pubkey>,
+) -> solana_sdk::instruction::Instruction {
+    let accounts = ecobottle::accounts::UpdateProfile {
+        user_profile: user.profile,
+        preferred_container: container_account,
+        user: user.key(),
+    };
+    let data = ecobottle::instruction::UpdateProfile {
+        avatar_uri,
+        preferred_container,
+    };
+    ix(accounts, data)
+}
+
+fn suspension_ix(env: &TestEnv, user: &User, suspended: bool) -> solana_sdk::instruction::Instruction {
+    let accounts = ecobottle::accounts::SetUserSuspension {
+        user_profile: user.profile,
+        global_state: global_state_pda(),
+        authority: env.authority.pubkey(),
+    };
+    ix(accounts, ecobottle::instruction::SetUserSuspension { suspended })
+}
+
+#[tokio::test]
+async fn initialize_sets_global_state() {
+    let mut env = TestEnv::new().await;
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.version, ecobottle::CURRENT_ACCOUNT_VERSION);
+    assert_eq!(global_state.authority, env.authority.pubkey());
+    assert_eq!(global_state.ecoc_per_kg, ECOC_PER_KG);
+    assert_eq!(global_state.min_deposit_weight, MIN_DEPOSIT_WEIGHT);
+    assert_eq!(global_state.ecoc_mint, env.ecoc_mint);
+    assert_eq!(global_state.emission_factors, ecobottle::DEFAULT_EMISSION_FACTORS);
+}
+
+#[tokio::test]
+async fn register_user_creates_profile_and_reserves_username() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("Ana").await;
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.owner, user.key());
+    assert_eq!(profile.username, "Ana");
+    assert_eq!(profile.reputation_score, ecobottle::REPUTATION_INITIAL);
+    assert_eq!(profile.referrer, None);
+
+    // El nombre se reserva normalizado: "ana" y "Ana" son el mismo
+    let record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(record.owner, user.key());
+
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    assert_eq!(global_state.total_users, 1);
+}
+
+#[tokio::test]
+async fn register_user_validates_username() {
+    let mut env = TestEnv::new().await;
+    let wallet = env.wallet().await;
+
+    let result = env.register_user(&wallet, &"ñ".repeat(33), None, None).await;
+    assert_error(result, ErrorCode::UsernameTooLong);
+
+    let result = env.register_user(&wallet, "   ", None, None).await;
+    assert_error(result, ErrorCode::UsernameEmpty);
+
+    // 32 caracteres multibyte siguen siendo válidos
+    env.register_user(&wallet, &"ñ".repeat(32), None, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn register_user_rejects_taken_username() {
+    let mut env = TestEnv::new().await;
+    let ana = env.new_user("ana").await;
+    let wallet = env.wallet().await;
+
+    // La reserva se normaliza: "ANA" choca con "ana"
+    let result = env.register_user(&wallet, "ANA", None, None).await;
+    assert_error(result, ErrorCode::UsernameTaken);
+
+    let record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(record.owner, ana.key());
+}
+
+#[tokio::test]
+async fn register_user_with_referrer_counts_referral() {
+    let mut env = TestEnv::new().await;
+    let referrer = env.new_user("ana").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.referrer, Some(referrer.key()));
+
+    let referrer_profile: UserProfile = env.get(&referrer.profile).await;
+    assert_eq!(referrer_profile.referral_count, 1);
+}
+
+#[tokio::test]
+async fn register_user_rejects_invalid_referrers() {
+    let mut env = TestEnv::new().await;
+    let ana = env.new_user("ana").await;
+    let wallet = env.wallet().await;
+
+    let result = env.register_user(&wallet, "beto", Some(wallet.pubkey()), None).await;
+    assert_error(result, ErrorCode::SelfReferral);
+
+    let result = env.register_user(&wallet, "beto", Some(ana.key()), None).await;
+    assert_error(result, ErrorCode::MissingReferrerAccounts);
+
+    let carla = env.new_user("carla").await;
+    let result = env.register_user(&wallet, "beto", Some(ana.key()), Some(carla.profile)).await;
+    assert_error(result, ErrorCode::ReferrerMismatch);
+}
+
+#[tokio::test]
+async fn update_username_moves_reservation() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+
+    let instruction = update_username_ix(&user, "ana", "Anita");
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.username, "Anita");
+    assert!(!env.exists(&username_pda("ana")).await);
+    let record: UsernameRecord = env.get(&username_pda("anita")).await;
+    assert_eq!(record.owner, user.key());
+}
+
+#[tokio::test]
+async fn update_username_rejects_reservation_of_another_user() {
+    let mut env = TestEnv::new().await;
+    env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+
+    // Perfil cuyo nombre apunta a una reserva ajena (p. ej. un perfil migrado
+    // desde v0, sin reserva propia)
+    let mut profile: UserProfile = env.get(&beto.profile).await;
+    profile.username = "ana".to_string();
+    env.set_program_account(&beto.profile, &profile, 8 + UserProfile::LEN).await;
+
+    let instruction = update_username_ix(&beto, "ana", "carla");
+    let result = env.process(&[instruction], &[&beto.wallet]).await;
+    assert_error(result, ErrorCode::UsernameNotOwned);
+}
+
+#[tokio::test]
+async fn update_username_rejects_taken_username() {
+    let mut env = TestEnv::new().await;
+    let ana = env.new_user("ana").await;
+    let beto = env.new_user("beto").await;
+
+    let instruction = update_username_ix(&beto, "beto", "Ana");
+    let result = env.process(&[instruction], &[&beto.wallet]).await;
+    assert_error(result, ErrorCode::UsernameTaken);
+
+    let record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(record.owner, ana.key());
+    assert!(env.exists(&username_pda("beto")).await);
+}
+
+#[tokio::test]
+async fn update_username_refunds_sponsored_reservation() {
+    let mut env = TestEnv::new().await;
+    let relayer = env.authority.pubkey();
+
+    // Wallet sin SOL: el relayer paga la reserva al registrar y al renombrar
+    let wallet = Keypair::new();
+    let mut accounts = env.register_user_accounts(&wallet.pubkey(), "ana");
+    accounts.payer = relayer;
+    let data = ecobottle::instruction::RegisterUser {
+        username: "ana".to_string(),
+        referrer: None,
+    };
+    env.process(&[ix(accounts, data)], &[&wallet]).await.unwrap();
+    let record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(record.payer, relayer);
+
+    let rename = |payer| ecobottle::accounts::UpdateUsername {
+        user_profile: user_profile_pda(&wallet.pubkey()),
+        old_username_record: username_pda("ana"),
+        new_username_record: Some(username_pda("anita")),
+        user: wallet.pubkey(),
+        payer,
+        system_program: anchor_lang::system_program::ID,
+    };
+    let data = || ecobottle::instruction::UpdateUsername {
+        new_username: "Anita".to_string(),
+    };
+
+    // La renta de la reserva no puede acabar en la wallet del usuario
+    let result = env.process(&[ix(rename(wallet.pubkey()), data())], &[&wallet]).await;
+    assert!(result.is_err());
+
+    let rent = env.lamports(&username_pda("ana")).await;
+    env.process(&[ix(rename(relayer), data())], &[&wallet]).await.unwrap();
+
+    assert_eq!(env.lamports(&wallet.pubkey()).await, 0);
+    assert!(!env.exists(&username_pda("ana")).await);
+    let record: UsernameRecord = env.get(&username_pda("anita")).await;
+    assert_eq!((record.owner, record.payer), (wallet.pubkey(), relayer));
+    assert_eq!(env.lamports(&username_pda("anita")).await, rent);
+}
+
+#[tokio::test]
+async fn update_username_requires_new_reservation() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+
+    // Solo un cambio de mayúsculas puede omitir la reserva del nuevo nombre
+    let accounts = ecobottle::accounts::UpdateUsername {
+        user_profile: user.profile,
+        old_username_record: username_pda("ana"),
+        new_username_record: None,
+        user: user.key(),
+        payer: user.key(),
+        system_program: anchor_lang::system_program::ID,
+    };
+    let data = ecobottle::instruction::UpdateUsername {
+        new_username: "beto".to_string(),
+    };
+    let result = env.process(&[ix(accounts, data)], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::MissingUsernameRecord);
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.username, "ana");
+    assert!(env.exists(&username_pda("ana")).await);
+}
+
+#[tokio::test]
+async fn update_username_allows_case_only_change() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+
+    let instruction = update_username_ix(&user, "ana", "Ana");
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.username, "Ana");
+    let record: UsernameRecord = env.get(&username_pda("ana")).await;
+    assert_eq!(record.owner, user.key());
+    assert_eq!(record.username, "Ana");
+}
+
+#[tokio::test]
+async fn update_profile_sets_avatar_and_preferred_container() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 100).await;
+
+    let uri = "ipfs://avatar".to_string();
+    let instruction = update_profile_ix(&user, Some(uri.clone()), Some(container), Some(container));
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.avatar_uri, uri);
+    assert_eq!(profile.preferred_container, container);
+
+    // Pubkey::default() elimina el contenedor preferido
+    let instruction = update_profile_ix(&user, None, Some(Default::default()), None);
+    env.process(&[instruction], &[&user.wallet]).await.unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(profile.preferred_container, Default::default());
+}
+
+#[tokio::test]
+async fn update_profile_validates_input() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 100).await;
+    let other = env.new_container("EB-002", 100).await;
+
+    let instruction = update_profile_ix(&user, Some("a".repeat(129)), None, None);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::AvatarUriTooLong);
+
+    let instruction = update_profile_ix(&user, None, Some(container), None);
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::MissingPreferredContainer);
+
+    let instruction = update_profile_ix(&user, None, Some(container), Some(other));
+    let result = env.process(&[instruction], &[&user.wallet]).await;
+    assert_error(result, ErrorCode::MissingPreferredContainer);
+}
+
+#[tokio::test]
+async fn suspension_penalizes_reputation_once() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+
+    let instruction = suspension_ix(&env, &user, true);
+    env.process(&[instruction], &[]).await.unwrap();
+    let instruction = suspension_ix(&env, &user, true);
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert!(profile.is_suspended);
+    assert_eq!(
+        profile.reputation_score,
+        ecobottle::REPUTATION_INITIAL - ecobottle::REPUTATION_SUSPENSION_PENALTY
+    );
+
+    let instruction = suspension_ix(&env, &user, false);
+    env.process(&[instruction], &[]).await.unwrap();
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert!(!profile.is_suspended);
+}
+
+#[tokio::test]
+async fn get_reputation_returns_view() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+
+    let accounts = ecobottle::accounts::GetReputation {
+        user_profile: user.profile,
+    };
+    let data = env.view(ix(accounts, ecobottle::instruction::GetReputation {})).await;
+    let view = ReputationView::try_from_slice(&data).unwrap();
+    assert_eq!(view.reputation_score, ecobottle::REPUTATION_INITIAL);
+    assert_eq!(view.reward_multiplier_bps, 10_000);
+    assert!(!view.is_suspended);
+}
@@ -0,0 +1,146 @@
+//! Vistas de solo lectura: cotización de depósitos, resumen de usuario y estado de contenedor
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AnchorDeserialize;
+use common::*;
+use ecobottle::{
+    ContainerStatusView, DepositQuote, ErrorCode, GlobalState, Material, SmartContainer, UserProfile, UserSummaryView,
+};
+use solana_sdk::instruction::Instruction;
+
+fn quote_ix(user: &User, container: &Pubkey, weight_grams: u64, material: Material, referrer: Option<&User>) -> Instruction {
+    let accounts = ecobottle::accounts::QuoteDepositReward {
+        global_state: global_state_pda(),
+        container: *container,
+        user_profile: user.profile,
+        referrer_profile: referrer.map(|referrer| referrer.profile),
+    };
+    ix(accounts, ecobottle::instruction::QuoteDepositReward { weight_grams, material })
+}
+
+/// Cotización calculada con las cuentas actuales, como la haría la vista
+async fn quote(env: &mut TestEnv, user: &User, container: &Pubkey, weight_grams: u64, referrer: Option<&User>) -> DepositQuote {
+    let global_state: GlobalState = env.get(&global_state_pda()).await;
+    let container: SmartContainer = env.get(container).await;
+    let profile: UserProfile = env.get(&user.profile).await;
+    let referrer_profile = match referrer {
+        Some(referrer) => Some(env.get::<UserProfile>(&referrer.profile).await),
+        None => None,
+    };
+    ecobottle::quote_deposit(&global_state, &container, &profile, referrer_profile.as_ref(), weight_grams).unwrap()
+}
+
+#[tokio::test]
+async fn quote_matches_processed_deposit() {
+    let mut env = TestEnv::new().await;
+    let referrer = env.new_user("ana").await;
+    let user = env.new_user_with_referrer("beto", Some(&referrer)).await;
+    let container = env.new_container("EB-001", 50).await;
+
+    let accounts = env.update_config_accounts();
+    let data = ecobottle::instruction::UpdateReferralConfig {
+        new_bonus_per_kg: Some(500_000),
+        new_max_grams: Some(1_500),
+        new_cap_per_referrer: Some(10_000_000),
+    };
+    env.process(&[ix(accounts, data)], &[]).await.unwrap();
+
+    let expected = quote(&mut env, &user, &container, 1_000, Some(&referrer)).await;
+    assert_eq!(expected.base_reward, ECOC_PER_KG);
+    assert_eq!(expected.ecoc_reward, ECOC_PER_KG);
+    assert_eq!(expected.referral_grams, 1_000);
+    assert_eq!(expected.referral_bonus, 500_000);
+    assert_eq!(expected.fill_level_bps, 200);
+    assert!(!expected.triggers_collection);
+
+    let data = env.view(quote_ix(&user, &container, 1_000, Material::Pet, Some(&referrer))).await;
+    assert_eq!(DepositQuote::try_from_slice(&data).unwrap(), expected);
+
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    assert_eq!(env.token_balance(&user.token_account).await, expected.ecoc_reward);
+    assert_eq!(env.token_balance(&referrer.token_account).await, expected.referral_bonus);
+    let state: SmartContainer = env.get(&container).await;
+    assert_eq!(state.co2_avoided_grams, expected.co2_grams);
+    assert_eq!(state.fill_level_bps(), expected.fill_level_bps);
+
+    // El depósito subió la reputación: la siguiente cotización aplica el nuevo
+    // multiplicador y solo quedan 500 g con bono
+    let expected = quote(&mut env, &user, &container, 1_000, Some(&referrer)).await;
+    let profile: UserProfile = env.get(&user.profile).await;
+    assert_eq!(expected.reward_multiplier_bps, profile.reward_multiplier_bps());
+    assert_eq!(expected.referral_grams, 500);
+    assert_eq!(expected.referral_bonus, 250_000);
+
+    let balance = env.token_balance(&user.token_account).await;
+    let mut accounts = env.deposit_accounts(&user, &container).await;
+    accounts.referrer_profile = Some(referrer.profile);
+    accounts.referrer_token_account = Some(referrer.token_account);
+    let receipt_hash = env.next_receipt();
+    env.deposit_with(&user, accounts, 1_000, receipt_hash, vec![]).await.unwrap();
+    assert_eq!(env.token_balance(&user.token_account).await, balance + expected.ecoc_reward);
+    assert_eq!(env.token_balance(&referrer.token_account).await, 750_000);
+}
+
+#[tokio::test]
+async fn quote_rejects_like_process_deposit() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 1).await;
+
+    let result = env.process(&[quote_ix(&user, &container, 1_000, Material::Glass, None)], &[]).await;
+    assert_error(result, ErrorCode::MaterialMismatch);
+
+    let result = env.process(&[quote_ix(&user, &container, MIN_DEPOSIT_WEIGHT - 1, Material::Pet, None)], &[]).await;
+    assert_error(result, ErrorCode::WeightTooLow);
+
+    let result = env.process(&[quote_ix(&user, &container, 1_001, Material::Pet, None)], &[]).await;
+    assert_error(result, ErrorCode::ContainerFull);
+
+    // Cruzar el umbral de llenado se anticipa en la cotización
+    let expected = quote(&mut env, &user, &container, 800, None).await;
+    assert_eq!(expected.fill_level_bps, ecobottle::DEFAULT_FILL_THRESHOLD_BPS as u64);
+    assert!(expected.triggers_collection);
+    env.deposit(&user, &container, 800).await.unwrap();
+    let state: SmartContainer = env.get(&container).await;
+    assert!(state.needs_collection);
+    assert!(!quote(&mut env, &user, &container, 100, None).await.triggers_collection);
+
+    let toggle = env.toggle_container_ix(&container);
+    env.process(&[toggle], &[]).await.unwrap();
+    let result = env.process(&[quote_ix(&user, &container, 100, Material::Pet, None)], &[]).await;
+    assert_error(result, ErrorCode::ContainerInactive);
+}
+
+#[tokio::test]
+async fn user_summary_and_container_status_views() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user("ana").await;
+    let container = env.new_container("EB-001", 10).await;
+    env.deposit(&user, &container, 2_500).await.unwrap();
+
+    let accounts = ecobottle::accounts::GetUserSummary {
+        global_state: global_state_pda(),
+        user_profile: user.profile,
+    };
+    let data = env.view(ix(accounts, ecobottle::instruction::GetUserSummary {})).await;
+    let view = UserSummaryView::try_from_slice(&data).unwrap();
+    assert_eq!(view.owner, user.key());
+    assert_eq!(view.total_deposits, 1);
+    assert_eq!(view.total_pet_weight, 2_500);
+    assert_eq!(view.week_weight_grams, 2_500);
+    assert_eq!(view.referral_grams_remaining, 0);
+
+    let accounts = ecobottle::accounts::GetContainerStatus { container };
+    let data = env.view(ix(accounts, ecobottle::instruction::GetContainerStatus {})).await;
+    let view = ContainerStatusView::try_from_slice(&data).unwrap();
+    assert_eq!(view.container_id, "EB-001");
+    assert_eq!(view.remaining_grams, 7_500);
+    assert_eq!(view.fill_level_bps, 2_500);
+    assert_eq!(view.open_request, None);
+}